@@ -0,0 +1,803 @@
+//! 服务器配置、配置档、多服务器负载均衡和令牌池
+
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Instant;
+use tracing::{info, warn};
+use tokio::time::Duration;
+use rand::Rng;
+use crate::data::strip_bom;
+use crate::error::{PushError, PushResult};
+use crate::sender::mask_token;
+
+/// ThingsBoard服务器配置结构体
+///
+/// 包含连接ThingsBoard所需的基本配置信息
+#[derive(Debug)]
+pub struct Config {
+    /// ThingsBoard服务器地址列表 (例如: http://localhost:8080)，配置中以逗号分隔
+    pub servers: Vec<String>,
+    /// 设备访问令牌，用于身份验证；配置中以逗号分隔多个令牌时，
+    /// 它们属于同一个逻辑设备，发送时轮换使用以分摊单令牌的速率限制
+    pub device_tokens: Vec<String>,
+}
+
+/// 多服务器负载均衡策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LbStrategy {
+    /// 依次轮询每个服务器
+    RoundRobin,
+    /// 每次随机选择一个服务器
+    Random,
+}
+
+/// 单个服务器的健康状态
+#[derive(Debug)]
+pub struct ServerState {
+    /// 服务器地址
+    url: String,
+    /// 连续失败次数
+    consecutive_failures: u32,
+    /// 被临时摘除时，恢复可用的时刻
+    evicted_until: Option<Instant>,
+}
+
+/// 客户端侧负载均衡的服务器池
+///
+/// 按策略在多个ThingsBoard接入节点间分发请求，某个节点连续失败达到阈值后
+/// 临时摘除一段时间，冷却结束后重新参与分发
+#[derive(Debug)]
+pub struct ServerPool {
+    /// 各服务器的状态
+    servers: Mutex<Vec<ServerState>>,
+    /// 轮询游标
+    cursor: AtomicUsize,
+    /// 分发策略
+    strategy: LbStrategy,
+    /// 触发摘除的连续失败次数
+    max_failures: u32,
+    /// 摘除时长
+    cooldown: Duration,
+}
+
+impl ServerPool {
+    /// 创建服务器池
+    pub fn new(urls: &[String], strategy: LbStrategy, max_failures: u32, cooldown: Duration) -> Self {
+        let servers = urls
+            .iter()
+            .map(|url| ServerState {
+                url: url.clone(),
+                consecutive_failures: 0,
+                evicted_until: None,
+            })
+            .collect();
+        ServerPool {
+            servers: Mutex::new(servers),
+            cursor: AtomicUsize::new(0),
+            strategy,
+            max_failures,
+            cooldown,
+        }
+    }
+
+    /// 选出本次请求使用的服务器
+    ///
+    /// 只在未被摘除的服务器中选择；如果全部被摘除，则选择最早恢复的那个，
+    /// 保证总能发出请求
+    pub fn pick(&self) -> String {
+        let mut servers = self.servers.lock().unwrap();
+        let now = Instant::now();
+        for server in servers.iter_mut() {
+            if server.evicted_until.is_some_and(|until| until <= now) {
+                info!("{}", t!("lb.restored", server.url));
+                server.evicted_until = None;
+                server.consecutive_failures = 0;
+            }
+        }
+
+        let available: Vec<usize> = (0..servers.len())
+            .filter(|&i| servers[i].evicted_until.is_none())
+            .collect();
+        if available.is_empty() {
+            return servers
+                .iter()
+                .min_by_key(|s| s.evicted_until)
+                .map(|s| s.url.clone())
+                .unwrap();
+        }
+
+        let index = match self.strategy {
+            LbStrategy::RoundRobin => {
+                available[self.cursor.fetch_add(1, Ordering::Relaxed) % available.len()]
+            }
+            LbStrategy::Random => available[rand::thread_rng().gen_range(0..available.len())],
+        };
+        servers[index].url.clone()
+    }
+
+    /// 记录一次请求结果，连续失败达到阈值时临时摘除该服务器
+    pub fn report(&self, url: &str, success: bool) {
+        let mut servers = self.servers.lock().unwrap();
+        // 只有一个服务器时摘除没有意义
+        let can_evict = servers.len() > 1;
+        if let Some(server) = servers.iter_mut().find(|s| s.url == url) {
+            if success {
+                server.consecutive_failures = 0;
+                return;
+            }
+            server.consecutive_failures += 1;
+            if can_evict && server.evicted_until.is_none() && server.consecutive_failures >= self.max_failures {
+                server.evicted_until = Some(Instant::now() + self.cooldown);
+                warn!(
+                    "{}",
+                    t!("lb.evicted", server.url, server.consecutive_failures, self.cooldown.as_secs())
+                );
+            }
+        }
+    }
+}
+
+/// 设备令牌池的分配策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolStrategy {
+    /// 每条消息依次使用下一个令牌
+    RoundRobin,
+    /// 每条消息随机选择一个令牌
+    Random,
+    /// 同一个数据项始终使用同一个令牌
+    StickyPerItem,
+}
+
+/// 令牌文件超过该大小时按需从文件读取令牌，不再全部驻留内存
+pub const TOKEN_POOL_LAZY_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// 汇总中最多逐个列出的令牌数
+pub const SUMMARY_TOKEN_LIMIT: usize = 20;
+
+/// 令牌池中令牌的存放方式
+#[derive(Debug)]
+pub enum TokenStore {
+    /// 全部令牌驻留内存
+    Memory(Vec<String>),
+    /// 内存中只保留每个令牌在文件中的位置（每个令牌12字节），发送时按需读取
+    File {
+        /// 令牌文件路径
+        path: String,
+        /// 打开的令牌文件，按位置读取时加锁，多个发送任务可以共享
+        file: Mutex<fs::File>,
+        /// 每个令牌在文件中的起始字节偏移
+        offsets: Vec<u64>,
+        /// 每个令牌的字节长度
+        lens: Vec<u32>,
+    },
+}
+
+impl TokenStore {
+    /// 令牌个数
+    pub fn len(&self) -> usize {
+        match self {
+            TokenStore::Memory(tokens) => tokens.len(),
+            TokenStore::File { offsets, .. } => offsets.len(),
+        }
+    }
+
+    /// 是否没有任何令牌
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 取出第 `index` 个令牌
+    ///
+    /// # 错误
+    ///
+    /// 按需读取模式下令牌文件在运行期间被删除或截断时返回错误
+    pub fn get(&self, index: usize) -> PushResult<Cow<'_, str>> {
+        match self {
+            TokenStore::Memory(tokens) => Ok(Cow::Borrowed(tokens[index].as_str())),
+            TokenStore::File { path, file, offsets, lens } => {
+                use std::io::{Read, Seek, SeekFrom};
+                let mut buf = vec![0; lens[index] as usize];
+                let mut file = file.lock().unwrap();
+                file.seek(SeekFrom::Start(offsets[index]))
+                    .and_then(|_| file.read_exact(&mut buf))
+                    .map_err(|source| PushError::Io {
+                        message: t!("token_pool.read_failed_at", path, index + 1),
+                        source,
+                    })?;
+                // 加载时已校验只包含ASCII字符
+                Ok(Cow::Owned(String::from_utf8_lossy(&buf).into_owned()))
+            }
+        }
+    }
+}
+
+/// 设备令牌池
+///
+/// 从令牌文件加载大量设备令牌，按策略把每次发送分配给不同设备，
+/// 并统计每个令牌收到的消息数
+#[derive(Debug)]
+pub struct TokenPool {
+    /// 有效的设备令牌
+    pub tokens: TokenStore,
+    /// 分配策略
+    pub strategy: PoolStrategy,
+    /// 轮询游标
+    cursor: AtomicUsize,
+    /// 每个令牌成功发送的消息数
+    sent: Vec<AtomicU64>,
+    /// 是否为同一设备的多个令牌轮换（否则每个令牌代表一个不同的设备）
+    pub rotation: bool,
+}
+
+impl TokenPool {
+    /// 为同一个逻辑设备的多个令牌创建轮询使用的令牌池
+    ///
+    /// # 参数
+    ///
+    /// * `tokens` - 属于同一设备的访问令牌
+    pub fn rotation(tokens: Vec<String>) -> Self {
+        let sent = tokens.iter().map(|_| AtomicU64::new(0)).collect();
+        TokenPool {
+            tokens: TokenStore::Memory(tokens),
+            strategy: PoolStrategy::RoundRobin,
+            cursor: AtomicUsize::new(0),
+            sent,
+            rotation: true,
+        }
+    }
+
+    /// 为一批不同设备的令牌创建令牌池，每个令牌代表一个设备
+    ///
+    /// # 参数
+    ///
+    /// * `tokens` - 各设备的访问令牌
+    /// * `strategy` - 令牌分配策略
+    pub fn devices(tokens: TokenStore, strategy: PoolStrategy) -> Self {
+        let sent = (0..tokens.len()).map(|_| AtomicU64::new(0)).collect();
+        TokenPool {
+            tokens,
+            strategy,
+            cursor: AtomicUsize::new(0),
+            sent,
+            rotation: false,
+        }
+    }
+
+    /// 选出本次发送使用的令牌下标
+    ///
+    /// # 参数
+    ///
+    /// * `item_index` - 当前数据项在数据文件中的下标，供 sticky-per-item 策略使用
+    pub fn pick(&self, item_index: usize) -> usize {
+        match self.strategy {
+            PoolStrategy::RoundRobin => self.cursor.fetch_add(1, Ordering::Relaxed) % self.tokens.len(),
+            PoolStrategy::Random => rand::thread_rng().gen_range(0..self.tokens.len()),
+            PoolStrategy::StickyPerItem => item_index % self.tokens.len(),
+        }
+    }
+
+    /// 记录某个令牌成功发送了一条消息
+    pub fn record_sent(&self, token_index: usize) {
+        self.sent[token_index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 输出各令牌的发送条数，令牌较多时只列出前 [`SUMMARY_TOKEN_LIMIT`] 个，其余给出总体分布
+    pub fn print_summary(&self) {
+        info!("{}", t!("summary.tokens"));
+        let counts: Vec<u64> = self.sent.iter().map(|sent| sent.load(Ordering::Relaxed)).collect();
+        for (index, sent) in counts.iter().enumerate().take(SUMMARY_TOKEN_LIMIT) {
+            let token = self.tokens.get(index).map(Cow::into_owned).unwrap_or_default();
+            info!("{}", t!("summary.token_sent", mask_token(&token), sent));
+        }
+        if counts.len() > SUMMARY_TOKEN_LIMIT {
+            let min = counts.iter().min().copied().unwrap_or(0);
+            let max = counts.iter().max().copied().unwrap_or(0);
+            let unused = counts.iter().filter(|&&sent| sent == 0).count();
+            info!("{}", t!("summary.tokens_omitted", counts.len() - SUMMARY_TOKEN_LIMIT, min, max, unused));
+        }
+    }
+}
+
+/// 配置档（profile）中的可选设置
+///
+/// 每个环境可以在配置文件里声明自己的服务器、令牌以及默认的数据文件和发送参数，
+/// 未填写的字段回退到环境变量或命令行默认值
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ProfileSettings {
+    /// ThingsBoard服务器地址
+    pub server: Option<String>,
+    /// 设备访问令牌
+    pub device_token: Option<String>,
+    /// 默认数据文件路径
+    pub data_file: Option<String>,
+    /// 默认发送间隔（秒）
+    pub interval: Option<u64>,
+    /// 默认发送轮数
+    pub count: Option<u64>,
+    /// 默认随机字段名称
+    pub random_key: Option<String>,
+}
+
+/// 配置文件结构体
+///
+/// 对应 `--config` 指定的JSON文件，`profiles` 下按名称存放各环境的配置档
+#[derive(Debug, Default, Deserialize)]
+pub struct ConfigFile {
+    /// 按名称索引的配置档
+    #[serde(default)]
+    pub profiles: HashMap<String, ProfileSettings>,
+}
+
+/// 从文件加载设备令牌池
+///
+/// 文件中每行一个令牌，以 `#` 开头的行视为注释。空行和包含空白或非法字符的行会被跳过，
+/// 跳过的行数在启动时打印。文件逐行流式读取；按需读取模式下只记录每个令牌的位置，
+/// 发送时再从文件读取令牌本身，百万级令牌文件也只占用十几MB内存
+///
+/// # 参数
+///
+/// * `file_path` - 令牌文件路径
+/// * `strategy` - 令牌分配策略
+/// * `lazy` - 强制按需读取；为false时文件超过 [`TOKEN_POOL_LAZY_THRESHOLD`] 也会自动按需读取
+///
+/// # 返回值
+///
+/// * `PushResult<TokenPool>` - 成功时返回令牌池，失败时返回错误信息
+///
+/// # 错误
+///
+/// 当文件无法读取或文件中没有任何有效令牌时返回错误
+pub fn load_token_pool(file_path: &str, strategy: PoolStrategy, lazy: bool) -> PushResult<TokenPool> {
+    use std::io::BufRead;
+
+    let read_failed = |source| PushError::Io { message: t!("token_pool.read_failed", file_path), source };
+    let file = fs::File::open(file_path).map_err(read_failed)?;
+    let file_size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    let lazy = lazy || file_size > TOKEN_POOL_LAZY_THRESHOLD;
+
+    let mut reader = std::io::BufReader::new(file);
+    let mut tokens = Vec::new();
+    let mut offsets = Vec::new();
+    let mut lens = Vec::new();
+    let mut blank = 0;
+    let mut invalid = 0;
+    let mut offset = 0u64;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let read = reader.read_until(b'\n', &mut buf).map_err(read_failed)?;
+        if read == 0 {
+            break;
+        }
+        let line_start = offset;
+        offset += read as u64;
+
+        // 令牌只允许ASCII字符，按字节裁剪首尾空白即可得到令牌在行内的位置
+        let start = buf.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(buf.len());
+        let end = buf.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(start, |end| end + 1);
+        let line = &buf[start..end];
+        if line.is_empty() || line.starts_with(b"#") {
+            blank += 1;
+        } else if line.iter().all(|&b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_') {
+            if lazy {
+                offsets.push(line_start + start as u64);
+                lens.push(line.len() as u32);
+            } else {
+                tokens.push(String::from_utf8_lossy(line).into_owned());
+            }
+        } else {
+            invalid += 1;
+        }
+    }
+
+    let store = if lazy {
+        let file = fs::File::open(file_path).map_err(read_failed)?;
+        TokenStore::File {
+            path: file_path.to_string(),
+            file: Mutex::new(file),
+            offsets,
+            lens,
+        }
+    } else {
+        TokenStore::Memory(tokens)
+    };
+    if store.is_empty() {
+        return Err(PushError::ConfigInvalid(t!("token_pool.empty", file_path)));
+    }
+    let lazy = if lazy { t!("token_pool.lazy") } else { String::new() };
+    info!("{}", t!("token_pool.loaded", store.len(), blank, invalid, lazy));
+
+    Ok(TokenPool::devices(store, strategy))
+}
+
+/// `--token-pattern` 一次最多展开的令牌数
+pub const TOKEN_PATTERN_LIMIT: u64 = 10_000_000;
+
+/// 按模式字符串和序号范围批量生成设备令牌
+///
+/// 模式中的 `{n}` 替换为序号，`{n:W}` 替换为补零到W位的序号；模式只写 `{n}` 而范围起点带前导零时
+/// （如 `0001..1000`），按起点的位数补零。范围为闭区间
+///
+/// # 参数
+///
+/// * `pattern` - 令牌模式，如 `device_{n:4}`
+/// * `range` - 序号范围，如 `1..1000`
+///
+/// # 返回值
+///
+/// * `PushResult<Vec<String>>` - 按序号顺序展开的令牌
+///
+/// # 错误
+///
+/// 当模式中没有 `{n}` 占位、范围格式错误或为空、展开数量超过 [`TOKEN_PATTERN_LIMIT`]，
+/// 或者展开后的令牌包含字母、数字、`-`、`_` 以外的字符时返回错误
+pub fn expand_token_pattern(pattern: &str, range: &str) -> PushResult<Vec<String>> {
+    let range_invalid = || PushError::ConfigInvalid(t!("token_pattern.range_invalid", range));
+    let no_placeholder = || PushError::ConfigInvalid(t!("token_pattern.no_placeholder", pattern));
+    let (start_text, end_text) = range.split_once("..").ok_or_else(range_invalid)?;
+    let parse = |text: &str| text.trim().parse::<u64>().map_err(|_| range_invalid());
+    let (start, end) = (parse(start_text)?, parse(end_text)?);
+    if start > end {
+        return Err(range_invalid());
+    }
+    if end - start >= TOKEN_PATTERN_LIMIT {
+        return Err(PushError::ConfigInvalid(t!("token_pattern.too_many", end - start + 1, TOKEN_PATTERN_LIMIT)));
+    }
+
+    // 找到占位符并确定补零宽度
+    let open = pattern.find("{n").ok_or_else(no_placeholder)?;
+    let close = pattern[open..].find('}').map(|close| open + close).ok_or_else(no_placeholder)?;
+    let width = match &pattern[open + 2..close] {
+        "" if start_text.trim().len() > 1 && start_text.trim().starts_with('0') => start_text.trim().len(),
+        "" => 0,
+        spec => spec
+            .strip_prefix(':')
+            .and_then(|width| width.parse::<usize>().ok())
+            .ok_or_else(no_placeholder)?,
+    };
+    let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+
+    let tokens: Vec<String> =
+        (start..=end).map(|n| format!("{}{:0width$}{}", prefix, n, suffix, width = width)).collect();
+    // 与令牌池文件相同的字符限制，序号部分总是合法的，检查首个即可
+    if !tokens[0].chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(PushError::ConfigInvalid(t!("token_pattern.invalid_chars", tokens[0])));
+    }
+    Ok(tokens)
+}
+
+/// 从文件加载配置档
+///
+/// # 参数
+///
+/// * `file_path` - 配置文件路径
+///
+/// # 返回值
+///
+/// * `PushResult<ConfigFile>` - 成功时返回配置文件内容，失败时返回错误信息
+///
+/// # 错误
+///
+/// 当文件不存在、无法读取或JSON格式错误时返回错误
+pub fn load_config_file(file_path: &str) -> PushResult<ConfigFile> {
+    let content = fs::read_to_string(file_path)
+        .map_err(|source| PushError::Io { message: t!("config.file_read_failed", file_path), source })?;
+    serde_json::from_str(strip_bom(&content))
+        .map_err(|e| PushError::ConfigInvalid(format!("{}: {}", t!("config.file_parse_failed", file_path), e)))
+}
+
+/// 读取可以通过文件注入的敏感设置
+///
+/// 按照Docker secrets的惯例，任何敏感设置 `NAME` 都可以改用 `NAME_FILE` 指向一个
+/// 保存实际值的文件。只设置了 `NAME_FILE` 时读取该文件并去掉首尾空白；
+/// 两者同时设置时视为配置冲突
+///
+/// # 参数
+///
+/// * `name` - 设置名称（如 "device_token"）
+/// * `lookup` - 按名称查询变量值的函数，通常是环境变量
+///
+/// # 返回值
+///
+/// * `PushResult<Option<String>>` - 设置的值，两者都未设置时返回None
+///
+/// # 错误
+///
+/// 当 `NAME` 和 `NAME_FILE` 同时设置，或 `NAME_FILE` 指向的文件无法读取时返回错误
+pub fn read_secret_setting(name: &str, lookup: impl Fn(&str) -> Option<String>) -> PushResult<Option<String>> {
+    let file_var = format!("{}_FILE", name);
+    match (lookup(name), lookup(&file_var)) {
+        (Some(_), Some(_)) => Err(PushError::ConfigInvalid(t!("config.secret_conflict", name, file_var))),
+        (Some(value), None) => Ok(Some(value)),
+        (None, Some(path)) => {
+            let content = fs::read_to_string(&path).map_err(|source| PushError::Io {
+                message: t!("config.secret_file_failed", file_var, path),
+                source,
+            })?;
+            Ok(Some(strip_bom(&content).trim().to_string()))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// 规范化服务器地址
+///
+/// 缺少协议前缀时补全 `http://`，去掉结尾的斜杠，并校验是合法的 http/https URL，
+/// 这样拼接 `/api/v1/...` 时不会出现双斜杠或缺少协议
+///
+/// # 参数
+///
+/// * `server` - 配置中的服务器地址
+///
+/// # 返回值
+///
+/// * `PushResult<String>` - 规范化后的地址，如 `http://host:8080` 或 `https://host/tb`
+///
+/// # 错误
+///
+/// 当地址无法解析、协议不是 http/https 或缺少主机名时返回错误
+pub fn normalize_server_url(server: &str) -> PushResult<String> {
+    let with_scheme = if server.contains("://") {
+        server.to_string()
+    } else {
+        format!("http://{}", server)
+    };
+    let url = reqwest::Url::parse(&with_scheme)
+        .map_err(|e| PushError::ConfigInvalid(format!("{}: {}", t!("config.server_invalid", server), e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(PushError::ConfigInvalid(t!("config.server_scheme", server, url.scheme())));
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err(PushError::ConfigInvalid(t!("config.server_no_host", server)));
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(PushError::ConfigInvalid(t!("config.server_query", server)));
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+/// ThingsBoard生成的设备访问令牌的典型长度，短于它的令牌在加载配置时给出警告
+pub const TYPICAL_TOKEN_LEN: usize = 20;
+
+/// 从环境变量加载ThingsBoard配置
+///
+/// 从.env文件或系统环境变量中读取服务器地址和设备令牌，
+/// 如果选择了配置档，配置档中的服务器地址和令牌优先。
+/// 设备令牌也可以通过 `device_token_FILE` 从文件读取
+///
+/// # 参数
+///
+/// * `profile` - 可选的配置档
+/// * `require_token` - 是否必须提供设备令牌（使用令牌池时不需要）
+///
+/// # 返回值
+///
+/// * `PushResult<Config>` - 成功时返回配置对象，失败时返回错误信息
+///
+/// # 错误
+///
+/// 当配置档和环境变量中都没有'server'或'device_token'时返回 [`PushError::ConfigMissing`]，
+/// 服务器地址无效时返回 [`PushError::ConfigInvalid`]
+///
+/// `server` 和 `device_token` 都可以用逗号分隔多个值：多个服务器做负载均衡，
+/// 多个令牌视为同一设备的轮换令牌；短于 [`TYPICAL_TOKEN_LEN`] 的令牌只给出警告
+pub fn load_config(profile: Option<&ProfileSettings>, require_token: bool) -> PushResult<Config> {
+    let server = match profile.and_then(|p| p.server.clone()) {
+        Some(server) => server,
+        None => env::var("server").map_err(|_| PushError::ConfigMissing(t!("config.server_missing")))?,
+    };
+    // 支持逗号分隔的多个服务器地址，启动时规范化并校验，避免拼接出错误的请求URL
+    let servers: Vec<String> = server
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(normalize_server_url)
+        .collect::<PushResult<_>>()?;
+    if servers.is_empty() {
+        return Err(PushError::ConfigMissing(t!("config.server_empty")));
+    }
+    let device_token = match profile.and_then(|p| p.device_token.clone()) {
+        Some(token) => token,
+        None => match read_secret_setting("device_token", |name| env::var(name).ok())? {
+            Some(token) => token,
+            None if require_token => return Err(PushError::ConfigMissing(t!("config.token_missing"))),
+            None => String::new(),
+        },
+    };
+    // 支持逗号分隔的多个令牌，它们指向同一个逻辑设备
+    let device_tokens: Vec<String> = device_token
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+    if require_token && device_tokens.is_empty() {
+        return Err(PushError::ConfigMissing(t!("config.token_empty")));
+    }
+    // 过短的令牌多半是占位值或复制时漏了字符，只提示不报错，真正的校验留给服务器
+    for token in device_tokens.iter().filter(|t| t.chars().count() < TYPICAL_TOKEN_LEN) {
+        warn!(
+            "{}",
+            t!("config.token_short", mask_token(token), token.chars().count(), TYPICAL_TOKEN_LEN)
+        );
+    }
+
+    Ok(Config {
+        servers,
+        device_tokens,
+    })
+}
+
+/// 按“命令行 > 配置档 > 默认值”的优先级取参数值
+///
+/// # 参数
+///
+/// * `matches` - 命令行解析结果
+/// * `id` - 参数名称（该参数必须带有默认值）
+/// * `profile_value` - 配置档中对应的值
+///
+/// # 返回值
+///
+/// * `(String, bool)` - 最终生效的参数值，以及该值是否来自配置档
+pub fn resolve_arg(matches: &ArgMatches, id: &str, profile_value: Option<String>) -> (String, bool) {
+    let from_cli = matches.value_source(id) == Some(ValueSource::CommandLine);
+    match profile_value {
+        Some(value) if !from_cli => (value, true),
+        _ => (matches.get_one::<String>(id).unwrap().clone(), false),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::i18n::{Lang, render};
+
+    /// 构造一个基于固定键值表的变量查询函数，其它模块的测试同样使用
+    pub(crate) fn lookup_from(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> + use<> {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| map.get(name).cloned()
+    }
+
+    #[test]
+    fn secret_setting_reads_plain_value() {
+        let value = read_secret_setting("device_token", lookup_from(&[("device_token", "plain")])).unwrap();
+        assert_eq!(value.as_deref(), Some("plain"));
+    }
+
+    #[test]
+    fn secret_setting_reads_and_trims_file() {
+        let path = env::temp_dir().join("push_tb_secret_setting_test.txt");
+        fs::write(&path, "  from-file-token\n").unwrap();
+        let lookup = lookup_from(&[("device_token_FILE", path.to_str().unwrap())]);
+        let value = read_secret_setting("device_token", lookup).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(value.as_deref(), Some("from-file-token"));
+    }
+
+    #[test]
+    fn secret_setting_rejects_both_plain_and_file() {
+        let lookup = lookup_from(&[("device_token", "plain"), ("device_token_FILE", "/tmp/x")]);
+        let err = read_secret_setting("device_token", lookup).unwrap_err();
+        assert!(err.to_string().contains("不能同时设置"));
+    }
+
+    #[test]
+    fn secret_setting_reports_unreadable_file() {
+        let lookup = lookup_from(&[("device_token_FILE", "/nonexistent/secret")]);
+        let err = read_secret_setting("device_token", lookup).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("device_token_FILE"));
+        assert!(message.contains("/nonexistent/secret"));
+    }
+
+    #[test]
+    fn secret_setting_absent() {
+        let value = read_secret_setting("device_token", lookup_from(&[])).unwrap();
+        assert!(value.is_none());
+    }
+
+    #[test]
+    fn config_missing_error_renders_in_both_languages() {
+        assert_eq!(render(Lang::Zh, "config.server_missing", &[]), "未找到环境变量 'server'");
+        assert_eq!(render(Lang::En, "config.server_missing", &[]), "Environment variable 'server' not found");
+    }
+
+    #[test]
+    fn token_pattern_expands_range_with_padding() {
+        assert_eq!(expand_token_pattern("device_{n}", "1..3").unwrap(), vec!["device_1", "device_2", "device_3"]);
+        let padded = expand_token_pattern("device_{n:4}", "1..1000").unwrap();
+        assert_eq!(padded.len(), 1000);
+        assert_eq!((padded[0].as_str(), padded[999].as_str()), ("device_0001", "device_1000"));
+        // 起点带前导零时按起点的位数补零
+        assert_eq!(expand_token_pattern("{n}-tb", "008..010").unwrap(), vec!["008-tb", "009-tb", "010-tb"]);
+
+        assert!(expand_token_pattern("device", "1..3").is_err());
+        assert!(expand_token_pattern("device_{n}", "3..1").is_err());
+        assert!(expand_token_pattern("device_{n}", "1-3").is_err());
+        assert!(expand_token_pattern("device {n}", "1..3").is_err());
+    }
+
+    #[test]
+    fn server_urls_are_normalized_at_load() {
+        assert_eq!(normalize_server_url("tb.local:8080").unwrap(), "http://tb.local:8080");
+        assert_eq!(normalize_server_url("http://tb.local:8080/").unwrap(), "http://tb.local:8080");
+        assert_eq!(normalize_server_url("https://example.com/tb//").unwrap(), "https://example.com/tb");
+        assert_eq!(normalize_server_url("HTTP://Example.com").unwrap(), "http://example.com");
+        assert!(normalize_server_url("ftp://tb.local").is_err());
+        assert!(normalize_server_url("http://").is_err());
+        assert!(normalize_server_url("http://tb local").is_err());
+        assert!(normalize_server_url("http://tb.local/?a=1").is_err());
+
+        let profile = ProfileSettings {
+            server: Some("tb1:8080/, https://tb2/".to_string()),
+            device_token: Some("abcdefghijklmnopqrst".to_string()),
+            ..Default::default()
+        };
+        let config = load_config(Some(&profile), true).unwrap();
+        assert_eq!(config.servers, vec!["http://tb1:8080", "https://tb2"]);
+    }
+
+    #[test]
+    fn config_errors_have_specific_variants() {
+        let profile = |server: &str, token: &str| ProfileSettings {
+            server: Some(server.to_string()),
+            device_token: Some(token.to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(load_config(Some(&profile("tb:8080", " , ")), true), Err(PushError::ConfigMissing(_))));
+        assert!(matches!(load_config(Some(&profile(" , ", "token")), true), Err(PushError::ConfigMissing(_))));
+        assert!(matches!(load_config(Some(&profile("ftp://tb", "token")), true), Err(PushError::ConfigInvalid(_))));
+
+        let lookup = lookup_from(&[("device_token_FILE", "/nonexistent/secret")]);
+        assert!(matches!(read_secret_setting("device_token", lookup), Err(PushError::Io { .. })));
+        let lookup = lookup_from(&[("device_token", "plain"), ("device_token_FILE", "/tmp/x")]);
+        assert!(matches!(read_secret_setting("device_token", lookup), Err(PushError::ConfigInvalid(_))));
+    }
+
+    #[test]
+    fn server_pool_rotates_and_evicts_failing_servers() {
+        let urls = ["http://a".to_string(), "http://b".to_string()];
+        let pool = ServerPool::new(&urls, LbStrategy::RoundRobin, 2, Duration::from_secs(60));
+        let picks: Vec<String> = (0..4).map(|_| pool.pick()).collect();
+        assert_eq!(picks, ["http://a", "http://b", "http://a", "http://b"]);
+
+        // 连续失败达到阈值后摘除，成功会清零连续失败次数
+        pool.report("http://a", false);
+        pool.report("http://a", true);
+        pool.report("http://a", false);
+        assert!((0..4).any(|_| pool.pick() == "http://a"));
+        pool.report("http://a", false);
+        assert!((0..4).all(|_| pool.pick() == "http://b"));
+
+        // 只有一个服务器时不摘除
+        let single = ServerPool::new(&urls[..1], LbStrategy::RoundRobin, 1, Duration::from_secs(60));
+        single.report("http://a", false);
+        assert_eq!(single.pick(), "http://a");
+    }
+
+    #[test]
+    fn token_pool_strategies_pick_expected_tokens() {
+        let tokens = || TokenStore::Memory(vec!["t0".to_string(), "t1".to_string(), "t2".to_string()]);
+        let rotation = TokenPool::rotation(vec!["a".to_string(), "b".to_string()]);
+        assert!(rotation.rotation);
+        assert_eq!((0..5).map(|index| rotation.pick(index)).collect::<Vec<_>>(), [0, 1, 0, 1, 0]);
+
+        // 按数据项固定分配时，同一数据项总是使用同一个令牌
+        let sticky = TokenPool::devices(tokens(), PoolStrategy::StickyPerItem);
+        assert!(!sticky.rotation);
+        assert_eq!([4, 1, 4, 3].map(|index| sticky.pick(index)), [1, 1, 1, 0]);
+        assert_eq!(sticky.tokens.get(2).unwrap(), "t2");
+
+        let random = TokenPool::devices(tokens(), PoolStrategy::Random);
+        assert!((0..50).all(|index| random.pick(index) < 3));
+    }
+}
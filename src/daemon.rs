@@ -0,0 +1,229 @@
+//! 后台运行：`--daemon` 启动脱离终端的子进程，PID文件记录进程号，`stop` 子命令据此停止进程
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::time::{sleep, Duration};
+use tracing::{info, warn};
+use crate::error::{PushError, PushResult};
+
+/// `--daemon` 和 `stop` 未指定 `--pid-file` 时使用的PID文件
+pub const DEFAULT_PID_FILE: &str = "push-message-thingsboard.pid";
+
+/// 启动后台进程后等待多久确认它没有立即退出
+pub const DAEMON_STARTUP_CHECK: Duration = Duration::from_secs(1);
+
+/// 运行期间存在的PID文件，drop时删除
+pub struct PidFile {
+    /// 文件路径
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// 写入当前进程号
+    ///
+    /// # 错误
+    ///
+    /// 文件中记录的进程仍在运行，或者文件无法写入时返回错误
+    pub fn create(path: &str) -> PushResult<Self> {
+        if let Some(pid) = running_pid(Path::new(path)) {
+            return Err(PushError::Process(t!("daemon.already_running", pid, path)));
+        }
+        fs::write(path, format!("{}\n", std::process::id()))
+            .map_err(|source| PushError::Io { message: t!("daemon.pid_write_failed", path), source })?;
+        Ok(PidFile { path: PathBuf::from(path) })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // 只删除仍然属于本进程的PID文件，避免删掉之后启动的进程写入的文件
+        if fs::read_to_string(&self.path).is_ok_and(|content| content.trim() == std::process::id().to_string()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// 读取PID文件，返回其中仍在运行的进程号
+///
+/// 文件不存在、内容无效或进程已经退出时返回 `None`
+pub fn running_pid(path: &Path) -> Option<u32> {
+    let pid: u32 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    process_alive(pid).then_some(pid)
+}
+
+/// 进程是否仍在运行
+#[cfg(unix)]
+pub fn process_alive(pid: u32) -> bool {
+    // 0和超出范围的值会被kill解释为进程组，不是有效的进程号
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    // 信号0只检查进程是否存在，不会真正发出信号；
+    // 没有权限（EPERM）说明进程存在但属于其他用户，只有ESRCH表示进程已经退出
+    let signaled = unsafe { libc::kill(pid, 0) } == 0;
+    signaled || std::io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+}
+
+/// 非Unix平台无法检查其它进程，总是认为已经退出
+#[cfg(not(unix))]
+pub fn process_alive(_pid: u32) -> bool {
+    false
+}
+
+/// 启动后台进程：以相同参数（去掉 `--daemon`）重新运行自身
+///
+/// 子进程在新会话中运行，脱离控制终端，标准输入输出重定向到 `/dev/null`，
+/// 由子进程自己写入和删除PID文件。等待片刻确认子进程没有因配置错误立即退出
+///
+/// # 参数
+///
+/// * `pid_file` - PID文件路径
+/// * `add_pid_file` - 命令行没有指定 `--pid-file`，需要为子进程补上
+///
+/// # 返回值
+///
+/// * `PushResult<u32>` - 后台进程的进程号
+///
+/// # 错误
+///
+/// 已有后台进程在运行、无法启动子进程，或子进程启动后立即退出时返回错误
+#[cfg(unix)]
+pub async fn spawn_daemon(pid_file: &str, add_pid_file: bool) -> PushResult<u32> {
+    use std::os::unix::process::CommandExt;
+
+    if let Some(pid) = running_pid(Path::new(pid_file)) {
+        return Err(PushError::Process(t!("daemon.already_running", pid, pid_file)));
+    }
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().skip(1).filter(|arg| arg != "--daemon").collect();
+    if add_pid_file {
+        args.extend(["--pid-file".into(), pid_file.into()]);
+    }
+    let exe = std::env::current_exe()
+        .map_err(|source| PushError::Io { message: t!("daemon.current_exe_failed"), source })?;
+    let mut command = std::process::Command::new(exe);
+    command
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    // 在新会话中运行，关闭终端时不会收到SIGHUP，终端中的Ctrl-C也不会传给它
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let spawn_failed = |source| PushError::Io { message: t!("daemon.spawn_failed"), source };
+    let mut child = command.spawn().map_err(spawn_failed)?;
+
+    sleep(DAEMON_STARTUP_CHECK).await;
+    if let Some(status) = child.try_wait().map_err(spawn_failed)? {
+        let code = status.code().map_or_else(|| status.to_string(), |code| code.to_string());
+        return Err(PushError::Process(t!("daemon.exited", code)));
+    }
+    Ok(child.id())
+}
+
+/// 非Unix平台不支持后台运行
+#[cfg(not(unix))]
+pub async fn spawn_daemon(_pid_file: &str, _add_pid_file: bool) -> PushResult<u32> {
+    Err(PushError::Process(t!("daemon.unsupported")))
+}
+
+/// 停止PID文件中的进程：发送中断信号并等待其退出
+///
+/// 后台进程收到中断信号后与按下Ctrl-C相同：完成进行中的发送，在日志中写出汇总后退出。
+/// 超过 `timeout` 仍未退出时再发送一次中断信号，使其立即退出。
+/// 进程本来就没有运行时删除过期的PID文件，同样视为成功
+///
+/// # 参数
+///
+/// * `pid_file` - PID文件路径
+/// * `timeout` - 等待进程完成进行中的发送的时长
+///
+/// # 错误
+///
+/// PID文件无法读取、无法发送信号，或者进程始终没有退出时返回错误
+pub async fn stop_daemon(pid_file: &str, timeout: Duration) -> PushResult<()> {
+    let content = fs::read_to_string(pid_file)
+        .map_err(|source| PushError::Io { message: t!("daemon.pid_missing", pid_file), source })?;
+    let pid: u32 = content.trim().parse().map_err(|_| PushError::Process(t!("daemon.pid_invalid", pid_file)))?;
+    if !process_alive(pid) {
+        let _ = fs::remove_file(pid_file);
+        warn!("{}", t!("daemon.not_running", pid, pid_file));
+        return Ok(());
+    }
+
+    send_interrupt(pid)?;
+    info!("{}", t!("daemon.stopping", pid, timeout.as_secs()));
+    for force in [false, true] {
+        let deadline = Instant::now() + if force { DAEMON_STARTUP_CHECK } else { timeout };
+        while Instant::now() < deadline {
+            // 进程退出时会删除PID文件，在没有回收僵尸进程的容器中以此为准
+            if !process_alive(pid) || !Path::new(pid_file).exists() {
+                info!("{}", t!("daemon.stopped", pid));
+                return Ok(());
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        if !force {
+            warn!("{}", t!("daemon.force_stop", pid, timeout.as_secs()));
+            send_interrupt(pid)?;
+        }
+    }
+    Err(PushError::Process(t!("daemon.stop_failed", pid)))
+}
+
+/// 向进程发送SIGINT，效果与在终端中按Ctrl-C相同
+#[cfg(unix)]
+fn send_interrupt(pid: u32) -> PushResult<()> {
+    if unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) } == -1 {
+        let source = std::io::Error::last_os_error();
+        return Err(PushError::Io { message: t!("daemon.signal_failed", pid), source });
+    }
+    Ok(())
+}
+
+/// 非Unix平台不支持向其它进程发送信号
+#[cfg(not(unix))]
+fn send_interrupt(_pid: u32) -> PushResult<()> {
+    Err(PushError::Process(t!("daemon.unsupported")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn pid_file_guards_against_a_second_instance() {
+        let path = env::temp_dir().join(format!("push_tb_pid_{}.pid", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let _ = fs::remove_file(&path);
+
+        let pid_file = PidFile::create(path_str).unwrap();
+        assert_eq!(running_pid(&path), Some(std::process::id()));
+        assert!(matches!(PidFile::create(path_str), Err(PushError::Process(_))));
+        drop(pid_file);
+        assert!(!path.exists());
+
+        // 内容无效的PID文件视为没有进程在运行
+        fs::write(&path, "not a pid\n").unwrap();
+        assert_eq!(running_pid(&path), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn invalid_pids_are_never_alive() {
+        assert!(process_alive(std::process::id()));
+        assert!(!process_alive(0));
+        assert!(!process_alive(u32::MAX));
+    }
+}
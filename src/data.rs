@@ -0,0 +1,1513 @@
+//! 数据文件：加载、格式检测、合并、检查和发送前试算
+
+use serde_json::{Number, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use rand_chacha::ChaCha12Rng;
+use rand::SeedableRng;
+use crate::error::{PushError, PushResult};
+use crate::random::{FieldGenerator, RandomWalk, seeded_rng};
+use crate::sender::SendOptions;
+use crate::telemetry::{Condition, EmptyPolicy, FieldGroup, ValidRange, extract_telemetry_values, parse_condition};
+
+/// 数据文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    /// 直接数组格式，没有 random_key 等配置
+    Array,
+    /// 包装对象格式
+    Object,
+}
+
+/// 数据文件解析结果结构体
+///
+/// 包含从数据文件中解析出的随机键和数据数组
+#[derive(Debug)]
+pub struct DataFileResult {
+    /// 数据文件格式
+    format: DataFormat,
+    /// 随机键名称（如 "drp"）
+    pub random_key: Option<String>,
+    /// 字段生成配置（数据文件中的 "random_config"），按名称索引
+    pub random_config: BTreeMap<String, FieldGenerator>,
+    /// 发往属性通道的字段（数据文件中的 "attribute_fields"），未列出的字段走遥测
+    pub attribute_fields: Vec<String>,
+    /// 字段上报条件（数据文件中的 "conditions"），按字段名索引
+    pub conditions: BTreeMap<String, Condition>,
+    /// 字段的合理范围（数据文件中的 "valid_range"），按字段路径索引
+    pub valid_range: BTreeMap<String, ValidRange>,
+    /// 字段分组的发送间隔（数据文件中的 "field_groups"），按组名索引
+    pub field_groups: BTreeMap<String, FieldGroup>,
+    /// 数据数组
+    pub data: Vec<Value>,
+}
+
+impl DataFileResult {
+    /// 没有任何数据和配置的空结果，用于合并多个数据文件
+    fn empty() -> Self {
+        DataFileResult {
+            format: DataFormat::Array,
+            random_key: None,
+            random_config: BTreeMap::new(),
+            attribute_fields: Vec::new(),
+            conditions: BTreeMap::new(),
+            valid_range: BTreeMap::new(),
+            field_groups: BTreeMap::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// 合并另一个数据文件：random_key 保留先设置的值，其余配置后合并的覆盖同名项，数据项追加在后面
+    fn merge(&mut self, file: DataFileResult) {
+        if file.format == DataFormat::Object {
+            self.format = DataFormat::Object;
+        }
+        self.random_key = self.random_key.take().or(file.random_key);
+        self.random_config.extend(file.random_config);
+        for field in file.attribute_fields {
+            if !self.attribute_fields.contains(&field) {
+                self.attribute_fields.push(field);
+            }
+        }
+        self.conditions.extend(file.conditions);
+        self.valid_range.extend(file.valid_range);
+        self.field_groups.extend(file.field_groups);
+        self.data.extend(file.data);
+    }
+
+    /// 用命令行或配置档指定的随机键覆盖数据文件中的设置，两种格式都适用
+    pub fn override_random_key(&mut self, random_key: Option<String>) {
+        if random_key.is_some() {
+            self.random_key = random_key;
+        }
+    }
+
+    /// 随机键实际作用的字段，格式为 `顶层字段.随机键`，按名称排序去重
+    ///
+    /// 随机键只修改数据项中嵌套对象内的同名字段，没有任何匹配时随机修改不会生效
+    pub fn random_targets(&self) -> Vec<String> {
+        let Some(ref key) = self.random_key else {
+            return Vec::new();
+        };
+        let targets: std::collections::BTreeSet<String> = self
+            .data
+            .iter()
+            .filter_map(Value::as_object)
+            .flat_map(|item| item.iter())
+            .filter(|(_, value)| value.get(key).is_some())
+            .map(|(name, _)| format!("{}.{}", name, key))
+            .collect();
+        targets.into_iter().collect()
+    }
+
+    /// 去掉空对象和非对象的数据项，避免它们在发送循环中每轮都失败
+    ///
+    /// # 参数
+    ///
+    /// * `source` - 数据文件或目录路径，用于提示信息
+    /// * `strict` - 为 `true` 时存在不可用的数据项直接报错，否则输出警告后跳过
+    ///
+    /// # 错误
+    ///
+    /// `strict` 模式下存在不可用的数据项，或者去掉后没有剩余数据项时返回错误，错误信息中列出数据项序号（从1开始）
+    pub fn drop_unusable_items(&mut self, source: &str, strict: bool) -> PushResult<()> {
+        let unusable: Vec<usize> = self
+            .data
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.as_object().is_none_or(serde_json::Map::is_empty))
+            .map(|(index, _)| index + 1)
+            .collect();
+        if unusable.is_empty() {
+            return Ok(());
+        }
+        let listed = unusable
+            .iter()
+            .take(UNUSABLE_ITEMS_LISTED)
+            .map(usize::to_string)
+            .chain((unusable.len() > UNUSABLE_ITEMS_LISTED).then(|| "…".to_string()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        if strict {
+            return Err(PushError::DataFileInvalid(t!("data.unusable_strict", source, unusable.len(), listed)));
+        }
+        warn!("{}", t!("data.unusable_skipped", source, unusable.len(), listed));
+        self.data.retain(|item| item.as_object().is_some_and(|fields| !fields.is_empty()));
+        if self.data.is_empty() {
+            return Err(PushError::DataFileInvalid(t!("data.no_usable_items", source)));
+        }
+        Ok(())
+    }
+}
+
+/// 发送前逐条试算数据项，找出发送时必然失败的数据项
+///
+/// 按实际的字段规则提取遥测值，随机修改使用一次性的随机数生成器，不影响发送时的随机序列；
+/// 试算期间不输出随机修改、范围告警等日志
+///
+/// # 参数
+///
+/// * `data` - 全部数据项
+/// * `random_key` - 随机键名称
+/// * `options` - 发送选项，使用其中的字段规则和 `--on-empty` 策略
+/// * `merge` - 是否为合并时序模式，此时记录自带的 `ts` 必须是非负整数
+///
+/// # 返回值
+///
+/// * `Vec<(usize, String)>` - 不可发送的数据项下标（从0开始）和原因
+pub fn preflight_items(
+    data: &[Value],
+    random_key: &Option<String>,
+    options: &SendOptions,
+    merge: bool,
+) -> Vec<(usize, String)> {
+    let mut rng = ChaCha12Rng::seed_from_u64(0);
+    tracing::dispatcher::with_default(&tracing::Dispatch::none(), || {
+        data.iter()
+            .enumerate()
+            .filter_map(|(index, item)| {
+                preflight_item(item, random_key, options, merge, &mut rng)
+                    .err()
+                    .map(|e| (index, e.to_string()))
+            })
+            .collect()
+    })
+}
+
+/// 试算一条数据项，发送时会失败的返回原因
+pub fn preflight_item(
+    item: &Value,
+    random_key: &Option<String>,
+    options: &SendOptions,
+    merge: bool,
+    rng: &mut ChaCha12Rng,
+) -> PushResult<()> {
+    if item.as_object().is_none_or(serde_json::Map::is_empty) {
+        return Err(PushError::payload(t!("data.item_unusable")));
+    }
+    let mut record = item.clone();
+    if merge
+        && let Some(ts) = record.as_object_mut().and_then(|fields| fields.remove("ts"))
+        && ts.as_u64().is_none()
+    {
+        return Err(PushError::payload(t!("send.merge_ts_invalid", ts)));
+    }
+    let values = extract_telemetry_values(&record, random_key, &options.field_rules, rng)?;
+    if values.is_empty() && options.on_empty == EmptyPolicy::Error {
+        return Err(PushError::payload(t!("send.empty_values")));
+    }
+    Ok(())
+}
+
+/// 不可用数据项的提示中最多列出的序号个数
+pub const UNUSABLE_ITEMS_LISTED: usize = 10;
+
+/// 在启动信息中说明随机修改是否生效以及作用于哪些字段
+///
+/// # 参数
+///
+/// * `data` - 数据文件解析结果（已应用命令行覆盖）
+/// * `source` - 随机键来源的附加说明，如来自配置档
+pub fn log_randomization(data: &DataFileResult, source: &str) {
+    match data.random_key {
+        Some(ref key) => {
+            let targets = data.random_targets();
+            if targets.is_empty() {
+                warn!("{}", t!("banner.random_key_unmatched", key));
+            } else {
+                info!("{}{}", t!("banner.random_key", key, targets.join(", ")), source);
+            }
+        }
+        None if data.format == DataFormat::Array => info!("{}", t!("banner.random_off_array")),
+        None => info!("{}", t!("banner.random_off")),
+    }
+}
+
+/// 数据文件检查问题的级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueLevel {
+    /// 数据无法按预期发送，`validate` 以非0退出码结束
+    Error,
+    /// 可以发送但可能不符合预期，超过 `--max-warnings` 时才视为失败
+    Warning,
+}
+
+/// `validate` 子命令发现的一个问题
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// 问题级别
+    pub level: IssueLevel,
+    /// 数据项下标（从0开始），针对整个文件配置的问题为None
+    index: Option<usize>,
+    /// 字段路径，如 `rain.drp`；针对整个数据项的问题为空
+    path: String,
+    /// 问题描述
+    message: String,
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mark = match self.level {
+            IssueLevel::Error => "❌",
+            IssueLevel::Warning => "⚠️",
+        };
+        let location = match (self.index, self.path.is_empty()) {
+            (Some(index), true) => t!("validate.at_item", index + 1),
+            (Some(index), false) => t!("validate.at_item_path", index + 1, self.path),
+            (None, true) => t!("validate.at_file"),
+            (None, false) => t!("validate.at_config", self.path),
+        };
+        write!(f, "{} {}: {}", mark, location, self.message)
+    }
+}
+
+/// 逐项检查数据，返回发现的全部问题，按数据项下标排列，文件级配置的问题在前
+///
+/// 检查内容：
+/// 1. 每个数据项都是非空的JSON对象，字段名非空
+/// 2. random_key、conditions、valid_range、field_groups 引用的字段至少在一个数据项中存在
+/// 3. 值的类型：null会被ThingsBoard忽略、数组整体作为一个值存储，给出警告
+/// 4. 指定了允许的字段名时，数据项中不允许出现其它顶层字段
+pub fn validate_data(data: &DataFileResult, allow_keys: Option<&HashSet<String>>) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    let config_issue = |level, path: &str, message: String| ValidationIssue { level, index: None, path: path.to_string(), message };
+
+    // 按 `.` 逐层进入嵌套对象，判断字段在任一数据项中是否存在
+    let exists = |path: &str| {
+        data.data.iter().any(|item| {
+            let mut current = Some(item);
+            for segment in path.split('.') {
+                current = current.and_then(|value| value.get(segment));
+            }
+            current.is_some()
+        })
+    };
+
+    if let Some(ref key) = data.random_key
+        && data.random_targets().is_empty()
+    {
+        issues.push(config_issue(IssueLevel::Error, "random_key", t!("validate.random_key_unmatched", key)));
+    }
+    for field in data.conditions.keys().filter(|field| !exists(field)) {
+        issues.push(config_issue(IssueLevel::Error, &format!("conditions.{}", field), t!("validate.field_missing", field)));
+    }
+    for field in data.valid_range.keys().filter(|field| !exists(field)) {
+        issues.push(config_issue(IssueLevel::Error, &format!("valid_range.{}", field), t!("validate.field_missing", field)));
+    }
+    for (name, group) in &data.field_groups {
+        for field in group.fields.iter().filter(|field| !exists(field)) {
+            issues.push(config_issue(IssueLevel::Error, &format!("field_groups.{}", name), t!("validate.field_missing", field)));
+        }
+    }
+    for field in data.attribute_fields.iter().filter(|field| !exists(field)) {
+        issues.push(config_issue(IssueLevel::Warning, "attribute_fields", t!("validate.field_missing", field)));
+    }
+    // 轨迹坐标写入已有纬度字段的顶层或嵌套对象，都没有时新增在顶层
+    for (name, generator) in &data.random_config {
+        let FieldGenerator::Geo(config) = generator else {
+            continue;
+        };
+        let has_lat = |value: &Value| value.get(&config.lat_field).is_some();
+        let present = data.data.iter().any(|item| {
+            has_lat(item) || item.as_object().is_some_and(|obj| obj.values().any(has_lat))
+        });
+        if !present {
+            issues.push(config_issue(
+                IssueLevel::Warning,
+                &format!("random_config.{}", name),
+                t!("validate.geo_field_added", config.lat_field, config.lng_field),
+            ));
+        }
+    }
+
+    // 随机游走同样写入已有该字段的顶层或嵌套对象
+    for (name, generator) in &data.random_config {
+        let FieldGenerator::RandomWalk(config) = generator else {
+            continue;
+        };
+        if let Err(e) = RandomWalk::new(name, config.clone(), seeded_rng(None, 0)) {
+            issues.push(config_issue(IssueLevel::Error, &format!("random_config.{}", name), e.to_string()));
+            continue;
+        }
+        let field = config.field.as_deref().unwrap_or(name);
+        let has_field = |value: &Value| value.get(field).is_some();
+        let present = data.data.iter().any(|item| {
+            has_field(item) || item.as_object().is_some_and(|obj| obj.values().any(has_field))
+        });
+        if !present {
+            issues.push(config_issue(
+                IssueLevel::Warning,
+                &format!("random_config.{}", name),
+                t!("validate.walk_field_added", field),
+            ));
+        }
+    }
+
+    for (index, item) in data.data.iter().enumerate() {
+        let mut item_issue = |level, path: String, message: String| {
+            issues.push(ValidationIssue { level, index: Some(index), path, message })
+        };
+        let Value::Object(fields) = item else {
+            item_issue(IssueLevel::Error, String::new(), t!("validate.not_object", json_type_name(item)));
+            continue;
+        };
+        if fields.is_empty() {
+            item_issue(IssueLevel::Error, String::new(), t!("validate.empty_item"));
+            continue;
+        }
+        if let Some(allowed) = allow_keys {
+            for key in fields.keys().filter(|key| !allowed.contains(*key)) {
+                item_issue(IssueLevel::Error, key.clone(), t!("validate.key_not_allowed"));
+            }
+        }
+        // 深度优先检查每个值，路径用 `.` 连接
+        let mut pending: Vec<(String, &Value)> = fields.iter().rev().map(|(key, value)| (key.clone(), value)).collect();
+        while let Some((path, value)) = pending.pop() {
+            if path.is_empty() || path.ends_with('.') {
+                item_issue(IssueLevel::Error, path.clone(), t!("validate.empty_key"));
+            }
+            match value {
+                Value::Null => item_issue(IssueLevel::Warning, path, t!("validate.null_value")),
+                Value::Array(_) => item_issue(IssueLevel::Warning, path, t!("validate.array_value")),
+                Value::Object(nested) if nested.is_empty() => {
+                    item_issue(IssueLevel::Warning, path, t!("validate.empty_object"))
+                }
+                Value::Object(nested) => {
+                    pending.extend(nested.iter().rev().map(|(key, value)| (format!("{}.{}", path, key), value)));
+                }
+                _ => {}
+            }
+        }
+    }
+    issues
+}
+
+/// `--inspect` 统计的一个字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldStats {
+    /// 字段路径，嵌套字段用 `.` 连接（如 `rain.drp`）
+    pub path: String,
+    /// 包含该字段的数据项个数
+    pub count: usize,
+    /// 出现过的值类型（按界面语言），按首次出现的顺序
+    pub types: Vec<String>,
+    /// 数值的个数，其余统计只针对数值
+    pub numbers: usize,
+    /// 最小值，保留数据文件中的原始数值，超出f64精度的整数也不会失真
+    pub min: Option<Number>,
+    /// 最大值，同样保留原始数值
+    pub max: Option<Number>,
+    /// 数值总和，只用于计算平均值
+    pub sum: f64,
+}
+
+impl FieldStats {
+    /// 数值的平均值，没有数值时返回None
+    pub fn avg(&self) -> Option<f64> {
+        (self.numbers > 0).then(|| self.sum / self.numbers as f64)
+    }
+}
+
+/// 比较两个JSON数值的大小
+///
+/// 两个都是整数时按整数精确比较，避免超过2^53的整数经f64转换后相等；其余情况按f64比较
+fn compare_numbers(a: &Number, b: &Number) -> std::cmp::Ordering {
+    let exact = |n: &Number| n.as_i64().map(i128::from).or_else(|| n.as_u64().map(i128::from));
+    match (exact(a), exact(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(std::cmp::Ordering::Equal),
+    }
+}
+
+/// 统计每个字段的出现次数、值类型和数值范围
+///
+/// 嵌套对象逐层展开，只统计叶子字段（数组和空对象作为一个值统计）；
+/// 非对象的数据项没有字段，不参与统计
+///
+/// # 参数
+///
+/// * `data` - 数据项
+///
+/// # 返回值
+///
+/// * `Vec<FieldStats>` - 各字段的统计，按字段首次出现的顺序排列
+pub fn field_stats(data: &[Value]) -> Vec<FieldStats> {
+    let mut stats: Vec<FieldStats> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for fields in data.iter().filter_map(Value::as_object) {
+        let mut pending: Vec<(String, &Value)> = fields.iter().rev().map(|(key, value)| (key.clone(), value)).collect();
+        while let Some((path, value)) = pending.pop() {
+            if let Value::Object(nested) = value
+                && !nested.is_empty()
+            {
+                pending.extend(nested.iter().rev().map(|(key, value)| (format!("{}.{}", path, key), value)));
+                continue;
+            }
+            let position = *positions.entry(path.clone()).or_insert_with(|| {
+                stats.push(FieldStats {
+                    path,
+                    count: 0,
+                    types: Vec::new(),
+                    numbers: 0,
+                    min: None,
+                    max: None,
+                    sum: 0.0,
+                });
+                stats.len() - 1
+            });
+            let field = &mut stats[position];
+            field.count += 1;
+            let type_name = json_type_name(value);
+            if !field.types.contains(&type_name) {
+                field.types.push(type_name);
+            }
+            if let Value::Number(number) = value {
+                field.numbers += 1;
+                field.sum += number.as_f64().unwrap_or(0.0);
+                if field.min.as_ref().is_none_or(|min| compare_numbers(number, min).is_lt()) {
+                    field.min = Some(number.clone());
+                }
+                if field.max.as_ref().is_none_or(|max| compare_numbers(number, max).is_gt()) {
+                    field.max = Some(number.clone());
+                }
+            }
+        }
+    }
+    stats
+}
+
+/// JSON值类型的名称（按当前界面语言），用于错误提示
+pub fn json_type_name(value: &Value) -> String {
+    match value {
+        Value::Null => t!("json.null"),
+        Value::Bool(_) => t!("json.bool"),
+        Value::Number(_) => t!("json.number"),
+        Value::String(_) => t!("json.string"),
+        Value::Array(_) => t!("json.array"),
+        Value::Object(_) => t!("json.object"),
+    }
+}
+
+/// 出错行超过这个字符数时，只显示出错位置前后各一半的内容
+pub const JSON_SNIPPET_WIDTH: usize = 80;
+
+/// 描述JSON语法错误：行列号、出错行及上一行的内容（用 `^` 标出位置），以及常见原因的提示
+///
+/// # 参数
+///
+/// * `content` - 解析的JSON文本
+/// * `error` - `serde_json` 返回的解析错误
+///
+/// # 返回值
+///
+/// * `String` - 多行的错误描述，错误不带位置信息时只返回原始错误
+pub fn describe_json_error(content: &str, error: &serde_json::Error) -> String {
+    let (line, column) = (error.line(), error.column());
+    let raw = error.to_string();
+    let reason = raw.split(" at line ").next().unwrap_or(&raw);
+    if line == 0 {
+        return raw;
+    }
+
+    let lines: Vec<&str> = content.lines().collect();
+    let current = lines.get(line - 1).copied().unwrap_or("");
+    // serde_json 的列号按字节计算且从1开始，换算为字符位置
+    let mut byte = column.saturating_sub(1).min(current.len());
+    while !current.is_char_boundary(byte) {
+        byte -= 1;
+    }
+    let offending = current[byte..].chars().next();
+    let caret_at = current[..byte].chars().count();
+
+    let mut out = t!("json.error_at", line, column, reason);
+    let gutter = line.to_string().len();
+    if line >= 2 && let Some(previous) = lines.get(line - 2) {
+        let (previous, _) = json_snippet(previous, 0);
+        out.push_str(&format!("\n  {:>gutter$} | {}", line - 1, previous));
+    }
+    let (snippet, caret) = json_snippet(current, caret_at);
+    // 中日韩等全角字符在终端中占两列
+    let caret_width: usize = snippet.chars().take(caret).map(|c| if c.is_ascii() { 1 } else { 2 }).sum();
+    out.push_str(&format!("\n  {:>gutter$} | {}", line, snippet));
+    out.push_str(&format!("\n  {:>gutter$} | {}^", "", " ".repeat(caret_width)));
+
+    let hint = if reason.starts_with("trailing comma") {
+        Some("json.hint_trailing_comma")
+    } else if error.classify() == serde_json::error::Category::Eof {
+        Some("json.hint_unclosed")
+    } else if offending == Some('\'') {
+        Some("json.hint_single_quote")
+    } else if offending == Some('/') || offending == Some('#') {
+        Some("json.hint_comment")
+    } else if reason.starts_with("key must be a string") {
+        Some("json.hint_unquoted_key")
+    } else if reason.starts_with("expected `,`") {
+        Some("json.hint_missing_comma")
+    } else {
+        None
+    };
+    if let Some(hint) = hint {
+        out.push('\n');
+        out.push_str(&t!(hint));
+    }
+    out
+}
+
+/// 截取一行中出错位置附近的内容
+///
+/// # 返回值
+///
+/// * `(String, usize)` - 截取后的内容和出错位置在其中的字符下标
+pub fn json_snippet(line: &str, at: usize) -> (String, usize) {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.len() <= JSON_SNIPPET_WIDTH {
+        return (line.to_string(), at);
+    }
+    let start = at.saturating_sub(JSON_SNIPPET_WIDTH / 2);
+    let end = (start + JSON_SNIPPET_WIDTH).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    let mut caret = at - start;
+    if start > 0 {
+        snippet.insert(0, '…');
+        caret += 1;
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    (snippet, caret)
+}
+
+/// 数据文件中引用其它文件的指令名称
+pub const INCLUDE_KEY: &str = "$include";
+
+/// 读取JSON文件并递归展开顶层的 `"$include"` 指令
+///
+/// `"$include"` 可以是单个路径或路径数组，相对路径相对于当前文件所在目录解析。
+/// 合并策略为深合并：对象逐键递归合并，数组和其它值整体覆盖；
+/// 多个被引用的文件按顺序合并，后面的覆盖前面的，当前文件自身的内容覆盖所有被引用的文件
+///
+/// # 参数
+///
+/// * `path` - JSON文件路径
+/// * `stack` - 当前引用链上的文件（规范化路径），用于检测循环引用
+///
+/// # 返回值
+///
+/// * `PushResult<Value>` - 展开并合并后的JSON值
+///
+/// # 错误
+///
+/// 当文件无法读取、为空、JSON格式错误、被引用的文件不是对象或存在循环引用时返回错误
+pub fn load_json_with_includes(path: &Path, stack: &mut Vec<PathBuf>) -> PushResult<Value> {
+    let display = path.display();
+    let read_failed = |source| PushError::DataFileIo { path: display.to_string(), source };
+    let canonical = fs::canonicalize(path).map_err(read_failed)?;
+    if let Some(start) = stack.iter().position(|p| *p == canonical) {
+        let chain: Vec<String> = stack[start..]
+            .iter()
+            .chain(std::iter::once(&canonical))
+            .map(|p| p.display().to_string())
+            .collect();
+        return Err(PushError::DataFileInvalid(t!("data.include_cycle", chain.join(" -> "))));
+    }
+
+    let content = fs::read_to_string(path).map_err(read_failed)?;
+    let content = strip_bom(&content);
+    if content.trim().is_empty() {
+        return Err(PushError::DataFileInvalid(t!("data.empty_file", display, t!("data.format_hint"))));
+    }
+    let mut value: Value = serde_json::from_str(content).map_err(|error| PushError::DataFileParse {
+        path: display.to_string(),
+        line: error.line(),
+        column: error.column(),
+        message: describe_json_error(content, &error),
+    })?;
+
+    let includes = match value.as_object_mut().and_then(|obj| obj.remove(INCLUDE_KEY)) {
+        None => return Ok(value),
+        Some(Value::String(include)) => vec![include],
+        Some(Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(include) => Ok(include),
+                other => Err(PushError::DataFileInvalid(t!(
+                    "data.include_item_type",
+                    display,
+                    INCLUDE_KEY,
+                    json_type_name(&other)
+                ))),
+            })
+            .collect::<PushResult<_>>()?,
+        Some(other) => {
+            return Err(PushError::DataFileInvalid(t!(
+                "data.include_type",
+                display,
+                INCLUDE_KEY,
+                json_type_name(&other)
+            )));
+        }
+    };
+
+    stack.push(canonical);
+    let base_dir = path.parent().unwrap_or(Path::new(""));
+    let mut merged = Value::Object(serde_json::Map::new());
+    for include in includes {
+        let include_path = base_dir.join(&include);
+        // 被引用文件的读取和语法错误中已有该文件的路径，其它错误补充是从哪里引用的
+        let included = load_json_with_includes(&include_path, stack).map_err(|e| match e {
+            PushError::DataFileInvalid(reason) => {
+                PushError::DataFileInvalid(format!("{}: {}", t!("data.include_failed", display, include), reason))
+            }
+            other => other,
+        })?;
+        if !included.is_object() {
+            return Err(PushError::DataFileInvalid(t!(
+                "data.include_not_object",
+                display,
+                include,
+                json_type_name(&included)
+            )));
+        }
+        deep_merge(&mut merged, included);
+    }
+    stack.pop();
+
+    deep_merge(&mut merged, value);
+    Ok(merged)
+}
+
+/// 把 `overlay` 深合并到 `base` 上
+///
+/// 两边都是对象时逐键递归合并，否则 `overlay` 整体覆盖 `base`（数组不做拼接）
+pub fn deep_merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// 从文件加载JSON数据
+///
+/// 读取指定路径的JSON文件并解析为DataFileResult结构体
+/// 支持两种格式：
+/// 1. 直接数组格式: [{"sensor1": {...}}, {"sensor2": {...}}]
+/// 2. 包装对象格式: {"random_key": "...", "data": [{"sensor1": {...}}, {"sensor2": {...}}]}
+///
+/// 包装对象可以用 `"$include"` 引用其它文件（如公共的 random_config），
+/// 展开规则见 [`load_json_with_includes`]
+///
+/// # 参数
+///
+/// * `file_path` - JSON数据文件的路径
+///
+/// # 返回值
+///
+/// * `PushResult<DataFileResult>` - 成功时返回包含随机键和数据数组的结果，失败时返回错误信息
+///
+/// # 错误
+///
+/// 文件不存在或无法读取时返回 [`PushError::DataFileIo`]，JSON格式错误时返回带行列号的
+/// [`PushError::DataFileParse`]；文件为空、数据数组为空、没有任何JSON对象数据项或字段配置无效时返回
+/// [`PushError::DataFileInvalid`]，错误信息中包含文件路径和检测到的格式
+pub fn load_data_file(file_path: &str) -> PushResult<DataFileResult> {
+    // 首先解析为通用Value，并展开 "$include" 引用的文件
+    let json_value = load_json_with_includes(Path::new(file_path), &mut Vec::new())?;
+
+    // 根据JSON结构判断格式并提取数据数组和随机键
+    let format_name;
+    let result = match json_value {
+        // 格式1: 直接数组 [{"sensor1": {...}}, {"sensor2": {...}}]
+        Value::Array(arr) => {
+            format_name = t!("data.format_array");
+            info!("{}", t!("data.detected", format_name));
+            // 数组格式没有放置 random_key 的位置，误写成数据项的字段时会被当作普通遥测发送
+            for (index, item) in arr.iter().enumerate() {
+                if item.get("random_key").is_some() {
+                    warn!("{}", t!("data.array_random_key", file_path, index + 1));
+                }
+            }
+            DataFileResult {
+                format: DataFormat::Array,
+                random_key: None,
+                random_config: BTreeMap::new(),
+                attribute_fields: Vec::new(),
+                conditions: BTreeMap::new(),
+                valid_range: BTreeMap::new(),
+                field_groups: BTreeMap::new(),
+                data: arr,
+            }
+        }
+        // 格式2: 包装对象 {"random_key": "...", "data": [...]}
+        Value::Object(obj) => {
+            format_name = t!("data.format_object");
+            info!("{}", t!("data.detected", format_name));
+
+            // 提取数据数组
+            let data = match obj.get("data") {
+                Some(Value::Array(data_array)) if data_array.is_empty() => {
+                    return Err(PushError::DataFileInvalid(t!(
+                        "data.data_field_empty",
+                        file_path,
+                        format_name,
+                        t!("data.format_hint")
+                    )));
+                }
+                Some(Value::Array(data_array)) => data_array.clone(),
+                _ => {
+                    return Err(PushError::DataFileInvalid(t!(
+                        "data.data_field_missing",
+                        file_path,
+                        format_name,
+                        t!("data.format_hint")
+                    )));
+                }
+            };
+            // 字段配置的格式错误在说明后附上serde的具体原因
+            let invalid = |key: &'static str| {
+                move |e: serde_json::Error| PushError::DataFileInvalid(format!("{}: {}", t!(key, file_path), e))
+            };
+
+            // 查找随机键的值（"random_key" 字段的值，这个值指示要随机修改哪个字段）
+            let random_key = obj.get("random_key")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string());
+
+            // 字段生成配置（"random_config" 字段，键为配置名称，值中的 mode 指定生成方式）
+            let random_config = match obj.get("random_config") {
+                Some(config) => serde_json::from_value(config.clone()).map_err(invalid("data.random_config_invalid"))?,
+                None => BTreeMap::new(),
+            };
+
+            // 属性字段列表（"attribute_fields" 字段，这些字段发往属性端点而不是遥测端点）
+            let attribute_fields = match obj.get("attribute_fields") {
+                Some(fields) => {
+                    serde_json::from_value(fields.clone()).map_err(invalid("data.attribute_fields_invalid"))?
+                }
+                None => Vec::new(),
+            };
+
+            // 字段上报条件（"conditions" 字段，键为字段名，值为条件表达式，启动时解析以便尽早发现语法错误）
+            let conditions = match obj.get("conditions") {
+                Some(Value::Object(conditions)) => conditions
+                    .iter()
+                    .map(|(field, expr)| {
+                        let condition = expr
+                            .as_str()
+                            .ok_or_else(|| t!("data.condition_not_string"))
+                            .and_then(|expr| parse_condition(expr).map_err(|e| e.to_string()))
+                            .map_err(|reason| {
+                                PushError::DataFileInvalid(format!(
+                                    "{}: {}",
+                                    t!("data.condition_invalid", file_path, field),
+                                    reason
+                                ))
+                            })?;
+                        Ok((field.clone(), condition))
+                    })
+                    .collect::<PushResult<_>>()?,
+                Some(_) => return Err(PushError::DataFileInvalid(t!("data.conditions_invalid", file_path))),
+                None => BTreeMap::new(),
+            };
+
+            // 字段合理范围（"valid_range" 字段，键为字段路径，值中的 min/max 为边界，clamp 为是否截断）
+            let valid_range: BTreeMap<String, ValidRange> = match obj.get("valid_range") {
+                Some(ranges) => serde_json::from_value(ranges.clone()).map_err(invalid("data.valid_range_format"))?,
+                None => BTreeMap::new(),
+            };
+            for (field, range) in &valid_range {
+                let ordered = match (range.min, range.max) {
+                    (None, None) => false,
+                    (Some(min), Some(max)) => min <= max,
+                    _ => true,
+                };
+                if !ordered {
+                    return Err(PushError::DataFileInvalid(t!("data.valid_range_invalid", file_path, field)));
+                }
+            }
+
+            // 字段分组（"field_groups" 字段，键为组名，值中的 interval 为该组字段的发送间隔）
+            let field_groups: BTreeMap<String, FieldGroup> = match obj.get("field_groups") {
+                Some(groups) => serde_json::from_value(groups.clone()).map_err(invalid("data.field_groups_invalid"))?,
+                None => BTreeMap::new(),
+            };
+            let mut grouped = HashMap::new();
+            for (name, group) in &field_groups {
+                if !(group.interval > 0.0 && group.interval.is_finite()) {
+                    return Err(PushError::DataFileInvalid(t!("data.field_group_interval", file_path, name)));
+                }
+                for field in &group.fields {
+                    if let Some(other) = grouped.insert(field.as_str(), name.as_str()) {
+                        return Err(PushError::DataFileInvalid(t!(
+                            "data.field_group_duplicate",
+                            file_path,
+                            field,
+                            other,
+                            name
+                        )));
+                    }
+                }
+            }
+
+            DataFileResult {
+                format: DataFormat::Object,
+                random_key,
+                random_config,
+                attribute_fields,
+                conditions,
+                valid_range,
+                field_groups,
+                data,
+            }
+        }
+        other => {
+            return Err(PushError::DataFileInvalid(t!(
+                "data.unsupported",
+                file_path,
+                json_type_name(&other),
+                t!("data.format_hint")
+            )));
+        }
+    };
+
+    // 验证数据是否为空，区分数组本身为空和没有可用的数据项
+    if result.data.is_empty() {
+        return Err(PushError::DataFileInvalid(t!(
+            "data.array_empty",
+            file_path,
+            format_name,
+            t!("data.format_hint")
+        )));
+    }
+    if !result.data.iter().any(Value::is_object) {
+        return Err(PushError::DataFileInvalid(t!(
+            "data.no_objects",
+            file_path,
+            result.data.len(),
+            format_name,
+            t!("data.format_hint")
+        )));
+    }
+
+    Ok(result)
+}
+
+/// 去掉文本开头的UTF-8 BOM
+///
+/// Windows工具导出的文件常以BOM开头，`serde_json` 会把它当作非法字符，报 "expected value at line 1 column 1"
+pub fn strip_bom(content: &str) -> &str {
+    content.strip_prefix('\u{FEFF}').unwrap_or(content)
+}
+
+/// 加载数据：指定了 `--dir` 时读取整个目录，多次指定 `--file` 时按权重交织，否则读取单个数据文件
+pub fn load_data_source(data_files: &[String], data_dir: Option<&str>) -> PushResult<DataFileResult> {
+    match (data_dir, data_files) {
+        (Some(dir), _) => load_data_dir(Path::new(dir)),
+        (None, [single]) => load_data_file(parse_weighted_file(single)?.0),
+        (None, files) => load_weighted_files(files),
+    }
+}
+
+/// 解析 `--file` 的 `路径:权重` 写法，没有权重时为1
+///
+/// 只有最后一个冒号之后全是数字时才视为权重，Windows盘符（如 `C:\data.json`）不受影响
+///
+/// # 错误
+///
+/// 权重为0或超出范围时返回错误
+pub fn parse_weighted_file(spec: &str) -> PushResult<(&str, u32)> {
+    match spec.rsplit_once(':') {
+        Some((path, weight))
+            if !path.is_empty() && !weight.is_empty() && weight.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            let weight = weight.parse().ok().filter(|&weight| weight > 0);
+            Ok((path, weight.ok_or_else(|| PushError::ConfigInvalid(t!("data.weight_invalid", spec)))?))
+        }
+        _ => Ok((spec, 1)),
+    }
+}
+
+/// 按权重交织多个数据文件
+///
+/// 各文件的配置按命令行顺序合并（规则同 `--dir`），数据项按平滑加权轮询排成一轮的发送顺序，见 [`weighted_interleave`]
+///
+/// # 参数
+///
+/// * `specs` - `--file` 的各个值，`路径` 或 `路径:权重`
+///
+/// # 错误
+///
+/// 权重无效或任一文件无法加载时返回错误
+pub fn load_weighted_files(specs: &[String]) -> PushResult<DataFileResult> {
+    let mut merged = DataFileResult::empty();
+    let mut sources = Vec::with_capacity(specs.len());
+    let mut described = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let (path, weight) = parse_weighted_file(spec)?;
+        let mut file = load_data_file(path)?;
+        let records = std::mem::take(&mut file.data);
+        described.push(t!("data.weighted_file", path, weight, records.len()));
+        merged.merge(file);
+        sources.push((weight, records));
+    }
+    merged.data = weighted_interleave(&sources);
+    info!("{}", t!("data.weighted_loaded", described.join(", "), merged.data.len()));
+    Ok(merged)
+}
+
+/// 按平滑加权轮询（与nginx的upstream算法相同）把多个数据源的记录排成一轮的发送顺序
+///
+/// 每一步各数据源的当前值加上自己的权重，取当前值最大的数据源（相同时取靠前的），再把它的当前值减去总权重。
+/// 每个周期（总权重步）中各数据源恰好被选中权重次，且尽量均匀地分散，如权重 5:1 得到 `AAABAA`。
+/// 一轮包含足够多的周期，使每个数据源的记录都至少发送一遍，记录不足的数据源从头循环
+///
+/// # 参数
+///
+/// * `sources` - 各数据源的权重和记录
+pub fn weighted_interleave(sources: &[(u32, Vec<Value>)]) -> Vec<Value> {
+    let sources: Vec<_> = sources.iter().filter(|(_, records)| !records.is_empty()).collect();
+    let total_weight: i64 = sources.iter().map(|(weight, _)| i64::from(*weight)).sum();
+    let cycles = sources.iter().map(|(weight, records)| records.len().div_ceil(*weight as usize)).max().unwrap_or(0);
+    let mut current = vec![0i64; sources.len()];
+    let mut cursors = vec![0usize; sources.len()];
+    let mut interleaved = Vec::with_capacity(cycles * total_weight as usize);
+    for _ in 0..cycles * total_weight as usize {
+        for (value, (weight, _)) in current.iter_mut().zip(&sources) {
+            *value += i64::from(*weight);
+        }
+        let mut pick = 0;
+        for (index, value) in current.iter().enumerate() {
+            if *value > current[pick] {
+                pick = index;
+            }
+        }
+        current[pick] -= total_weight;
+        let records = &sources[pick].1;
+        interleaved.push(records[cursors[pick] % records.len()].clone());
+        cursors[pick] += 1;
+    }
+    interleaved
+}
+
+/// 数据目录中支持的文件扩展名（不区分大小写）
+pub const DATA_DIR_EXTENSIONS: &[&str] = &["json", "jsonl", "csv"];
+
+/// 读取数据目录，把其中的数据文件按文件名顺序合并为一个数据流
+///
+/// 每个文件按扩展名解析：`.json` 与 `--file` 相同（两种格式都支持），`.jsonl` 每行一个JSON对象，
+/// `.csv` 首行为表头、每行一条记录。包装对象格式文件中的 random_key 取第一个设置的值，
+/// 其余配置按文件名顺序合并，后面的文件覆盖同名项
+///
+/// # 参数
+///
+/// * `dir` - 数据目录路径
+///
+/// # 返回值
+///
+/// * `PushResult<DataFileResult>` - 合并后的数据，任一文件为包装对象格式时格式记为包装对象
+///
+/// # 错误
+///
+/// 目录无法读取，或者没有任何可用的数据文件时返回错误；单个文件解析失败时只输出警告并跳过
+pub fn load_data_dir(dir: &Path) -> PushResult<DataFileResult> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|source| PushError::Io { message: t!("data.dir_read_failed", dir.display()), source })?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| {
+                    DATA_DIR_EXTENSIONS.iter().any(|supported| ext.eq_ignore_ascii_case(supported))
+                })
+        })
+        .collect();
+    files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+
+    let mut merged = DataFileResult::empty();
+    let mut loaded = 0;
+    for path in &files {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let extension = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default().to_ascii_lowercase();
+        let parsed = match extension.as_str() {
+            "jsonl" => read_jsonl_records(path),
+            "csv" => read_csv_records(path),
+            _ => path
+                .to_str()
+                .ok_or_else(|| PushError::DataFileInvalid(t!("data.path_not_utf8", path.display())))
+                .and_then(load_data_file),
+        };
+        let file = match parsed {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("{}", t!("data.dir_file_skipped", name, e));
+                continue;
+            }
+        };
+        info!("{}", t!("data.dir_file_loaded", name, file.data.len()));
+        loaded += 1;
+        merged.merge(file);
+    }
+
+    if merged.data.is_empty() {
+        return Err(PushError::DataFileInvalid(t!("data.dir_empty", dir.display(), DATA_DIR_EXTENSIONS.join(", "))));
+    }
+    info!("{}", t!("data.dir_loaded", dir.display(), loaded, files.len()));
+    Ok(merged)
+}
+
+/// 读取JSON Lines文件，每个非空行是一条JSON对象记录
+///
+/// # 错误
+///
+/// 文件无法读取、任一行不是JSON对象或没有任何记录时返回错误，错误信息中包含行号
+pub fn read_jsonl_records(path: &Path) -> PushResult<DataFileResult> {
+    let content = fs::read_to_string(path)
+        .map_err(|source| PushError::DataFileIo { path: path.display().to_string(), source })?;
+    let mut data = Vec::new();
+    for (index, line) in strip_bom(&content).lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Value = serde_json::from_str(line).map_err(|e| PushError::DataFileParse {
+            path: path.display().to_string(),
+            line: index + 1,
+            column: e.column(),
+            message: format!("{}: {}", t!("data.jsonl_line_invalid", index + 1), e),
+        })?;
+        if !record.is_object() {
+            return Err(PushError::DataFileInvalid(t!("data.jsonl_line_invalid", index + 1)));
+        }
+        data.push(record);
+    }
+    records_only(data)
+}
+
+/// 读取CSV文件，首行为表头，每行转换为以表头为键的JSON对象
+///
+/// 能解析为数字或布尔值的单元格按对应类型发送，其余按字符串；单元格去掉首尾空白，空单元格省略该字段
+///
+/// # 错误
+///
+/// 文件无法读取、CSV格式错误或没有任何记录时返回错误
+pub fn read_csv_records(path: &Path) -> PushResult<DataFileResult> {
+    let content = fs::read_to_string(path)
+        .map_err(|source| PushError::DataFileIo { path: path.display().to_string(), source })?;
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(strip_bom(&content).as_bytes());
+    // CSV错误只有行号，列号记为0
+    let parse_failed = |e: csv::Error| PushError::DataFileParse {
+        path: path.display().to_string(),
+        line: e.position().map_or(0, |position| position.line() as usize),
+        column: 0,
+        message: e.to_string(),
+    };
+    let headers = reader.headers().map_err(parse_failed)?.clone();
+    let mut data = Vec::new();
+    for row in reader.records() {
+        let row = row.map_err(parse_failed)?;
+        let record: serde_json::Map<String, Value> = headers
+            .iter()
+            .zip(row.iter())
+            .filter(|(_, cell)| !cell.is_empty())
+            .map(|(header, cell)| {
+                let value = match serde_json::from_str::<Value>(cell) {
+                    Ok(value @ (Value::Number(_) | Value::Bool(_))) => value,
+                    _ => Value::String(cell.to_string()),
+                };
+                (header.to_string(), value)
+            })
+            .collect();
+        data.push(Value::Object(record));
+    }
+    records_only(data)
+}
+
+/// 把只有数据记录的文件（JSON Lines、CSV）包装为数组格式的解析结果
+pub fn records_only(data: Vec<Value>) -> PushResult<DataFileResult> {
+    if data.is_empty() {
+        return Err(PushError::DataFileInvalid(t!("data.no_records")));
+    }
+    Ok(DataFileResult {
+        format: DataFormat::Array,
+        random_key: None,
+        random_config: BTreeMap::new(),
+        attribute_fields: Vec::new(),
+        conditions: BTreeMap::new(),
+        valid_range: BTreeMap::new(),
+        field_groups: BTreeMap::new(),
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use crate::random::generate_random_value;
+    use crate::telemetry::{FieldRules, NonFinitePolicy};
+
+    #[test]
+    fn field_stats_count_fields_and_summarize_numbers() {
+        let data: Vec<Value> = serde_json::from_str(
+            r#"[
+                {"rain": {"drp": 1.5, "unit": "mm"}, "status": "ok"},
+                {"rain": {"drp": 0.5}, "status": 3, "tags": []},
+                {"rain": {"drp": 4}, "empty": {}},
+                "not an object"
+            ]"#,
+        )
+        .unwrap();
+        let stats = field_stats(&data);
+        let paths: Vec<&str> = stats.iter().map(|field| field.path.as_str()).collect();
+        assert_eq!(paths, ["rain.drp", "rain.unit", "status", "tags", "empty"]);
+
+        let range = |field: &FieldStats| {
+            (field.min.as_ref().map(Number::to_string), field.max.as_ref().map(Number::to_string))
+        };
+        let drp = &stats[0];
+        assert_eq!((drp.count, drp.numbers), (3, 3));
+        assert_eq!(range(drp), (Some("0.5".to_string()), Some("4".to_string())));
+        assert_eq!(drp.avg(), Some(2.0));
+        assert_eq!(stats[1].count, 1);
+        assert_eq!(stats[1].avg(), None);
+        assert_eq!(range(&stats[1]), (None, None));
+        // 同一字段出现过的类型都会列出，数值统计只包含数值
+        assert_eq!(stats[2].types.len(), 2);
+        assert_eq!(stats[2].numbers, 1);
+        assert_eq!(range(&stats[2]), (Some("3".to_string()), Some("3".to_string())));
+        assert_eq!(stats[3].types, [json_type_name(&Value::Array(Vec::new()))]);
+
+        // 超过f64精度的整数保持原值，f64下相等的两个整数也能分出大小
+        let data: Vec<Value> =
+            serde_json::from_str(r#"[{"id": 9007199254740993}, {"id": 9007199254740992}, {"id": -1.5}]"#).unwrap();
+        let stats = field_stats(&data);
+        assert_eq!(range(&stats[0]), (Some("-1.5".to_string()), Some("9007199254740993".to_string())));
+    }
+
+    #[test]
+    fn random_key_works_with_both_data_formats() {
+        let load = |name: &str, content: &str, flag: Option<&str>| {
+            let path = env::temp_dir().join(name);
+            fs::write(&path, content).unwrap();
+            let mut data = load_data_file(path.to_str().unwrap()).unwrap();
+            fs::remove_file(&path).ok();
+            data.override_random_key(flag.map(str::to_string));
+            (data.format, data.random_key.clone(), data.random_targets())
+        };
+        let array = r#"[{"rain": {"drp": 1, "unit": "mm"}}, {"wind": {"speed": 3}}]"#;
+        let object = r#"{"random_key": "drp", "data": [{"rain": {"drp": 1}}, {"wind": {"drp": 2, "speed": 3}}]}"#;
+
+        assert_eq!(load("push_tb_array_plain.json", array, None), (DataFormat::Array, None, vec![]));
+        assert_eq!(
+            load("push_tb_array_flag.json", array, Some("drp")),
+            (DataFormat::Array, Some("drp".to_string()), vec!["rain.drp".to_string()])
+        );
+        assert_eq!(
+            load("push_tb_object_plain.json", object, None),
+            (DataFormat::Object, Some("drp".to_string()), vec!["rain.drp".to_string(), "wind.drp".to_string()])
+        );
+        assert_eq!(
+            load("push_tb_object_flag.json", object, Some("speed")),
+            (DataFormat::Object, Some("speed".to_string()), vec!["wind.speed".to_string()])
+        );
+        // 随机键不匹配任何嵌套字段时没有作用目标
+        assert!(load("push_tb_array_miss.json", array, Some("missing")).2.is_empty());
+    }
+
+    #[test]
+    fn large_numbers_round_trip_byte_for_byte() {
+        let inputs = ["9007199254740993", "18446744073709551615", "-9223372036854775808", "1.2e18", "123456789012345678901234567890"];
+        for input in inputs {
+            let number: Value = serde_json::from_str(input).unwrap();
+            let kept = generate_random_value("id", &number, NonFinitePolicy::Error, &mut rand::thread_rng()).unwrap();
+            assert_eq!(serde_json::to_string(&kept.unwrap()).unwrap(), input);
+        }
+
+        // 随机键命中的大数保持原值，其余字段原样通过
+        let record: Value =
+            serde_json::from_str(r#"{"meter": {"id": 123456789012345678901234567890, "v": 1.2e18}, "serial": 9007199254740993}"#)
+                .unwrap();
+        let rng = &mut rand::thread_rng();
+        for random_key in [Some("id".to_string()), Some("v".to_string()), None] {
+            let values = extract_telemetry_values(&record, &random_key, &FieldRules::default(), rng).unwrap();
+            let meter = serde_json::to_string(&values["meter"]).unwrap();
+            assert!(meter.contains("123456789012345678901234567890") && meter.contains("1.2e18"), "{}", meter);
+            assert_eq!(serde_json::to_string(&values["serial"]).unwrap(), "9007199254740993");
+        }
+    }
+
+    #[test]
+    fn data_dir_merges_formats_in_file_name_order() {
+        let dir = env::temp_dir().join(format!("push_tb_data_dir_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let files = [
+            ("b.csv", "device,temp,online,code\nd1,21.5,true,007\nd2,,false,8\n"),
+            ("a.json", r#"{"random_key": "drp", "data": [{"rain": {"drp": 1}}]}"#),
+            ("c.JSONL", "{\"seq\": 1}\n\n{\"seq\": 2}\n"),
+            ("d.jsonl", "{\"seq\": 3}\nnot json\n"),
+            ("e.txt", "ignored"),
+        ];
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+        }
+        let data = load_data_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!((data.format, data.random_key.as_deref()), (DataFormat::Object, Some("drp")));
+        assert_eq!(
+            data.data,
+            vec![
+                serde_json::json!({"rain": {"drp": 1}}),
+                serde_json::json!({"device": "d1", "temp": 21.5, "online": true, "code": "007"}),
+                serde_json::json!({"device": "d2", "online": false, "code": 8}),
+                serde_json::json!({"seq": 1}),
+                serde_json::json!({"seq": 2}),
+            ]
+        );
+        assert!(load_data_dir(&env::temp_dir().join("push_tb_missing_dir")).is_err());
+    }
+
+    #[test]
+    fn validate_lists_every_problem_with_location() {
+        let mut data = records_only(vec![
+            serde_json::json!({"rain": {"drp": 1, "gauge": {}}, "note": null}),
+            serde_json::json!(42),
+            serde_json::json!({}),
+            serde_json::json!({"rain": {"drp": 2}, "readings": [1, 2], "extra": 1}),
+        ])
+        .unwrap();
+        data.random_key = Some("speed".to_string());
+        data.valid_range.insert("rain.drp".to_string(), ValidRange { min: Some(0.0), max: None, clamp: false });
+        data.valid_range.insert("wind.speed".to_string(), ValidRange { min: Some(0.0), max: None, clamp: false });
+
+        let allowed = HashSet::from(["rain".to_string(), "note".to_string(), "readings".to_string()]);
+        let issues = validate_data(&data, Some(&allowed));
+        let found: Vec<_> = issues.iter().map(|issue| (issue.level, issue.index, issue.path.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (IssueLevel::Error, None, "random_key"),
+                (IssueLevel::Error, None, "valid_range.wind.speed"),
+                (IssueLevel::Warning, Some(0), "note"),
+                (IssueLevel::Warning, Some(0), "rain.gauge"),
+                (IssueLevel::Error, Some(1), ""),
+                (IssueLevel::Error, Some(2), ""),
+                (IssueLevel::Error, Some(3), "extra"),
+                (IssueLevel::Warning, Some(3), "readings"),
+            ]
+        );
+        assert!(validate_data(&records_only(vec![serde_json::json!({"a": 1})]).unwrap(), None).is_empty());
+    }
+
+    #[test]
+    fn validate_renders_geo_field_warning() {
+        let mut data = records_only(vec![serde_json::json!({"rain": {"drp": 1}})]).unwrap();
+        let generator = serde_json::json!({"mode": "geo", "start": [30.0, 120.0], "speed": 5.0});
+        data.random_config.insert("track".to_string(), serde_json::from_value(generator).unwrap());
+
+        let issues = validate_data(&data, None);
+        assert_eq!(issues.len(), 1);
+        let rendered = issues[0].to_string();
+        assert!(rendered.contains("'lat'，轨迹坐标 lat/lng 将新增在顶层"), "{}", rendered);
+        assert!(!rendered.contains("{}"), "{}", rendered);
+    }
+
+    #[test]
+    fn data_files_tolerate_bom_and_crlf() {
+        let fixture = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/data_bom.json");
+        assert!(fs::read(fixture).unwrap().starts_with(&[0xEF, 0xBB, 0xBF]));
+        let data = load_data_file(fixture).unwrap();
+        assert_eq!(data.random_key.as_deref(), Some("drp"));
+        assert_eq!(data.data, vec![serde_json::json!({"rain": {"drp": 1.5, "unit": "mm"}})]);
+
+        let dir = env::temp_dir().join(format!("push_tb_bom_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.csv"), "\u{FEFF}device, temp \r\nd1, 21.5 \r\n").unwrap();
+        fs::write(dir.join("b.jsonl"), "\u{FEFF}{\"seq\": 1}  \r\n\r\n{\"seq\": 2}\r\n").unwrap();
+        let merged = load_data_dir(&dir).unwrap();
+        fs::remove_dir_all(&dir).ok();
+        assert_eq!(
+            merged.data,
+            vec![serde_json::json!({"device": "d1", "temp": 21.5}), serde_json::json!({"seq": 1}), serde_json::json!({"seq": 2})]
+        );
+
+        // 真正的格式错误仍按原样报告
+        let path = env::temp_dir().join("push_tb_bom_invalid.json");
+        fs::write(&path, "\u{FEFF}[{\"a\": }]").unwrap();
+        let error = format!("{:#}", load_data_file(path.to_str().unwrap()).unwrap_err());
+        fs::remove_file(&path).ok();
+        assert!(error.contains(&t!("json.error_at", 1, 8, "expected value")), "{}", error);
+    }
+
+    #[test]
+    fn unusable_items_are_skipped_or_rejected_when_strict() {
+        let fixture = "tests/fixtures/data_mixed.json";
+
+        let mut data = load_data_file(fixture).unwrap();
+        data.drop_unusable_items(fixture, false).unwrap();
+        let temperatures: Vec<&Value> = data.data.iter().map(|item| &item["sensor"]["temperature"]).collect();
+        assert_eq!(temperatures, [21.5, 22.0]);
+
+        let mut data = load_data_file(fixture).unwrap();
+        let error = data.drop_unusable_items(fixture, true).unwrap_err().to_string();
+        assert!(error.contains(fixture) && error.contains("2, 3"), "{}", error);
+
+        data.data = vec![Value::Object(Default::default()); 2];
+        assert!(data.drop_unusable_items(fixture, false).is_err());
+    }
+
+    #[test]
+    fn json_errors_point_at_line_and_column_with_hint() {
+        let describe = |content: &str| describe_json_error(content, &serde_json::from_str::<Value>(content).unwrap_err());
+
+        let trailing = describe("[\n  {\"a\": 1},\n  {\"b\": 2},\n]");
+        assert!(trailing.starts_with(&t!("json.error_at", 4, 1, "trailing comma")), "{}", trailing);
+        assert!(trailing.contains("  3 |   {\"b\": 2},\n  4 | ]\n    | ^"), "{}", trailing);
+        assert!(trailing.ends_with(&t!("json.hint_trailing_comma")));
+
+        let missing = describe("{\"a\": 1\n \"b\": 2}");
+        assert!(missing.contains("  2 |  \"b\": 2}\n    |  ^"), "{}", missing);
+        assert!(missing.ends_with(&t!("json.hint_missing_comma")));
+
+        assert!(describe("{'a': 1}").ends_with(&t!("json.hint_single_quote")));
+        assert!(describe("{a: 1}").ends_with(&t!("json.hint_unquoted_key")));
+        assert!(describe("[{\"a\": 1}").ends_with(&t!("json.hint_unclosed")));
+
+        let long = format!("[{}, oops]", "1, ".repeat(100));
+        let snippet = describe(&long);
+        assert!(snippet.contains("…") && snippet.contains("oops"), "{}", snippet);
+    }
+
+    #[test]
+    fn preflight_reports_every_unsendable_item() {
+        let data = vec![
+            serde_json::json!({"sensor": {"temperature": 21.5}}),
+            serde_json::json!({}),
+            serde_json::json!(42),
+            serde_json::json!({"old": 1, "new": 2}),
+            serde_json::json!({"ts": "yesterday", "sensor": {"temperature": 22.0}}),
+        ];
+        let options = SendOptions {
+            field_rules: FieldRules {
+                rename: HashMap::from([("old".to_string(), "new".to_string())]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let invalid = preflight_items(&data, &Some("temperature".to_string()), &options, false);
+        let indexes: Vec<usize> = invalid.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indexes, [1, 2, 3]);
+        assert_eq!(invalid[0].1, t!("data.item_unusable"));
+        assert_eq!(invalid[2].1, t!("send.rename_conflict", "old", "new"));
+
+        // 合并时序模式下额外检查记录自带的 ts
+        let invalid = preflight_items(&data, &None, &options, true);
+        assert_eq!(invalid.last().unwrap().0, 4);
+
+        // 提取结果为空时按 --on-empty 策略判断
+        let data = vec![serde_json::json!({"sensor": {"temperature": 21.5}})];
+        let conditions = BTreeMap::from([("sensor".to_string(), parse_condition("sensor.temperature > 100").unwrap())]);
+        let mut options = SendOptions {
+            field_rules: FieldRules { conditions, ..Default::default() },
+            ..Default::default()
+        };
+        assert_eq!(preflight_items(&data, &None, &options, false).len(), 1);
+        options.on_empty = EmptyPolicy::Skip;
+        assert!(preflight_items(&data, &None, &options, false).is_empty());
+    }
+
+    #[test]
+    fn weighted_files_parse_and_interleave_smoothly() {
+        assert_eq!(parse_weighted_file("a.json:3").unwrap(), ("a.json", 3));
+        assert_eq!(parse_weighted_file("a.json").unwrap(), ("a.json", 1));
+        assert_eq!(parse_weighted_file("C:\\data\\a.json").unwrap(), ("C:\\data\\a.json", 1));
+        assert_eq!(parse_weighted_file("dir:x/a.json").unwrap(), ("dir:x/a.json", 1));
+        assert!(parse_weighted_file("a.json:0").is_err());
+
+        let records = |name: &str, len: usize| (0..len).map(|i| Value::from(format!("{name}{i}"))).collect::<Vec<_>>();
+        let order = |sources: &[(u32, Vec<Value>)]| {
+            weighted_interleave(sources).iter().map(|v| v.as_str().unwrap().to_string()).collect::<Vec<_>>()
+        };
+        // 5:1 分散成 AAABAA，而不是 AAAAAB
+        assert_eq!(order(&[(5, records("a", 5)), (1, records("b", 1))]), ["a0", "a1", "a2", "b0", "a3", "a4"]);
+        // B 的记录更多时一轮延长到 B 全部发送一遍，A 从头循环
+        assert_eq!(
+            order(&[(2, records("a", 2)), (1, records("b", 2))]),
+            ["a0", "b0", "a1", "a0", "b1", "a1"]
+        );
+        // 空文件不参与轮询
+        assert_eq!(order(&[(3, Vec::new()), (1, records("b", 2))]), ["b0", "b1"]);
+    }
+
+    #[test]
+    fn data_file_errors_have_specific_variants() {
+        let load = |name: &str, content: &str| {
+            let path = env::temp_dir().join(name);
+            fs::write(&path, content).unwrap();
+            let result = load_data_file(path.to_str().unwrap());
+            fs::remove_file(&path).ok();
+            result
+        };
+
+        let missing = load_data_file("/nonexistent/push_tb_data.json").unwrap_err();
+        assert!(matches!(missing, PushError::DataFileIo { ref path, .. } if path == "/nonexistent/push_tb_data.json"));
+
+        let broken = load("push_tb_broken.json", "[\n  {\"a\": 1,}\n]").unwrap_err();
+        assert!(matches!(broken, PushError::DataFileParse { line: 2, column: 11, .. }), "{:?}", broken);
+
+        let jsonl = load("push_tb_broken.jsonl", "{\"a\": 1}\n{\"a\": }\n").unwrap_err();
+        assert!(matches!(jsonl, PushError::DataFileParse { line: 2, .. }), "{:?}", jsonl);
+
+        assert!(matches!(load("push_tb_empty.json", "[]"), Err(PushError::DataFileInvalid(_))));
+        assert!(matches!(load("push_tb_blank.json", "  \n"), Err(PushError::DataFileInvalid(_))));
+        assert!(matches!(load("push_tb_scalars.json", "[1, 2]"), Err(PushError::DataFileInvalid(_))));
+    }
+
+    #[test]
+    fn includes_are_deep_merged_and_cycles_rejected() {
+        let dir = env::temp_dir().join(format!("push_tb_include_{}", std::process::id()));
+        fs::create_dir_all(dir.join("common")).unwrap();
+        let files = [
+            ("common/random.json", r#"{"random_key": "drp", "random_config": {"drp": {"min": 0, "max": 5}, "unit": "mm"}}"#),
+            ("common/override.json", r#"{"random_config": {"drp": {"max": 9}}}"#),
+            (
+                "main.json",
+                r#"{"$include": ["common/random.json", "common/override.json"], "random_key": "rain", "data": [{"rain": 1}]}"#,
+            ),
+            ("loop_a.json", r#"{"$include": "loop_b.json"}"#),
+            ("loop_b.json", r#"{"$include": "loop_a.json"}"#),
+        ];
+        for (name, content) in files {
+            fs::write(dir.join(name), content).unwrap();
+        }
+
+        // 后引用的文件覆盖先引用的，当前文件覆盖所有被引用的文件；对象逐键合并
+        let merged = load_json_with_includes(&dir.join("main.json"), &mut Vec::new()).unwrap();
+        assert_eq!(
+            merged,
+            serde_json::json!({
+                "random_key": "rain",
+                "random_config": {"drp": {"min": 0, "max": 9}, "unit": "mm"},
+                "data": [{"rain": 1}]
+            })
+        );
+        let cycle = load_json_with_includes(&dir.join("loop_a.json"), &mut Vec::new()).unwrap_err();
+        fs::remove_dir_all(&dir).ok();
+        assert!(matches!(&cycle, PushError::DataFileInvalid(message) if message.contains("loop_b.json")));
+
+        // 数组和其它值整体覆盖，不做拼接
+        let mut base = serde_json::json!({"list": [1, 2], "value": {"a": 1}});
+        deep_merge(&mut base, serde_json::json!({"list": [3], "value": 7}));
+        assert_eq!(base, serde_json::json!({"list": [3], "value": 7}));
+    }
+}
@@ -0,0 +1,141 @@
+//! 错误类型：库函数返回按种类区分的 [`PushError`]，重试、退出码和认证中止等逻辑按变体判断，
+//! 不再匹配错误信息的文字
+
+use crate::sender::HttpStatusError;
+
+/// 库函数的返回类型
+pub type PushResult<T> = std::result::Result<T, PushError>;
+
+/// 加载配置和数据、生成负载以及发送过程中的错误
+///
+/// 显示的文字与界面语言一致，保持原来的提示内容；底层的IO和网络错误作为 `source` 保留
+#[derive(Debug, thiserror::Error)]
+pub enum PushError {
+    /// 缺少必需的配置（服务器地址、设备令牌）
+    #[error("{0}")]
+    ConfigMissing(String),
+    /// 配置、命令行参数或配置文件的内容无效
+    #[error("{0}")]
+    ConfigInvalid(String),
+    /// 读写数据文件以外的文件失败（配置文件、令牌池、状态文件、审计和报告文件等）
+    #[error("{message}")]
+    Io {
+        /// 说明是哪个文件、在做什么
+        message: String,
+        /// 底层的IO错误
+        #[source]
+        source: std::io::Error,
+    },
+    /// 数据文件（或其中引用的文件）无法读取
+    #[error("{}", t!("data.read_failed", .path))]
+    DataFileIo {
+        /// 文件路径
+        path: String,
+        /// 底层的IO错误
+        #[source]
+        source: std::io::Error,
+    },
+    /// 数据文件不是有效的JSON
+    #[error("{}: {message}", t!("data.parse_failed", .path))]
+    DataFileParse {
+        /// 文件路径
+        path: String,
+        /// 出错的行号，从1开始
+        line: usize,
+        /// 出错的列号，从1开始；文件在行首提前结束时为0，CSV没有列号时也为0
+        column: usize,
+        /// 带出错位置上下文的说明
+        message: String,
+    },
+    /// 数据文件是有效的JSON，但内容不符合要求（缺少数据数组、字段配置无效、循环引用等）
+    #[error("{0}")]
+    DataFileInvalid(String),
+    /// 数据项无法生成有效的负载（不是对象、非有限数、字段冲突等）
+    #[error("{reason}")]
+    PayloadInvalid {
+        /// 数据项序号（从0开始），逐条发送时调用方才知道序号，库函数返回None
+        index: Option<usize>,
+        /// 原因
+        reason: String,
+    },
+    /// 后台进程或 `--on-complete` 命令没有按预期启动、停止或结束
+    #[error("{0}")]
+    Process(String),
+    /// 服务器返回了非成功状态码
+    #[error(transparent)]
+    HttpStatus(HttpStatusError),
+    /// 服务器拒绝了设备令牌（401、403，以及ThingsBoard对无效令牌返回的404）
+    #[error(transparent)]
+    Auth(HttpStatusError),
+    /// 网络请求失败（连接被拒绝、DNS解析失败、重定向过多等）
+    #[error("{}", t!("send.request_failed", .server))]
+    Network {
+        /// 请求的服务器
+        server: String,
+        /// 底层的网络错误
+        #[source]
+        source: reqwest::Error,
+    },
+    /// 请求超时
+    #[error("{}", t!("send.request_timeout", .server))]
+    Timeout {
+        /// 请求的服务器
+        server: String,
+        /// 底层的网络错误
+        #[source]
+        source: reqwest::Error,
+    },
+}
+
+impl PushError {
+    /// 负载无效的错误，序号未知
+    pub fn payload(reason: impl Into<String>) -> Self {
+        PushError::PayloadInvalid { index: None, reason: reason.into() }
+    }
+
+    /// 服务器返回非成功状态码时的错误，认证失败归为 [`PushError::Auth`]
+    pub fn from_status(error: HttpStatusError) -> Self {
+        if error.is_auth_failure() { PushError::Auth(error) } else { PushError::HttpStatus(error) }
+    }
+
+    /// 网络请求失败时的错误，超时归为 [`PushError::Timeout`]
+    pub fn from_request(server: &str, source: reqwest::Error) -> Self {
+        let server = server.to_string();
+        if source.is_timeout() { PushError::Timeout { server, source } } else { PushError::Network { server, source } }
+    }
+
+    /// 服务器返回的状态码，不是HTTP状态错误时为None
+    pub fn status_code(&self) -> Option<u16> {
+        self.http_status().map(|error| error.status.as_u16())
+    }
+
+    /// 服务器返回的HTTP状态错误，包括认证失败
+    pub fn http_status(&self) -> Option<&HttpStatusError> {
+        match self {
+            PushError::HttpStatus(error) | PushError::Auth(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// 错误信息连同底层原因，形如 `发送HTTP请求失败: http://host: error sending request`，用于报告和审计
+    pub fn detail(&self) -> String {
+        let mut text = self.to_string();
+        let mut source = std::error::Error::source(self);
+        while let Some(cause) = source {
+            text.push_str(": ");
+            text.push_str(&cause.to_string());
+            source = cause.source();
+        }
+        text
+    }
+
+    /// 错误的种类，用于日志事件、指标标签和报告：`http_status`、`network`、`timeout` 或 `payload`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PushError::HttpStatus(_) | PushError::Auth(_) => "http_status",
+            PushError::Network { .. } => "network",
+            PushError::Timeout { .. } => "timeout",
+            _ => "payload",
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! 进程退出码：按运行统计确定退出码，以及结束后执行的 `--on-complete` 命令
+
+use tracing::info;
+use crate::error::{PushError, PushResult};
+use crate::stats::RunStats;
+
+/// 全部发送成功（或已指定 `--ignore-failures`）
+pub const EXIT_SUCCESS: u8 = 0;
+/// 配置或数据错误，包括演练模式下的负载生成失败
+pub const EXIT_CONFIG_ERROR: u8 = 1;
+/// 有发送失败
+pub const EXIT_SEND_FAILED: u8 = 2;
+/// 开启 `--fail-fast` 或 `--max-consecutive-failures` 后因连续发送失败而中止
+pub const EXIT_FAIL_FAST: u8 = 3;
+/// 发送成功，但 `--on-complete` 指定的命令执行失败
+pub const EXIT_ON_COMPLETE_FAILED: u8 = 4;
+/// 服务器拒绝了设备令牌（401/403/404），发送已中止
+pub const EXIT_AUTH_FAILED: u8 = 5;
+/// 按Ctrl-C中断（与shell中被SIGINT终止的进程相同）
+pub const EXIT_INTERRUPTED: u8 = 130;
+
+/// 执行 `--on-complete` 指定的命令并等待其结束
+///
+/// # 参数
+///
+/// * `command` - shell命令
+/// * `env_vars` - 额外传给命令的环境变量
+///
+/// # 错误
+///
+/// 当命令无法启动或以非0状态结束时返回错误
+pub async fn run_on_complete(command: &str, env_vars: &[(&str, String)]) -> PushResult<()> {
+    info!("{}", t!("on_complete.running", command));
+    let mut process = if cfg!(windows) {
+        let mut process = tokio::process::Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    } else {
+        let mut process = tokio::process::Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    };
+    let status = process
+        .envs(env_vars.iter().map(|(name, value)| (*name, value.as_str())))
+        .status()
+        .await
+        .map_err(|source| PushError::Io { message: t!("on_complete.spawn_failed", command), source })?;
+    if !status.success() {
+        return Err(PushError::Process(t!("on_complete.failed", command, status)));
+    }
+    info!("{}", t!("on_complete.succeeded"));
+    Ok(())
+}
+
+/// 根据运行统计确定进程退出码
+///
+/// # 参数
+///
+/// * `stats` - 运行统计
+/// * `aborted_after` - 因连续失败中止时为触发中止的连续失败次数（`--fail-fast` 为1），未中止时为None
+/// * `ignore_failures` - 是否忽略发送失败（兼容原来总是返回0的行为）
+///
+/// # 返回值
+///
+/// * `(u8, String)` - 退出码及原因说明
+pub fn exit_status(stats: &RunStats, aborted_after: Option<u64>, ignore_failures: bool) -> (u8, String) {
+    if stats.failed == 0 {
+        return (EXIT_SUCCESS, t!("exit.no_failures"));
+    }
+    // 重新入队后成功的记录已经送达，之前的失败不影响退出码
+    if stats.unrecovered() == 0 {
+        return (EXIT_SUCCESS, t!("exit.all_recovered", stats.failed));
+    }
+    if ignore_failures {
+        return (EXIT_SUCCESS, t!("exit.ignore_failures", stats.failed));
+    }
+    match aborted_after {
+        Some(1) => return (EXIT_FAIL_FAST, t!("exit.fail_fast")),
+        Some(max) => return (EXIT_FAIL_FAST, t!("exit.consecutive_failures", max)),
+        None => {}
+    }
+    // 演练模式下的失败只可能来自负载生成和校验，属于数据错误
+    if stats.dry_run {
+        return (EXIT_CONFIG_ERROR, t!("exit.dry_run_failed", stats.failed));
+    }
+    if stats.succeeded == 0 {
+        (EXIT_SEND_FAILED, t!("exit.all_failed", stats.failed))
+    } else {
+        (EXIT_SEND_FAILED, t!("exit.some_failed", stats.failed, stats.succeeded))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requeued_failures_that_recover_do_not_fail_the_run() {
+        let mut stats = RunStats::new(false);
+        stats.succeeded = 3;
+        stats.failed = 2;
+        stats.requeued = 2;
+        assert_eq!(exit_status(&stats, None, false).0, EXIT_SEND_FAILED);
+
+        stats.recovered = 2;
+        assert_eq!(exit_status(&stats, None, false).0, EXIT_SUCCESS);
+
+        // 仍有一条重试后没有成功
+        stats.failed = 3;
+        assert_eq!(exit_status(&stats, None, false).0, EXIT_SEND_FAILED);
+        assert_eq!(exit_status(&stats, Some(3), false).0, EXIT_FAIL_FAST);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn on_complete_reports_commands_that_fail() {
+        let env_vars = [("PUSH_FAILED", "0".to_string())];
+        assert!(run_on_complete("test \"$PUSH_FAILED\" = 0", &env_vars).await.is_ok());
+        assert!(matches!(run_on_complete("exit 3", &env_vars).await, Err(PushError::Process(_))));
+    }
+}
@@ -0,0 +1,2621 @@
+//! 界面语言和消息目录
+
+use std::sync::OnceLock;
+
+/// 界面语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    /// 中文（默认）
+    Zh,
+    /// 英文
+    En,
+}
+
+/// 当前界面语言，未设置时使用中文
+pub static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// 当前界面语言
+pub fn current_lang() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::Zh)
+}
+
+/// 根据环境变量推断界面语言
+///
+/// 按 `LC_ALL` > `LC_MESSAGES` > `LANG` 的优先级取第一个非空的值，
+/// 以 `en` 开头时使用英文，其余情况（包括未设置）使用中文
+///
+/// # 参数
+///
+/// * `lookup` - 环境变量查询函数
+pub fn lang_from_env(lookup: impl Fn(&str) -> Option<String>) -> Lang {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .filter_map(lookup)
+        .find(|value| !value.is_empty());
+    match locale {
+        Some(locale) if locale.to_ascii_lowercase().starts_with("en") => Lang::En,
+        _ => Lang::Zh,
+    }
+}
+
+/// 消息目录中的一条消息
+pub struct Message {
+    /// 消息键
+    key: &'static str,
+    /// 中文模板
+    zh: &'static str,
+    /// 英文模板，为空表示尚未翻译
+    en: &'static str,
+}
+
+/// 按指定语言渲染消息目录中的消息
+pub fn render(lang: Lang, key: &str, args: &[&dyn std::fmt::Display]) -> String {
+    render_from(CATALOG, lang, key, args)
+}
+
+/// 在给定的消息目录中查找并渲染消息
+///
+/// 模板中的 `{}` 按顺序替换为参数，`{0}`、`{1}` 按位置替换，便于不同语言调整语序；
+/// 其它花括号内容（如进度条模板中的 `{pos}`）原样保留。
+/// 需要格式说明（如保留小数）的参数由调用方预先格式化
+///
+/// # 参数
+///
+/// * `catalog` - 消息目录
+/// * `lang` - 界面语言
+/// * `key` - 消息键
+/// * `args` - 填入占位符的参数
+///
+/// # 返回值
+///
+/// * `String` - 渲染结果；英文未翻译时回退到中文，目录中不存在的键原样返回，不会panic
+pub fn render_from(catalog: &[Message], lang: Lang, key: &str, args: &[&dyn std::fmt::Display]) -> String {
+    let Some(message) = catalog.iter().find(|m| m.key == key) else {
+        return key.to_string();
+    };
+    let template = match lang {
+        Lang::En if !message.en.is_empty() => message.en,
+        _ => message.zh,
+    };
+
+    let mut rendered = String::with_capacity(template.len());
+    let mut next_arg = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let placeholder = rest[start + 1..]
+            .find('}')
+            .map(|end| &rest[start + 1..start + 1 + end])
+            .filter(|inner| inner.chars().all(|c| c.is_ascii_digit()));
+        let Some(inner) = placeholder else {
+            rendered.push('{');
+            rest = &rest[start + 1..];
+            continue;
+        };
+        let index = if inner.is_empty() {
+            next_arg += 1;
+            next_arg - 1
+        } else {
+            inner.parse().unwrap_or(usize::MAX)
+        };
+        match args.get(index) {
+            Some(arg) => rendered.push_str(&arg.to_string()),
+            None => rendered.push_str(&rest[start..start + inner.len() + 2]),
+        }
+        rest = &rest[start + inner.len() + 2..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// 编译期消息目录
+///
+/// 新增消息时同时提供中英文模板，两种语言的 `{}` 占位符个数必须一致；
+/// 暂未翻译的消息 `en` 留空即可，渲染时回退到中文
+pub const CATALOG: &[Message] = &[
+    // 进度
+    Message {
+        key: "progress.bar",
+        zh: "{} {pos}/{len} {per_sec} 失败 {msg} 剩余 {eta}",
+        en: "{} {pos}/{len} {per_sec} failed {msg} ETA {eta}",
+    },
+    Message {
+        key: "progress.plain",
+        zh: "📈 进度 {}/{} ({}%), {} 条/秒, 失败 {}, 预计剩余 {} 秒",
+        en: "📈 Progress {}/{} ({}%), {} msg/s, failed {}, ETA {} s",
+    },
+    // 启动信息
+    Message {
+        key: "banner.config_loaded",
+        zh: "✅ 配置加载成功:",
+        en: "✅ Configuration loaded:",
+    },
+    Message {
+        key: "banner.servers",
+        zh: "   服务器: {}",
+        en: "   Servers: {}",
+    },
+    Message {
+        key: "banner.token_rotation",
+        zh: "   设备Token: 同一设备的 {} 个令牌轮换使用",
+        en: "   Device token: rotating {} tokens of the same device",
+    },
+    Message {
+        key: "banner.token_pool",
+        zh: "   设备令牌池: {} 个令牌, 策略 {}",
+        en: "   Device token pool: {} tokens, strategy {}",
+    },
+    Message {
+        key: "banner.token",
+        zh: "   设备Token: {}",
+        en: "   Device token: {}",
+    },
+    Message {
+        key: "banner.from_profile",
+        zh: " (来自配置档 '{}')",
+        en: " (from profile '{}')",
+    },
+    Message {
+        key: "banner.profile",
+        zh: "📁 使用配置档: {}",
+        en: "📁 Using profile: {}",
+    },
+    Message {
+        key: "banner.data_file",
+        zh: "   数据文件: {}{}",
+        en: "   Data file: {}{}",
+    },
+    Message {
+        key: "banner.interval",
+        zh: "   发送间隔: {} 秒{}",
+        en: "   Interval: {} s{}",
+    },
+    Message {
+        key: "banner.intervals",
+        zh: "⏳ 记录间隔 {} 秒，轮次间隔 {} 秒",
+        en: "⏳ Item interval {} s, round interval {} s",
+    },
+    Message {
+        key: "banner.count",
+        zh: "   发送轮数: {}{}",
+        en: "   Rounds: {}{}",
+    },
+    Message {
+        key: "banner.data_loaded",
+        zh: "✅ 数据文件加载成功，包含 {} 条记录",
+        en: "✅ Data file loaded with {} records",
+    },
+    Message {
+        key: "banner.random_key",
+        zh: "🎲 随机修改已启用: 随机键 '{}'，作用于 {}",
+        en: "🎲 Randomization enabled: random key '{}' applies to {}",
+    },
+    Message {
+        key: "banner.random_key_unmatched",
+        zh: "⚠️ 随机键 '{}' 不匹配任何数据项中嵌套对象的字段，不会有值被随机修改",
+        en: "⚠️ Random key '{}' matches no field inside the items' nested objects, nothing will be randomized",
+    },
+    Message {
+        key: "banner.random_off",
+        zh: "🎲 未启用随机修改（数据文件没有 random_key，可以用 --random-key 指定）",
+        en: "🎲 Randomization disabled (the data file has no random_key; use --random-key to set one)",
+    },
+    Message {
+        key: "banner.random_off_array",
+        zh: "🎲 未启用随机修改（数组格式的数据文件不支持 random_key，可以用 --random-key 指定或改用包装对象格式）",
+        en: "🎲 Randomization disabled (array-format data files cannot set random_key; use --random-key or the wrapped-object format)",
+    },
+    Message {
+        key: "banner.seed",
+        zh: "🎲 随机种子: {}（每个设备派生独立的子种子）",
+        en: "🎲 Random seed: {} (each device derives its own sub-seed)",
+    },
+    Message {
+        key: "banner.lb",
+        zh: "⚖️ 负载均衡: {} 个服务器, 策略 {}",
+        en: "⚖️ Load balancing: {} servers, strategy {}",
+    },
+    Message {
+        key: "banner.attribute_fields",
+        zh: "🏷️ 属性字段（发往attributes端点）: {}",
+        en: "🏷️ Attribute fields (sent to the attributes endpoint): {}",
+    },
+    Message {
+        key: "banner.no_time_field",
+        zh: "🕒 不注入发送时间字段",
+        en: "🕒 Send time field disabled",
+    },
+    Message {
+        key: "banner.time_field",
+        zh: "🕒 发送时间字段: {}",
+        en: "🕒 Send time field: {}",
+    },
+    Message {
+        key: "banner.clock_adjust",
+        zh: "🕰️ 已开启时钟补偿：按响应Date头估计服务器时间差并加到 ts 上，第一个响应之前使用本机时钟",
+        en: "🕰️ Clock adjustment on: the server clock offset is estimated from response Date headers and added to ts; the local clock is used until the first response",
+    },
+    Message {
+        key: "send.clock_adjusted",
+        zh: "🕰️ 时钟偏移估计更新为 {} ms（之前 {} ms），之后的 ts 按此补偿",
+        en: "🕰️ Clock offset estimate updated to {} ms (was {} ms); later ts values are adjusted by it",
+    },
+    Message {
+        key: "banner.url_template",
+        zh: "🔗 设备API地址模板: {}",
+        en: "🔗 Device API URL template: {}",
+    },
+    Message {
+        key: "banner.edge",
+        zh: "🔗 Edge 模式：数据写入 Edge 后由 Edge 异步同步到云端，Edge 与云端断开时在 Edge 上排队，云端看到的数据可能有延迟",
+        en: "🔗 Edge mode: data is written to the Edge and synced to the cloud asynchronously; while the Edge is disconnected it queues locally, so the cloud may lag behind",
+    },
+    Message {
+        key: "banner.connection_tuning",
+        zh: "🔌 连接参数: TCP_NODELAY {}, TCP keepalive {}, 空闲连接保留 {}, 每个服务器最多空闲连接 {}",
+        en: "🔌 Connection: TCP_NODELAY {}, TCP keepalive {}, idle connections kept {}, max idle per server {}",
+    },
+    Message {
+        key: "banner.connection_off",
+        zh: "关闭",
+        en: "off",
+    },
+    Message {
+        key: "banner.connection_unlimited",
+        zh: "不限",
+        en: "unlimited",
+    },
+    Message {
+        key: "banner.connection_forever",
+        zh: "不限时",
+        en: "forever",
+    },
+    Message {
+        key: "banner.no_redirects",
+        zh: "↪️ 不跟随HTTP重定向，3xx响应按发送失败处理",
+        en: "↪️ HTTP redirects are not followed; 3xx responses count as failures",
+    },
+    Message {
+        key: "banner.stats_interval",
+        zh: "📈 滚动统计: 每 {} 秒输出一次该窗口内的速率、成功率和延迟",
+        en: "📈 Rolling stats: rate, success rate and latency every {} seconds",
+    },
+    Message {
+        key: "banner.concurrency",
+        zh: "🚀 并发发送: {} 个请求同时进行，队列容量 {}",
+        en: "🚀 Concurrent sending: {} requests in flight, queue capacity {}",
+    },
+    Message {
+        key: "banner.merge_timeseries",
+        zh: "📦 合并时序模式: 每个请求最多 {} 个数据点，记录的 ts 字段作为时间戳",
+        en: "📦 Merged time series: up to {} data points per request, record field ts is the timestamp",
+    },
+    Message {
+        key: "banner.merge_ignored",
+        zh: "⚠️ 合并时序模式下忽略数据文件中的 geo 轨迹和 field_groups",
+        en: "⚠️ geo tracks and field_groups in the data file are ignored in merged time series mode",
+    },
+    Message {
+        key: "banner.strict_payload",
+        zh: "📦 严格请求体: 只发送 {\"ts\", \"values\"}",
+        en: "📦 Strict payload: only {\"ts\", \"values\"} is sent",
+    },
+    Message {
+        key: "banner.flat_payload",
+        zh: "📦 扁平请求体: 只发送 values 对象，由服务器分配时间戳",
+        en: "📦 Flat payload: only the values object is sent, the server assigns the timestamp",
+    },
+    Message {
+        key: "banner.no_send_time",
+        zh: "🕒 不向遥测数据注入发送时间字段",
+        en: "🕒 Send time is not injected into telemetry values",
+    },
+    Message {
+        key: "banner.send_time_key",
+        zh: "🕒 遥测数据中的发送时间字段: {}",
+        en: "🕒 Send time key in telemetry values: {}",
+    },
+    Message {
+        key: "banner.seq_field",
+        zh: "🔢 全局序号字段: {}",
+        en: "🔢 Sequence field: {}",
+    },
+    Message {
+        key: "banner.signing",
+        zh: "🔏 请求签名: {} -> {}{}",
+        en: "🔏 Request signing: {} -> {}{}",
+    },
+    Message {
+        key: "banner.sign_key_missing",
+        zh: "--{} 需要签名密钥，请通过 --sign-key 或环境变量 sign_key / sign_key_FILE 设置",
+        en: "--{} requires a signing key; set it with --sign-key or the sign_key / sign_key_FILE environment variable",
+    },
+    Message {
+        key: "banner.signing_timestamp",
+        zh: " (时间戳: {})",
+        en: " (timestamp: {})",
+    },
+    Message {
+        key: "banner.show_response",
+        zh: "📨 输出成功响应: 响应头 [{}], 响应体最多 {} 个字符",
+        en: "📨 Showing successful responses: headers [{}], body up to {} characters",
+    },
+    Message {
+        key: "metrics.listening",
+        zh: "📡 Prometheus指标: http://{}/metrics",
+        en: "📡 Prometheus metrics: http://{}/metrics",
+    },
+    Message {
+        key: "metrics.bind_failed",
+        zh: "无法监听指标地址: {}",
+        en: "Failed to listen on metrics address: {}",
+    },
+    Message {
+        key: "metrics.request_failed",
+        zh: "指标请求处理失败: {}",
+        en: "Failed to handle a metrics request: {}",
+    },
+    Message {
+        key: "metrics.accept_failed",
+        zh: "⚠️ 指标端点接受连接失败: {}",
+        en: "⚠️ Metrics endpoint failed to accept a connection: {}",
+    },
+    Message {
+        key: "statsd.enabled",
+        zh: "📡 StatsD上报: {} (前缀 {})",
+        en: "📡 StatsD metrics: {} (prefix {})",
+    },
+    Message {
+        key: "statsd.resolve_failed",
+        zh: "无法解析StatsD地址: {}",
+        en: "Failed to resolve StatsD address: {}",
+    },
+    Message {
+        key: "statsd.socket_failed",
+        zh: "无法创建StatsD的UDP套接字",
+        en: "Failed to create the StatsD UDP socket",
+    },
+    Message {
+        key: "banner.audit_file",
+        zh: "📝 审计文件: {}",
+        en: "📝 Audit file: {}",
+    },
+    Message {
+        key: "banner.tee",
+        zh: "🪞 数据副本: {}",
+        en: "🪞 Copying sent data to {}",
+    },
+    Message {
+        key: "banner.report_csv",
+        zh: "📄 CSV报告: {}",
+        en: "📄 CSV report: {}",
+    },
+    Message {
+        key: "banner.dry_run",
+        zh: "🧪 演练模式：只生成并打印负载，不会发出任何网络请求",
+        en: "🧪 Dry run: payloads are generated and printed, no network requests are made",
+    },
+    Message {
+        key: "banner.dry_run_fast",
+        zh: "🧪 演练模式：只生成并打印负载，不会发出任何网络请求，跳过等待间隔",
+        en: "🧪 Dry run: payloads are generated and printed, no network requests are made, intervals are skipped",
+    },
+    Message {
+        key: "banner.rate",
+        zh: "🚦 发送速率: {} 条/秒{}",
+        en: "🚦 Send rate: {} msg/s{}",
+    },
+    Message {
+        key: "banner.warmup",
+        zh: "，前 {} 秒{}预热",
+        en: ", {} s {} warm-up",
+    },
+    Message {
+        key: "banner.warmup_linear",
+        zh: "线性",
+        en: "linear",
+    },
+    Message {
+        key: "banner.warmup_exponential",
+        zh: "指数",
+        en: "exponential",
+    },
+    Message {
+        key: "tui.not_a_terminal",
+        zh: "--tui 需要在交互式终端中运行，标准输入或输出被重定向时请改用普通日志输出",
+        en: "--tui requires an interactive terminal; use the regular log output when stdin or stdout is redirected",
+    },
+    Message {
+        key: "tui.failed",
+        zh: "⚠️ TUI仪表盘异常退出: {}",
+        en: "⚠️ TUI dashboard exited with an error: {}",
+    },
+    Message {
+        key: "tui.title",
+        zh: " ThingsBoard数据推送 ",
+        en: " ThingsBoard push ",
+    },
+    Message {
+        key: "tui.totals",
+        zh: "成功 {}  失败 {}  当前速率 {} 条/秒  {}",
+        en: "Sent {}  Failed {}  Rate {} msg/s  {}",
+    },
+    Message {
+        key: "tui.running",
+        zh: "▶ 发送中",
+        en: "▶ running",
+    },
+    Message {
+        key: "tui.paused",
+        zh: "⏸ 已暂停",
+        en: "⏸ paused",
+    },
+    Message {
+        key: "tui.latency",
+        zh: " 延迟 (最近 {} ms) ",
+        en: " Latency (last {} ms) ",
+    },
+    Message {
+        key: "tui.recent",
+        zh: " 最近发送 ",
+        en: " Recent sends ",
+    },
+    Message {
+        key: "tui.devices",
+        zh: " 设备 ",
+        en: " Devices ",
+    },
+    Message {
+        key: "tui.keys",
+        zh: " p/空格 暂停/继续   q 退出",
+        en: " p/space pause/resume   q quit",
+    },
+    Message {
+        key: "send.flatten_conflict",
+        zh: "嵌套字段展开后的键 '{}' 与记录中的其它字段重名",
+        en: "Flattened key '{}' collides with another field in the record",
+    },
+    Message {
+        key: "send.random_modified",
+        zh: "🎲 随机修改字段 '{}': {} -> {}",
+        en: "🎲 Randomized field '{}': {} -> {}",
+    },
+    Message {
+        key: "send.injected",
+        zh: "💉 注入字段 '{}' = {} ({}/{})",
+        en: "💉 Injected field '{}' = {} ({}/{})",
+    },
+    Message {
+        key: "send.payload_reparse_failed",
+        zh: "无法解析已序列化的请求体: {}",
+        en: "Failed to parse the serialized request body: {}",
+    },
+    Message {
+        key: "http.request_body",
+        zh: "   请求体: {}",
+        en: "   Body: {}",
+    },
+    Message {
+        key: "http.request_failed",
+        zh: "   ⏱️ 请求失败，耗时 {} ms",
+        en: "   ⏱️ Request failed after {} ms",
+    },
+    Message {
+        key: "http.response_body",
+        zh: "   响应体: {}",
+        en: "   Response body: {}",
+    },
+    Message {
+        key: "http.empty_body",
+        zh: "<空>",
+        en: "<empty>",
+    },
+    Message {
+        key: "http.non_text",
+        zh: "<非文本>",
+        en: "<non-text>",
+    },
+    Message {
+        key: "http.timing",
+        zh: "   ⏱️ 响应头 {} ms, 完整响应 {} ms",
+        en: "   ⏱️ Headers {} ms, full response {} ms",
+    },
+    Message {
+        key: "send.rename_conflict",
+        zh: "字段 '{}' 重命名为 '{}' 后与记录中的其它字段重名",
+        en: "Field '{}' renamed to '{}' collides with another field in the record",
+    },
+    Message {
+        key: "rename.json_invalid",
+        zh: "重命名映射不是有效的JSON对象: {}: {}",
+        en: "Rename mapping is not a valid JSON object: {}: {}",
+    },
+    Message {
+        key: "rename.json_not_string",
+        zh: "重命名映射中 '{}' 的新名称必须是字符串，实际为 {}",
+        en: "New name for '{}' in the rename mapping must be a string, got {}",
+    },
+    Message {
+        key: "rename.pair_invalid",
+        zh: "重命名配置格式应为 old=new: {}",
+        en: "Rename must be written as old=new: {}",
+    },
+    Message {
+        key: "rename.empty_name",
+        zh: "重命名配置中的字段名不能为空: {}={}",
+        en: "Field names in a rename cannot be empty: {}={}",
+    },
+    Message {
+        key: "rename.conflicting_targets",
+        zh: "字段 '{}' 同时被重命名为 '{}' 和 '{}'",
+        en: "Field '{}' is renamed to both '{}' and '{}'",
+    },
+    Message {
+        key: "rename.duplicate_target",
+        zh: "字段 '{}' 和 '{}' 都被重命名为 '{}'",
+        en: "Fields '{}' and '{}' are both renamed to '{}'",
+    },
+    Message {
+        key: "banner.rename",
+        zh: "🏷️ 字段重命名: {}",
+        en: "🏷️ Field renames: {}",
+    },
+    Message {
+        key: "summary_json.tui_conflict",
+        zh: "--summary-json - 需要独占stdout，不能与 --tui 同时使用",
+        en: "--summary-json - needs stdout to itself and cannot be combined with --tui",
+    },
+    Message {
+        key: "summary_json.write_failed",
+        zh: "无法写入JSON汇总文件: {}",
+        en: "Failed to write the JSON summary file: {}",
+    },
+    Message {
+        key: "summary_json.stdout_failed",
+        zh: "无法写出JSON汇总",
+        en: "Failed to write the JSON summary",
+    },
+    Message {
+        key: "summary_json.written",
+        zh: "🧾 JSON汇总已写入: {}",
+        en: "🧾 JSON summary written to {}",
+    },
+    Message {
+        key: "stats_tb.sent",
+        zh: "🧾 运行统计已推送到压测监控设备: {}",
+        en: "🧾 Run statistics pushed to the monitoring device: {}",
+    },
+    Message {
+        key: "stats_tb.bad_status",
+        zh: "⚠️ 推送运行统计到 {} 失败: 服务器返回 {}",
+        en: "⚠️ Pushing run statistics to {} failed: server returned {}",
+    },
+    Message {
+        key: "stats_tb.failed",
+        zh: "⚠️ 推送运行统计到 {} 失败: {}",
+        en: "⚠️ Pushing run statistics to {} failed: {}",
+    },
+    Message {
+        key: "stats_tb.dry_run_skipped",
+        zh: "🧪 演练模式不发出网络请求，未把运行统计推送到压测监控设备 {}",
+        en: "🧪 Dry run makes no network requests; run statistics were not pushed to monitoring device {}",
+    },
+    Message {
+        key: "data.field_groups_invalid",
+        zh: "数据文件 {} 中的 'field_groups' 格式不正确，每一组应为 {\"interval\": 秒数, \"fields\": [字段名...]}",
+        en: "'field_groups' in data file {} is malformed, each group must be {\"interval\": seconds, \"fields\": [names...]}",
+    },
+    Message {
+        key: "data.field_group_interval",
+        zh: "数据文件 {} 中字段组 '{}' 的 interval 必须是大于0的秒数",
+        en: "Field group '{1}' in data file {0} must have an interval greater than 0 seconds",
+    },
+    Message {
+        key: "data.field_group_duplicate",
+        zh: "数据文件 {} 中的字段 '{}' 同时属于字段组 '{}' 和 '{}'",
+        en: "Field '{1}' in data file {0} belongs to both field groups '{2}' and '{3}'",
+    },
+    Message {
+        key: "data.valid_range_format",
+        zh: "数据文件 {} 中的 'valid_range' 必须是以字段路径为键、{\"min\", \"max\", \"clamp\"} 为值的对象",
+        en: "'valid_range' in data file {} must be an object mapping field paths to {\"min\", \"max\", \"clamp\"}",
+    },
+    Message {
+        key: "data.valid_range_invalid",
+        zh: "数据文件 {} 中字段 '{}' 的合理范围至少需要 min、max 之一，且 min 不能大于 max",
+        en: "Data file {}: valid range of field '{}' needs min or max, and min must not exceed max",
+    },
+    Message {
+        key: "validate.at_item",
+        zh: "数据项 {}",
+        en: "item {}",
+    },
+    Message {
+        key: "validate.at_item_path",
+        zh: "数据项 {} 字段 {}",
+        en: "item {} field {}",
+    },
+    Message {
+        key: "validate.at_file",
+        zh: "数据文件",
+        en: "data file",
+    },
+    Message {
+        key: "validate.at_config",
+        zh: "配置 {}",
+        en: "config {}",
+    },
+    Message {
+        key: "validate.summary",
+        zh: "🔎 检查了 {} 个数据项: {} 个错误, {} 个警告",
+        en: "🔎 Checked {} items: {} errors, {} warnings",
+    },
+    Message {
+        key: "validate.too_many_warnings",
+        zh: "❌ 警告数 {} 超过 --max-warnings {}",
+        en: "❌ {} warnings exceed --max-warnings {}",
+    },
+    Message {
+        key: "validate.random_key_unmatched",
+        zh: "随机键 '{}' 不匹配任何数据项中的嵌套字段，随机修改不会生效",
+        en: "random key '{}' matches no nested field in any item, randomization has no effect",
+    },
+    Message {
+        key: "validate.field_missing",
+        zh: "引用的字段 '{}' 不在任何数据项中",
+        en: "referenced field '{}' is not present in any item",
+    },
+    Message {
+        key: "validate.walk_field_added",
+        zh: "数据项中没有字段 '{}'，随机游走的值将新增在顶层",
+        en: "no item has field '{}', the random walk value will be added at the top level",
+    },
+    Message {
+        key: "validate.geo_field_added",
+        zh: "数据项中没有字段 '{0}'，轨迹坐标 {0}/{1} 将新增在顶层",
+        en: "no item has field '{0}', track coordinates {0}/{1} will be added at the top level",
+    },
+    Message {
+        key: "validate.not_object",
+        zh: "数据项不是JSON对象（实际为{}），发送时会失败",
+        en: "item is not a JSON object (found {}), sending it will fail",
+    },
+    Message {
+        key: "validate.empty_item",
+        zh: "数据项是空对象，没有可发送的字段",
+        en: "item is an empty object with nothing to send",
+    },
+    Message {
+        key: "validate.key_not_allowed",
+        zh: "字段不在 --allow-keys 列表中",
+        en: "field is not in the --allow-keys list",
+    },
+    Message {
+        key: "validate.empty_key",
+        zh: "字段名为空",
+        en: "field name is empty",
+    },
+    Message {
+        key: "validate.null_value",
+        zh: "值为null，ThingsBoard会忽略该字段",
+        en: "value is null, ThingsBoard ignores the field",
+    },
+    Message {
+        key: "validate.array_value",
+        zh: "值为数组，ThingsBoard按JSON文本整体存储（可用 --flatten --flatten-arrays 展开）",
+        en: "value is an array, ThingsBoard stores it as JSON text (use --flatten --flatten-arrays to expand)",
+    },
+    Message {
+        key: "validate.empty_object",
+        zh: "值为空对象，没有可发送的内容",
+        en: "value is an empty object with nothing to send",
+    },
+    Message {
+        key: "data.dir_read_failed",
+        zh: "无法读取数据目录 {}",
+        en: "Failed to read data directory {}",
+    },
+    Message {
+        key: "data.dir_file_skipped",
+        zh: "⚠️ 跳过无法解析的数据文件 {}: {}",
+        en: "⚠️ Skipped unparsable data file {}: {}",
+    },
+    Message {
+        key: "arg.url_template_missing",
+        zh: "地址模板 {} 缺少占位符 {}",
+        en: "URL template {} is missing the {} placeholder",
+    },
+    Message {
+        key: "arg.url_template_unknown",
+        zh: "地址模板 {} 含有未知占位符 {}（支持 {server}、{token}、{channel}）",
+        en: "URL template {} contains unknown placeholder {} (supported: {server}, {token}, {channel})",
+    },
+    Message {
+        key: "data.walk_step_invalid",
+        zh: "随机游走 '{}' 的步长标准差无效: {}（必须是不小于0的有限数）",
+        en: "Invalid step_std for random walk '{}': {} (must be a finite number not less than 0)",
+    },
+    Message {
+        key: "data.walk_bounds_invalid",
+        zh: "随机游走 '{}' 的下界 {} 大于上界 {}",
+        en: "Random walk '{}' has min {} greater than max {}",
+    },
+    Message {
+        key: "data.weight_invalid",
+        zh: "数据文件权重无效: {}（权重必须是大于0的整数）",
+        en: "Invalid data file weight: {} (the weight must be an integer greater than 0)",
+    },
+    Message {
+        key: "data.weighted_file",
+        zh: "{}（权重 {}，{} 条）",
+        en: "{} (weight {}, {} records)",
+    },
+    Message {
+        key: "data.weighted_loaded",
+        zh: "🔃 按权重交织数据文件: {}，每轮 {} 条",
+        en: "🔃 Interleaving data files by weight: {}, {} records per round",
+    },
+    Message {
+        key: "data.dir_file_loaded",
+        zh: "📄 读取数据文件 {}: {} 条记录",
+        en: "📄 Read data file {}: {} records",
+    },
+    Message {
+        key: "data.dir_loaded",
+        zh: "📂 数据目录 {}: 读取 {}/{} 个文件",
+        en: "📂 Data directory {}: read {}/{} files",
+    },
+    Message {
+        key: "data.dir_empty",
+        zh: "数据目录 {} 中没有可用的数据文件（支持的扩展名: {}）",
+        en: "No usable data files in directory {} (supported extensions: {})",
+    },
+    Message {
+        key: "data.jsonl_line_invalid",
+        zh: "第 {} 行不是有效的JSON对象",
+        en: "Line {} is not a valid JSON object",
+    },
+    Message {
+        key: "data.no_records",
+        zh: "文件中没有任何记录",
+        en: "The file contains no records",
+    },
+    Message {
+        key: "data.path_not_utf8",
+        zh: "路径不是有效的UTF-8: {}",
+        en: "Path is not valid UTF-8: {}",
+    },
+    Message {
+        key: "data.condition_not_string",
+        zh: "条件表达式必须是字符串",
+        en: "Condition expression must be a string",
+    },
+    Message {
+        key: "condition.unclosed_string",
+        zh: "字符串缺少结尾的 {}",
+        en: "String is missing its closing {}",
+    },
+    Message {
+        key: "condition.number_invalid",
+        zh: "无效的数字: {}: {}",
+        en: "Invalid number: {}: {}",
+    },
+    Message {
+        key: "condition.path_invalid",
+        zh: "无效的字段路径: {}",
+        en: "Invalid field path: {}",
+    },
+    Message {
+        key: "condition.unknown_char",
+        zh: "无法识别的字符 '{}'",
+        en: "Unrecognized character '{}'",
+    },
+    Message {
+        key: "condition.missing_close",
+        zh: "缺少右括号",
+        en: "Missing closing parenthesis",
+    },
+    Message {
+        key: "condition.unexpected",
+        zh: "此处不应出现 {}",
+        en: "Unexpected {}",
+    },
+    Message {
+        key: "condition.incomplete",
+        zh: "表达式不完整",
+        en: "Incomplete expression",
+    },
+    Message {
+        key: "condition.trailing",
+        zh: "表达式结尾有多余的 {}",
+        en: "Unexpected trailing {}",
+    },
+    Message {
+        key: "data.conditions_invalid",
+        zh: "数据文件 {} 中的 'conditions' 必须是以字段名为键、条件表达式为值的对象",
+        en: "'conditions' in data file {} must be an object mapping field names to condition expressions",
+    },
+    Message {
+        key: "data.condition_invalid",
+        zh: "数据文件 {} 中字段 '{}' 的上报条件无效",
+        en: "Invalid condition for field '{1}' in data file {0}",
+    },
+    Message {
+        key: "banner.valid_range",
+        zh: "📏 字段合理范围: {}",
+        en: "📏 Valid field ranges: {}",
+    },
+    Message {
+        key: "banner.conditions",
+        zh: "🔀 条件上报字段: {}",
+        en: "🔀 Conditional fields: {}",
+    },
+    Message {
+        key: "banner.notify",
+        zh: "📣 运行通知: {} (运行ID {})",
+        en: "📣 Run notifications: {} (run id {})",
+    },
+    Message {
+        key: "banner.notify_failures",
+        zh: "📣 每累计 {} 次发送失败通知一次",
+        en: "📣 Notifying after every {} failed sends",
+    },
+    Message {
+        key: "notify.template_read_failed",
+        zh: "无法读取通知模板文件: {}",
+        en: "Failed to read notification template file: {}",
+    },
+    Message {
+        key: "notify.template_invalid",
+        zh: "通知模板渲染后不是有效的JSON: {}",
+        en: "Notification template does not render to valid JSON: {}",
+    },
+    Message {
+        key: "notify.template_unclosed",
+        zh: "通知模板中的占位符缺少结尾的 }}",
+        en: "Notification template placeholder is missing its closing }}",
+    },
+    Message {
+        key: "notify.template_unknown",
+        zh: "通知模板中有未知占位符: {}",
+        en: "Unknown placeholder in notification template: {}",
+    },
+    Message {
+        key: "notify.text",
+        zh: "push-message-thingsboard 运行 {} {}：成功 {} 条，失败 {} 条，耗时 {} 秒",
+        en: "push-message-thingsboard run {} {}: {} sent, {} failed, {} s elapsed",
+    },
+    Message {
+        key: "notify.text_last_error",
+        zh: "，最近错误: {}",
+        en: ", last error: {}",
+    },
+    Message {
+        key: "notify.status_completed",
+        zh: "已完成",
+        en: "completed",
+    },
+    Message {
+        key: "notify.status_failed",
+        zh: "已结束（有发送失败）",
+        en: "finished with failures",
+    },
+    Message {
+        key: "notify.status_aborted",
+        zh: "因连续失败中止",
+        en: "aborted after consecutive failures",
+    },
+    Message {
+        key: "notify.status_interrupted",
+        zh: "被中断",
+        en: "interrupted",
+    },
+    Message {
+        key: "notify.status_failing",
+        zh: "发送失败累计中",
+        en: "is accumulating failures",
+    },
+    Message {
+        key: "webhook.bad_status",
+        zh: "⚠️ Webhook回调返回异常状态: {} - {}",
+        en: "⚠️ Webhook got an unexpected status: {} - {}",
+    },
+    Message {
+        key: "webhook.failed",
+        zh: "⚠️ Webhook回调失败: {} - {}",
+        en: "⚠️ Webhook failed: {} - {}",
+    },
+    Message {
+        key: "notify.sent",
+        zh: "📣 运行通知已发送: {}",
+        en: "📣 Run notification sent to {}",
+    },
+    Message {
+        key: "notify.bad_status",
+        zh: "⚠️ 运行通知返回异常状态: {} - {}",
+        en: "⚠️ Run notification got an unexpected status: {} - {}",
+    },
+    Message {
+        key: "notify.failed",
+        zh: "⚠️ 运行通知发送失败: {} - {}",
+        en: "⚠️ Run notification failed: {} - {}",
+    },
+    Message {
+        key: "banner.user_agent",
+        zh: "🪪 User-Agent: {}",
+        en: "🪪 User-Agent: {}",
+    },
+    Message {
+        key: "log.syslog_unsupported",
+        zh: "syslog仅支持Unix平台",
+        en: "syslog is only supported on Unix",
+    },
+    Message {
+        key: "log.syslog_unavailable",
+        zh: "⚠️ 无法连接syslog，日志改为以JSON格式输出到stderr: {}",
+        en: "⚠️ syslog is unavailable, logging JSON to stderr instead: {}",
+    },
+    Message {
+        key: "inject.missing_trigger",
+        zh: "注入配置缺少触发条件 '@': {}",
+        en: "Injection is missing its '@' trigger: {}",
+    },
+    Message {
+        key: "inject.missing_value",
+        zh: "注入配置缺少 '=': {}",
+        en: "Injection is missing '=': {}",
+    },
+    Message {
+        key: "inject.empty_field",
+        zh: "注入配置的字段名为空: {}",
+        en: "Injection has an empty field name: {}",
+    },
+    Message {
+        key: "inject.times_invalid",
+        zh: "注入持续次数必须是有效的数字: {}: {}",
+        en: "Injection repeat count must be a valid number: {}: {}",
+    },
+    Message {
+        key: "inject.secs_invalid",
+        zh: "注入时间点必须是有效的秒数: {}: {}",
+        en: "Injection time must be a valid number of seconds: {}: {}",
+    },
+    Message {
+        key: "inject.send_invalid",
+        zh: "注入发送次数必须是有效的数字: {}: {}",
+        en: "Injection send number must be a valid number: {}: {}",
+    },
+    Message {
+        key: "banner.geo_track",
+        zh: "🧭 轨迹字段 '{}': 起点 {}, 速度 {} 米/秒, {}",
+        en: "🧭 Track field '{}': start {}, speed {} m/s, {}",
+    },
+    Message {
+        key: "banner.geo_track_random",
+        zh: "随机游走",
+        en: "random walk",
+    },
+    Message {
+        key: "banner.geo_track_waypoints",
+        zh: "{} 个路径点",
+        en: "{} waypoints",
+    },
+    Message {
+        key: "banner.injection",
+        zh: "💉 注入计划: 字段 '{}' = {} ({}, 持续 {} 次)",
+        en: "💉 Injection plan: field '{}' = {} ({}, for {} sends)",
+    },
+    Message {
+        key: "banner.random_walk",
+        zh: "🎲 随机游走字段 '{}': 步长标准差 {}, {}",
+        en: "🎲 Random walk field '{}': step std {}, {}",
+    },
+    Message {
+        key: "walk.unbounded",
+        zh: "无上下界",
+        en: "unbounded",
+    },
+    Message {
+        key: "walk.reflect",
+        zh: "范围 [{}, {}] 撞界反弹",
+        en: "range [{}, {}], reflect at bounds",
+    },
+    Message {
+        key: "walk.clamp",
+        zh: "范围 [{}, {}] 越界截断",
+        en: "range [{}, {}], clamp at bounds",
+    },
+    Message {
+        key: "banner.field_group",
+        zh: "⏲️ 字段组 '{}': 每 {} 秒发送, 字段 {}",
+        en: "⏲️ Field group '{}': every {} s, fields {}",
+    },
+    Message {
+        key: "banner.idempotency",
+        zh: "🧷 幂等键: {}，请求头 {}{}",
+        en: "🧷 Idempotency key: {}, header {}{}",
+    },
+    Message {
+        key: "banner.idempotency_field",
+        zh: "，同时写入字段 '{}'",
+        en: ", also written to field '{}'",
+    },
+    Message {
+        key: "banner.token_pattern",
+        zh: "🔑 按模式生成了 {} 个设备令牌: {} ... {}",
+        en: "🔑 Generated {} device tokens from pattern: {} ... {}",
+    },
+    Message {
+        key: "banner.webhook",
+        zh: "🔔 Webhook回调: {} ({})",
+        en: "🔔 Webhook: {} ({})",
+    },
+    Message {
+        key: "banner.webhook_per_round",
+        zh: "每轮",
+        en: "per round",
+    },
+    Message {
+        key: "banner.webhook_per_item",
+        zh: "每条",
+        en: "per item",
+    },
+    Message {
+        key: "banner.client_failed",
+        zh: "无法创建HTTP客户端",
+        en: "Failed to create HTTP client",
+    },
+    Message {
+        key: "banner.device_rotation",
+        zh: "{}(轮换{}个令牌)",
+        en: "{}(rotating {} tokens)",
+    },
+    Message {
+        key: "banner.device_pool",
+        zh: "令牌池({})",
+        en: "token pool({})",
+    },
+    Message {
+        key: "banner.start",
+        zh: "🚀 开始发送数据，每轮 {} 条",
+        en: "🚀 Sending started, {} items per round",
+    },
+    // 命令行参数
+    Message {
+        key: "arg.send_arg_with_subcommand",
+        zh: "发送参数 --{} 只能用于 send，不能和子命令 '{}' 一起使用",
+        en: "send argument --{} can only be used with send, not with the '{}' subcommand",
+    },
+    Message {
+        key: "arg.interval_invalid",
+        zh: "间隔时间必须是有效的数字",
+        en: "Interval must be a valid number",
+    },
+    Message {
+        key: "arg.interval_invalid_named",
+        zh: "--{} 必须是有效的秒数",
+        en: "--{} must be a valid number of seconds",
+    },
+    Message {
+        key: "arg.count_invalid",
+        zh: "发送次数必须是有效的数字",
+        en: "Count must be a valid number",
+    },
+    Message {
+        key: "arg.syslog_app_name_invalid",
+        zh: "syslog APP-NAME必须是1~48个可见ASCII字符: {}",
+        en: "syslog APP-NAME must be 1 to 48 visible ASCII characters: {}",
+    },
+    Message {
+        key: "arg.plan_limit_invalid",
+        zh: "预览条数必须是有效的数字",
+        en: "Preview limit must be a valid number",
+    },
+    Message {
+        key: "arg.lb_max_failures_invalid",
+        zh: "摘除阈值必须是有效的数字",
+        en: "Eviction threshold must be a valid number",
+    },
+    Message {
+        key: "arg.lb_cooldown_invalid",
+        zh: "摘除时长必须是有效的数字",
+        en: "Eviction time must be a valid number",
+    },
+    Message {
+        key: "arg.progress_every_invalid",
+        zh: "心跳间隔必须是有效的数字",
+        en: "Heartbeat interval must be a valid number",
+    },
+    Message {
+        key: "arg.progress_every_zero",
+        zh: "心跳间隔必须大于0，不需要心跳时请使用 --no-heartbeat",
+        en: "Heartbeat interval must be greater than 0; use --no-heartbeat to turn heartbeats off",
+    },
+    Message {
+        key: "arg.rate_invalid",
+        zh: "发送速率必须是有效的数字",
+        en: "Rate must be a valid number",
+    },
+    Message {
+        key: "arg.rate_not_positive",
+        zh: "发送速率必须大于0",
+        en: "Rate must be greater than 0",
+    },
+    Message {
+        key: "arg.warmup_invalid",
+        zh: "预热时长必须是有效的数字",
+        en: "Warm-up time must be a valid number",
+    },
+    Message {
+        key: "arg.warmup_negative",
+        zh: "预热时长不能为负数",
+        en: "Warm-up time cannot be negative",
+    },
+    Message {
+        key: "arg.seed_invalid",
+        zh: "随机种子必须是有效的非负整数",
+        en: "Seed must be a valid non-negative integer",
+    },
+    Message {
+        key: "arg.response_max_len_invalid",
+        zh: "响应体截断长度必须是有效的数字",
+        en: "Response body length limit must be a valid number",
+    },
+    Message {
+        key: "arg.size_invalid",
+        zh: "无效的大小: {}: {}",
+        en: "Invalid size: {}: {}",
+    },
+    Message {
+        key: "arg.size_unit_unknown",
+        zh: "无法识别的大小单位 '{}'，可用 B、KB、MB、GB",
+        en: "Unrecognized size unit '{}'; use B, KB, MB or GB",
+    },
+    Message {
+        key: "arg.header_name_invalid",
+        zh: "无效的请求头名称: {}",
+        en: "Invalid header name: {}",
+    },
+    Message {
+        key: "arg.audit_max_size_invalid",
+        zh: "审计文件大小上限无效",
+        en: "Invalid audit file size limit",
+    },
+    Message {
+        key: "arg.audit_max_files_invalid",
+        zh: "审计文件保留个数必须是有效的数字",
+        en: "Number of audit files to keep must be a valid number",
+    },
+    Message {
+        key: "arg.max_failures_invalid",
+        zh: "连续失败次数必须是大于0的整数",
+        en: "Consecutive failure limit must be an integer greater than 0",
+    },
+    Message {
+        key: "arg.notify_on_failures_invalid",
+        zh: "失败通知间隔必须是大于0的整数",
+        en: "Failure notification interval must be an integer greater than 0",
+    },
+    // 配置加载
+    Message {
+        key: "config.profile_not_found",
+        zh: "配置文件 {} 中未找到配置档 '{}'",
+        en: "Profile '{1}' not found in config file {0}",
+    },
+    Message {
+        key: "config.file_read_failed",
+        zh: "无法读取配置文件: {}",
+        en: "Failed to read config file: {}",
+    },
+    Message {
+        key: "config.file_parse_failed",
+        zh: "无法解析配置文件: {}",
+        en: "Failed to parse config file: {}",
+    },
+    Message {
+        key: "config.secret_conflict",
+        zh: "'{}' 和 '{}' 不能同时设置",
+        en: "'{}' and '{}' cannot both be set",
+    },
+    Message {
+        key: "config.secret_file_failed",
+        zh: "无法读取 '{}' 指向的文件: {}",
+        en: "Failed to read the file referenced by '{}': {}",
+    },
+    Message {
+        key: "config.server_missing",
+        zh: "未找到环境变量 'server'",
+        en: "Environment variable 'server' not found",
+    },
+    Message {
+        key: "config.server_empty",
+        zh: "环境变量 'server' 中没有有效的服务器地址",
+        en: "Environment variable 'server' contains no valid server address",
+    },
+    Message {
+        key: "config.token_missing",
+        zh: "未找到环境变量 'device_token' 或 'device_token_FILE'",
+        en: "Environment variable 'device_token' or 'device_token_FILE' not found",
+    },
+    Message {
+        key: "config.server_invalid",
+        zh: "服务器地址 '{}' 不是合法的URL",
+        en: "Server address '{}' is not a valid URL",
+    },
+    Message {
+        key: "config.server_scheme",
+        zh: "服务器地址 '{}' 的协议 '{}' 不受支持，只支持 http 和 https",
+        en: "Server address '{}' uses unsupported scheme '{}', only http and https are supported",
+    },
+    Message {
+        key: "config.server_no_host",
+        zh: "服务器地址 '{}' 中缺少主机名",
+        en: "Server address '{}' has no host",
+    },
+    Message {
+        key: "config.server_query",
+        zh: "服务器地址 '{}' 不应包含查询参数或片段",
+        en: "Server address '{}' must not contain a query or fragment",
+    },
+    Message {
+        key: "config.token_empty",
+        zh: "环境变量 'device_token' 中没有有效的设备令牌",
+        en: "Environment variable 'device_token' contains no valid device token",
+    },
+    Message {
+        key: "config.token_short",
+        zh: "⚠️ 设备令牌 {} 只有 {} 个字符，ThingsBoard 的令牌通常为 {} 个字符，请检查是否填错",
+        en: "⚠️ Device token {} has only {} characters; ThingsBoard tokens are usually {} characters, please check for typos",
+    },
+    // 令牌模式
+    Message {
+        key: "token_pattern.range_invalid",
+        zh: "令牌序号范围 '{}' 格式不正确，应为 起点..终点（闭区间，起点不大于终点），如 1..1000",
+        en: "Invalid token range '{}', expected START..END (inclusive, START <= END), e.g. 1..1000",
+    },
+    Message {
+        key: "token_pattern.too_many",
+        zh: "令牌序号范围包含 {} 个令牌，超过上限 {}",
+        en: "Token range covers {} tokens, exceeding the limit of {}",
+    },
+    Message {
+        key: "token_pattern.no_placeholder",
+        zh: "令牌模式 '{}' 中没有有效的 {n} 或 {n:宽度} 占位符",
+        en: "Token pattern '{}' has no valid {n} or {n:WIDTH} placeholder",
+    },
+    Message {
+        key: "token_pattern.invalid_chars",
+        zh: "按模式生成的令牌 '{}' 包含字母、数字、'-'、'_' 以外的字符",
+        en: "Generated token '{}' contains characters other than letters, digits, '-' and '_'",
+    },
+    Message {
+        key: "token_pool.read_failed",
+        zh: "无法读取令牌池文件: {}",
+        en: "Failed to read token pool file: {}",
+    },
+    Message {
+        key: "token_pool.read_failed_at",
+        zh: "无法从令牌池文件 {} 读取第 {} 个令牌",
+        en: "Failed to read token {1} from token pool file {0}",
+    },
+    Message {
+        key: "token_pool.empty",
+        zh: "令牌池文件 {} 中没有有效的设备令牌",
+        en: "Token pool file {} contains no valid device tokens",
+    },
+    Message {
+        key: "token_pool.loaded",
+        zh: "🔑 令牌池加载成功: {} 个有效令牌, 跳过空行/注释 {} 行, 无效行 {} 行{}",
+        en: "🔑 Token pool loaded: {} valid tokens, {} blank/comment lines skipped, {} invalid lines{}",
+    },
+    Message {
+        key: "token_pool.lazy",
+        zh: "（按需从文件读取）",
+        en: " (read from the file on demand)",
+    },
+    // 数据文件
+    Message {
+        key: "data.format_hint",
+        zh: "请检查格式，支持直接数组 [{\"sensor\": {...}}] 或包装对象 {\"random_key\": \"...\", \"data\": [{\"sensor\": {...}}]}",
+        en: "Supported formats are a plain array [{\"sensor\": {...}}] or a wrapper object {\"random_key\": \"...\", \"data\": [{\"sensor\": {...}}]}",
+    },
+    Message {
+        key: "data.read_failed",
+        zh: "无法读取数据文件: {}",
+        en: "Failed to read data file: {}",
+    },
+    Message {
+        key: "data.parse_failed",
+        zh: "无法解析JSON数据文件: {}",
+        en: "Failed to parse JSON data file: {}",
+    },
+    Message {
+        key: "data.empty_file",
+        zh: "数据文件 {} 是空文件。{}",
+        en: "Data file {} is empty. {}",
+    },
+    Message {
+        key: "data.include_cycle",
+        zh: "数据文件存在循环引用: {}",
+        en: "Circular include in data files: {}",
+    },
+    Message {
+        key: "data.include_item_type",
+        zh: "数据文件 {} 中的 '{}' 必须是路径字符串，实际为{}",
+        en: "'{1}' in data file {0} must contain path strings, found {2}",
+    },
+    Message {
+        key: "data.include_type",
+        zh: "数据文件 {} 中的 '{}' 必须是路径字符串或路径数组，实际为{}",
+        en: "'{1}' in data file {0} must be a path string or an array of paths, found {2}",
+    },
+    Message {
+        key: "data.include_failed",
+        zh: "数据文件 {} 引用的 {} 加载失败",
+        en: "Failed to load {1} included by data file {0}",
+    },
+    Message {
+        key: "data.include_not_object",
+        zh: "数据文件 {} 引用的 {} 必须是JSON对象，实际为{}",
+        en: "{1} included by data file {0} must be a JSON object, found {2}",
+    },
+    Message {
+        key: "data.format_array",
+        zh: "直接数组格式",
+        en: "plain array format",
+    },
+    Message {
+        key: "data.format_object",
+        zh: "包装对象格式",
+        en: "wrapper object format",
+    },
+    Message {
+        key: "data.array_random_key",
+        zh: "⚠️ 数据文件 {} 的第 {} 个数据项包含 'random_key' 字段：数组格式不支持该设置，它会被当作普通遥测字段发送。请改用包装对象格式或 --random-key",
+        en: "⚠️ Item {1} of data file {0} contains a 'random_key' field: the array format does not support it and it will be sent as an ordinary telemetry field. Use the wrapped-object format or --random-key",
+    },
+    Message {
+        key: "data.detected",
+        zh: "🔍 检测到{}的数据文件",
+        en: "🔍 Detected data file in {}",
+    },
+    Message {
+        key: "data.data_field_empty",
+        zh: "数据文件 {} 的 'data' 字段是空数组（检测到{}），请至少添加一条数据。{}",
+        en: "The 'data' field of data file {} is an empty array ({}); add at least one item. {}",
+    },
+    Message {
+        key: "data.data_field_missing",
+        zh: "数据文件 {} 中未找到 'data' 字段或 'data' 不是数组（检测到{}）。{}",
+        en: "Data file {} has no 'data' field or it is not an array ({}). {}",
+    },
+    Message {
+        key: "data.random_config_invalid",
+        zh: "数据文件 {} 中的 'random_config' 格式不正确",
+        en: "'random_config' in data file {} is malformed",
+    },
+    Message {
+        key: "data.attribute_fields_invalid",
+        zh: "数据文件 {} 中的 'attribute_fields' 必须是字符串数组",
+        en: "'attribute_fields' in data file {} must be an array of strings",
+    },
+    Message {
+        key: "data.unsupported",
+        zh: "数据文件 {} 的格式不受支持（检测到顶层为{}）。{}",
+        en: "Data file {} has an unsupported format (top level is {}). {}",
+    },
+    Message {
+        key: "data.array_empty",
+        zh: "数据文件 {} 中的数组为空（检测到{}），请至少添加一条数据。{}",
+        en: "The array in data file {} is empty ({}); add at least one item. {}",
+    },
+    Message {
+        key: "data.unusable_skipped",
+        zh: "⚠️ {} 中有 {} 个数据项是空对象或不是JSON对象，已跳过（第 {} 项）",
+        en: "⚠️ {} has {} items that are empty or not JSON objects; skipped (items {})",
+    },
+    Message {
+        key: "data.unusable_strict",
+        zh: "{} 中有 {} 个数据项是空对象或不是JSON对象（第 {} 项），--strict 模式下不允许",
+        en: "{} has {} items that are empty or not JSON objects (items {}), not allowed with --strict",
+    },
+    Message {
+        key: "data.invalid_item",
+        zh: "⚠️ 第 {} 个数据项无法发送: {}",
+        en: "⚠️ Item {} cannot be sent: {}",
+    },
+    Message {
+        key: "data.invalid_strict",
+        zh: "{} 中有 {} 个数据项无法发送（见上方列出的原因），--strict 模式下不允许",
+        en: "{} has {} items that cannot be sent (reasons listed above), not allowed with --strict",
+    },
+    Message {
+        key: "data.no_valid_items",
+        zh: "{} 中没有可以发送的数据项",
+        en: "{} has no items that can be sent",
+    },
+    Message {
+        key: "data.item_unusable",
+        zh: "是空对象或不是JSON对象",
+        en: "empty or not a JSON object",
+    },
+    Message {
+        key: "banner.items_valid",
+        zh: "✅ 发送前检查: {} / {} 个数据项可以发送",
+        en: "✅ Pre-flight check: {} of {} items valid",
+    },
+    Message {
+        key: "data.no_usable_items",
+        zh: "{} 中的数据项都是空对象，没有可发送的数据",
+        en: "Every item in {} is an empty object, nothing to send",
+    },
+    Message {
+        key: "data.no_objects",
+        zh: "数据文件 {} 中的 {} 条数据都不是JSON对象（检测到{}），过滤后没有可发送的数据。{}",
+        en: "None of the {1} items in data file {0} is a JSON object ({2}), nothing left to send. {3}",
+    },
+    Message {
+        key: "json.error_at",
+        zh: "第 {} 行第 {} 列: {}",
+        en: "line {} column {}: {}",
+    },
+    Message {
+        key: "json.hint_trailing_comma",
+        zh: "💡 JSON不允许在最后一个元素后面加逗号，删除这个位置前面的逗号",
+        en: "💡 JSON does not allow a comma after the last element; remove the comma before this position",
+    },
+    Message {
+        key: "json.hint_unclosed",
+        zh: "💡 文件在解析完成前结束，检查是否缺少 ] 或 }，或者有未闭合的引号",
+        en: "💡 The file ended early; check for a missing ] or }, or an unclosed quote",
+    },
+    Message {
+        key: "json.hint_single_quote",
+        zh: "💡 JSON字符串和键名必须使用双引号，不能使用单引号",
+        en: "💡 JSON strings and keys must use double quotes, not single quotes",
+    },
+    Message {
+        key: "json.hint_comment",
+        zh: "💡 JSON不支持注释，删除 // 、/* */ 或 # 开头的内容",
+        en: "💡 JSON does not support comments; remove anything starting with //, /* */ or #",
+    },
+    Message {
+        key: "json.hint_unquoted_key",
+        zh: "💡 键名必须用双引号括起来，如 \"temperature\": 25",
+        en: "💡 Keys must be wrapped in double quotes, e.g. \"temperature\": 25",
+    },
+    Message {
+        key: "json.hint_missing_comma",
+        zh: "💡 上一个元素后面可能缺少逗号，或者字符串缺少结束的引号",
+        en: "💡 A comma may be missing after the previous element, or a string is missing its closing quote",
+    },
+    Message { key: "json.null", zh: "null", en: "null" },
+    Message { key: "json.bool", zh: "布尔值", en: "a boolean" },
+    Message { key: "json.number", zh: "数字", en: "a number" },
+    Message { key: "json.string", zh: "字符串", en: "a string" },
+    Message { key: "json.array", zh: "数组", en: "an array" },
+    Message { key: "json.object", zh: "对象", en: "an object" },
+    // 发送
+    Message {
+        key: "send.stopping",
+        zh: "⛔ 收到中断信号，不再发送新的数据，等待进行中的发送完成（再按一次 Ctrl-C 立即退出）",
+        en: "⛔ Interrupt received: no new sends will start, waiting for in-flight sends (press Ctrl-C again to exit immediately)",
+    },
+    Message {
+        key: "send.force_exit",
+        zh: "⛔ 再次收到中断信号，立即退出",
+        en: "⛔ Second interrupt received, exiting immediately",
+    },
+    Message {
+        key: "send.clock_failed",
+        zh: "无法获取系统时间",
+        en: "Failed to read the system clock",
+    },
+    Message {
+        key: "send.empty_values",
+        zh: "未能提取到有效的遥测数据",
+        en: "No telemetry values could be extracted",
+    },
+    Message {
+        key: "send.not_object",
+        zh: "数据格式不正确，期望JSON对象",
+        en: "Invalid data item, expected a JSON object",
+    },
+    Message {
+        key: "send.serialize_attributes_failed",
+        zh: "无法序列化属性数据",
+        en: "Failed to serialize attributes",
+    },
+    Message {
+        key: "send.serialize_telemetry_failed",
+        zh: "无法序列化遥测数据",
+        en: "Failed to serialize telemetry",
+    },
+    Message {
+        key: "send.request_failed",
+        zh: "发送HTTP请求失败: {}",
+        en: "HTTP request to {} failed",
+    },
+    Message {
+        key: "send.request_timeout",
+        zh: "HTTP请求超时: {}",
+        en: "HTTP request to {} timed out",
+    },
+    Message {
+        key: "send.http_status",
+        zh: "HTTP请求失败: {} - {}",
+        en: "HTTP request failed: {} - {}",
+    },
+    Message {
+        key: "send.http_redirect_method",
+        zh: "HTTP请求失败: 服务器返回重定向 {}（目标 {}）。该重定向会把POST改为GET并丢弃数据，因此未跟随，请把服务器地址改为 {}",
+        en: "HTTP request failed: server redirected with {} (to {}). This redirect turns the POST into a GET without the data, so it was not followed; update your server URL to {}",
+    },
+    Message {
+        key: "send.too_many_redirects",
+        zh: "重定向超过 {} 次（--max-redirects），已停止跟随",
+        en: "more than {} redirects (--max-redirects), stopped following",
+    },
+    Message {
+        key: "send.http_redirect",
+        zh: "HTTP请求失败: 服务器返回重定向 {}（目标 {}），未跟随（--max-redirects）。请确认服务器地址，例如是否应使用 https",
+        en: "HTTP request failed: server redirected with {} (to {}), not followed (--max-redirects). Check the server URL, e.g. whether it should use https",
+    },
+    Message {
+        key: "send.http_status_empty",
+        zh: "HTTP请求失败: 服务器返回 {} 但无响应体，可能是网关或代理错误",
+        en: "HTTP request failed: server returned {} with an empty body, possibly a gateway or proxy error",
+    },
+    Message {
+        key: "send.http_status_headers",
+        zh: "（响应头 {}）",
+        en: " (response headers {})",
+    },
+    Message {
+        key: "send.sent",
+        zh: "📤 数据发送成功!",
+        en: "📤 Data sent!",
+    },
+    Message {
+        key: "send.sent_time",
+        zh: "🕒 发送时间: {}",
+        en: "🕒 Sent at: {}",
+    },
+    Message {
+        key: "send.sent_values",
+        zh: "📊 发送数据: {}",
+        en: "📊 Telemetry: {}",
+    },
+    Message {
+        key: "send.sent_attributes",
+        zh: "🏷️ 属性数据: {}",
+        en: "🏷️ Attributes: {}",
+    },
+    Message {
+        key: "send.dry_run_post",
+        zh: "🧪 [演练，未发送] POST {}",
+        en: "🧪 [dry run, not sent] POST {}",
+    },
+    Message {
+        key: "send.dry_run_payload",
+        zh: "📊 负载内容: {}",
+        en: "📊 Payload: {}",
+    },
+    Message {
+        key: "response.status",
+        zh: "📨 响应({}): {}",
+        en: "📨 Response ({}): {}",
+    },
+    Message {
+        key: "response.body",
+        zh: "   响应体: {}",
+        en: "   Body: {}",
+    },
+    Message {
+        key: "response.empty",
+        zh: "<空>",
+        en: "<empty>",
+    },
+    Message {
+        key: "response.truncated",
+        zh: "...（已截断 {} 个字符）",
+        en: "... ({} more characters truncated)",
+    },
+    Message {
+        key: "send.not_due",
+        zh: "⏲️ 数据项 {}/{} 本次没有到期的字段组，已跳过",
+        en: "⏲️ Item {}/{} has no field group due this time, skipped",
+    },
+    Message {
+        key: "send.skipped",
+        zh: "⏭️ 数据项 {}/{} 提取结果为空，已跳过",
+        en: "⏭️ Item {}/{} produced no values, skipped",
+    },
+    Message {
+        key: "send.workers_stopped",
+        zh: "发送工作者已意外退出",
+        en: "Send workers exited unexpectedly",
+    },
+    Message {
+        key: "send.merged_sent",
+        zh: "📤 已合并发送 {} 个数据点 (ts {} ... {})",
+        en: "📤 Sent {} merged data points (ts {} ... {})",
+    },
+    Message {
+        key: "send.merged_succeeded",
+        zh: "✅ 合并发送成功: {} 个数据点 - 数据项 {}-{}/{} ({} ms)",
+        en: "✅ Merged send succeeded: {} data points - items {}-{}/{} ({} ms)",
+    },
+    Message {
+        key: "send.merged_generated",
+        zh: "🧪 合并负载已生成（未发送）: {} 个数据点 - 数据项 {}-{}/{}",
+        en: "🧪 Merged payload generated (not sent): {} data points - items {}-{}/{}",
+    },
+    Message {
+        key: "send.merged_skipped",
+        zh: "⏭️ 数据项 {}-{}/{} 都为空，本批已跳过",
+        en: "⏭️ Items {}-{}/{} are all empty, batch skipped",
+    },
+    Message {
+        key: "send.merged_failed",
+        zh: "❌ 合并发送失败 - 数据项 {}-{}/{} ({} ms): {}",
+        en: "❌ Merged send failed - items {}-{}/{} after {} ms: {}",
+    },
+    Message {
+        key: "send.merge_ts_invalid",
+        zh: "记录的 ts 字段 {} 不是毫秒时间戳（非负整数）",
+        en: "Record field ts {} is not a millisecond timestamp (non-negative integer)",
+    },
+    Message {
+        key: "send.out_of_range",
+        zh: "⚠️ 字段 '{}' 的值 {} 超出合理范围 {}",
+        en: "⚠️ Field '{}' value {} is outside the valid range {}",
+    },
+    Message {
+        key: "send.out_of_range_clamped",
+        zh: "⚠️ 字段 '{}' 的值 {} 超出合理范围 {}，已截断为 {}",
+        en: "⚠️ Field '{}' value {} is outside the valid range {}, clamped to {}",
+    },
+    Message {
+        key: "send.non_finite",
+        zh: "字段 '{}' 的值 {} 不是有限数，JSON无法表示",
+        en: "Field '{}' has non-finite value {}, which JSON cannot represent",
+    },
+    Message {
+        key: "send.non_finite_dropped",
+        zh: "⚠️ 字段 '{}' 的值 {} 不是有限数，本次已省略该字段",
+        en: "⚠️ Field '{}' has non-finite value {}, omitted this time",
+    },
+    Message {
+        key: "send.generated",
+        zh: "🧪 第{}条负载已生成（未发送） - 数据项 {}/{}",
+        en: "🧪 Payload #{} generated (not sent) - item {}/{}",
+    },
+    Message {
+        key: "send.succeeded",
+        zh: "✅ 第{}次发送成功 - 数据项 {}/{} ({} ms，响应头 {} ms)",
+        en: "✅ Send #{} succeeded - item {}/{} ({} ms, headers {} ms)",
+    },
+    Message {
+        key: "send.failed",
+        zh: "❌ 发送失败 ({} ms): {}",
+        en: "❌ Send failed after {} ms: {}",
+    },
+    Message {
+        key: "send.requeued",
+        zh: "🔁 第 {} 条已放回本轮队列末尾，稍后重试 ({}/{})",
+        en: "🔁 Item {} moved to the end of this round's queue for a later retry ({}/{})",
+    },
+    Message {
+        key: "banner.requeue",
+        zh: "🔁 失败的记录放回本轮队列末尾重试，每条最多 {} 次",
+        en: "🔁 Failed records are retried at the end of the round, at most {} times each",
+    },
+    Message {
+        key: "send.fail_fast_stop",
+        zh: "⛔ 已开启 --fail-fast，首次发送失败后立即停止",
+        en: "⛔ --fail-fast is set, stopping after the first failure",
+    },
+    Message {
+        key: "send.throttled",
+        zh: "🐢 服务器限流（HTTP 429），所有发送暂停 {} 秒后重发同一条数据（{}，第 {} 次）",
+        en: "🐢 Server is throttling (HTTP 429); all sends pause for {} s, then the same item is retried ({}, attempt {})",
+    },
+    Message {
+        key: "send.throttle_retry_after",
+        zh: "按 Retry-After 响应头",
+        en: "as requested by Retry-After",
+    },
+    Message {
+        key: "send.throttle_backoff",
+        zh: "响应没有 Retry-After，按指数退避",
+        en: "no Retry-After, exponential backoff",
+    },
+    Message {
+        key: "send.auth_failed_stop",
+        zh: "⛔ 认证失败（HTTP {}）：设备令牌 {} 很可能无效或对应的设备已被删除，请检查令牌和服务器地址 {}。已停止发送，确需继续时使用 --ignore-auth-errors",
+        en: "⛔ Authentication failed (HTTP {}): device token {} is most likely invalid or its device was deleted; check the token and the server URL {}. Sending stopped; use --ignore-auth-errors to keep going",
+    },
+    Message {
+        key: "send.auth_failed_stop_edge",
+        zh: "⛔ 认证失败（HTTP {}）：Edge 不认识设备令牌 {}。请在云端确认设备已分配给该 Edge，且 Edge 已与云端连接并完成同步（云端新建或修改的令牌同步前 Edge 不可用），再检查服务器地址 {}。已停止发送，确需继续时使用 --ignore-auth-errors",
+        en: "⛔ Authentication failed (HTTP {}): the Edge does not know device token {}. On the cloud, make sure the device is assigned to this Edge and that the Edge is connected and synced (tokens created or changed on the cloud are unknown until synced), then check the server URL {}. Sending stopped; use --ignore-auth-errors to keep going",
+    },
+    Message {
+        key: "send.consecutive_failures_stop",
+        zh: "⛔ 已连续 {} 次发送失败，停止发送",
+        en: "⛔ {} consecutive failures, stopping",
+    },
+    Message {
+        key: "send.next_round",
+        zh: "⏳ 等待 {} 秒后继续下一轮发送...",
+        en: "⏳ Waiting {} s before the next round...",
+    },
+    // 负载均衡、审计、报告、日志文件
+    Message {
+        key: "lb.restored",
+        zh: "♻️ 服务器 {} 冷却结束，重新加入负载均衡",
+        en: "♻️ Server {} cooled down and rejoined the pool",
+    },
+    Message {
+        key: "lb.evicted",
+        zh: "⚠️ 服务器 {} 连续失败 {} 次，暂时摘除 {} 秒",
+        en: "⚠️ Server {} failed {} times in a row, evicted for {} s",
+    },
+    Message {
+        key: "audit.open_failed",
+        zh: "无法打开审计文件: {}",
+        en: "Failed to open audit file: {}",
+    },
+    Message {
+        key: "audit.write_failed",
+        zh: "⚠️ 写入审计文件 {} 失败: {}",
+        en: "⚠️ Failed to write audit file {}: {}",
+    },
+    Message {
+        key: "audit.rotate_failed",
+        zh: "⚠️ 轮转审计文件 {} 失败，继续写入当前文件: {}",
+        en: "⚠️ Failed to rotate audit file {}, still writing to the current file: {}",
+    },
+    Message {
+        key: "audit.rename_failed",
+        zh: "无法把审计文件改名为 {}",
+        en: "Failed to rename the audit file to {}",
+    },
+    Message {
+        key: "audit.remove_failed",
+        zh: "无法删除旧的审计文件 {}",
+        en: "Failed to remove old audit file {}",
+    },
+    Message {
+        key: "report.create_failed",
+        zh: "无法创建CSV报告: {}",
+        en: "Failed to create CSV report: {}",
+    },
+    Message {
+        key: "report.write_failed",
+        zh: "无法写入CSV报告: {}",
+        en: "Failed to write CSV report: {}",
+    },
+    Message {
+        key: "tee.create_failed",
+        zh: "无法创建数据副本文件: {}",
+        en: "Failed to create the tee file: {}",
+    },
+    Message {
+        key: "tee.write_failed",
+        zh: "⚠️ 写入数据副本 {} 失败: {}",
+        en: "⚠️ Failed to write tee file {}: {}",
+    },
+    Message {
+        key: "report.row_failed",
+        zh: "⚠️ 写入CSV报告 {} 失败: {}",
+        en: "⚠️ Failed to write CSV report {}: {}",
+    },
+    Message {
+        key: "log.open_failed",
+        zh: "无法打开日志文件: {}",
+        en: "Failed to open log file: {}",
+    },
+    // 运行汇总
+    Message {
+        key: "heartbeat.message",
+        zh: "💓 心跳: 已完成 {} 条, 成功率 {}%, 近期延迟 {} ms, 近期速率 {} 条/秒",
+        en: "💓 Heartbeat: {} done, {}% succeeded, recent latency {} ms, recent rate {} msg/s",
+    },
+    Message {
+        key: "heartbeat.total",
+        zh: ", 进度 {}%, 预计剩余 {}",
+        en: ", {}% complete, ETA {}",
+    },
+    Message {
+        key: "summary.done",
+        zh: "🎉 数据发送完成！总共发送了 {} 条数据",
+        en: "🎉 Done! {} messages sent in total",
+    },
+    Message {
+        key: "summary.interrupted",
+        zh: "⛔ 发送已中断！总共发送了 {} 条数据",
+        en: "⛔ Interrupted! {} messages sent in total",
+    },
+    Message {
+        key: "summary.dry_run_done",
+        zh: "🧪 演练完成！0 条已发送（dry run），生成了 {} 条数据",
+        en: "🧪 Dry run finished! 0 sent (dry run), {} payloads generated",
+    },
+    Message {
+        key: "summary.dry_run_interrupted",
+        zh: "⛔ 演练已中断！0 条已发送（dry run），生成了 {} 条数据",
+        en: "⛔ Dry run interrupted! 0 sent (dry run), {} payloads generated",
+    },
+    Message {
+        key: "summary.counts",
+        zh: "📊 尝试 {} 条, 发送成功 {} 条, 失败 {} 条, 跳过 {} 条",
+        en: "📊 Attempted {}, sent {}, failed {}, skipped {}",
+    },
+    Message {
+        key: "summary.counts_dry_run",
+        zh: "📊 尝试 {} 条, 生成成功 {} 条, 失败 {} 条, 跳过 {} 条",
+        en: "📊 Attempted {}, generated {}, failed {}, skipped {}",
+    },
+    Message {
+        key: "summary.bytes",
+        zh: "📦 发送 {} 字节, 耗时 {} 秒, 平均 {} 条/秒",
+        en: "📦 Sent {} bytes in {} s, {} msg/s on average",
+    },
+    Message {
+        key: "summary.bytes_dry_run",
+        zh: "📦 生成 {} 字节, 耗时 {} 秒, 平均 {} 条/秒",
+        en: "📦 Generated {} bytes in {} s, {} msg/s on average",
+    },
+    Message {
+        key: "summary.latency",
+        zh: "⏱️ 延迟(ms): min {} / mean {} / p50 {} / p95 {} / p99 {} / max {}",
+        en: "⏱️ Latency (ms): min {} / mean {} / p50 {} / p95 {} / p99 {} / max {}",
+    },
+    Message {
+        key: "summary.timestamps_adjusted",
+        zh: "🕒 {} 个遥测时间戳与之前的消息落在同一毫秒，已顺延以免被ThingsBoard覆盖（--raw-timestamps 关闭）",
+        en: "🕒 {} telemetry timestamps collided with earlier messages and were bumped so ThingsBoard keeps them (disable with --raw-timestamps)",
+    },
+    Message {
+        key: "summary.throttled",
+        zh: "🐢 服务器限流 {} 次，累计等待 {} 秒（限流重试成功的数据不计入失败）",
+        en: "🐢 Throttled by the server {} times, waited {} s in total (items that succeeded after retrying are not counted as failed)",
+    },
+    Message {
+        key: "summary.clock_adjust",
+        zh: "🕰️ 时间戳补偿的时钟偏移: {} ms",
+        en: "🕰️ Clock offset applied to timestamps: {} ms",
+    },
+    Message {
+        key: "summary.server_offset",
+        zh: "🕰️ 服务器时间差(ms): min {} / mean {} / max {} ({} 个样本，Date头精度为1秒)",
+        en: "🕰️ Server clock offset (ms): min {} / mean {} / max {} ({} samples, Date header has 1 s precision)",
+    },
+    Message {
+        key: "summary.requeued",
+        zh: "🔁 失败后重新入队: {} 次，其中 {} 次失败随后重试成功",
+        en: "🔁 Requeued after failure: {} times, {} failures later recovered",
+    },
+    Message {
+        key: "summary.failures",
+        zh: "❌ 失败原因:",
+        en: "❌ Failure reasons:",
+    },
+    Message {
+        key: "summary.failure_reason",
+        zh: "   {}: {} 次",
+        en: "   {}: {} times",
+    },
+    Message {
+        key: "summary.tokens",
+        zh: "📊 各令牌发送统计:",
+        en: "📊 Messages per token:",
+    },
+    Message {
+        key: "summary.token_sent",
+        zh: "   {}: {} 条",
+        en: "   {}: {}",
+    },
+    Message {
+        key: "summary.tokens_omitted",
+        zh: "   ... 省略其余 {} 个令牌（单个令牌 最少 {} 条 / 最多 {} 条，{} 个令牌未发送）",
+        en: "   ... {} more tokens omitted (per token min {} / max {}, {} tokens unused)",
+    },
+    Message {
+        key: "summary.tee",
+        zh: "🪞 数据副本已写入 {}，共 {} 条",
+        en: "🪞 Sent data copied to {} ({} lines)",
+    },
+    Message {
+        key: "summary.report_csv",
+        zh: "📄 CSV报告已写入 {}，共 {} 行",
+        en: "📄 CSV report written to {} ({} rows)",
+    },
+    Message {
+        key: "summary.audit_files",
+        zh: "📝 审计文件:",
+        en: "📝 Audit files:",
+    },
+    // 退出码
+    // generate 子命令
+    Message {
+        key: "generate.count_zero",
+        zh: "generate 的轮数必须大于0",
+        en: "generate needs a count greater than 0",
+    },
+    Message {
+        key: "generate.create_failed",
+        zh: "无法创建输出文件: {}",
+        en: "Failed to create output file: {}",
+    },
+    Message {
+        key: "generate.write_failed",
+        zh: "无法写入输出文件: {}",
+        en: "Failed to write output file: {}",
+    },
+    Message {
+        key: "generate.done",
+        zh: "✅ 已生成 {} 行数据，写入 {}",
+        en: "✅ Generated {} rows into {}",
+    },
+    // 完成后命令
+    Message {
+        key: "on_complete.running",
+        zh: "🪝 执行完成后命令: {}",
+        en: "🪝 Running on-complete command: {}",
+    },
+    Message {
+        key: "on_complete.succeeded",
+        zh: "🪝 完成后命令执行成功",
+        en: "🪝 On-complete command succeeded",
+    },
+    Message {
+        key: "on_complete.skipped",
+        zh: "🪝 发送被中断或处于演练模式，跳过完成后命令: {}",
+        en: "🪝 Run was interrupted or is a dry run, skipping on-complete command: {}",
+    },
+    Message {
+        key: "on_complete.spawn_failed",
+        zh: "无法启动完成后命令: {}",
+        en: "Failed to start on-complete command: {}",
+    },
+    Message {
+        key: "on_complete.failed",
+        zh: "完成后命令执行失败: {} ({})",
+        en: "On-complete command failed: {} ({})",
+    },
+    Message {
+        key: "exit.message",
+        zh: "🚦 退出码 {}: {}",
+        en: "🚦 Exit code {}: {}",
+    },
+    Message {
+        key: "exit.no_failures",
+        zh: "没有发送失败",
+        en: "no failed sends",
+    },
+    Message {
+        key: "exit.all_recovered",
+        zh: "{} 次失败的记录重新入队后都已发送成功",
+        en: "all {} failed sends recovered after being requeued",
+    },
+    Message {
+        key: "exit.ignore_failures",
+        zh: "已指定 --ignore-failures，忽略 {} 条失败",
+        en: "--ignore-failures is set, ignoring {} failures",
+    },
+    // 断点续传
+    Message {
+        key: "state.rng_position_invalid",
+        zh: "随机数生成器位置无效: {}: {}",
+        en: "Invalid random generator position: {}: {}",
+    },
+    Message {
+        key: "state.read_failed",
+        zh: "无法读取状态文件: {}",
+        en: "Failed to read state file: {}",
+    },
+    Message {
+        key: "state.corrupt",
+        zh: "状态文件 {} 已损坏或格式不正确，删除它或去掉 --resume 从头开始",
+        en: "State file {} is corrupt or malformed; delete it or drop --resume to start over",
+    },
+    Message {
+        key: "state.version",
+        zh: "状态文件 {} 的格式版本为 {}，本程序只支持版本 {}",
+        en: "State file {} has format version {}, this build only supports version {}",
+    },
+    Message {
+        key: "state.data_changed",
+        zh: "状态文件 {} 记录的数据内容与本次的数据文件不同，不能继续；去掉 --resume 从头开始",
+        en: "State file {} was written for different data than the current data file; drop --resume to start over",
+    },
+    Message {
+        key: "state.option_changed",
+        zh: "状态文件 {} 记录的参数 {} 为 {}，本次为 {}，不能继续；改回原来的参数或去掉 --resume 从头开始",
+        en: "State file {} recorded option {} as {} but this run uses {}; restore it or drop --resume to start over",
+    },
+    Message {
+        key: "state.missing",
+        zh: "⚠️ 状态文件 {} 不存在，从头开始发送",
+        en: "⚠️ State file {} does not exist, starting from the beginning",
+    },
+    Message {
+        key: "state.already_finished",
+        zh: "✅ 状态文件 {} 记录的发送已经全部完成（共尝试 {} 条），没有需要继续的内容",
+        en: "✅ The run recorded in state file {} already finished ({} attempted), nothing to resume",
+    },
+    Message {
+        key: "state.resumed",
+        zh: "⏩ 从第 {} 轮第 {} 条继续发送（之前已尝试 {} 条），状态文件: {}",
+        en: "⏩ Resuming at round {}, item {} ({} attempted before), state file: {}",
+    },
+    Message {
+        key: "state.saved",
+        zh: "💾 发送进度已写入状态文件: {}",
+        en: "💾 Progress written to state file: {}",
+    },
+    Message {
+        key: "state.write_failed",
+        zh: "⚠️ 无法写入状态文件 {}: {}",
+        en: "⚠️ Failed to write state file {}: {}",
+    },
+    // 发送计划预览
+    Message {
+        key: "plan.header",
+        zh: "🗓️ 发送计划预览 (记录间隔 {} 秒, 轮次间隔 {} 秒, 每轮 {} 条):",
+        en: "🗓️ Send plan preview (item interval {} s, round interval {} s, {} items per round):",
+    },
+    Message {
+        key: "plan.header_rate",
+        zh: "🗓️ 发送计划预览 (速率 {} 条/秒, 预热 {} 秒, 每轮 {} 条):",
+        en: "🗓️ Send plan preview (rate {} items/s, warmup {} s, {} items per round):",
+    },
+    Message {
+        key: "plan.random_key",
+        zh: "🎲 随机字段: {}",
+        en: "🎲 Random field: {}",
+    },
+    Message {
+        key: "plan.non_object",
+        zh: "<非对象数据: {}>",
+        en: "<non-object data: {}>",
+    },
+    Message {
+        key: "plan.item",
+        zh: "   #{} {} (+{}s) 第{}轮 数据项 {}/{} 字段: {}",
+        en: "   #{} {} (+{}s) round {} item {}/{} fields: {}",
+    },
+    Message {
+        key: "plan.omitted",
+        zh: "   ... 省略其余 {} 条",
+        en: "   ... {} more omitted",
+    },
+    Message {
+        key: "plan.total",
+        zh: "📋 总计: {} 轮, {} 条数据, 预计耗时 {} 秒, 预计结束于 {}",
+        en: "📋 Total: {} rounds, {} items, about {} s, expected to finish at {}",
+    },
+    Message {
+        key: "plan.total_infinite",
+        zh: "📋 总计: 无限循环, 每轮 {} 条数据, 每轮约 {} 秒",
+        en: "📋 Total: endless loop, {} items per round, about {} s per round",
+    },
+    // 配置热更新
+    Message {
+        key: "reload.listen_failed",
+        zh: "无法监听SIGHUP信号",
+        en: "Failed to listen for SIGHUP",
+    },
+    Message {
+        key: "reload.failed",
+        zh: "⚠️ 收到SIGHUP，但重新读取配置失败，继续使用原配置: {}",
+        en: "⚠️ Got SIGHUP but reloading the config failed; keeping the current config: {}",
+    },
+    Message {
+        key: "reload.reloaded",
+        zh: "🔄 收到SIGHUP，已重新读取配置档 '{}'",
+        en: "🔄 Got SIGHUP, reloaded profile '{}'",
+    },
+    Message {
+        key: "reload.intervals",
+        zh: "   发送间隔: 记录 {} -> {} 秒, 轮次 {} -> {} 秒",
+        en: "   Intervals: item {} -> {} s, round {} -> {} s",
+    },
+    Message {
+        key: "reload.interval_invalid",
+        zh: "⚠️ 发送间隔 '{}' 不是有效的数字，已忽略",
+        en: "⚠️ Interval '{}' is not a valid number; ignored",
+    },
+    Message {
+        key: "reload.count",
+        zh: "   发送轮数: {} -> {}",
+        en: "   Count: {} -> {}",
+    },
+    Message {
+        key: "reload.count_invalid",
+        zh: "⚠️ 发送轮数 '{}' 不是有效的数字，已忽略",
+        en: "⚠️ Count '{}' is not a valid number; ignored",
+    },
+    Message {
+        key: "reload.random_key",
+        zh: "   随机字段: {} -> {}",
+        en: "   Random field: {} -> {}",
+    },
+    Message {
+        key: "reload.unchanged",
+        zh: "   可热更新的参数没有变化",
+        en: "   No reloadable settings changed",
+    },
+    Message {
+        key: "reload.not_reloadable",
+        zh: "⚠️ 参数 {} 不支持热更新，已忽略，重启后生效",
+        en: "⚠️ {} cannot be reloaded; ignored until restart",
+    },
+    // 后台运行
+    Message {
+        key: "daemon.log_required",
+        zh: "--daemon 后台运行时没有控制台输出，请用 --log-file 指定日志文件或使用 --log-target syslog",
+        en: "--daemon has no console output; use --log-file to choose a log file or --log-target syslog",
+    },
+    Message {
+        key: "daemon.started",
+        zh: "🌙 已在后台运行，PID {}，PID文件: {}",
+        en: "🌙 Running in the background, PID {}, PID file: {}",
+    },
+    Message {
+        key: "daemon.stop_hint",
+        zh: "   停止: push-message-thingsboard stop --pid-file {}",
+        en: "   To stop: push-message-thingsboard stop --pid-file {}",
+    },
+    Message {
+        key: "daemon.already_running",
+        zh: "已有进程在运行（PID {}，PID文件: {}），请先停止它或换一个 --pid-file",
+        en: "Another process is already running (PID {}, PID file: {}); stop it first or use a different --pid-file",
+    },
+    Message {
+        key: "daemon.pid_write_failed",
+        zh: "无法写入PID文件: {}",
+        en: "Failed to write PID file: {}",
+    },
+    Message {
+        key: "daemon.spawn_failed",
+        zh: "无法启动后台进程",
+        en: "Failed to start the background process",
+    },
+    Message {
+        key: "daemon.current_exe_failed",
+        zh: "无法确定当前可执行文件路径",
+        en: "Failed to determine the path of the current executable",
+    },
+    Message {
+        key: "daemon.exited",
+        zh: "后台进程启动后立即退出（退出码 {}），详见日志",
+        en: "The background process exited right after starting (exit code {}); see the log for details",
+    },
+    Message {
+        key: "daemon.unsupported",
+        zh: "后台运行仅支持Unix平台，Windows下请使用服务管理器",
+        en: "Running in the background is only supported on Unix; use a service manager on Windows",
+    },
+    Message {
+        key: "daemon.pid_missing",
+        zh: "无法读取PID文件 {}，后台进程可能没有运行",
+        en: "Failed to read PID file {}; the background process may not be running",
+    },
+    Message {
+        key: "daemon.pid_invalid",
+        zh: "PID文件 {} 的内容不是有效的进程号",
+        en: "PID file {} does not contain a valid process ID",
+    },
+    Message {
+        key: "daemon.not_running",
+        zh: "⚠️ 进程 {} 已不在运行，已删除过期的PID文件 {}",
+        en: "⚠️ Process {} is no longer running; removed the stale PID file {}",
+    },
+    Message {
+        key: "daemon.stopping",
+        zh: "⛔ 已向进程 {} 发送中断信号，等待其完成进行中的发送（最多 {} 秒）",
+        en: "⛔ Sent an interrupt to process {}, waiting for in-flight sends to finish (up to {} seconds)",
+    },
+    Message {
+        key: "daemon.stopped",
+        zh: "✅ 进程 {} 已停止",
+        en: "✅ Process {} stopped",
+    },
+    Message {
+        key: "daemon.force_stop",
+        zh: "⚠️ 进程 {} 在 {} 秒内没有退出，再次发送中断信号使其立即退出",
+        en: "⚠️ Process {} did not exit within {} seconds, sending a second interrupt to exit immediately",
+    },
+    Message {
+        key: "daemon.stop_failed",
+        zh: "进程 {} 没有退出",
+        en: "Process {} did not exit",
+    },
+    Message {
+        key: "daemon.signal_failed",
+        zh: "无法向进程 {} 发送信号",
+        en: "Failed to send a signal to process {}",
+    },
+    Message {
+        key: "exit.auth_failed",
+        zh: "服务器拒绝了设备令牌（HTTP {}），发送已中止",
+        en: "the server rejected the device token (HTTP {}), sending aborted",
+    },
+    Message {
+        key: "exit.interrupted",
+        zh: "被中断（Ctrl-C 或 stop 子命令）",
+        en: "interrupted (Ctrl-C or the stop subcommand)",
+    },
+    Message {
+        key: "exit.fail_fast",
+        zh: "已开启 --fail-fast，首次发送失败后中止",
+        en: "--fail-fast is set, aborted after the first failure",
+    },
+    Message {
+        key: "exit.consecutive_failures",
+        zh: "连续 {} 次发送失败后中止",
+        en: "aborted after {} consecutive failures",
+    },
+    Message {
+        key: "exit.dry_run_failed",
+        zh: "演练中有 {} 条数据生成失败",
+        en: "{} payloads failed to generate during the dry run",
+    },
+    Message {
+        key: "exit.all_failed",
+        zh: "全部 {} 条发送失败",
+        en: "all {} sends failed",
+    },
+    Message {
+        key: "exit.on_complete_failed",
+        zh: "发送成功，但 --on-complete 命令执行失败",
+        en: "sending succeeded, but the --on-complete command failed",
+    },
+    Message {
+        key: "exit.some_failed",
+        zh: "{} 条发送失败，{} 条成功",
+        en: "{} sends failed, {} succeeded",
+    },
+    Message {
+        key: "init.dir_failed",
+        zh: "无法创建目录: {}",
+        en: "Failed to create directory: {}",
+    },
+    Message {
+        key: "init.env_file",
+        zh: "# ThingsBoard服务器地址，多个地址用逗号分隔\nserver={}\n\n# 设备访问令牌\ndevice_token={}\n",
+        en: "# ThingsBoard server URL; separate multiple URLs with commas\nserver={}\n\n# Device access token\ndevice_token={}\n",
+    },
+    Message {
+        key: "init.write_failed",
+        zh: "无法写入文件: {}",
+        en: "Failed to write file: {}",
+    },
+    Message {
+        key: "init.created",
+        zh: "✅ 已生成: {}",
+        en: "✅ Created: {}",
+    },
+    Message {
+        key: "init.skipped",
+        zh: "⏭️ 已存在，跳过: {}（使用 --force 覆盖）",
+        en: "⏭️ Already exists, skipped: {} (use --force to overwrite)",
+    },
+    Message {
+        key: "init.next_steps",
+        zh: "💡 下一步: 在 .env 中填入设备令牌，运行 push-message-thingsboard check 测试连通性，再运行 push-message-thingsboard send 开始发送",
+        en: "💡 Next: put the device token in .env, run push-message-thingsboard check to test connectivity, then push-message-thingsboard send to start sending",
+    },
+    Message {
+        key: "check.ok",
+        zh: "✅ {} 令牌 {}: HTTP {}，{} ms",
+        en: "✅ {} token {}: HTTP {}, {} ms",
+    },
+    Message {
+        key: "check.failed",
+        zh: "❌ {} 令牌 {}: {}",
+        en: "❌ {} token {}: {}",
+    },
+    Message {
+        key: "check.summary",
+        zh: "🔎 连通性检查: {}/{} 通过",
+        en: "🔎 Connectivity check: {}/{} passed",
+    },
+    Message {
+        key: "config.file_found",
+        zh: "⚙️ 配置文件: {}，配置档: {}",
+        en: "⚙️ Config file: {}, profiles: {}",
+    },
+    Message {
+        key: "config.file_missing",
+        zh: "⚙️ 配置文件: {}（不存在）",
+        en: "⚙️ Config file: {} (not found)",
+    },
+    Message {
+        key: "config.token_unset",
+        zh: "未设置",
+        en: "not set",
+    },
+    Message {
+        key: "config.random_key",
+        zh: "   随机键: {}{}",
+        en: "   Random key: {}{}",
+    },
+    Message {
+        key: "inspect.header",
+        zh: "📊 数据文件概况 {}: {} 个数据项, {} 个字段",
+        en: "📊 Data file overview {}: {} items, {} fields",
+    },
+    Message {
+        key: "inspect.field",
+        zh: "   {}: 出现在 {}/{} 个数据项, 类型 {}",
+        en: "   {}: in {}/{} items, {}",
+    },
+    Message {
+        key: "inspect.numeric",
+        zh: ", min {} / max {} / avg {}",
+        en: ", min {} / max {} / avg {}",
+    },
+    Message {
+        key: "inspect.geo",
+        zh: "🧭 轨迹字段 '{}': 起点 {}, 速度 {} 米/秒, {} 个路径点",
+        en: "🧭 Geo track '{}': start {}, speed {} m/s, {} waypoints",
+    },
+    Message {
+        key: "inspect.config",
+        zh: "   {}: {}",
+        en: "   {}: {}",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::lookup_from;
+
+    #[test]
+    fn render_supports_positional_arguments() {
+        let rendered = render(Lang::En, "config.profile_not_found", &[&"config.json", &"prod"]);
+        assert_eq!(rendered, "Profile 'prod' not found in config file config.json");
+        let rendered = render(Lang::Zh, "config.profile_not_found", &[&"config.json", &"prod"]);
+        assert_eq!(rendered, "配置文件 config.json 中未找到配置档 'prod'");
+    }
+
+    #[test]
+    fn render_falls_back_for_untranslated_and_unknown_keys() {
+        let catalog = [Message { key: "only.zh", zh: "只有中文 {}", en: "" }];
+        assert_eq!(render_from(&catalog, Lang::En, "only.zh", &[&1]), "只有中文 1");
+        assert_eq!(render_from(&catalog, Lang::En, "missing.key", &[]), "missing.key");
+    }
+
+    #[test]
+    fn render_keeps_non_numeric_braces() {
+        let rendered = render(Lang::En, "progress.bar", &[&"{bar:40}"]);
+        assert_eq!(rendered, "{bar:40} {pos}/{len} {per_sec} failed {msg} ETA {eta}");
+    }
+
+    #[test]
+    fn catalog_languages_use_the_same_arguments() {
+        fn placeholders(template: &str) -> usize {
+            let mut count = 0;
+            let mut rest = template;
+            while let Some(start) = rest.find('{') {
+                rest = &rest[start + 1..];
+                if let Some(end) = rest.find('}')
+                    && rest[..end].chars().all(|c| c.is_ascii_digit())
+                {
+                    count += 1;
+                }
+            }
+            count
+        }
+
+        let mut keys = std::collections::HashSet::new();
+        for message in CATALOG {
+            assert!(keys.insert(message.key), "重复的消息键: {}", message.key);
+            if !message.en.is_empty() {
+                assert_eq!(placeholders(message.zh), placeholders(message.en), "占位符个数不一致: {}", message.key);
+            }
+        }
+    }
+
+    /// 模板需要的参数个数：`{}` 按出现次数，`{N}` 按最大位置
+    fn required_args(template: &str) -> usize {
+        let mut sequential = 0;
+        let mut positional = 0;
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            rest = &rest[start + 1..];
+            let Some(end) = rest.find('}') else {
+                break;
+            };
+            match &rest[..end] {
+                "" => sequential += 1,
+                inner if inner.chars().all(|c| c.is_ascii_digit()) => {
+                    positional = positional.max(inner.parse::<usize>().unwrap() + 1)
+                }
+                _ => {}
+            }
+        }
+        sequential.max(positional)
+    }
+
+    /// 从源码中找出全部以字符串字面量为键的 `t!` 调用，返回键和参数个数
+    ///
+    /// 参数按括号深度之外的逗号切分，字符串字面量中的逗号和括号不计入
+    fn t_calls(source: &str) -> Vec<(String, usize)> {
+        let mut calls = Vec::new();
+        for (start, _) in source.match_indices("t!(") {
+            let before = source[..start].chars().next_back();
+            if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            let mut args = vec![String::new()];
+            let mut depth = 0;
+            let mut in_string = false;
+            let mut escaped = false;
+            for c in source[start + 3..].chars() {
+                if in_string {
+                    in_string = c != '"' || escaped;
+                    escaped = c == '\\' && !escaped;
+                } else {
+                    match c {
+                        '"' => in_string = true,
+                        '(' | '[' | '{' => depth += 1,
+                        ')' if depth == 0 => break,
+                        ')' | ']' | '}' => depth -= 1,
+                        ',' if depth == 0 => {
+                            args.push(String::new());
+                            continue;
+                        }
+                        _ => {}
+                    }
+                }
+                args.last_mut().unwrap().push(c);
+            }
+            if args.last().is_some_and(|arg| arg.trim().is_empty()) {
+                args.pop();
+            }
+            let key = args[0].trim();
+            if let Some(key) = key.strip_prefix('"').and_then(|key| key.strip_suffix('"')) {
+                calls.push((key.to_string(), args.len() - 1));
+            }
+        }
+        calls
+    }
+
+    #[test]
+    fn catalog_calls_pass_every_argument() {
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut checked = 0;
+        for entry in std::fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            let source = std::fs::read_to_string(&path).unwrap();
+            for (key, args) in t_calls(&source) {
+                let Some(message) = CATALOG.iter().find(|m| m.key == key) else {
+                    panic!("{}: 消息目录中没有键 {}", path.display(), key);
+                };
+                assert_eq!(required_args(message.zh), args, "{}: {} 的参数个数与模板不一致", path.display(), key);
+                checked += 1;
+            }
+        }
+        assert!(checked > 100, "只找到 {} 处 t! 调用", checked);
+    }
+
+    /// 找出测试模块之前的全部含汉字的字符串字面量，返回行号和字面量前面的代码
+    ///
+    /// 跳过注释，支持转义、原始字符串、字符字面量和生命周期
+    fn han_literals(source: &str) -> Vec<(usize, String)> {
+        let source = source.split("#[cfg(test)]").next().unwrap();
+        let chars: Vec<char> = source.chars().collect();
+        let mut literals = Vec::new();
+        let mut code = String::new();
+        let mut line = 1;
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            if c == '/' && next == Some('/') {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            if c == '/' && next == Some('*') {
+                while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '/') {
+                    line += usize::from(chars[i] == '\n');
+                    i += 1;
+                }
+                i += 2;
+                continue;
+            }
+            if c == '\'' {
+                let end = if next == Some('\\') {
+                    chars[i + 2..].iter().position(|&c| c == '\'').map(|p| i + 3 + p)
+                } else if chars.get(i + 2) == Some(&'\'') {
+                    Some(i + 3)
+                } else {
+                    None
+                };
+                if let Some(end) = end {
+                    code.push_str("' '");
+                    i = end;
+                    continue;
+                }
+            }
+            let raw_hashes = (c == 'r' && !code.ends_with(|c: char| c.is_alphanumeric() || c == '_'))
+                .then(|| chars[i + 1..].iter().take_while(|&&c| c == '#').count())
+                .filter(|&hashes| chars.get(i + 1 + hashes) == Some(&'"'));
+            if c == '"' || raw_hashes.is_some() {
+                let start_line = line;
+                let mut text = String::new();
+                if let Some(hashes) = raw_hashes {
+                    i += hashes + 2;
+                    let closing: String = std::iter::once('"').chain(std::iter::repeat_n('#', hashes)).collect();
+                    while !chars[i..].starts_with(&closing.chars().collect::<Vec<_>>()) {
+                        text.push(chars[i]);
+                        i += 1;
+                    }
+                    i += closing.len();
+                } else {
+                    i += 1;
+                    while chars[i] != '"' {
+                        if chars[i] == '\\' {
+                            i += 1;
+                        }
+                        text.push(chars[i]);
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                line += text.matches('\n').count();
+                if text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c)) {
+                    literals.push((start_line, code.clone()));
+                }
+                code.push_str("\"\"");
+                continue;
+            }
+            line += usize::from(c == '\n');
+            code.push(c);
+            i += 1;
+        }
+        literals
+    }
+
+    #[test]
+    fn user_facing_strings_are_in_the_catalog() {
+        // 消息目录本身、命令行帮助和程序名允许直接写中文
+        const ALLOWED: [&str; 7] =
+            ["zh:", ".help(", ".long_help(", ".about(", ".long_about(", ".after_help(", "Command::new("];
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut hard_coded = Vec::new();
+        for entry in std::fs::read_dir(src).unwrap() {
+            let path = entry.unwrap().path();
+            let source = std::fs::read_to_string(&path).unwrap();
+            for (line, code) in han_literals(&source) {
+                let code = code.trim_end();
+                let help_const = code
+                    .rsplit(';')
+                    .next()
+                    .is_some_and(|statement| statement.contains("_HELP: &str") && statement.ends_with('='));
+                if !help_const && !ALLOWED.iter().any(|allowed| code.ends_with(allowed)) {
+                    hard_coded.push(format!("{}:{}", path.display(), line));
+                }
+            }
+        }
+        assert!(hard_coded.is_empty(), "以下中文字符串应移入消息目录: {:#?}", hard_coded);
+    }
+
+    #[test]
+    fn lang_detected_from_locale_variables() {
+        assert_eq!(lang_from_env(lookup_from(&[])), Lang::Zh);
+        assert_eq!(lang_from_env(lookup_from(&[("LANG", "en_US.UTF-8")])), Lang::En);
+        assert_eq!(lang_from_env(lookup_from(&[("LANG", "zh_CN.UTF-8")])), Lang::Zh);
+        assert_eq!(lang_from_env(lookup_from(&[("LC_ALL", "zh_CN.UTF-8"), ("LANG", "en_US.UTF-8")])), Lang::Zh);
+        assert_eq!(lang_from_env(lookup_from(&[("LC_ALL", ""), ("LANG", "en_GB.UTF-8")])), Lang::En);
+    }
+}
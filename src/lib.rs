@@ -0,0 +1,70 @@
+//! ThingsBoard数据推送工具的库部分
+//!
+//! 命令行程序只负责解析参数和组织发送流程，数据加载、遥测提取、随机值生成和发送逻辑都在这里，
+//! 其它工具可以直接复用。常用类型在crate根部重新导出：
+//!
+//! * [`Config`] - 服务器地址和设备令牌
+//! * [`DataFileResult`] - 加载后的数据文件，见 [`load_data_file`]
+//! * [`TelemetrySender`] - 向设备API发送数据项
+//! * [`PushError`] - 库函数返回的错误，按种类区分，见 [`error`]
+
+/// 输出逐条发送的过程信息（成功提示、发送内容、随机修改等）
+#[macro_export]
+macro_rules! item_info {
+    ($($arg:tt)*) => {
+        tracing::info!(target: "push_message_thingsboard::item", $($arg)*)
+    };
+}
+
+/// 输出完整的请求/响应调试信息
+#[macro_export]
+macro_rules! http_debug {
+    ($($arg:tt)*) => {
+        tracing::debug!(target: "push_message_thingsboard::http", $($arg)*)
+    };
+}
+
+/// 输出一行展示文本到stdout，纯文本模式下emoji替换为ASCII标签
+#[macro_export]
+macro_rules! out {
+    ($($arg:tt)*) => {
+        println!("{}", $crate::output::display_text(&format!($($arg)*)))
+    };
+}
+
+/// 按当前界面语言渲染消息目录中的消息，用法同 `format!`，参数统一按 `{}` 输出
+#[macro_export]
+macro_rules! t {
+    ($key:expr) => {
+        $crate::i18n::render($crate::i18n::current_lang(), $key, &[])
+    };
+    ($key:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::render($crate::i18n::current_lang(), $key, &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+
+pub mod config;
+pub mod daemon;
+pub mod data;
+pub mod error;
+pub mod exit;
+pub mod generate;
+pub mod i18n;
+pub mod notify;
+pub mod output;
+pub mod plan;
+pub mod random;
+pub mod reload;
+pub mod runner;
+pub mod schedule;
+pub mod sender;
+pub mod stats;
+pub mod telemetry;
+pub mod tui;
+
+pub use config::{Config, ServerPool, load_config};
+pub use data::{DataFileResult, load_data_file, load_data_source};
+pub use error::{PushError, PushResult};
+pub use random::{generate_random_value, seeded_rng};
+pub use sender::{HttpStatusError, SendOptions, TelemetrySender};
+pub use telemetry::extract_telemetry_values;
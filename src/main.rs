@@ -15,434 +15,2529 @@
  */
 
 use anyhow::{Context, Result};
-use clap::{Arg, Command};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use dotenv::dotenv;
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::fs;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::Path;
+use std::io::{IsTerminal, Write};
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use chrono::Local;
-use tokio::time::{sleep, Duration};
-use rand::Rng;
-
-/// ThingsBoard服务器配置结构体
-///
-/// 包含连接ThingsBoard所需的基本配置信息
-#[derive(Debug, Deserialize)]
-struct Config {
-    /// ThingsBoard服务器地址 (例如: http://localhost:8080)
-    server: String,
-    /// 设备访问令牌，用于身份验证
-    device_token: String,
-}
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
+use tokio::time::Duration;
+use rand_chacha::ChaCha12Rng;
+use push_message_thingsboard::config::{
+    LbStrategy, PoolStrategy, ProfileSettings, ServerPool, TokenPool, TokenStore, expand_token_pattern, load_config,
+    load_config_file, load_token_pool, normalize_server_url, read_secret_setting, resolve_arg,
+};
+use push_message_thingsboard::data::{
+    IssueLevel, load_data_file, load_data_source, log_randomization, preflight_items, validate_data,
+};
+use push_message_thingsboard::error::{PushError, PushResult};
+use push_message_thingsboard::i18n::{LANG, Lang, lang_from_env};
+use push_message_thingsboard::output::{
+    LogFormat, PLAIN_OUTPUT, Progress, SYSLOG_FACILITIES, SyslogTarget, init_logging,
+};
+use push_message_thingsboard::random::{
+    FieldGenerator, GEO_SEED_STREAM, GeoTrack, RandomWalk, WALK_SEED_STREAM, seeded_rng,
+};
+use push_message_thingsboard::runner::SendLoop;
+use push_message_thingsboard::schedule::{
+    CountPolicy, Pacer, RateSchedule, RunPosition, SendIntervals, SendQuota, StateFile, WarmupCurve,
+};
+use push_message_thingsboard::sender::{
+    AuditLog, AuditRotation, ClockAdjuster, ConnectionTuning, DEFAULT_URL_TEMPLATE, HttpSender, IdempotencyKeys,
+    IdempotencyMode, RequestSigner, ResponseDisplay, SendOptions, Sender, SignAlgorithm, Throttle, TimestampAllocator,
+    UrlTemplate, check_device, mask_token, parse_size, redact_url_tokens, redirect_policy, spawn_send_workers,
+};
+use push_message_thingsboard::stats::{
+    CsvReport, Heartbeat, METRICS_HELP, Metrics, StatsdClient, TeeFile, WindowStats, serve_metrics,
+};
+use push_message_thingsboard::telemetry::{
+    EmptyPolicy, FieldRules, FieldScheduler, FlattenRules, NonFinitePolicy, PayloadShape, parse_injection,
+    parse_rename,
+};
+use push_message_thingsboard::tui::{TUI_ACTIVE, TUI_STATE, TuiCommand, run_tui};
+use push_message_thingsboard::daemon::{DEFAULT_PID_FILE, PidFile, spawn_daemon, stop_daemon};
+use push_message_thingsboard::exit::{
+    EXIT_AUTH_FAILED, EXIT_CONFIG_ERROR, EXIT_INTERRUPTED, EXIT_ON_COMPLETE_FAILED, EXIT_SEND_FAILED, EXIT_SUCCESS,
+    exit_status, run_on_complete,
+};
+use push_message_thingsboard::generate::{OutputFormat, generate_rows, write_rows};
+use push_message_thingsboard::notify::{CliEvents, Notifier, push_stats_to_tb};
+use push_message_thingsboard::plan::{describe_walk_bounds, print_data_inspection, print_send_plan};
+#[cfg(unix)]
+use push_message_thingsboard::reload::spawn_reload_listener;
+use push_message_thingsboard::{out, t};
 
-/// ThingsBoard遥测数据结构体
-///
-/// 符合ThingsBoard API要求的遥测数据格式
-#[derive(Debug, Serialize)]
-struct TelemetryData {
-    /// 时间戳（毫秒）
-    ts: u64,
-    /// 遥测数据键值对
-    values: HashMap<String, Value>,
-    /// 发送时间
-    time: String,
-}
+/// `--log-format` 参数的详细帮助，记录JSON日志的字段结构
+const LOG_FORMAT_HELP: &str = "\
+日志输出格式：human（默认，带emoji的人类可读格式）或 json（每行一个JSON对象）。
 
-/// 数据文件解析结果结构体
-///
-/// 包含从数据文件中解析出的随机键和数据数组
-#[derive(Debug)]
-struct DataFileResult {
-    /// 随机键名称（如 "drp"）
-    random_key: Option<String>,
-    /// 数据数组
-    data: Vec<Value>,
-}
+json 模式只输出结构化事件，输出到stderr或 --log-file 指定的文件。每个事件都包含：
+  ts          ISO 8601 时间戳
+  level       日志级别（INFO/WARN/ERROR/DEBUG）
+  event       事件类型
+  message     对应的人类可读消息
 
-/// 程序主入口函数
+各事件类型的附加字段：
+  startup      servers, items, item_interval, round_interval, count, device
+  send         item_index, round, status_code, latency_ms, headers_ms, bytes, device, keys, [server_offset_ms]
+  generated    item_index, round, bytes, device, keys（--dry-run 时代替 send）
+  send_failed  item_index, round, error_kind, error, latency_ms, device, [status_code]
+               （--merge-timeseries 时 send/generated/send_failed 按批输出，附带 points，item_index 为本批第一项）
+  exit         exit_code, reason
+  on_complete_failed  error（--on-complete 命令无法启动或以非0状态结束）
+  syslog_unavailable  error（--log-target syslog 无法连接套接字，改为输出到stderr）
+  heartbeat    done, succeeded, success_rate, rate, [recent_latency_ms, percent, eta_secs]
+               （每 --progress-every 秒一次，-q 时也输出；rate 为近几次心跳间的滑动平均条/秒）
+  window       seconds, done, rate, success_rate, failed, [mean_latency_ms, max_latency_ms]
+               （指定 --stats-interval 时每个窗口一次，-q 时也输出；只统计本窗口内完成的发送）
+  summary      attempted, sent, generated, dry_run, failed, skipped, bytes, elapsed_ms, rate, interrupted,
+               latency_min_ms, latency_mean_ms, latency_p50_ms, latency_p95_ms,
+               latency_p99_ms, latency_max_ms,
+               [server_offset_min_ms, server_offset_mean_ms, server_offset_max_ms]
+
+server_offset_ms 为响应 Date 头的服务器时间减去本地发出请求的时间（毫秒，Date 头精度为1秒），
+响应没有 Date 头时省略。
+
+keys 为本条遥测数据的字段名，以逗号分隔。
+
+latency_ms 为本条从开始构建负载到读完响应体（或出错）的总耗时，headers_ms 为其中等待响应头的耗时。
+
+error_kind 取值：http_status（服务器返回错误状态码）、network（连接或传输失败）、
+timeout（请求超时）、payload（数据无法转换为遥测格式）";
+
+/// `--summary-json` 的帮助说明
+const SUMMARY_JSON_HELP: &str = "\
+运行结束时（包括被中断）写出一份JSON汇总，便于CI断言成功率和延迟。
+指定 - 时输出到stdout，且stdout上只有这份JSON（日志全部改到stderr），可以直接交给 jq。
+
+顶层字段：
+  schema        汇总格式版本，当前为 1
+  started_at    开始时间（RFC 3339）
+  finished_at   结束时间（RFC 3339）
+  interrupted   是否被中断
+  dry_run       是否为演练模式
+  counts        attempted, sent, generated, failed, skipped, requeued, recovered
+  success_rate  成功条数 / (成功 + 失败)，0~1，没有完成的发送时为 1
+  bytes, elapsed_ms, rate
+  failures      按HTTP状态码或错误类型分组的失败次数
+  latency_ms    min, mean, p50, p95, p99, max（没有样本时为 null）
+  seed          --seed 指定的随机种子，未指定时为 null
+  config        servers, device（打码）, data_file, profile, item_interval, round_interval, count, rate, random_key
+  timestamps_adjusted  被顺延的遥测时间戳个数
+  throttled     服务器限流（HTTP 429）的次数，不计入 failed
+  throttle_wait_ms  因限流累计等待的时间（毫秒）
+  exit_code     进程退出码
+  exit_reason   退出原因";
+
+/// `--stats-to-tb` 的详细帮助
+const STATS_TO_TB_HELP: &str = "\
+运行结束时（包括被中断）把统计结果作为一条遥测推送到令牌为TOKEN的压测监控设备（与发送目标同一个服务器），
+便于在仪表盘上追踪历次压测。推送失败只打印警告，不影响退出码；演练模式下不推送。
+
+字段名为 push_ 加 --summary-json 中的字段名，嵌套字段用下划线连接，带单位的以单位结尾：
+  push_attempted, push_sent, push_generated, push_failed, push_skipped, push_requeued, push_recovered
+  push_success_rate, push_bytes, push_elapsed_ms, push_rate, push_timestamps_adjusted
+  push_throttled, push_throttle_wait_ms
+  push_latency_min_ms, push_latency_mean_ms, push_latency_p50_ms, push_latency_p95_ms,
+  push_latency_p99_ms, push_latency_max_ms（没有延迟样本时不输出）
+  push_interrupted, push_dry_run, push_exit_code, push_exit_reason
+  push_device（打码后的设备令牌）, push_data_file, push_seed（字符串，未指定 --seed 时不输出）";
+
+/// `--notify-template` 的详细帮助
+const NOTIFY_TEMPLATE_HELP: &str = "\
+通知负载模板，渲染后必须是有效的JSON；以 @ 开头时从该文件读取模板。
+未指定时发送包含下列全部字段的JSON对象。
+
+占位符写作 {{名称}}，字符串按JSON转义后写入（不含引号，应放在模板的引号中），数字原样写入：
+  run_id        本次运行的标识（开始时间-进程号）
+  status        completed、failed、aborted（连续失败中止）、interrupted 或 failing（运行期间的失败通知）
+  attempted, succeeded, failed, skipped
+  first_error   第一次失败的错误信息，没有时为空
+  last_error    最近一次失败的错误信息，没有时为空
+  duration_secs 已运行秒数
+  exit_code     退出码，运行期间的失败通知中为空
+  exit_reason   退出原因，运行期间的失败通知中为空
+  text          一行可读的运行摘要
+
+Slack/Teams incoming webhook 示例：
+  --notify-template '{\"text\": \"{{text}}\"}'";
+
+/// 构建命令行参数解析器
 ///
-/// 负责解析命令行参数、加载配置、读取数据文件并执行数据发送任务
+/// 发送相关的参数同时挂在 `send` 子命令和根命令上，不带子命令时按 `send` 处理，
+/// 兼容旧的调用方式；配置档、日志和界面语言等参数是全局参数，写在子命令前后均可
 ///
 /// # 返回值
 ///
-/// * `Result<()>` - 成功时返回Ok(())，失败时返回错误信息
-#[tokio::main]
-async fn main() -> Result<()> {
-    // 加载.env文件中的环境变量
-    dotenv().ok();
-
-    // 构建命令行参数解析器
-    let matches = Command::new("ThingsBoard数据推送工具")
+/// * `Command` - 完整的命令行定义
+fn build_cli() -> Command {
+    Command::new("ThingsBoard数据推送工具")
         .version("1.0")
         .author("Yu Xinyang")
         .about("向ThingsBoard发送模拟数据")
+        .after_help("不带子命令时等同于 send，例如 push-message-thingsboard -i 5 -c 0 -f data.json；发送参数见 send --help")
         .arg(
-            Arg::new("interval")
-                .short('i')
-                .long("interval")
-                .value_name("SECONDS")
-                .help("发送数据的间隔时间（秒）")
-                .default_value("5"),
+            Arg::new("config")
+                .long("config")
+                .value_name("FILE")
+                .help("配置文件路径（JSON格式，包含各环境的配置档）")
+                .default_value("push-config.json")
+                .global(true),
+        )
+        .arg(
+            Arg::new("profile")
+                .short('p')
+                .long("profile")
+                .value_name("NAME")
+                .help("使用配置文件中指定名称的配置档")
+                .global(true),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .help("静默模式：不输出逐条发送信息；-qq 时只输出错误")
+                .action(ArgAction::Count)
+                .global(true),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .help("输出完整的请求/响应调试信息到stderr；-vv 额外输出DNS、TLS、连接复用等连接层事件")
+                .action(ArgAction::Count)
+                .conflicts_with("quiet")
+                .global(true),
+        )
+        .arg(
+            Arg::new("plain")
+                .long("plain")
+                .help("纯文本输出：用 [OK]、[ERR] 等ASCII标签代替emoji，不输出颜色（设置 NO_COLOR 或输出不是终端时自动开启）")
+                .action(ArgAction::SetTrue)
+                .global(true),
+        )
+        .arg(
+            Arg::new("lang")
+                .long("lang")
+                .value_name("LANG")
+                .help("输出语言：zh 或 en（默认根据 LC_ALL/LANG 推断，无法推断时使用中文）")
+                .value_parser(["zh", "en"])
+                .global(true),
         )
         .arg(
-            Arg::new("count")
-                .short('c')
-                .long("count")
-                .value_name("NUMBER")
-                .help("发送数据的次数，0表示无限循环")
-                .default_value("1"),
+            Arg::new("log-format")
+                .long("log-format")
+                .value_name("FORMAT")
+                .help("日志输出格式：human 或 json（每行一个JSON对象）")
+                .long_help(LOG_FORMAT_HELP)
+                .value_parser(["human", "json"])
+                .default_value("human")
+                .global(true),
         )
         .arg(
-            Arg::new("data-file")
-                .short('f')
-                .long("file")
+            Arg::new("log-file")
+                .long("log-file")
                 .value_name("FILE")
-                .help("数据文件路径")
-                .default_value("data.json"),
+                .help("把日志追加写入该文件而不是控制台")
+                .global(true),
+        )
+        .arg(
+            Arg::new("log-target")
+                .long("log-target")
+                .value_name("TARGET")
+                .help("日志输出目标：console 或 syslog（以JSON格式发往本地syslog，不可用时退回stderr）")
+                .value_parser(["console", "syslog"])
+                .default_value("console")
+                .conflicts_with("log-file")
+                .global(true),
+        )
+        .arg(
+            Arg::new("syslog-facility")
+                .long("syslog-facility")
+                .value_name("FACILITY")
+                .help("syslog设施")
+                .value_parser(clap::builder::PossibleValuesParser::new(
+                    SYSLOG_FACILITIES.iter().map(|(name, _)| *name),
+                ))
+                .default_value("user")
+                .global(true),
+        )
+        .arg(
+            Arg::new("syslog-app-name")
+                .long("syslog-app-name")
+                .value_name("NAME")
+                .help("syslog消息中的APP-NAME")
+                .default_value(env!("CARGO_PKG_NAME"))
+                .global(true),
+        )
+        .arg(
+            Arg::new("syslog-socket")
+                .long("syslog-socket")
+                .value_name("PATH")
+                .help("syslog套接字路径，未指定时依次尝试 /dev/log、/var/run/syslog、/var/run/log")
+                .global(true),
+        )
+        .args(send_args().into_iter().map(|arg| arg.hide(true)))
+        .subcommand(
+            Command::new("send")
+                .about("按间隔向ThingsBoard发送数据（默认子命令）")
+                .args(send_args()),
+        )
+        .subcommand(
+            Command::new("generate")
+                .about("离线生成数据文件（不连接服务器），可用于ThingsBoard批量导入")
+                .arg(
+                    Arg::new("data-file")
+                        .short('f')
+                        .long("file")
+                        .value_name("FILE")
+                        .help("数据文件路径")
+                        .default_value("data.json"),
+                )
+                .arg(
+                    Arg::new("count")
+                        .short('c')
+                        .long("count")
+                        .value_name("NUMBER")
+                        .help("生成的轮数，每轮包含数据文件中的全部记录")
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::new("interval")
+                        .short('i')
+                        .long("interval")
+                        .value_name("SECONDS")
+                        .help("相邻两行的时间戳间隔（秒）")
+                        .default_value("5"),
+                )
+                .arg(
+                    Arg::new("random-key")
+                        .long("random-key")
+                        .value_name("FIELD")
+                        .help("需要随机修改的字段名称，覆盖数据文件中的设置"),
+                )
+                .arg(
+                    Arg::new("rename")
+                        .long("rename")
+                        .value_name("OLD=NEW")
+                        .help("输出前重命名顶层字段，格式同发送时的 --rename")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("flatten")
+                        .long("flatten")
+                        .help("把嵌套对象展开为扁平键（如 rain.intensity），便于在ThingsBoard中按时间序列绘图")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("flatten-separator")
+                        .long("flatten-separator")
+                        .value_name("SEP")
+                        .help("展开嵌套对象时父子键之间的分隔符")
+                        .default_value(".")
+                        .value_parser(clap::builder::NonEmptyStringValueParser::new())
+                        .requires("flatten"),
+                )
+                .arg(
+                    Arg::new("flatten-arrays")
+                        .long("flatten-arrays")
+                        .help("展开时把数组按下标展开（如 readings.0），默认数组整体作为一个值")
+                        .action(ArgAction::SetTrue)
+                        .requires("flatten"),
+                )
+                .arg(
+                    Arg::new("non-finite")
+                        .long("non-finite")
+                        .value_name("POLICY")
+                        .help("字段值为 NaN 或无穷大时的处理策略，同发送时的 --non-finite")
+                        .value_parser(["drop", "null", "error", "clamp"])
+                        .default_value("drop"),
+                )
+                .arg(
+                    Arg::new("seed")
+                        .long("seed")
+                        .value_name("SEED")
+                        .help("随机种子，指定后生成结果可复现"),
+                )
+                .arg(
+                    Arg::new("output-format")
+                        .long("output-format")
+                        .value_name("FORMAT")
+                        .help("输出格式：json（每行一个 {\"ts\", \"values\"} 对象）或 tb-csv（timestamp列 + 各字段列，嵌套字段按 a.b 展平）")
+                        .value_parser(["json", "tb-csv"])
+                        .default_value("json"),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("输出文件路径，已存在时覆盖")
+                        .required(true),
+                ),
         )
-        .get_matches();
+        .subcommand(
+            Command::new("validate")
+                .about("检查数据文件，列出全部问题（不连接服务器），有错误时以非0退出码结束，适合在CI中使用")
+                .arg(
+                    Arg::new("data-file")
+                        .short('f')
+                        .long("file")
+                        .value_name("FILE")
+                        .help("数据文件路径")
+                        .default_value("data.json"),
+                )
+                .arg(
+                    Arg::new("dir")
+                        .long("dir")
+                        .value_name("DIR")
+                        .help("检查数据目录，读取方式同发送时的 --dir")
+                        .conflicts_with("data-file"),
+                )
+                .arg(
+                    Arg::new("allow-keys")
+                        .long("allow-keys")
+                        .value_name("KEYS")
+                        .help("允许出现的顶层字段名（逗号分隔或重复指定），数据项中出现其它字段时报错")
+                        .value_delimiter(',')
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("max-warnings")
+                        .long("max-warnings")
+                        .value_name("NUMBER")
+                        .help("警告数超过该值时同样以非0退出码结束，默认不限制")
+                        .value_parser(clap::value_parser!(usize)),
+                ),
+        )
+        .subcommand(
+            Command::new("init")
+                .about("生成起步用的 .env、示例数据文件和配置文件模板，已存在的文件默认跳过")
+                .arg(
+                    Arg::new("dir")
+                        .value_name("DIR")
+                        .help("生成文件的目录，不存在时自动创建")
+                        .default_value("."),
+                )
+                .arg(
+                    Arg::new("server")
+                        .long("server")
+                        .value_name("URL")
+                        .help("写入 .env 和配置档的服务器地址")
+                        .default_value("http://localhost:8080"),
+                )
+                .arg(
+                    Arg::new("token")
+                        .long("token")
+                        .value_name("TOKEN")
+                        .help("写入 .env 的设备令牌，未指定时写入占位值"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("覆盖已存在的文件")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("check")
+                .about("检查配置并测试服务器连通性：用每个设备令牌读取一次设备属性（不写入数据），令牌被拒绝时以退出码5结束")
+                .arg(
+                    Arg::new("url-template")
+                        .long("url-template")
+                        .value_name("TEMPLATE")
+                        .help("设备API请求地址模板，同发送时的 --url-template")
+                        .default_value(DEFAULT_URL_TEMPLATE),
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .help("每个请求的超时时间（秒）")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .default_value("10"),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("显示合并配置档和环境变量后的最终配置（令牌已脱敏），并列出配置文件中的配置档")
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .help("以JSON格式输出")
+                        .action(ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("stop")
+                .about("停止用 --daemon 启动的后台进程：按PID文件发送中断信号，等待其完成进行中的发送并退出")
+                .arg(
+                    Arg::new("pid-file")
+                        .long("pid-file")
+                        .value_name("FILE")
+                        .help("后台进程的PID文件")
+                        .default_value(DEFAULT_PID_FILE),
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECONDS")
+                        .help("等待进程退出的最长时间（秒），超时后再发送一次中断信号使其立即退出")
+                        .value_parser(clap::value_parser!(u64))
+                        .default_value("30"),
+                ),
+        )
+}
+
+/// `send` 子命令的参数
+///
+/// 根命令上也注册同一组参数（在帮助中隐藏），使不带子命令的调用保持原有行为
+fn send_args() -> Vec<Arg> {
+    vec![
+        Arg::new("interval")
+            .short('i')
+            .long("interval")
+            .value_name("SECONDS")
+            .help("发送数据的间隔时间（秒），同时作为记录间隔和轮次间隔，未指定时使用配置档中的值")
+            .default_value("5"),
+        Arg::new("item-interval")
+            .long("item-interval")
+            .value_name("SECONDS")
+            .help("同一轮内相邻两条记录之间的间隔（秒），覆盖 --interval"),
+        Arg::new("round-interval")
+            .long("round-interval")
+            .value_name("SECONDS")
+            .help("一轮发送完成后到下一轮开始之间的间隔（秒），覆盖 --interval"),
+        Arg::new("rate")
+            .long("rate")
+            .value_name("PER_SECOND")
+            .help("按目标速率（条/秒）连续发送，代替 --interval 的逐条间隔"),
+        Arg::new("warmup")
+            .long("warmup")
+            .value_name("SECONDS")
+            .help("慢启动预热：前N秒内速率从0逐步爬升到 --rate，之后保持稳定")
+            .requires("rate"),
+        Arg::new("warmup-curve")
+            .long("warmup-curve")
+            .value_name("CURVE")
+            .help("预热阶段的爬升曲线")
+            .value_parser(["linear", "exponential"])
+            .default_value("linear")
+            .requires("warmup"),
+        Arg::new("count")
+            .short('c')
+            .long("count")
+            .value_name("NUMBER")
+            .help("发送数据的次数，0表示无限循环，未指定时使用配置档中的值")
+            .default_value("1"),
+        Arg::new("count-policy")
+            .long("count-policy")
+            .value_name("POLICY")
+            .help("--count 的计数方式：attempts 每条数据尝试 count 次后结束，失败不补发；successes 每条数据成功 count 次后结束，失败的数据项在后续轮次补发")
+            .value_parser(["attempts", "successes"])
+            .default_value("attempts"),
+        Arg::new("state-file")
+            .long("state-file")
+            .value_name("FILE")
+            .help("把发送进度（轮次、数据项位置、随机数状态）写入该文件：每 5 秒、每轮结束和退出时各写一次，配合 --resume 从中断处继续"),
+        Arg::new("resume")
+            .long("resume")
+            .help("从 --state-file 记录的位置继续发送；数据内容或 --seed、--count 等关键参数与记录不同时拒绝继续")
+            .requires("state-file")
+            .action(ArgAction::SetTrue),
+        Arg::new("data-file")
+            .short('f')
+            .long("file")
+            .value_name("FILE")
+            .help(
+                "数据文件路径，未指定时使用配置档中的值；可多次指定并用 路径:权重 设置优先级（默认为1），\
+                 各文件的记录按平滑加权轮询交织发送",
+            )
+            .action(ArgAction::Append)
+            .default_value("data.json"),
+        Arg::new("dir")
+            .long("dir")
+            .value_name("DIR")
+            .help("数据目录：按文件名顺序读取其中的 .json、.jsonl、.csv 文件，合并为一个数据流发送，无法解析的文件跳过并警告")
+            .conflicts_with("data-file"),
+        Arg::new("random-key")
+            .long("random-key")
+            .value_name("FIELD")
+            .help("需要随机修改的字段名称，覆盖配置档和数据文件中的设置"),
+        Arg::new("lb-strategy")
+            .long("lb-strategy")
+            .value_name("STRATEGY")
+            .help("配置了多个server时的负载均衡策略")
+            .value_parser(["roundrobin", "random"])
+            .default_value("roundrobin"),
+        Arg::new("lb-max-failures")
+            .long("lb-max-failures")
+            .value_name("NUMBER")
+            .help("某个server连续失败多少次后临时摘除")
+            .default_value("3"),
+        Arg::new("lb-cooldown")
+            .long("lb-cooldown")
+            .value_name("SECONDS")
+            .help("被摘除的server多少秒后重新参与负载均衡")
+            .default_value("30"),
+        Arg::new("token-pool")
+            .long("token-pool")
+            .value_name("FILE")
+            .help("设备令牌池文件，每行一个令牌，发送时在这些设备间分配"),
+        Arg::new("token-pattern")
+            .long("token-pattern")
+            .value_name("PATTERN")
+            .help("按模式批量生成设备令牌代替令牌池文件，{n} 替换为序号，{n:4} 补零到4位")
+            .requires("token-range")
+            .conflicts_with("token-pool"),
+        Arg::new("token-range")
+            .long("token-range")
+            .value_name("START..END")
+            .help("--token-pattern 的序号范围（闭区间），如 1..1000")
+            .requires("token-pattern"),
+        Arg::new("token-pool-lazy")
+            .long("token-pool-lazy")
+            .help("令牌池只在内存中保存令牌位置，发送时按需从文件读取（文件超过64MB时自动启用）")
+            .action(ArgAction::SetTrue)
+            .requires("token-pool"),
+        Arg::new("pool-strategy")
+            .long("pool-strategy")
+            .value_name("STRATEGY")
+            .help("令牌池的分配策略")
+            .value_parser(["roundrobin", "random", "sticky-per-item"])
+            .default_value("roundrobin"),
+        Arg::new("inject")
+            .long("inject")
+            .value_name("FIELD=VALUE@WHEN[xTIMES]")
+            .help("在第N次发送（@N）或开始后第N秒（@Ns）把字段临时设为指定值，持续TIMES次（默认1次），可重复指定")
+            .action(ArgAction::Append),
+        Arg::new("seq-field")
+            .long("seq-field")
+            .value_name("NAME")
+            .help("为每条消息注入全局单调递增的序号，用于检测丢失或乱序（不带值时字段名为 seq）")
+            .num_args(0..=1)
+            .default_missing_value("seq"),
+        Arg::new("idempotency-key")
+            .long("idempotency-key")
+            .value_name("MODE")
+            .help("为每条数据生成幂等键放入请求头，重发同一条数据时键不变：hash 内容摘要 / seq 序号")
+            .value_parser(["hash", "seq"]),
+        Arg::new("idempotency-header")
+            .long("idempotency-header")
+            .value_name("NAME")
+            .help("携带幂等键的请求头名称")
+            .default_value("Idempotency-Key")
+            .requires("idempotency-key"),
+        Arg::new("idempotency-field")
+            .long("idempotency-field")
+            .value_name("NAME")
+            .help("同时把幂等键写入遥测数据的该字段")
+            .requires("idempotency-key"),
+        Arg::new("idempotency-salt")
+            .long("idempotency-salt")
+            .value_name("TEXT")
+            .help("参与生成幂等键的盐值，需要让重新运行产生不同的键时使用")
+            .requires("idempotency-key"),
+        Arg::new("max-redirects")
+            .long("max-redirects")
+            .value_name("N")
+            .help(
+                "最多跟随的HTTP重定向次数（只跟随保留POST的307/308，301/302/303按发送失败处理）；\
+                 0表示不跟随，3xx响应按发送失败处理并显示重定向目标",
+            )
+            .value_parser(clap::value_parser!(usize))
+            .default_value("10"),
+        Arg::new("max-throttle-wait")
+            .long("max-throttle-wait")
+            .value_name("SECONDS")
+            .help("服务器限流（HTTP 429）时单条数据累计等待重试的上限（秒），超过后按发送失败处理；0表示不重试")
+            .value_parser(clap::value_parser!(u64))
+            .default_value("300"),
+        Arg::new("tcp-nodelay")
+            .long("tcp-nodelay")
+            .value_name("BOOL")
+            .help("是否开启TCP_NODELAY（禁用Nagle算法），小的遥测请求立即发出，降低尾延迟；--tcp-nodelay false 关闭")
+            .value_parser(clap::value_parser!(bool))
+            .num_args(0..=1)
+            .default_missing_value("true")
+            .default_value("true"),
+        Arg::new("tcp-keepalive")
+            .long("tcp-keepalive")
+            .value_name("SECONDS")
+            .help("为连接开启SO_KEEPALIVE，空闲该秒数后发送探测，及时发现被中间设备断开的长连接；默认不开启")
+            .value_parser(clap::value_parser!(u64).range(1..)),
+        Arg::new("pool-idle-timeout")
+            .long("pool-idle-timeout")
+            .value_name("SECONDS")
+            .help("空闲的HTTP keep-alive连接保留的时间（秒），超时后关闭；0表示一直保留")
+            .value_parser(clap::value_parser!(u64))
+            .default_value("90"),
+        Arg::new("pool-max-idle")
+            .long("pool-max-idle")
+            .value_name("N")
+            .help("每个服务器最多保留的空闲连接数；0表示不复用连接，每个请求都新建TCP连接（用于测试建连开销）")
+            .value_parser(clap::value_parser!(usize)),
+        Arg::new("user-agent")
+            .long("user-agent")
+            .value_name("UA")
+            .help("HTTP请求的User-Agent，便于在服务端访问日志中区分压测流量")
+            .default_value(DEFAULT_USER_AGENT),
+        Arg::new("webhook")
+            .long("webhook")
+            .value_name("URL")
+            .help("发送成功后异步POST一个结果摘要到该URL，失败只记警告"),
+        Arg::new("webhook-mode")
+            .long("webhook-mode")
+            .value_name("MODE")
+            .help("webhook的触发时机：每条成功发送后(item)或每轮结束后(round)")
+            .value_parser(["item", "round"])
+            .default_value("item"),
+        Arg::new("notify-url")
+            .long("notify-url")
+            .value_name("URL")
+            .help("运行结束或因连续失败中止时POST一条运行通知（可用于Slack/Teams的incoming webhook），失败只记警告"),
+        Arg::new("notify-on-failures")
+            .long("notify-on-failures")
+            .value_name("N")
+            .help("运行期间每累计N次发送失败额外发送一次通知")
+            .requires("notify-url"),
+        Arg::new("notify-template")
+            .long("notify-template")
+            .value_name("TEMPLATE")
+            .help("通知负载模板（JSON，支持 {{占位符}}），以 @ 开头时从文件读取")
+            .long_help(NOTIFY_TEMPLATE_HELP)
+            .requires("notify-url"),
+        Arg::new("seed")
+            .long("seed")
+            .value_name("SEED")
+            .help("随机种子：每个设备（令牌池中的每个令牌）和每条地理轨迹从它派生独立的子种子，数据各不相同但可复现"),
+        Arg::new("metrics-listen")
+            .long("metrics-listen")
+            .value_name("ADDR")
+            .help("在指定地址（如 0.0.0.0:9184）上提供Prometheus格式的 /metrics 端点，指标名以 tb_push_ 开头")
+            .long_help(METRICS_HELP),
+        Arg::new("statsd")
+            .long("statsd")
+            .value_name("HOST:PORT")
+            .help("每次发送后通过UDP向StatsD/DogStatsD发出计数（带 result、status_class 标签）和耗时指标，发后即忘"),
+        Arg::new("statsd-prefix")
+            .long("statsd-prefix")
+            .value_name("PREFIX")
+            .help("StatsD指标名前缀")
+            .default_value("tb_push")
+            .requires("statsd"),
+        Arg::new("show-response")
+            .long("show-response")
+            .help("成功时也读取并输出响应状态、选定的响应头和响应体（如网关返回的关联ID）")
+            .action(ArgAction::SetTrue),
+        Arg::new("response-headers")
+            .long("response-headers")
+            .value_name("HEADERS")
+            .help("--show-response 时输出的响应头，逗号分隔，不区分大小写")
+            .default_value("x-request-id,x-correlation-id,content-type")
+            .requires("show-response"),
+        Arg::new("response-max-len")
+            .long("response-max-len")
+            .value_name("CHARS")
+            .help("--show-response 时响应体最多输出的字符数，超出部分截断")
+            .default_value("1024")
+            .requires("show-response"),
+        Arg::new("on-complete")
+            .long("on-complete")
+            .value_name("COMMAND")
+            .help("发送结束并打印统计后执行的shell命令，统计结果通过 PUSH_* 环境变量传入；命令失败时以退出码4结束")
+            .long_help(ON_COMPLETE_HELP),
+        Arg::new("on-empty")
+            .long("on-empty")
+            .value_name("POLICY")
+            .help("记录提取后为空时的处理策略：error 视为失败，skip 跳过，send 发送空对象")
+            .value_parser(["error", "skip", "send"])
+            .default_value("error"),
+        Arg::new("time-field")
+            .long("time-field")
+            .value_name("NAME")
+            .help("注入的发送时间字段名（遥测数据和请求体顶层都使用该名称），默认遥测中为 send_time、顶层为 time"),
+        Arg::new("no-time-field")
+            .long("no-time-field")
+            .help("完全不注入发送时间字段")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("time-field"),
+        Arg::new("strict-payload")
+            .long("strict-payload")
+            .help("遥测请求体只包含文档规定的 ts 和 values，不附带顶层发送时间字段")
+            .action(ArgAction::SetTrue),
+        Arg::new("flat-payload")
+            .long("flat-payload")
+            .help("遥测请求体只发送 values 对象本身，由服务器分配时间戳")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("strict-payload"),
+        Arg::new("merge-timeseries")
+            .long("merge-timeseries")
+            .help("把多条记录（各自的 ts 字段作为时间戳）打包成时序数组，每批用一个请求发送，适合导入历史数据")
+            .action(ArgAction::SetTrue)
+            .conflicts_with_all([
+                "flat-payload",
+                "attribute-fields",
+                "token-pool",
+                "token-pattern",
+                "idempotency-key",
+                "inject",
+                "rate",
+                "tee",
+                "report-csv",
+            ]),
+        Arg::new("merge-size")
+            .long("merge-size")
+            .value_name("POINTS")
+            .help("合并时序模式下每个请求最多携带的数据点数")
+            .default_value("100")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .requires("merge-timeseries"),
+        Arg::new("concurrency")
+            .long("concurrency")
+            .value_name("N")
+            .help("同时进行的发送请求数，大于1时由多个发送工作者并发发送，结果仍按数据顺序统计")
+            .default_value("1")
+            .value_parser(clap::value_parser!(u64).range(1..=1024))
+            .conflicts_with("merge-timeseries"),
+        Arg::new("queue-size")
+            .long("queue-size")
+            .value_name("N")
+            .help("并发发送时等待发送的数据条数上限，队列满时暂停生成数据，避免发送跟不上时内存无限增长")
+            .default_value("100")
+            .value_parser(clap::value_parser!(u64).range(1..)),
+        Arg::new("send-time-key")
+            .long("send-time-key")
+            .value_name("NAME")
+            .help("只修改注入到遥测数据中的发送时间字段名（默认 send_time），请求体顶层的 time 不变")
+            .conflicts_with_all(["time-field", "no-time-field"]),
+        Arg::new("no-send-time")
+            .long("no-send-time")
+            .help("不向遥测数据注入 send_time 字段，遥测中只保留数据项自身的字段和显式要求的注入字段")
+            .action(ArgAction::SetTrue)
+            .conflicts_with_all(["time-field", "no-time-field", "send-time-key"]),
+        Arg::new("auto-clock-adjust")
+            .long("auto-clock-adjust")
+            .help("按响应Date头估计本机与服务器的时间差，生成 ts 时加上该偏移量，每分钟重新估计一次")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("flat-payload"),
+        Arg::new("raw-timestamps")
+            .long("raw-timestamps")
+            .help("直接使用系统时钟作为遥测时间戳，不为同一毫秒内的多条消息顺延（可能被ThingsBoard覆盖）")
+            .action(ArgAction::SetTrue),
+        Arg::new("sign-key")
+            .long("sign-key")
+            .value_name("KEY")
+            .help("请求签名密钥，设置后对每个请求体做HMAC签名并写入请求头（也可用环境变量 sign_key 或 sign_key_FILE 设置）"),
+        Arg::new("sign-header")
+            .long("sign-header")
+            .value_name("HEADER")
+            .help("写入签名的请求头名称")
+            .default_value("X-Signature"),
+        Arg::new("sign-algorithm")
+            .long("sign-algorithm")
+            .value_name("ALGORITHM")
+            .help("请求签名的HMAC算法")
+            .value_parser(["hmac-sha256", "hmac-sha512"])
+            .default_value("hmac-sha256"),
+        Arg::new("sign-timestamp-header")
+            .long("sign-timestamp-header")
+            .value_name("HEADER")
+            .help("把毫秒时间戳写入该请求头，并对 请求体+时间戳 签名（默认只签名请求体）"),
+        Arg::new("rename")
+            .long("rename")
+            .value_name("OLD=NEW")
+            .help("发送前重命名顶层字段，格式 old=new（逗号分隔或重复指定）或JSON对象 '{\"old\":\"new\"}'")
+            .action(ArgAction::Append),
+        Arg::new("flatten")
+            .long("flatten")
+            .help("把嵌套对象展开为扁平键（如 rain.intensity），便于在ThingsBoard中按时间序列绘图")
+            .action(ArgAction::SetTrue),
+        Arg::new("flatten-separator")
+            .long("flatten-separator")
+            .value_name("SEP")
+            .help("展开嵌套对象时父子键之间的分隔符")
+            .default_value(".")
+            .value_parser(clap::builder::NonEmptyStringValueParser::new())
+            .requires("flatten"),
+        Arg::new("flatten-arrays")
+            .long("flatten-arrays")
+            .help("展开时把数组按下标展开（如 readings.0），默认数组整体作为一个值")
+            .action(ArgAction::SetTrue)
+            .requires("flatten"),
+        Arg::new("non-finite")
+            .long("non-finite")
+            .value_name("POLICY")
+            .help("字段值为 NaN 或无穷大时的处理策略：drop 省略该字段并警告，null 发送 null，error 视为发送失败，clamp 无穷大取 ±f64::MAX")
+            .value_parser(["drop", "null", "error", "clamp"])
+            .default_value("drop"),
+        Arg::new("attribute-fields")
+            .long("attribute-fields")
+            .value_name("FIELDS")
+            .help("发往属性（attributes）端点的字段，逗号分隔，覆盖数据文件中的 attribute_fields；其余字段走遥测"),
+        Arg::new("audit-file")
+            .long("audit-file")
+            .value_name("FILE")
+            .help("审计文件：每个实际发出的请求追加一行JSON（时间、设备、完整负载、状态码、延迟）"),
+        Arg::new("audit-max-size")
+            .long("audit-max-size")
+            .value_name("SIZE")
+            .help("审计文件超过该大小时轮转，如 50MB、512KB")
+            .requires("audit-file"),
+        Arg::new("audit-max-files")
+            .long("audit-max-files")
+            .value_name("NUMBER")
+            .help("保留的已轮转审计文件个数，超出时删除最旧的")
+            .default_value("5")
+            .requires("audit-file"),
+        Arg::new("audit-rotate-daily")
+            .long("audit-rotate-daily")
+            .help("日期变化时轮转审计文件")
+            .action(ArgAction::SetTrue)
+            .requires("audit-file"),
+        Arg::new("report-csv")
+            .long("report-csv")
+            .value_name("FILE")
+            .help("把每次发送尝试写入CSV报告（时间、轮次、数据项、设备、结果、状态码、延迟、字节数、错误）"),
+        Arg::new("tee")
+            .long("tee")
+            .value_name("FILE")
+            .help("把每条发送成功的遥测数据（含生成的随机值和原始时间戳）写入该文件（JSON Lines，已存在时覆盖），便于与ThingsBoard导出的数据对账"),
+        Arg::new("fail-fast")
+            .long("fail-fast")
+            .help("首次发送失败后立即停止，以退出码3结束")
+            .action(ArgAction::SetTrue),
+        Arg::new("max-consecutive-failures")
+            .long("max-consecutive-failures")
+            .value_name("N")
+            .help("连续N次发送失败后停止，以退出码3结束（1 等同于 --fail-fast）")
+            .conflicts_with("fail-fast"),
+        Arg::new("ignore-failures")
+            .long("ignore-failures")
+            .help("发送失败时仍以退出码0结束（兼容旧行为）")
+            .action(ArgAction::SetTrue)
+            .conflicts_with_all(["fail-fast", "max-consecutive-failures"]),
+        Arg::new("url-template")
+            .long("url-template")
+            .value_name("TEMPLATE")
+            .help("设备API请求地址模板，占位符 {server}、{token}、{channel}（telemetry 或 attributes）")
+            .default_value(DEFAULT_URL_TEMPLATE),
+        Arg::new("edge")
+            .long("edge")
+            .help("目标是ThingsBoard Edge实例：认证失败时提示检查设备是否已分配给该Edge，并说明数据同步到云端的方式")
+            .action(ArgAction::SetTrue),
+        Arg::new("requeue")
+            .long("requeue")
+            .value_name("TIMES")
+            .help("把发送失败的记录放回本轮队列末尾，等本轮其余记录发完后再试，每条每轮最多重新入队TIMES次（不带值时为1）；认证失败不重新入队")
+            .num_args(0..=1)
+            .default_missing_value("1")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .conflicts_with("merge-timeseries"),
+        Arg::new("ignore-auth-errors")
+            .long("ignore-auth-errors")
+            .help("服务器返回401/403或令牌无效的404时继续发送（默认首次出现即中止，以退出码5结束）")
+            .action(ArgAction::SetTrue),
+        Arg::new("progress-every")
+            .long("progress-every")
+            .value_name("SECONDS")
+            .help("心跳间隔（秒）：定期输出已发送条数、成功率、近期延迟和预计剩余时间，-q 时也输出")
+            .default_value("60"),
+        Arg::new("daemon")
+            .long("daemon")
+            .help("在后台运行（仅Unix）：启动后台进程并写入PID文件后立即返回，需要配合 --log-file 或 --log-target syslog，用 stop 子命令停止")
+            .action(ArgAction::SetTrue),
+        Arg::new("pid-file")
+            .long("pid-file")
+            .value_name("FILE")
+            .help("把进程号写入该文件，退出时删除；--daemon 时默认为 push-message-thingsboard.pid"),
+        Arg::new("summary-json")
+            .long("summary-json")
+            .visible_alias("stats-output")
+            .value_name("PATH|-")
+            .help(SUMMARY_JSON_HELP)
+            .conflicts_with("plan"),
+        Arg::new("stats-to-tb")
+            .long("stats-to-tb")
+            .value_name("TOKEN")
+            .help(STATS_TO_TB_HELP)
+            .value_parser(clap::builder::NonEmptyStringValueParser::new())
+            .conflicts_with("plan"),
+        Arg::new("tui")
+            .long("tui")
+            .help("以终端仪表盘显示实时发送状态，p/空格暂停或继续，q退出；仅在终端中可用")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("quiet"),
+        Arg::new("no-heartbeat")
+            .long("no-heartbeat")
+            .help("不输出周期性心跳")
+            .action(ArgAction::SetTrue),
+        Arg::new("stats-interval")
+            .long("stats-interval")
+            .value_name("SECONDS")
+            .help("每隔指定秒数输出一行该时间窗口内的发送速率、成功率和平均延迟（类似 vmstat）")
+            .value_parser(clap::value_parser!(u64).range(1..)),
+        Arg::new("strict")
+            .long("strict")
+            .help("发送前检查发现无法发送的数据项（空对象、非对象、提取失败等）时报错退出，默认输出警告后排除这些数据项")
+            .action(ArgAction::SetTrue),
+        Arg::new("dry-run")
+            .long("dry-run")
+            .help("演练模式：完整生成并打印每条负载和目标URL，但不发出任何网络请求")
+            .action(ArgAction::SetTrue),
+        Arg::new("fast")
+            .long("fast")
+            .help("演练时跳过发送间隔的等待")
+            .action(ArgAction::SetTrue)
+            .requires("dry-run"),
+        Arg::new("plan")
+            .long("plan")
+            .help("只打印发送计划的时间线预览，不实际发送")
+            .action(ArgAction::SetTrue),
+        Arg::new("plan-limit")
+            .long("plan-limit")
+            .value_name("NUMBER")
+            .help("时间线预览中最多列出的记录条数")
+            .default_value("20"),
+        Arg::new("inspect")
+            .long("inspect")
+            .help("只打印数据文件概况：字段清单、各字段出现次数、数值字段的 min/max/avg 和随机配置，不发送")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("plan"),
+    ]
+}
+
+/// 程序主入口函数
+///
+/// 负责解析命令行参数、加载配置、读取数据文件并执行数据发送任务
+///
+/// # 返回值
+///
+/// * `Result<ExitCode>` - 运行结束时返回退出码（见 [`exit_status`]），
+///   配置或数据错误时返回错误信息（退出码为1）
+#[tokio::main]
+async fn main() -> Result<ExitCode> {
+    // 加载.env文件中的环境变量
+    dotenv().ok();
+
+    // 解析命令行参数，不带子命令时按 send 处理；全局参数在子命令的匹配结果中同样可以取到
+    let mut cli = build_cli();
+    let mut matches = cli.get_matches_mut();
+    // 界面语言最先确定，之后的参数错误也按所选语言输出
+    let lang = match matches.get_one::<String>("lang").map(String::as_str) {
+        Some("en") => Lang::En,
+        Some(_) => Lang::Zh,
+        None => lang_from_env(|name| env::var(name).ok()),
+    };
+    // 界面语言只在这里设置一次
+    LANG.set(lang).unwrap();
+    // 根命令上的发送参数只在不带子命令时生效，和其它子命令混用时报错而不是静默忽略
+    if let Some(name) = matches.subcommand_name()
+        && let Some(arg) = send_args()
+            .iter()
+            .find(|arg| matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+    {
+        let message = t!("arg.send_arg_with_subcommand", arg.get_long().unwrap_or_default(), name);
+        cli.error(clap::error::ErrorKind::ArgumentConflict, message).exit();
+    }
+    let (command, matches) = match matches.remove_subcommand() {
+        Some((name, sub_matches)) => (name, sub_matches),
+        None => ("send".to_string(), matches),
+    };
+    let sending = command == "send";
+
+    let plain = matches.get_flag("plain")
+        || env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || !std::io::stdout().is_terminal();
+    PLAIN_OUTPUT.store(plain, Ordering::Relaxed);
+
+    // syslog只接收结构化事件，固定使用JSON格式
+    let syslog = match matches.get_one::<String>("log-target").unwrap().as_str() {
+        "syslog" => {
+            let facility = matches.get_one::<String>("syslog-facility").unwrap();
+            let app_name = matches.get_one::<String>("syslog-app-name").unwrap();
+            if app_name.is_empty() || app_name.len() > 48 || !app_name.chars().all(|c| c.is_ascii_graphic()) {
+                anyhow::bail!(t!("arg.syslog_app_name_invalid", app_name));
+            }
+            Some(SyslogTarget {
+                facility: SYSLOG_FACILITIES.iter().find(|(name, _)| name == facility).map_or(1, |(_, code)| *code),
+                app_name: app_name.clone(),
+                socket: matches.get_one::<String>("syslog-socket").cloned(),
+            })
+        }
+        _ => None,
+    };
+    // 后台运行：以相同参数启动脱离终端的子进程后直接返回，日志只能写入文件或syslog
+    if sending && matches.get_flag("daemon") {
+        if matches.get_one::<String>("log-file").is_none() && syslog.is_none() {
+            anyhow::bail!(t!("daemon.log_required"));
+        }
+        let pid_file = matches.get_one::<String>("pid-file").map_or(DEFAULT_PID_FILE, String::as_str);
+        let pid = spawn_daemon(pid_file, matches.get_one::<String>("pid-file").is_none()).await?;
+        out!("{}", t!("daemon.started", pid, pid_file));
+        out!("{}", t!("daemon.stop_hint", pid_file));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let log_format = match matches.get_one::<String>("log-format").unwrap().as_str() {
+        _ if syslog.is_some() => LogFormat::Json,
+        "json" => LogFormat::Json,
+        _ => LogFormat::Human,
+    };
+    init_logging(
+        matches.get_count("quiet"),
+        matches.get_count("verbose"),
+        log_format,
+        matches.get_one::<String>("log-file").map(|s| s.as_str()),
+        sending && matches.get_one::<String>("summary-json").is_some_and(|path| path == "-"),
+        syslog.as_ref(),
+    )?;
+
+    match command.as_str() {
+        "generate" => {
+            run_generate(&matches)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        "validate" => return run_validate(&matches),
+        "init" => return run_init(&matches),
+        "check" => return run_check(&matches).await,
+        "config" => return run_config(&matches),
+        "stop" => {
+            let pid_file = matches.get_one::<String>("pid-file").unwrap();
+            let timeout = Duration::from_secs(*matches.get_one::<u64>("timeout").unwrap());
+            stop_daemon(pid_file, timeout).await?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        _ => {}
+    }
+    // PID文件在运行期间保留，返回时（包括出错）删除
+    let _pid_file = matches.get_one::<String>("pid-file").map(|path| PidFile::create(path)).transpose()?;
+
+    // TUI仪表盘需要独占一个真正的终端，否则画面会和重定向的输出混在一起
+    let tui = matches.get_flag("tui");
+    if tui && matches.get_one::<String>("summary-json").is_some_and(|path| path == "-") {
+        anyhow::bail!(t!("summary_json.tui_conflict"));
+    }
+    if tui {
+        if !std::io::stdout().is_terminal() || !std::io::stdin().is_terminal() {
+            anyhow::bail!(t!("tui.not_a_terminal"));
+        }
+        TUI_STATE.get_or_init(Mutex::default);
+    }
+
+    // 读取配置档，命令行参数优先于配置档，配置档优先于默认值
+    let profile_name = matches.get_one::<String>("profile");
+    let profile = load_profile(&matches)?;
+    let profile_ref = profile.as_ref();
 
     // 解析命令行参数
-    let interval: u64 = matches
-        .get_one::<String>("interval")
+    let (interval_arg, interval_from_profile) =
+        resolve_arg(&matches, "interval", profile_ref.and_then(|p| p.interval.map(|v| v.to_string())));
+    let interval: u64 = interval_arg.parse().with_context(|| t!("arg.interval_invalid"))?;
+    let intervals = SendIntervals::resolve(&matches, interval)?;
+
+    let (count_arg, count_from_profile) =
+        resolve_arg(&matches, "count", profile_ref.and_then(|p| p.count.map(|v| v.to_string())));
+    let count: u64 = count_arg.parse().with_context(|| t!("arg.count_invalid"))?;
+
+    let (data_file, data_file_from_profile) =
+        resolve_arg(&matches, "data-file", profile_ref.and_then(|p| p.data_file.clone()));
+    // 命令行多次指定 --file 时按权重交织，显示时用逗号连接
+    let data_files: Vec<String> = match data_file_from_profile {
+        true => vec![data_file],
+        false => matches.get_many::<String>("data-file").unwrap().cloned().collect(),
+    };
+    let data_file = data_files.join(", ");
+    let data_file = data_file.as_str();
+    let data_dir = matches.get_one::<String>("dir").map(String::as_str);
+
+    let cli_random_key = matches.get_one::<String>("random-key").cloned();
+    let random_key_from_profile =
+        cli_random_key.is_none() && profile_ref.is_some_and(|p| p.random_key.is_some());
+    let random_key_override = cli_random_key.or_else(|| profile_ref.and_then(|p| p.random_key.clone()));
+
+    // 数据概况：只加载数据文件并打印统计，不需要服务器配置
+    if matches.get_flag("inspect") {
+        let mut data_result = load_data_source(&data_files, data_dir)?;
+        data_result.override_random_key(random_key_override);
+        print_data_inspection(&data_result, data_dir.unwrap_or(data_file));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let rate_schedule = parse_rate_schedule(&matches)?;
+
+    // 预览模式：只根据参数推算时间线，不需要服务器配置
+    if matches.get_flag("plan") {
+        let plan_limit: usize = matches
+            .get_one::<String>("plan-limit")
+            .unwrap()
+            .parse()
+            .context(t!("arg.plan_limit_invalid"))?;
+        let mut data_result = load_data_source(&data_files, data_dir)?;
+        data_result.drop_unusable_items(data_dir.unwrap_or(data_file), matches.get_flag("strict"))?;
+        data_result.override_random_key(random_key_override);
+        print_send_plan(&data_result, intervals, rate_schedule.as_ref(), count, plan_limit);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // 读取令牌池，使用令牌池时不再需要单个设备令牌
+    let strategy = match matches.get_one::<String>("pool-strategy").unwrap().as_str() {
+        "random" => PoolStrategy::Random,
+        "sticky-per-item" => PoolStrategy::StickyPerItem,
+        _ => PoolStrategy::RoundRobin,
+    };
+    let token_pool = match (matches.get_one::<String>("token-pool"), matches.get_one::<String>("token-pattern")) {
+        (Some(path), _) => Some(load_token_pool(path, strategy, matches.get_flag("token-pool-lazy"))?),
+        (None, Some(pattern)) => {
+            let tokens = expand_token_pattern(pattern, matches.get_one::<String>("token-range").unwrap())?;
+            info!("{}", t!("banner.token_pattern", tokens.len(), tokens[0], tokens[tokens.len() - 1]));
+            Some(TokenPool::devices(TokenStore::Memory(tokens), strategy))
+        }
+        (None, None) => None,
+    };
+
+    // 读取配置
+    let config = load_config(profile_ref, token_pool.is_none())?;
+    // 同一设备配置了多个令牌时轮换使用；指定了令牌池时以令牌池为准
+    let token_pool = match token_pool {
+        None if config.device_tokens.len() > 1 => Some(TokenPool::rotation(config.device_tokens.clone())),
+        pool => pool,
+    };
+    info!("{}", t!("banner.config_loaded"));
+    info!("{}", t!("banner.servers", config.servers.join(", ")));
+    match token_pool {
+        Some(ref pool) if pool.rotation => info!("{}", t!("banner.token_rotation", pool.tokens.len())),
+        Some(ref pool) => {
+            info!("{}", t!("banner.token_pool", pool.tokens.len(), format!("{:?}", pool.strategy)))
+        }
+        None => info!("{}", t!("banner.token", mask_token(&config.device_tokens[0]))),
+    }
+    if let Some(name) = profile_name {
+        let source = t!("banner.from_profile", name);
+        let mark = |from_profile: bool| if from_profile { source.as_str() } else { "" };
+        info!("{}", t!("banner.profile", name));
+        info!("{}", t!("banner.data_file", data_file, mark(data_file_from_profile)));
+        info!("{}", t!("banner.interval", interval, mark(interval_from_profile)));
+        info!("{}", t!("banner.count", count, mark(count_from_profile)));
+    }
+    if intervals.item != intervals.round {
+        info!("{}", t!("banner.intervals", intervals.item, intervals.round));
+    }
+
+    // 读取数据文件
+    // 不可发送的数据项在字段规则确定后统一试算排除，见下方的发送前检查
+    let mut data_result = load_data_source(&data_files, data_dir)?;
+    data_result.override_random_key(random_key_override);
+    info!("{}", t!("banner.data_loaded", data_result.data.len()));
+    let random_key_source = match profile_name {
+        Some(name) if random_key_from_profile => t!("banner.from_profile", name),
+        _ => String::new(),
+    };
+    log_randomization(&data_result, &random_key_source);
+
+    // 构建多服务器负载均衡池
+    let lb_strategy = match matches.get_one::<String>("lb-strategy").unwrap().as_str() {
+        "random" => LbStrategy::Random,
+        _ => LbStrategy::RoundRobin,
+    };
+    let lb_max_failures: u32 = matches
+        .get_one::<String>("lb-max-failures")
         .unwrap()
         .parse()
-        .context("间隔时间必须是有效的数字")?;
-
-    let count: u64 = matches
-        .get_one::<String>("count")
+        .context(t!("arg.lb_max_failures_invalid"))?;
+    let lb_cooldown: u64 = matches
+        .get_one::<String>("lb-cooldown")
         .unwrap()
         .parse()
-        .context("发送次数必须是有效的数字")?;
+        .context(t!("arg.lb_cooldown_invalid"))?;
+    let heartbeat_every: u64 = matches
+        .get_one::<String>("progress-every")
+        .unwrap()
+        .parse()
+        .context(t!("arg.progress_every_invalid"))?;
+    if heartbeat_every == 0 {
+        anyhow::bail!(t!("arg.progress_every_zero"));
+    }
+    let server_pool = ServerPool::new(
+        &config.servers,
+        lb_strategy,
+        lb_max_failures.max(1),
+        Duration::from_secs(lb_cooldown),
+    );
+    if config.servers.len() > 1 {
+        info!("{}", t!("banner.lb", config.servers.len(), format!("{:?}", lb_strategy)));
+    }
 
-    let data_file = matches.get_one::<String>("data-file").unwrap();
+    // 随机种子：每个设备和每条轨迹从全局种子派生各自的子种子
+    let seed: Option<u64> = matches
+        .get_one::<String>("seed")
+        .map(|s| s.parse().context(t!("arg.seed_invalid")))
+        .transpose()?;
+    if let Some(seed) = seed {
+        info!("{}", t!("banner.seed", seed));
+    }
+    let mut device_rngs: HashMap<usize, ChaCha12Rng> = HashMap::new();
 
-    // 读取配置
-    let config = load_config()?;
-    println!("✅ 配置加载成功:");
-    println!("   服务器: {}", config.server);
-    println!("   设备Token: {}...", &config.device_token[..8]);
+    // 初始化字段生成器的运行状态
+    let mut geo_tracks: Vec<GeoTrack> = data_result
+        .random_config
+        .iter()
+        .filter_map(|(name, generator)| match generator {
+            FieldGenerator::Geo(config) => Some((name, config)),
+            _ => None,
+        })
+        .enumerate()
+        .map(|(track_index, (name, config))| {
+            let route = match config.waypoints.len() {
+                0 => t!("banner.geo_track_random"),
+                waypoints => t!("banner.geo_track_waypoints", waypoints),
+            };
+            info!("{}", t!("banner.geo_track", name, format!("{:?}", config.start), config.speed, route));
+            GeoTrack::new(config.clone(), seeded_rng(seed, GEO_SEED_STREAM + track_index as u64))
+        })
+        .collect();
+    let mut walks: Vec<RandomWalk> = data_result
+        .random_config
+        .iter()
+        .filter_map(|(name, generator)| match generator {
+            FieldGenerator::RandomWalk(config) => Some((name, config)),
+            _ => None,
+        })
+        .enumerate()
+        .map(|(walk_index, (name, config))| {
+            let walk = RandomWalk::new(name, config.clone(), seeded_rng(seed, WALK_SEED_STREAM + walk_index as u64))?;
+            info!(
+                "{}",
+                t!(
+                    "banner.random_walk",
+                    walk.field(),
+                    config.step_std,
+                    describe_walk_bounds(config)
+                )
+            );
+            Ok(walk)
+        })
+        .collect::<Result<_>>()?;
 
-    // 读取数据文件
-    let data_result = load_data_file(data_file)?;
-    println!("✅ 数据文件加载成功，包含 {} 条记录", data_result.data.len());
-    if let Some(ref key) = data_result.random_key {
-        println!("🎲 检测到随机字段: {}", key);
+    // 字段分组各自的发送间隔
+    for (name, group) in &data_result.field_groups {
+        info!("{}", t!("banner.field_group", name, group.interval, group.fields.join(", ")));
+    }
+    let field_scheduler = FieldScheduler::new(&data_result.field_groups);
+
+    // 解析阶跃注入配置
+    let injections = match matches.get_many::<String>("inject") {
+        Some(specs) => specs.map(|spec| parse_injection(spec)).collect::<PushResult<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    for injection in &injections {
+        let trigger = format!("{:?}", injection.trigger);
+        info!("{}", t!("banner.injection", injection.field, injection.value, trigger, injection.times));
+    }
+
+    // 汇总影响消息内容的发送选项
+    let mut send_options = SendOptions {
+        seq_field: matches.get_one::<String>("seq-field").cloned(),
+        on_empty: match matches.get_one::<String>("on-empty").unwrap().as_str() {
+            "skip" => EmptyPolicy::Skip,
+            "send" => EmptyPolicy::Send,
+            _ => EmptyPolicy::Error,
+        },
+        dry_run: matches.get_flag("dry-run"),
+        timestamps: TimestampAllocator::new(!matches.get_flag("raw-timestamps")),
+        clock_adjust: matches.get_flag("auto-clock-adjust").then(ClockAdjuster::new),
+        throttle: Throttle::new(Duration::from_secs(*matches.get_one::<u64>("max-throttle-wait").unwrap())),
+        url_template: UrlTemplate::parse(matches.get_one::<String>("url-template").unwrap())?,
+        ..Default::default()
+    };
+    if matches.value_source("url-template") == Some(ValueSource::CommandLine) {
+        info!("{}", t!("banner.url_template", send_options.url_template.as_str()));
+    }
+    // Edge的设备API与云端相同，差别在于设备需要分配给Edge，数据先写入Edge再异步同步到云端
+    let edge = matches.get_flag("edge");
+    if edge {
+        info!("{}", t!("banner.edge"));
+    }
+    send_options.idempotency = matches.get_one::<String>("idempotency-key").map(|mode| IdempotencyKeys {
+        mode: if mode == "seq" { IdempotencyMode::Seq } else { IdempotencyMode::Hash },
+        header: matches.get_one::<String>("idempotency-header").unwrap().clone(),
+        field: matches.get_one::<String>("idempotency-field").cloned(),
+        salt: matches.get_one::<String>("idempotency-salt").cloned().unwrap_or_default(),
+    });
+    if let Some(ref keys) = send_options.idempotency {
+        let field = keys.field.as_deref().map(|field| t!("banner.idempotency_field", field)).unwrap_or_default();
+        info!("{}", t!("banner.idempotency", format!("{:?}", keys.mode).to_lowercase(), keys.header, field));
+    }
+    // 属性字段：命令行优先于数据文件中的 attribute_fields
+    send_options.attribute_fields = match matches.get_one::<String>("attribute-fields") {
+        Some(fields) => fields
+            .split(',')
+            .map(str::trim)
+            .filter(|f| !f.is_empty())
+            .map(String::from)
+            .collect(),
+        None => data_result.attribute_fields.iter().cloned().collect(),
+    };
+    if !send_options.attribute_fields.is_empty() {
+        let mut fields: Vec<_> = send_options.attribute_fields.iter().map(String::as_str).collect();
+        fields.sort_unstable();
+        info!("{}", t!("banner.attribute_fields", fields.join(", ")));
+    }
+    send_options.field_rules = FieldRules {
+        rename: parse_rename(matches.get_many::<String>("rename").into_iter().flatten())?,
+        conditions: data_result.conditions.clone(),
+        valid_range: data_result.valid_range.clone(),
+        flatten: parse_flatten(&matches),
+        non_finite: parse_non_finite(&matches),
+    };
+    if !send_options.field_rules.conditions.is_empty() {
+        let fields: Vec<_> = send_options.field_rules.conditions.keys().map(String::as_str).collect();
+        info!("{}", t!("banner.conditions", fields.join(", ")));
+    }
+    if !send_options.field_rules.valid_range.is_empty() {
+        let ranges: Vec<_> =
+            send_options.field_rules.valid_range.iter().map(|(field, range)| format!("{} {}", field, range)).collect();
+        info!("{}", t!("banner.valid_range", ranges.join(", ")));
+    }
+    if !send_options.field_rules.rename.is_empty() {
+        let mut pairs: Vec<_> =
+            send_options.field_rules.rename.iter().map(|(old, new)| format!("{}→{}", old, new)).collect();
+        pairs.sort_unstable();
+        info!("{}", t!("banner.rename", pairs.join(", ")));
+    }
+    if matches.get_flag("no-time-field") {
+        send_options.time_field = None;
+        send_options.payload_time_key = None;
+        info!("{}", t!("banner.no_time_field"));
+    } else if let Some(name) = matches.get_one::<String>("time-field") {
+        send_options.time_field = Some(name.clone());
+        send_options.payload_time_key = Some(name.clone());
+        info!("{}", t!("banner.time_field", name));
+    } else if matches.get_flag("no-send-time") {
+        send_options.time_field = None;
+        info!("{}", t!("banner.no_send_time"));
+    } else if let Some(name) = matches.get_one::<String>("send-time-key") {
+        send_options.time_field = Some(name.clone());
+        info!("{}", t!("banner.send_time_key", name));
+    }
+    send_options.payload_shape = if matches.get_flag("flat-payload") {
+        PayloadShape::Flat
+    } else if matches.get_flag("strict-payload") {
+        PayloadShape::Strict
+    } else {
+        PayloadShape::Legacy
+    };
+    let merge_size = matches
+        .get_flag("merge-timeseries")
+        .then(|| *matches.get_one::<u64>("merge-size").unwrap() as usize);
+    if let Some(size) = merge_size {
+        info!("{}", t!("banner.merge_timeseries", size));
+        if !geo_tracks.is_empty() || !walks.is_empty() || !data_result.field_groups.is_empty() {
+            warn!("{}", t!("banner.merge_ignored"));
+        }
+    }
+
+    // 发送前检查：按实际的字段规则试算每条数据项，一次报告全部不可发送的数据项，而不是轮到时才失败
+    let source = data_dir.unwrap_or(data_file);
+    let total_items = data_result.data.len();
+    let invalid = preflight_items(&data_result.data, &data_result.random_key, &send_options, merge_size.is_some());
+    for (index, reason) in &invalid {
+        warn!(
+            event = "invalid_item",
+            item_index = *index as u64,
+            reason = reason.as_str(),
+            "{}",
+            t!("data.invalid_item", index + 1, reason)
+        );
+    }
+    if !invalid.is_empty() {
+        if matches.get_flag("strict") {
+            anyhow::bail!(t!("data.invalid_strict", source, invalid.len()));
+        }
+        let invalid: HashSet<usize> = invalid.into_iter().map(|(index, _)| index).collect();
+        let mut index = 0;
+        data_result.data.retain(|_| {
+            index += 1;
+            !invalid.contains(&(index - 1))
+        });
+        if data_result.data.is_empty() {
+            anyhow::bail!(t!("data.no_valid_items", source));
+        }
+    }
+    info!("{}", t!("banner.items_valid", data_result.data.len(), total_items));
+    match send_options.payload_shape {
+        PayloadShape::Strict => info!("{}", t!("banner.strict_payload")),
+        PayloadShape::Flat => info!("{}", t!("banner.flat_payload")),
+        PayloadShape::Legacy => {}
+    }
+    if let Some(ref field) = send_options.seq_field {
+        info!("{}", t!("banner.seq_field", field));
+    }
+    if matches.get_flag("show-response") {
+        let max_len: usize = matches
+            .get_one::<String>("response-max-len")
+            .unwrap()
+            .parse()
+            .context(t!("arg.response_max_len_invalid"))?;
+        let headers: Vec<String> = matches
+            .get_one::<String>("response-headers")
+            .unwrap()
+            .split(',')
+            .map(|h| h.trim().to_ascii_lowercase())
+            .filter(|h| !h.is_empty())
+            .collect();
+        info!("{}", t!("banner.show_response", headers.join(", "), max_len));
+        send_options.show_response = Some(ResponseDisplay { headers, max_len });
+    }
+    // 签名密钥和设备令牌一样属于机密，命令行未指定时读取环境变量 sign_key 或 sign_key_FILE，
+    // 避免出现在进程列表和shell历史中
+    let sign_key = match matches.get_one::<String>("sign-key") {
+        Some(key) => Some(key.clone()),
+        None => read_secret_setting("sign_key", |name| env::var(name).ok())?,
+    };
+    if sign_key.is_none()
+        && let Some(arg) = ["sign-header", "sign-algorithm", "sign-timestamp-header"]
+            .into_iter()
+            .find(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+    {
+        anyhow::bail!(t!("banner.sign_key_missing", arg));
+    }
+    if let Some(key) = sign_key {
+        let header = matches.get_one::<String>("sign-header").unwrap().clone();
+        let timestamp_header = matches.get_one::<String>("sign-timestamp-header").cloned();
+        // 启动时校验请求头名称，避免每次发送才报错
+        for name in std::iter::once(&header).chain(timestamp_header.as_ref()) {
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .with_context(|| t!("arg.header_name_invalid", name))?;
+        }
+        let algorithm = match matches.get_one::<String>("sign-algorithm").unwrap().as_str() {
+            "hmac-sha512" => SignAlgorithm::Sha512,
+            _ => SignAlgorithm::Sha256,
+        };
+        info!(
+            "{}",
+            t!(
+                "banner.signing",
+                format!("{:?}", algorithm),
+                header,
+                timestamp_header
+                    .as_ref()
+                    .map(|h| t!("banner.signing_timestamp", h))
+                    .unwrap_or_default()
+            )
+        );
+        send_options.signer = Some(RequestSigner {
+            key: key.as_bytes().to_vec(),
+            header,
+            algorithm,
+            timestamp_header,
+        });
+    }
+    // 审计文件在发送前打开，权限等问题在启动时就暴露出来
+    if let Some(path) = matches.get_one::<String>("audit-file") {
+        let rotation = AuditRotation {
+            max_size: matches
+                .get_one::<String>("audit-max-size")
+                .map(|size| parse_size(size).context(t!("arg.audit_max_size_invalid")))
+                .transpose()?
+                .filter(|&size| size > 0),
+            max_files: matches
+                .get_one::<String>("audit-max-files")
+                .unwrap()
+                .parse()
+                .context(t!("arg.audit_max_files_invalid"))?,
+            daily: matches.get_flag("audit-rotate-daily"),
+        };
+        send_options.audit = Some(AuditLog::open(path, rotation)?);
+        info!("{}", t!("banner.audit_file", path));
+    }
+    // CSV报告同样在发送前创建，与审计文件互相独立
+    let csv_report = match matches.get_one::<String>("report-csv") {
+        Some(path) => {
+            info!("{}", t!("banner.report_csv", path));
+            Some(CsvReport::create(path)?)
+        }
+        None => None,
+    };
+    let tee = match matches.get_one::<String>("tee") {
+        Some(path) => {
+            info!("{}", t!("banner.tee", path));
+            Some(TeeFile::create(path)?)
+        }
+        None => None,
+    };
+    let fast = matches.get_flag("fast");
+    let fail_fast = matches.get_flag("fail-fast");
+    let max_consecutive_failures: Option<u64> = match matches.get_one::<String>("max-consecutive-failures") {
+        _ if fail_fast => Some(1),
+        Some(value) => Some(
+            value
+                .parse()
+                .ok()
+                .filter(|&max| max > 0)
+                .context(t!("arg.max_failures_invalid"))?,
+        ),
+        None => None,
+    };
+    let requeue = matches.get_one::<u64>("requeue").copied().unwrap_or(0);
+    if requeue > 0 {
+        info!("{}", t!("banner.requeue", requeue));
+    }
+    if send_options.dry_run {
+        warn!("{}", t!(if fast { "banner.dry_run_fast" } else { "banner.dry_run" }));
+    }
+    if let Some(ref schedule) = rate_schedule {
+        let warmup = if schedule.warmup > 0.0 {
+            let curve = t!(match schedule.curve {
+                WarmupCurve::Linear => "banner.warmup_linear",
+                WarmupCurve::Exponential => "banner.warmup_exponential",
+            });
+            t!("banner.warmup", schedule.warmup, curve)
+        } else {
+            String::new()
+        };
+        info!("{}", t!("banner.rate", schedule.rate, warmup));
+    }
+
+    // webhook回调配置，演练模式下不发出回调
+    let webhook = matches
+        .get_one::<String>("webhook")
+        .filter(|_| !send_options.dry_run)
+        .cloned();
+    let webhook_per_round = matches.get_one::<String>("webhook-mode").unwrap() == "round";
+    if let Some(ref url) = webhook {
+        let mode = t!(if webhook_per_round { "banner.webhook_per_round" } else { "banner.webhook_per_item" });
+        info!("{}", t!("banner.webhook", url, mode));
+    }
+    // 运行通知同样在演练模式下不发出，模板在启动时校验
+    let notifier = match matches.get_one::<String>("notify-url").filter(|_| !send_options.dry_run) {
+        Some(url) => {
+            let template = match matches.get_one::<String>("notify-template") {
+                Some(template) => match template.strip_prefix('@') {
+                    Some(path) => {
+                        Some(fs::read_to_string(path).with_context(|| t!("notify.template_read_failed", path))?)
+                    }
+                    None => Some(template.clone()),
+                },
+                None => None,
+            };
+            let every_failures = match matches.get_one::<String>("notify-on-failures") {
+                Some(value) => Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|&every: &u64| every > 0)
+                        .context(t!("arg.notify_on_failures_invalid"))?,
+                ),
+                None => None,
+            };
+            let notifier = Notifier::new(url, template, every_failures)?;
+            info!("{}", t!("banner.notify", url, notifier.run_id));
+            if let Some(every) = every_failures {
+                info!("{}", t!("banner.notify_failures", every));
+            }
+            Some(notifier)
+        }
+        None => None,
+    };
+
+    // 创建HTTP客户端，-vv 时开启连接层的详细日志
+    let user_agent = matches.get_one::<String>("user-agent").unwrap();
+    if matches.value_source("user-agent") == Some(ValueSource::CommandLine) {
+        info!("{}", t!("banner.user_agent", user_agent));
+    }
+    // 重定向跟随次数，0表示不跟随，避免带令牌的请求被网关转到意料之外的地址
+    let max_redirects = *matches.get_one::<usize>("max-redirects").unwrap();
+    if max_redirects == 0 {
+        info!("{}", t!("banner.no_redirects"));
+    }
+    if send_options.clock_adjust.is_some() {
+        info!("{}", t!("banner.clock_adjust"));
+    }
+    // 连接和TCP参数，显式指定时输出生效的值
+    let tuning = ConnectionTuning {
+        nodelay: *matches.get_one::<bool>("tcp-nodelay").unwrap(),
+        keepalive: matches.get_one::<u64>("tcp-keepalive").map(|secs| Duration::from_secs(*secs)),
+        pool_idle_timeout: match *matches.get_one::<u64>("pool-idle-timeout").unwrap() {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        },
+        pool_max_idle: matches.get_one::<usize>("pool-max-idle").copied().unwrap_or(usize::MAX),
+    };
+    if tuning != ConnectionTuning::default() {
+        let seconds = |value: Option<Duration>, none: &str| value.map_or(t!(none), |d| format!("{}s", d.as_secs()));
+        info!(
+            "{}",
+            t!(
+                "banner.connection_tuning",
+                if tuning.nodelay { "on" } else { "off" },
+                seconds(tuning.keepalive, "banner.connection_off"),
+                seconds(tuning.pool_idle_timeout, "banner.connection_forever"),
+                matches.get_one::<usize>("pool-max-idle").map_or(t!("banner.connection_unlimited"), usize::to_string)
+            )
+        );
+    }
+    let client = tuning
+        .apply(Client::builder())
+        .user_agent(user_agent.as_str())
+        .redirect(redirect_policy(max_redirects))
+        .connection_verbose(matches.get_count("verbose") >= 2)
+        .build()
+        .with_context(|| t!("banner.client_failed"))?;
+
+    // Prometheus指标，监听端口在发送前绑定，端口被占用等问题在启动时暴露
+    let metrics = Arc::new(Metrics::default());
+    if let Some(addr) = matches.get_one::<String>("metrics-listen") {
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| t!("metrics.bind_failed", addr))?;
+        info!("{}", t!("metrics.listening", addr));
+        tokio::spawn(serve_metrics(listener, Arc::clone(&metrics)));
+    }
+
+    // StatsD上报，地址在启动时解析
+    let statsd = match matches.get_one::<String>("statsd") {
+        Some(addr) => {
+            let prefix = matches.get_one::<String>("statsd-prefix").unwrap();
+            let client = StatsdClient::connect(addr, prefix)?;
+            info!("{}", t!("statsd.enabled", addr, client.prefix));
+            Some(client)
+        }
+        None => None,
+    };
+
+    let device_label = match token_pool {
+        Some(ref pool) if pool.rotation => {
+            t!("banner.device_rotation", mask_token(&pool.tokens.get(0)?), pool.tokens.len())
+        }
+        Some(ref pool) => t!("banner.device_pool", pool.tokens.len()),
+        None => mask_token(&config.device_tokens[0]),
+    };
+    info!(
+        event = "startup",
+        servers = config.servers.join(",").as_str(),
+        items = data_result.data.len() as u64,
+        item_interval = intervals.item,
+        round_interval = intervals.round,
+        count,
+        device = device_label.as_str(),
+        "{}",
+        t!("banner.start", data_result.data.len())
+    );
+
+    // 总数已知时显示进度；静默模式和JSON日志模式下不显示
+    let total_messages = (count > 0).then(|| count * data_result.data.len() as u64);
+    let progress = Progress::new(
+        total_messages,
+        log_format == LogFormat::Human
+            && !tui
+            && matches.get_count("quiet") == 0
+            && matches.get_one::<String>("log-file").is_none(),
+    );
+
+    // 周期性心跳，静默模式和JSON日志模式下同样输出
+    let heartbeat = Arc::new(Heartbeat::new(total_messages));
+    let heartbeat_enabled = !matches.get_flag("no-heartbeat");
+    let heartbeat_period = Duration::from_secs(heartbeat_every);
+    let mut heartbeat_ticker = tokio::time::interval_at(tokio::time::Instant::now() + heartbeat_period, heartbeat_period);
+    heartbeat_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    // 按时间窗口滚动统计，窗口长度由 --stats-interval 指定
+    let window_stats = Arc::new(WindowStats::new(log_format == LogFormat::Human));
+    let stats_period = matches.get_one::<u64>("stats-interval").map(|secs| Duration::from_secs(*secs));
+    let mut stats_ticker = tokio::time::interval_at(
+        tokio::time::Instant::now() + stats_period.unwrap_or(heartbeat_period),
+        stats_period.unwrap_or(heartbeat_period),
+    );
+    stats_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    if let Some(period) = stats_period {
+        info!("{}", t!("banner.stats_interval", period.as_secs()));
     }
 
-    // 创建HTTP客户端
-    let client = Client::new();
+    // 使用配置档时，收到SIGHUP重新读取配置档并应用可热更新的参数
+    let current_profile = profile.clone().unwrap_or_default();
+    #[cfg(unix)]
+    let reload_rx = match profile_name {
+        Some(name) => Some(spawn_reload_listener(
+            matches.get_one::<String>("config").unwrap().clone(),
+            name.clone(),
+            current_profile.clone(),
+        )?),
+        None => None,
+    };
+    #[cfg(not(unix))]
+    let reload_rx: Option<watch::Receiver<ProfileSettings>> = None;
 
     // 开始数据发送循环
-    let mut sent_count = 0;
-    loop {
-        // 遍历数据文件中的每一项数据
-        for (index, item) in data_result.data.iter().enumerate() {
-            // 尝试发送遥测数据到ThingsBoard
-            match send_telemetry(&client, &config, item, &data_result.random_key).await {
-                Ok(_) => {
-                    sent_count += 1;
-                    println!("✅ 第{}次发送成功 - 数据项 {}/{}", sent_count, index + 1, data_result.data.len());
+    let started_at = Instant::now();
+    let started_wall = Local::now();
+    let mut round = 0;
+    let mut quota = SendQuota::new(
+        match matches.get_one::<String>("count-policy").unwrap().as_str() {
+            "successes" => CountPolicy::Successes,
+            _ => CountPolicy::Attempts,
+        },
+        data_result.data.len(),
+    );
+    // 第一次按Ctrl-C时发出停止请求：不再派发新的发送，等待进行中的发送完成后输出汇总
+    let (stop_tx, stop_rx) = watch::channel(false);
+    send_options.throttle.stop = Some(stop_rx.clone());
+    let pacer = Pacer::new(intervals, !fast && rate_schedule.is_none(), stop_rx);
+
+    // 断点续传：按状态文件恢复轮次、数据项位置、成功次数和随机数状态
+    let state_file = matches.get_one::<String>("state-file").map(|path| {
+        let options = BTreeMap::from([
+            ("seed".to_string(), serde_json::json!(seed)),
+            ("count".to_string(), Value::from(count)),
+            ("count_policy".to_string(), Value::from(matches.get_one::<String>("count-policy").unwrap().as_str())),
+            ("random_key".to_string(), serde_json::json!(data_result.random_key)),
+            ("merge_size".to_string(), serde_json::json!(merge_size)),
+            ("devices".to_string(), Value::from(token_pool.as_ref().map_or(1, |pool| pool.tokens.len()))),
+        ]);
+        StateFile::new(path, &data_result.data, options)
+    });
+    let mut resume_from = 0;
+    let mut sequence_base = 0;
+    if matches.get_flag("resume")
+        && let Some(ref state_file) = state_file
+    {
+        match state_file.load()? {
+            None => warn!("{}", t!("state.missing", state_file.path)),
+            Some(state) if state.finished => {
+                info!("{}", t!("state.already_finished", state_file.path, state.sequence));
+                return Ok(ExitCode::SUCCESS);
+            }
+            Some(state) => {
+                let corrupt = || t!("state.corrupt", state_file.path);
+                if state.delivered.len() != quota.delivered.len() || state.geo.len() != geo_tracks.len()
+                    || state.walks.len() != walks.len()
+                {
+                    anyhow::bail!(corrupt());
                 }
-                Err(e) => {
-                    eprintln!("❌ 发送失败: {}", e);
+                for (index, position) in &state.rngs {
+                    let mut rng = seeded_rng(seed, *index as u64);
+                    rng.set_word_pos(position.parse().with_context(corrupt)?);
+                    device_rngs.insert(*index, rng);
                 }
+                for (track, saved) in geo_tracks.iter_mut().zip(&state.geo) {
+                    track.restore(saved).with_context(corrupt)?;
+                }
+                for (walk, saved) in walks.iter_mut().zip(&state.walks) {
+                    walk.restore(saved).with_context(corrupt)?;
+                }
+                quota.delivered = state.delivered;
+                round = state.round - 1;
+                resume_from = state.next_index;
+                sequence_base = state.sequence;
+                info!("{}", t!("state.resumed", state.round, state.next_index + 1, state.sequence, state_file.path));
             }
+        }
+    }
+    // TUI仪表盘在独立线程中运行，通过控制通道让发送循环暂停或退出
+    let (tui_commands, tui_control) = watch::channel(TuiCommand::Running);
+    let mut tui_quit = tui_control.clone();
+    let tui_stop = Arc::new(AtomicBool::new(false));
+    let tui_thread = tui.then(|| {
+        TUI_ACTIVE.store(true, Ordering::Relaxed);
+        let stop = tui_stop.clone();
+        tokio::task::spawn_blocking(move || run_tui(tui_commands, stop))
+    });
+    // --concurrency 大于1时由发送工作者并发发送，派发与工作者之间是有界队列，发送跟不上时派发等待
+    let concurrency = *matches.get_one::<u64>("concurrency").unwrap() as usize;
+    let queue_size = *matches.get_one::<u64>("queue-size").unwrap() as usize;
+    let send_options = Arc::new(send_options);
+    let server_pool = Arc::new(server_pool);
+    // 发送循环只依赖传输层trait，负载的构建与传输方式无关
+    let sender: Arc<dyn Sender> =
+        Arc::new(HttpSender::new(client.clone(), Arc::clone(&server_pool), Arc::clone(&send_options)));
+    let workers = (concurrency > 1)
+        .then(|| spawn_send_workers(concurrency, queue_size, &sender, &send_options, &metrics));
+    let pipeline_depth = if workers.is_some() {
+        info!("{}", t!("banner.concurrency", concurrency, queue_size));
+        concurrency + queue_size
+    } else {
+        1
+    };
+    let mut send_loop = SendLoop {
+        token_pool: token_pool.as_ref(),
+        random_key: data_result.random_key.clone(),
+        seed,
+        merge_size,
+        rate_schedule: rate_schedule.as_ref().filter(|_| !fast),
+        workers,
+        pipeline_depth,
+        requeue,
+        max_consecutive_failures,
+        fail_fast,
+        // 遇到认证失败时记录状态码，之后的发送必然同样失败，直接中止
+        ignore_auth_errors: matches.get_flag("ignore-auth-errors"),
+        edge,
+        control: tui.then_some(tui_control),
+        heartbeat: Arc::clone(&heartbeat),
+        window_stats: Arc::clone(&window_stats),
+        metrics: Arc::clone(&metrics),
+        statsd,
+        progress,
+        csv_report,
+        tee,
+        quota,
+        round,
+        resume_from,
+        sequence_base,
+        position: RunPosition { round: round + 1, next_index: resume_from, sequence: sequence_base },
+        state_file,
+        device_rngs,
+        geo_tracks,
+        walks,
+        injections,
+        field_scheduler,
+        started_at,
+        ..SendLoop::new(&data_result.data, &config.device_tokens[0], &send_options, count, pacer)
+    };
+    let mut events = CliEvents {
+        client: &client,
+        webhook: webhook.as_deref(),
+        webhook_per_round,
+        notifier: notifier.as_ref(),
+        tasks: JoinSet::new(),
+        reload_rx,
+        matches: &matches,
+        profile_name: profile_name.map_or("", |name| name.as_str()),
+        current_profile,
+    };
 
-            // 在发送数据项之间等待指定间隔时间
-            if interval > 0 && index < data_result.data.len() - 1 {
-                sleep(Duration::from_secs(interval)).await;
+    // 按下Ctrl-C时停止派发新的发送，等待进行中的发送完成后同样输出统计信息；再按一次立即退出。
+    // 发送期间按间隔输出心跳
+    let (interrupted, aborted) = {
+        let mut run = std::pin::pin!(send_loop.run(sender.as_ref(), &mut events));
+        let mut ctrl_c = std::pin::pin!(tokio::signal::ctrl_c());
+        let mut interrupted = false;
+        loop {
+            tokio::select! {
+                aborted = &mut run => break (interrupted, aborted),
+                _ = &mut ctrl_c => {
+                    if interrupted {
+                        warn!("{}", t!("send.force_exit"));
+                        std::process::exit(EXIT_INTERRUPTED.into());
+                    }
+                    interrupted = true;
+                    stop_tx.send_replace(true);
+                    warn!("{}", t!("send.stopping"));
+                    ctrl_c.set(tokio::signal::ctrl_c());
+                }
+                _ = heartbeat_ticker.tick(), if heartbeat_enabled => heartbeat.emit(),
+                _ = stats_ticker.tick(), if stats_period.is_some() => window_stats.emit(),
+                _ = tui_quit.wait_for(|command| *command == TuiCommand::Quit), if tui && !interrupted => {
+                    interrupted = true;
+                    stop_tx.send_replace(true);
+                }
             }
         }
+    };
+
+    sender.shutdown().await;
+
+    // 退出时记录最后的位置，正常完成时标记为已完成
+    send_loop.save_state(!interrupted && !aborted && send_loop.count > 0);
+    if let Some(ref state_file) = send_loop.state_file {
+        info!("{}", t!("state.saved", state_file.path));
+    }
+    let SendLoop { stats, progress, pacer, count, random_key, auth_failure, csv_report, tee, .. } = send_loop;
+    let mut webhook_tasks = events.tasks;
+
+    // 关闭TUI仪表盘并恢复终端，之后的汇总照常输出
+    if let Some(thread) = tui_thread {
+        tui_stop.store(true, Ordering::Relaxed);
+        let result = thread.await;
+        TUI_ACTIVE.store(false, Ordering::Relaxed);
+        if let Ok(Err(e)) = result {
+            warn!("{}", t!("tui.failed", e));
+        }
+    }
+
+    // 等待尚未完成的webhook回调，中断时直接放弃
+    if interrupted {
+        webhook_tasks.shutdown().await;
+    }
+    while webhook_tasks.join_next().await.is_some() {}
+    progress.finish();
+
+    let elapsed = started_at.elapsed();
+    stats.print_summary(elapsed, interrupted);
+    let timestamps_adjusted = send_options.timestamps.adjusted();
+    if timestamps_adjusted > 0 {
+        info!(
+            event = "timestamps_adjusted",
+            count = timestamps_adjusted,
+            "{}",
+            t!("summary.timestamps_adjusted", timestamps_adjusted)
+        );
+    }
+    if let Some(ref clock) = send_options.clock_adjust {
+        info!("{}", t!("summary.clock_adjust", clock.offset_ms()));
+    }
+    let throttled = send_options.throttle.events();
+    let throttle_wait_ms = send_options.throttle.waited_ms();
+    if throttled > 0 {
+        warn!("{}", t!("summary.throttled", throttled, format!("{:.1}", throttle_wait_ms as f64 / 1000.0)));
+    }
+    if let Some(ref pool) = token_pool {
+        pool.print_summary();
+    }
+    if let Some(ref report) = csv_report {
+        info!("{}", t!("summary.report_csv", report.path, report.rows));
+    }
+    if let Some(ref tee) = tee {
+        info!("{}", t!("summary.tee", tee.path, tee.lines));
+    }
+    if let Some(ref audit) = send_options.audit {
+        info!("{}", t!("summary.audit_files"));
+        for path in audit.files() {
+            info!("   {}", path);
+        }
+    }
+
+    let aborted_after = max_consecutive_failures.filter(|_| aborted);
+    let (mut exit_code, mut reason) = if interrupted {
+        (EXIT_INTERRUPTED, t!("exit.interrupted"))
+    } else if let Some(status) = auth_failure {
+        (EXIT_AUTH_FAILED, t!("exit.auth_failed", status))
+    } else {
+        exit_status(&stats, aborted_after, matches.get_flag("ignore-failures"))
+    };
 
-        // 检查是否达到指定的发送次数
-        if count > 0 {
-            if sent_count >= count * data_result.data.len() as u64 {
-                break;
+    // 执行完成后命令，中断和演练时不执行；命令失败且发送本身成功时以专门的退出码结束
+    if let Some(command) = matches.get_one::<String>("on-complete") {
+        if interrupted || send_options.dry_run {
+            info!("{}", t!("on_complete.skipped", command));
+        } else {
+            let mut env_vars = stats.env_vars(elapsed);
+            env_vars.push(("PUSH_EXIT_CODE", exit_code.to_string()));
+            if let Some(ref report) = csv_report {
+                env_vars.push(("PUSH_REPORT_CSV", report.path.clone()));
+            }
+            if let Err(e) = run_on_complete(command, &env_vars).await {
+                error!(event = "on_complete_failed", error = format!("{:#}", e).as_str(), "❌ {:#}", e);
+                if exit_code == EXIT_SUCCESS {
+                    exit_code = EXIT_ON_COMPLETE_FAILED;
+                    reason = t!("exit.on_complete_failed");
+                }
             }
         }
+    }
 
-        // 如果需要继续发送，等待下一轮
-        if count == 0 || sent_count < count * data_result.data.len() as u64 {
-            println!("⏳ 等待 {} 秒后继续下一轮发送...", interval);
-            sleep(Duration::from_secs(interval)).await;
+    // JSON汇总在确定退出码之后写出，中断时同样写出；推回ThingsBoard的统计由同一份汇总生成
+    let summary_path = matches.get_one::<String>("summary-json");
+    let stats_token = matches.get_one::<String>("stats-to-tb");
+    if summary_path.is_some() || stats_token.is_some() {
+        let mut summary = serde_json::Map::new();
+        summary.insert("schema".to_string(), Value::from(1));
+        summary.insert("started_at".to_string(), Value::from(started_wall.to_rfc3339()));
+        summary.insert("finished_at".to_string(), Value::from(Local::now().to_rfc3339()));
+        summary.insert("interrupted".to_string(), Value::from(interrupted));
+        summary.insert("dry_run".to_string(), Value::from(send_options.dry_run));
+        if let Value::Object(stats_fields) = stats.summary_json(elapsed) {
+            summary.extend(stats_fields);
+        }
+        summary.insert("timestamps_adjusted".to_string(), Value::from(timestamps_adjusted));
+        summary.insert("throttled".to_string(), Value::from(throttled));
+        summary.insert("throttle_wait_ms".to_string(), Value::from(throttle_wait_ms));
+        summary.insert("seed".to_string(), serde_json::json!(seed));
+        summary.insert(
+            "config".to_string(),
+            serde_json::json!({
+                "servers": config.servers,
+                "device": device_label,
+                "data_file": data_dir.unwrap_or(data_file),
+                "profile": profile_name,
+                "item_interval": pacer.intervals.item,
+                "round_interval": pacer.intervals.round,
+                "count": count,
+                "rate": rate_schedule.as_ref().map(|schedule| schedule.rate),
+                "random_key": random_key,
+            }),
+        );
+        summary.insert("exit_code".to_string(), Value::from(exit_code));
+        summary.insert("exit_reason".to_string(), Value::from(reason.as_str()));
+        let summary = Value::Object(summary);
+        if let Some(path) = summary_path {
+            write_summary_json(path, &summary)?;
+        }
+        match stats_token {
+            Some(token) if send_options.dry_run => info!("{}", t!("stats_tb.dry_run_skipped", mask_token(token))),
+            Some(token) => {
+                push_stats_to_tb(&client, &send_options.url_template, &server_pool.pick(), token, &summary).await
+            }
+            None => {}
         }
     }
 
-    println!("🎉 数据发送完成！总共发送了 {} 条数据", sent_count);
+    // 运行通知放在最后，等待发出但不影响退出码
+    if let Some(ref notifier) = notifier {
+        let status = if interrupted {
+            "interrupted"
+        } else if aborted_after.is_some() || auth_failure.is_some() {
+            "aborted"
+        } else if stats.unrecovered() > 0 {
+            "failed"
+        } else {
+            "completed"
+        };
+        notifier.send(&client, status, &stats, elapsed, Some((exit_code, &reason))).await;
+    }
+
+    let message = t!("exit.message", exit_code, reason);
+    if exit_code == EXIT_SUCCESS {
+        info!(event = "exit", exit_code, reason = reason.as_str(), "{}", message);
+    } else {
+        warn!(event = "exit", exit_code, reason = reason.as_str(), "{}", message);
+    }
+    Ok(ExitCode::from(exit_code))
+}
+
+/// 写出 `--summary-json` 汇总，路径为 `-` 时输出到stdout
+///
+/// # 错误
+///
+/// 当汇总文件无法写入时返回错误
+fn write_summary_json(path: &str, summary: &Value) -> Result<()> {
+    let text = serde_json::to_string_pretty(summary)?;
+    if path == "-" {
+        let mut stdout = std::io::stdout().lock();
+        writeln!(stdout, "{}", text).and_then(|_| stdout.flush()).context(t!("summary_json.stdout_failed"))?;
+    } else {
+        fs::write(path, text + "\n").with_context(|| t!("summary_json.write_failed", path))?;
+        info!("{}", t!("summary_json.written", path));
+    }
     Ok(())
 }
 
-/// 从环境变量加载ThingsBoard配置
+/// 执行 `generate` 子命令：离线生成数据并写入文件
 ///
-/// 从.env文件或系统环境变量中读取服务器地址和设备令牌
+/// 按数据文件依次生成每条记录（随机字段照常随机），时间戳从当前时间开始，
+/// 每行递增 `--interval` 秒
+///
+/// # 错误
+///
+/// 当参数无效、数据文件无法加载或输出文件无法写入时返回错误
+fn run_generate(matches: &ArgMatches) -> Result<()> {
+    let data_file = matches.get_one::<String>("data-file").unwrap();
+    let count: u64 = matches
+        .get_one::<String>("count")
+        .unwrap()
+        .parse()
+        .with_context(|| t!("arg.count_invalid"))?;
+    if count == 0 {
+        anyhow::bail!(t!("generate.count_zero"));
+    }
+    let interval: u64 = matches
+        .get_one::<String>("interval")
+        .unwrap()
+        .parse()
+        .with_context(|| t!("arg.interval_invalid"))?;
+    let seed: Option<u64> = matches
+        .get_one::<String>("seed")
+        .map(|s| s.parse().context(t!("arg.seed_invalid")))
+        .transpose()?;
+    let output = matches.get_one::<String>("output").unwrap();
+
+    let mut data_result = load_data_file(data_file)?;
+    data_result.drop_unusable_items(data_file, false)?;
+    data_result.override_random_key(matches.get_one::<String>("random-key").cloned());
+    log_randomization(&data_result, "");
+
+    let rules = FieldRules {
+        rename: parse_rename(matches.get_many::<String>("rename").into_iter().flatten())?,
+        conditions: data_result.conditions.clone(),
+        valid_range: data_result.valid_range.clone(),
+        flatten: parse_flatten(matches),
+        non_finite: parse_non_finite(matches),
+    };
+
+    let format = match matches.get_one::<String>("output-format").unwrap().as_str() {
+        "tb-csv" => OutputFormat::TbCsv,
+        _ => OutputFormat::JsonLines,
+    };
+
+    let rows = generate_rows(&data_result, &rules, count, interval, seed)?;
+    write_rows(output, format, &rows, &rules.flatten.unwrap_or_default())?;
+    info!("{}", t!("generate.done", rows.len(), output));
+    Ok(())
+}
+
+/// 离线检查数据文件的 `validate` 子命令
+///
+/// 在 [`load_data_file`] 的格式检查之外逐项检查数据，列出全部问题而不是遇到第一个就停止
 ///
 /// # 返回值
 ///
-/// * `Result<Config>` - 成功时返回配置对象，失败时返回错误信息
+/// * `Result<ExitCode>` - 没有错误且警告数不超过 `--max-warnings` 时为0，否则为 [`EXIT_CONFIG_ERROR`]
 ///
 /// # 错误
 ///
-/// 当环境变量'server'或'device_token'不存在时返回错误
-fn load_config() -> Result<Config> {
-    let server = env::var("server").context("未找到环境变量 'server'")?;
-    let device_token = env::var("device_token").context("未找到环境变量 'device_token'")?;
+/// 数据文件无法读取或整体格式不正确时返回错误
+fn run_validate(matches: &ArgMatches) -> Result<ExitCode> {
+    let data_file = matches.get_one::<String>("data-file").unwrap();
+    let data_dir = matches.get_one::<String>("dir").map(String::as_str);
+    let data_result = load_data_source(std::slice::from_ref(data_file), data_dir)?;
+    let allow_keys: Option<HashSet<String>> = matches
+        .get_many::<String>("allow-keys")
+        .map(|keys| keys.map(|key| key.trim().to_string()).filter(|key| !key.is_empty()).collect());
 
-    Ok(Config {
-        server,
-        device_token,
-    })
+    let issues = validate_data(&data_result, allow_keys.as_ref());
+    for issue in &issues {
+        out!("{}", issue);
+    }
+    let errors = issues.iter().filter(|issue| issue.level == IssueLevel::Error).count();
+    let warnings = issues.len() - errors;
+    out!("{}", t!("validate.summary", data_result.data.len(), errors, warnings));
+
+    let max_warnings = matches.get_one::<usize>("max-warnings");
+    if let Some(max) = max_warnings
+        && warnings > *max
+    {
+        out!("{}", t!("validate.too_many_warnings", warnings, max));
+    }
+    if errors > 0 || max_warnings.is_some_and(|max| warnings > *max) {
+        return Ok(ExitCode::from(EXIT_CONFIG_ERROR));
+    }
+    Ok(ExitCode::SUCCESS)
 }
 
-/// 从文件加载JSON数据
-///
-/// 读取指定路径的JSON文件并解析为DataFileResult结构体
-/// 支持两种格式：
-/// 1. 直接数组格式: [{"sensor1": {...}}, {"sensor2": {...}}]
-/// 2. 包装对象格式: {"random_key": "...", "data": [{"sensor1": {...}}, {"sensor2": {...}}]}
+/// 按 `--profile` 从 `--config` 指定的配置文件中读取配置档
 ///
 /// # 参数
 ///
-/// * `file_path` - JSON数据文件的路径
+/// * `matches` - 命令行参数，`--profile` 和 `--config` 是全局参数，任何子命令都可以读取
 ///
 /// # 返回值
 ///
-/// * `Result<DataFileResult>` - 成功时返回包含随机键和数据数组的结果，失败时返回错误信息
+/// * `Result<Option<ProfileSettings>>` - 没有指定 `--profile` 时返回None
 ///
 /// # 错误
 ///
-/// 当文件不存在、无法读取或JSON格式错误时返回错误
-fn load_data_file(file_path: &str) -> Result<DataFileResult> {
-    let content = fs::read_to_string(file_path)
-        .with_context(|| format!("无法读取数据文件: {}", file_path))?;
-
-    // 首先尝试解析为通用Value
-    let json_value: Value = serde_json::from_str(&content)
-        .with_context(|| format!("无法解析JSON数据文件: {}", file_path))?;
-
-    // 根据JSON结构判断格式并提取数据数组和随机键
-    let result = match json_value {
-        // 格式1: 直接数组 [{"sensor1": {...}}, {"sensor2": {...}}]
-        Value::Array(arr) => {
-            println!("🔍 检测到直接数组格式的数据文件");
-            DataFileResult {
-                random_key: None,
-                data: arr,
-            }
-        }
-        // 格式2: 包装对象 {"random_key": "...", "data": [...]}
-        Value::Object(obj) => {
-            println!("🔍 检测到包装对象格式的数据文件");
-            
-            // 提取数据数组
-            let data = if let Some(Value::Array(data_array)) = obj.get("data") {
-                data_array.clone()
-            } else {
-                anyhow::bail!("包装对象格式中未找到 'data' 字段或 'data' 不是数组");
-            };
-
-            // 查找随机键的值（"random_key" 字段的值，这个值指示要随机修改哪个字段）
-            let random_key = obj.get("random_key")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-
-            DataFileResult {
-                random_key,
-                data,
-            }
-        }
-        _ => {
-            anyhow::bail!("不支持的JSON格式，期望数组或包含'data'字段的对象");
-        }
+/// 配置文件无法读取、格式错误或其中没有该配置档时返回错误
+fn load_profile(matches: &ArgMatches) -> Result<Option<ProfileSettings>> {
+    let Some(name) = matches.get_one::<String>("profile") else {
+        return Ok(None);
     };
+    let config_path = matches.get_one::<String>("config").unwrap();
+    let config_file = load_config_file(config_path)?;
+    let profile = config_file
+        .profiles
+        .get(name)
+        .cloned()
+        .with_context(|| t!("config.profile_not_found", config_path, name))?;
+    Ok(Some(profile))
+}
 
-    // 验证数据是否为空
-    if result.data.is_empty() {
-        anyhow::bail!("数据文件中没有找到有效数据");
-    }
-
-    Ok(result)
+/// `send` 子命令参数的默认值，参数没有默认值时返回空字符串
+fn send_default(id: &str) -> String {
+    send_args()
+        .iter()
+        .find(|arg| arg.get_id() == id)
+        .and_then(|arg| arg.get_default_values().first().map(|value| value.to_string_lossy().into_owned()))
+        .unwrap_or_default()
 }
 
-/// 向ThingsBoard发送遥测数据
+/// `init` 生成的示例数据文件
+const INIT_DATA: &str = include_str!("../data_example.json");
+
+/// 在目录中生成起步用的文件模板
 ///
-/// 将JSON数据转换为ThingsBoard遥测格式并通过HTTP API发送
-/// 如果提供了随机键，会随机修改对应字段的值
+/// 生成 `.env`（服务器地址和设备令牌）、示例数据文件 `data.json`，以及包含一个
+/// `dev` 配置档的配置文件（路径取自 `--config`，相对路径按目标目录解析）。
+/// 已存在的文件默认跳过，指定 `--force` 时覆盖
 ///
 /// # 参数
 ///
-/// * `client` - HTTP客户端实例
-/// * `config` - ThingsBoard配置信息
-/// * `data` - 要发送的JSON数据
-/// * `random_key` - 可选的随机键名称，如果存在会随机修改对应字段的值
+/// * `matches` - `init` 子命令的参数
 ///
 /// # 返回值
 ///
-/// * `Result<()>` - 成功时返回Ok(())，失败时返回错误信息
+/// * `Result<ExitCode>` - 全部文件处理完成时返回0
 ///
 /// # 错误
 ///
-/// 当网络请求失败、服务器返回错误状态码或数据格式错误时返回错误
-async fn send_telemetry(client: &Client, config: &Config, data: &Value, random_key: &Option<String>) -> Result<()> {
-    // 获取当前时间戳（毫秒），用于ThingsBoard时间序列数据
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .context("无法获取系统时间")?
-        .as_millis() as u64;
-    // 获取当前时间的字符串格式 yyyy-MM-dd HH:mm:ss
-    let send_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-    // 构建符合ThingsBoard API要求的遥测数据格式
-    let mut values = extract_telemetry_values(data, random_key)?;
-    // 将发送时间添加到遥测数据中
-    values.insert("send_time".to_string(), Value::String(send_time.clone()));
-
-    let telemetry = TelemetryData {
-        ts: timestamp,
-        values,
-        time: send_time,
-    };
-
-    // 构建ThingsBoard遥测数据API的请求URL
-    let url = format!("{}/api/v1/{}/telemetry", config.server, config.device_token);
-
-    // 发送HTTP POST请求到ThingsBoard
-    let response = client
-        .post(&url)
-        .header("Content-Type", "application/json")
-        .json(&telemetry)
-        .send()
-        .await
-        .context("发送HTTP请求失败")?;
-
-    // 检查响应状态并处理结果
-    if response.status().is_success() {
-        println!("📤 数据发送成功!");
-        println!("🕒 发送时间: {}", telemetry.time);
-        println!("📊 发送数据: {}", serde_json::to_string_pretty(&telemetry.values)?);
-        Ok(())
-    } else {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_default();
-        anyhow::bail!("HTTP请求失败: {} - {}", status, error_text);
+/// 服务器地址无效、目录无法创建或文件无法写入时返回错误
+fn run_init(matches: &ArgMatches) -> Result<ExitCode> {
+    let dir = Path::new(matches.get_one::<String>("dir").unwrap());
+    let server = normalize_server_url(matches.get_one::<String>("server").unwrap())?;
+    let token = matches.get_one::<String>("token").map_or("your_device_token", String::as_str);
+    let force = matches.get_flag("force");
+    fs::create_dir_all(dir).with_context(|| t!("init.dir_failed", dir.display()))?;
+
+    let env_file = t!("init.env_file", server, token);
+    let config_file = serde_json::to_string_pretty(&serde_json::json!({
+        "profiles": {
+            "dev": {
+                "server": server,
+                "data_file": "data.json",
+                "interval": 5,
+                "count": 1
+            }
+        }
+    }))? + "\n";
+    let files = [
+        (dir.join(".env"), env_file),
+        (dir.join("data.json"), INIT_DATA.to_string()),
+        (dir.join(matches.get_one::<String>("config").unwrap()), config_file),
+    ];
+    for (path, content) in files {
+        if path.exists() && !force {
+            out!("{}", t!("init.skipped", path.display()));
+            continue;
+        }
+        fs::write(&path, content).with_context(|| t!("init.write_failed", path.display()))?;
+        out!("{}", t!("init.created", path.display()));
     }
+    out!("{}", t!("init.next_steps"));
+    Ok(ExitCode::SUCCESS)
 }
 
-/// 从JSON数据中提取遥测值
+/// 检查配置并测试每个服务器和设备令牌的组合能否连通
 ///
-/// 保持原始的JSON对象结构，不展开嵌套对象
-/// 如果提供了随机键，会在对应字段中随机修改相关值
+/// 用 [`check_device`] 读取设备属性，不会写入任何遥测数据
 ///
 /// # 参数
 ///
-/// * `data` - 输入的JSON数据
-/// * `random_key` - 可选的随机键名称，如果存在会随机修改对应字段的值
+/// * `matches` - `check` 子命令的参数
 ///
 /// # 返回值
 ///
-/// * `Result<HashMap<String, Value>>` - 成功时返回遥测数据键值对，失败时返回错误信息
-///
-/// # 数据转换规则
-///
-/// 1. 对于嵌套对象（如{"rain": {...}}），会：
-///    - 保持完整的对象结构作为值
-///    - 使用顶层键名作为字段名
-///    - 如果指定了随机键，会在嵌套对象中查找并随机修改对应字段的值
-/// 2. 对于非对象值，直接使用原键值对
+/// * `Result<ExitCode>` - 全部通过时返回0，有令牌被拒绝时返回5，其它失败返回2
 ///
 /// # 错误
 ///
-/// 当输入数据不是JSON对象或无法提取有效数据时返回错误
-fn extract_telemetry_values(data: &Value, random_key: &Option<String>) -> Result<HashMap<String, Value>> {
-    let mut values = HashMap::new();
-
-    match data {
-        Value::Object(obj) => {
-            for (key, value) in obj {
-                // 如果存在随机键且当前值是对象，则尝试随机修改对应字段
-                if let (Some(random_field), Value::Object(nested_obj)) = (random_key, value) {
-                    if let Some(random_value) = nested_obj.get(random_field) {
-                        // 创建修改后的嵌套对象
-                        let mut modified_nested = nested_obj.clone();
-                        let new_random_value = generate_random_value(random_value)?;
-                        modified_nested.insert(random_field.clone(), new_random_value.clone());
-                        
-                        println!("🎲 随机修改字段 '{}': {} -> {}", 
-                            random_field, 
-                            random_value, 
-                            new_random_value
-                        );
-                        
-                        values.insert(key.clone(), Value::Object(modified_nested));
-                    } else {
-                        // 如果没有找到随机字段，保持原始结构
-                        values.insert(key.clone(), value.clone());
-                    }
-                } else {
-                    // 直接使用原始的键值对，保持对象结构
-                    values.insert(key.clone(), value.clone());
+/// 配置缺失或无效时返回错误（退出码为1）
+async fn run_check(matches: &ArgMatches) -> Result<ExitCode> {
+    let profile = load_profile(matches)?;
+    let config = load_config(profile.as_ref(), true)?;
+    let url_template = UrlTemplate::parse(matches.get_one::<String>("url-template").unwrap())?;
+    let client = Client::builder()
+        .timeout(Duration::from_secs(*matches.get_one::<u64>("timeout").unwrap()))
+        .user_agent(DEFAULT_USER_AGENT)
+        .build()
+        .context(t!("banner.client_failed"))?;
+
+    let mut passed = 0;
+    let mut auth_failed = false;
+    for server in &config.servers {
+        for token in &config.device_tokens {
+            match check_device(&client, &url_template, server, token).await {
+                Ok((status, elapsed)) => {
+                    passed += 1;
+                    out!("{}", t!("check.ok", server, mask_token(token), status, elapsed.as_millis()));
+                }
+                Err(e) => {
+                    auth_failed |= matches!(e, PushError::Auth(_));
+                    out!("{}", t!("check.failed", server, mask_token(token), redact_url_tokens(&e.detail())));
                 }
             }
         }
-        _ => {
-            anyhow::bail!("数据格式不正确，期望JSON对象");
-        }
-    }
-
-    // 验证是否提取到有效数据
-    if values.is_empty() {
-        anyhow::bail!("未能提取到有效的遥测数据");
     }
-
-    Ok(values)
+    let total = config.servers.len() * config.device_tokens.len();
+    out!("{}", t!("check.summary", passed, total));
+    Ok(match passed == total {
+        true => ExitCode::SUCCESS,
+        false if auth_failed => ExitCode::from(EXIT_AUTH_FAILED),
+        false => ExitCode::from(EXIT_SEND_FAILED),
+    })
 }
 
-/// 根据原始值的类型生成随机值
+/// 显示合并配置档和环境变量后的最终配置
 ///
-/// 支持数字类型的随机生成，保持原始值的数据类型
+/// 设置的优先级与发送时相同：配置档优先于环境变量和默认值。令牌只显示脱敏后的值
 ///
 /// # 参数
 ///
-/// * `original_value` - 原始值，用于确定生成随机值的类型和范围
+/// * `matches` - `config` 子命令的参数
 ///
 /// # 返回值
 ///
-/// * `Result<Value>` - 成功时返回随机生成的值，失败时返回错误信息
-///
-/// # 随机值生成规则
-///
-/// 1. 整数：生成 [1, 原值*2] 范围内的随机整数
-/// 2. 浮点数：生成 [1.0, 原值*2.0] 范围内的随机浮点数
-/// 3. 其他类型：保持原值不变
-fn generate_random_value(original_value: &Value) -> Result<Value> {
-    let mut rng = rand::thread_rng();
-    
-    match original_value {
-        Value::Number(num) => {
-            if let Some(int_val) = num.as_i64() {
-                // 整数类型：生成 [1, 原值*2] 范围内的随机整数
-                let max_val = std::cmp::max(1, int_val * 2);
-                let random_val = rng.gen_range(1..=max_val);
-                Ok(Value::Number(serde_json::Number::from(random_val)))
-            } else if let Some(float_val) = num.as_f64() {
-                // 浮点数类型：生成 [1.0, 原值*2.0] 范围内的随机浮点数
-                let max_val = if float_val > 0.0 { float_val * 2.0 } else { 100.0 };
-                let random_val = rng.gen_range(1.0..=max_val);
-                Ok(Value::Number(serde_json::Number::from_f64(random_val)
-                    .context("无法创建随机浮点数")?))
-            } else {
-                // 无法识别的数字类型，保持原值
-                Ok(original_value.clone())
-            }
-        }
-        _ => {
-            // 非数字类型，保持原值不变
-            Ok(original_value.clone())
-        }
+/// * `Result<ExitCode>` - 成功时返回0
+///
+/// # 错误
+///
+/// 配置文件格式错误、找不到指定的配置档或服务器地址缺失时返回错误
+fn run_config(matches: &ArgMatches) -> Result<ExitCode> {
+    let config_path = matches.get_one::<String>("config").unwrap();
+    let mut profile_names: Vec<String> = match Path::new(config_path).exists() {
+        true => load_config_file(config_path)?.profiles.into_keys().collect(),
+        false => Vec::new(),
+    };
+    profile_names.sort_unstable();
+    let profile_name = matches.get_one::<String>("profile");
+    let profile = load_profile(matches)?;
+    let config = load_config(profile.as_ref(), false)?;
+    let tokens: Vec<String> = config.device_tokens.iter().map(|token| mask_token(token)).collect();
+
+    // 每项设置的值和是否来自配置档
+    let setting = |value: Option<String>, id: &str| match value {
+        Some(value) => (value, true),
+        None => (send_default(id), false),
+    };
+    let profile_ref = profile.as_ref();
+    let (data_file, data_file_from_profile) = setting(profile_ref.and_then(|p| p.data_file.clone()), "data-file");
+    let (interval, interval_from_profile) =
+        setting(profile_ref.and_then(|p| p.interval.map(|v| v.to_string())), "interval");
+    let (count, count_from_profile) = setting(profile_ref.and_then(|p| p.count.map(|v| v.to_string())), "count");
+    let (random_key, random_key_from_profile) = setting(profile_ref.and_then(|p| p.random_key.clone()), "random-key");
+
+    if matches.get_flag("json") {
+        let summary = serde_json::json!({
+            "config_file": config_path,
+            "profiles": profile_names,
+            "profile": profile_name,
+            "servers": config.servers,
+            "device_tokens": tokens,
+            "data_file": data_file,
+            "interval": interval.parse::<u64>().ok(),
+            "count": count.parse::<u64>().ok(),
+            "random_key": (!random_key.is_empty()).then_some(random_key),
+        });
+        out!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match Path::new(config_path).exists() {
+        true if profile_names.is_empty() => out!("{}", t!("config.file_found", config_path, "-")),
+        true => out!("{}", t!("config.file_found", config_path, profile_names.join(", "))),
+        false => out!("{}", t!("config.file_missing", config_path)),
+    }
+    let source = profile_name.map(|name| t!("banner.from_profile", name)).unwrap_or_default();
+    let mark = |from_profile: bool| if from_profile { source.as_str() } else { "" };
+    if let Some(name) = profile_name {
+        out!("{}", t!("banner.profile", name));
+    }
+    out!("{}", t!("banner.servers", config.servers.join(", ")));
+    match tokens.is_empty() {
+        true => out!("{}", t!("banner.token", t!("config.token_unset"))),
+        false => out!("{}", t!("banner.token", tokens.join(", "))),
+    }
+    out!("{}", t!("banner.data_file", data_file, mark(data_file_from_profile)));
+    out!("{}", t!("banner.interval", interval, mark(interval_from_profile)));
+    out!("{}", t!("banner.count", count, mark(count_from_profile)));
+    if !random_key.is_empty() {
+        out!("{}", t!("config.random_key", random_key, mark(random_key_from_profile)));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// 默认的User-Agent：工具名/版本号
+const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// `--on-complete` 的详细帮助
+const ON_COMPLETE_HELP: &str = "\
+发送结束并打印统计后执行的shell命令（Unix下用 sh -c，Windows下用 cmd /C 执行），
+命令的标准输出和标准错误直接输出到当前终端。按 Ctrl-C 中断或 --dry-run 时不执行。
+
+传给命令的环境变量：
+  PUSH_ATTEMPTED    尝试发送的条数
+  PUSH_SUCCEEDED    发送成功的条数
+  PUSH_FAILED       发送失败的条数
+  PUSH_SKIPPED      跳过的空记录条数
+  PUSH_BYTES        发送的字节数
+  PUSH_ELAPSED_MS   总耗时（毫秒）
+  PUSH_LATENCY_P95_MS  延迟p95（毫秒，没有成功发送时为0）
+  PUSH_EXIT_CODE    不考虑该命令时本次运行的退出码
+  PUSH_REPORT_CSV   CSV报告路径（指定了 --report-csv 时）
+
+命令无法启动或以非0状态结束时，若本次运行原本成功，则以退出码4结束；
+原本已有发送失败时保留原来的退出码";
+
+
+/// 根据 `--flatten` 系列参数得到展开方式，未开启时返回None
+fn parse_flatten(matches: &ArgMatches) -> Option<FlattenRules> {
+    matches.get_flag("flatten").then(|| FlattenRules {
+        separator: matches.get_one::<String>("flatten-separator").unwrap().clone(),
+        arrays: matches.get_flag("flatten-arrays"),
+    })
+}
+
+/// 根据 `--rate`、`--warmup` 和 `--warmup-curve` 参数得到发送速率计划，未指定速率时返回None
+///
+/// # 错误
+///
+/// 速率不是正数或预热时长为负数时返回错误
+fn parse_rate_schedule(matches: &ArgMatches) -> Result<Option<RateSchedule>> {
+    let Some(rate) = matches.get_one::<String>("rate") else {
+        return Ok(None);
+    };
+    let rate: f64 = rate.parse().context(t!("arg.rate_invalid"))?;
+    if !rate.is_finite() || rate <= 0.0 {
+        anyhow::bail!(t!("arg.rate_not_positive"));
+    }
+    let warmup: f64 = match matches.get_one::<String>("warmup") {
+        Some(warmup) => warmup.parse().context(t!("arg.warmup_invalid"))?,
+        None => 0.0,
+    };
+    if !warmup.is_finite() || warmup < 0.0 {
+        anyhow::bail!(t!("arg.warmup_negative"));
     }
+    let curve = match matches.get_one::<String>("warmup-curve").unwrap().as_str() {
+        "exponential" => WarmupCurve::Exponential,
+        _ => WarmupCurve::Linear,
+    };
+    Ok(Some(RateSchedule { rate, warmup, curve }))
 }
 
+/// 根据 `--non-finite` 参数得到非有限值的处理策略
+fn parse_non_finite(matches: &ArgMatches) -> NonFinitePolicy {
+    match matches.get_one::<String>("non-finite").unwrap().as_str() {
+        "null" => NonFinitePolicy::Null,
+        "error" => NonFinitePolicy::Error,
+        "clamp" => NonFinitePolicy::Clamp,
+        _ => NonFinitePolicy::Drop,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_flags_work_with_and_without_the_send_subcommand() {
+        let cli = build_cli();
+        cli.clone().debug_assert();
 
+        // 旧的调用方式：不带子命令，发送参数写在根命令上
+        let flat = cli.clone().try_get_matches_from(["push", "-i", "5", "-c", "0", "-f", "data.json"]).unwrap();
+        assert!(flat.subcommand().is_none());
+        assert_eq!(flat.get_one::<String>("count").unwrap(), "0");
+
+        // 全局参数写在子命令前后都可以
+        let matches = cli
+            .clone()
+            .try_get_matches_from(["push", "-p", "dev", "send", "-c", "0", "--log-format", "json"])
+            .unwrap();
+        let (name, send) = matches.subcommand().unwrap();
+        assert_eq!(name, "send");
+        assert_eq!(send.get_one::<String>("count").unwrap(), "0");
+        assert_eq!(send.get_one::<String>("profile").unwrap(), "dev");
+        assert_eq!(send.get_one::<String>("log-format").unwrap(), "json");
+
+        let matches = cli.clone().try_get_matches_from(["push", "check", "--profile", "prod", "--timeout", "3"]).unwrap();
+        let (_, check) = matches.subcommand().unwrap();
+        assert_eq!(check.get_one::<String>("profile").unwrap(), "prod");
+        assert_eq!(*check.get_one::<u64>("timeout").unwrap(), 3);
+
+        // 其它子命令只接受自己的参数
+        assert!(cli.clone().try_get_matches_from(["push", "config", "--count", "3"]).is_err());
+        assert_eq!(send_default("interval"), "5");
+        assert_eq!(send_default("random-key"), "");
+    }
+}
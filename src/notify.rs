@@ -0,0 +1,470 @@
+//! 运行结果的对外通知：webhook回调、`--notify-url` 运行通知和 `--stats-to-tb` 统计遥测
+//!
+//! 通知都在后台或运行结束时发出，失败只打印警告，不影响发送和退出码
+
+use chrono::Local;
+use clap::ArgMatches;
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+use crate::config::ProfileSettings;
+use crate::error::{PushError, PushResult};
+use crate::exit::EXIT_SUCCESS;
+use crate::reload::apply_profile_reload;
+use crate::runner::SendEvents;
+use crate::schedule::SendIntervals;
+use crate::sender::{UrlTemplate, redact_url_tokens};
+use crate::stats::RunStats;
+use crate::telemetry::TelemetryData;
+
+/// 异步POST一个结果摘要到webhook
+///
+/// 回调在后台任务中执行，不阻塞也不影响主发送流程，失败时只打印警告
+///
+/// # 参数
+///
+/// * `tasks` - 后台回调任务集合，程序退出前会等待其中的任务完成
+/// * `client` - HTTP客户端实例
+/// * `url` - webhook地址
+/// * `payload` - 要POST的JSON摘要
+pub fn notify_webhook(tasks: &mut JoinSet<()>, client: &Client, url: &str, payload: Value) {
+    // 回收已经完成的回调任务，避免长时间运行时任务句柄无限累积
+    while tasks.try_join_next().is_some() {}
+
+    let client = client.clone();
+    let url = url.to_string();
+    tasks.spawn(async move {
+        match client.post(&url).json(&payload).timeout(NOTIFY_TIMEOUT).send().await {
+            Ok(response) if !response.status().is_success() => {
+                warn!("{}", t!("webhook.bad_status", url, response.status()));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("{}", t!("webhook.failed", url, e));
+            }
+        }
+    });
+}
+
+/// webhook回调和运行通知的请求超时，避免回调地址无响应时程序迟迟不能退出
+pub const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `--stats-to-tb` 推送的统计遥测的字段名前缀，与 `--on-complete` 的 `PUSH_*` 环境变量对应
+pub const STATS_TELEMETRY_PREFIX: &str = "push_";
+
+/// `--notify-url` 运行通知
+///
+/// 运行结束（包括中断和因连续失败中止）时发送一次，指定 `--notify-on-failures` 时
+/// 运行期间每累计N次失败额外发送一次。通知失败只打印警告，不影响退出码
+pub struct Notifier {
+    /// 通知地址
+    url: String,
+    /// 自定义负载模板，为None时发送默认的JSON负载
+    template: Option<String>,
+    /// 每累计多少次失败发送一次通知
+    every_failures: Option<u64>,
+    /// 本次运行的标识，由开始时间和进程号组成
+    pub run_id: String,
+}
+
+impl Notifier {
+    /// 创建运行通知并校验模板
+    ///
+    /// # 参数
+    ///
+    /// * `url` - 通知地址
+    /// * `template` - 自定义负载模板
+    /// * `every_failures` - 每累计多少次失败发送一次通知
+    ///
+    /// # 错误
+    ///
+    /// 当模板包含未知占位符，或渲染后不是有效的JSON时返回错误
+    pub fn new(url: &str, template: Option<String>, every_failures: Option<u64>) -> PushResult<Self> {
+        let notifier = Notifier {
+            url: url.to_string(),
+            template,
+            every_failures,
+            run_id: format!("{}-{}", Local::now().format("%Y%m%d%H%M%S"), std::process::id()),
+        };
+        // 用结束通知和运行期间的失败通知各渲染一次，提前暴露模板错误
+        let stats = RunStats::new(false);
+        notifier.payload("completed", &stats, Duration::ZERO, Some((EXIT_SUCCESS, "")))?;
+        if every_failures.is_some() {
+            notifier.payload("failing", &stats, Duration::ZERO, None)?;
+        }
+        Ok(notifier)
+    }
+
+    /// 失败数从 `failed_before` 增加到 `failed` 时是否跨过了 `every_failures` 的整数倍
+    ///
+    /// 合并时序发送失败时整批条数一起计入失败数，不能只看当前失败数是否恰好是整数倍
+    pub fn failures_crossed(&self, failed_before: u64, failed: u64) -> bool {
+        self.every_failures.is_some_and(|every| failed_before / every != failed / every)
+    }
+
+    /// 生成通知的占位符取值，未指定模板时直接作为负载发送
+    ///
+    /// # 参数
+    ///
+    /// * `status` - 运行状态：completed、failed、aborted、interrupted 或 failing
+    /// * `stats` - 当前的运行统计
+    /// * `elapsed` - 已运行时长
+    /// * `exit` - 运行结束时的退出码及原因，运行期间的通知为None
+    fn variables(
+        &self,
+        status: &str,
+        stats: &RunStats,
+        elapsed: Duration,
+        exit: Option<(u8, &str)>,
+    ) -> serde_json::Map<String, Value> {
+        let duration_secs = (elapsed.as_secs_f64() * 10.0).round() / 10.0;
+        let mut text = t!(
+            "notify.text",
+            self.run_id,
+            t!(&format!("notify.status_{}", status)),
+            stats.succeeded,
+            stats.failed,
+            duration_secs
+        );
+        if let Some(ref error) = stats.last_error {
+            text.push_str(&t!("notify.text_last_error", error));
+        }
+        let mut vars = serde_json::Map::new();
+        vars.insert("run_id".to_string(), Value::from(self.run_id.as_str()));
+        vars.insert("status".to_string(), Value::from(status));
+        vars.insert("attempted".to_string(), Value::from(stats.attempted));
+        vars.insert("succeeded".to_string(), Value::from(stats.succeeded));
+        vars.insert("failed".to_string(), Value::from(stats.failed));
+        vars.insert("skipped".to_string(), Value::from(stats.skipped));
+        vars.insert("first_error".to_string(), serde_json::json!(stats.first_error));
+        vars.insert("last_error".to_string(), serde_json::json!(stats.last_error));
+        vars.insert("duration_secs".to_string(), Value::from(duration_secs));
+        vars.insert("exit_code".to_string(), serde_json::json!(exit.map(|(code, _)| code)));
+        vars.insert("exit_reason".to_string(), serde_json::json!(exit.map(|(_, reason)| reason)));
+        vars.insert("text".to_string(), Value::from(text));
+        vars
+    }
+
+    /// 生成通知负载
+    ///
+    /// # 错误
+    ///
+    /// 当模板包含未知占位符，或渲染后不是有效的JSON时返回错误
+    fn payload(
+        &self,
+        status: &str,
+        stats: &RunStats,
+        elapsed: Duration,
+        exit: Option<(u8, &str)>,
+    ) -> PushResult<Value> {
+        let vars = self.variables(status, stats, elapsed, exit);
+        match self.template {
+            Some(ref template) => {
+                let rendered = render_notify_template(template, &vars)?;
+                serde_json::from_str(&rendered).map_err(|e| {
+                    PushError::ConfigInvalid(format!("{}: {}", t!("notify.template_invalid", rendered), e))
+                })
+            }
+            None => Ok(Value::Object(vars)),
+        }
+    }
+
+    /// 在后台发送运行期间的通知，不阻塞发送循环
+    pub fn spawn(
+        &self,
+        tasks: &mut JoinSet<()>,
+        client: &Client,
+        status: &str,
+        stats: &RunStats,
+        elapsed: Duration,
+        exit: Option<(u8, &str)>,
+    ) {
+        match self.payload(status, stats, elapsed, exit) {
+            Ok(payload) => {
+                while tasks.try_join_next().is_some() {}
+                tasks.spawn(post_notification(client.clone(), self.url.clone(), payload));
+            }
+            Err(e) => warn!("{}", t!("notify.failed", self.url, e)),
+        }
+    }
+
+    /// 发送通知并等待完成
+    pub async fn send(&self, client: &Client, status: &str, stats: &RunStats, elapsed: Duration, exit: Option<(u8, &str)>) {
+        match self.payload(status, stats, elapsed, exit) {
+            Ok(payload) => post_notification(client.clone(), self.url.clone(), payload).await,
+            Err(e) => warn!("{}", t!("notify.failed", self.url, e)),
+        }
+    }
+}
+
+/// 命令行工具对发送循环事件的处理：webhook回调、运行期间的失败通知和SIGHUP热更新
+pub struct CliEvents<'a> {
+    /// HTTP客户端实例
+    pub client: &'a Client,
+    /// webhook地址
+    pub webhook: Option<&'a str>,
+    /// webhook是否每轮回调一次（否则每条回调一次）
+    pub webhook_per_round: bool,
+    /// 运行通知
+    pub notifier: Option<&'a Notifier>,
+    /// 后台回调任务，程序退出前等待其完成
+    pub tasks: JoinSet<()>,
+    /// SIGHUP重新读取的配置档
+    pub reload_rx: Option<watch::Receiver<ProfileSettings>>,
+    /// 命令行参数，命令行上指定的参数不被配置档覆盖
+    pub matches: &'a ArgMatches,
+    /// 配置档名称
+    pub profile_name: &'a str,
+    /// 当前生效的配置档
+    pub current_profile: ProfileSettings,
+}
+
+impl SendEvents for CliEvents<'_> {
+    fn item_sent(&mut self, round: u64, index: usize, stats: &RunStats, telemetry: &TelemetryData) {
+        if let (Some(url), false) = (self.webhook, self.webhook_per_round) {
+            notify_webhook(
+                &mut self.tasks,
+                self.client,
+                url,
+                serde_json::json!({
+                    "event": "item_sent",
+                    "round": round,
+                    "item_index": index,
+                    "sent_count": stats.succeeded,
+                    "ts": telemetry.ts,
+                    "time": telemetry.time,
+                    "keys": telemetry.values.keys().collect::<Vec<_>>(),
+                }),
+            );
+        }
+    }
+
+    fn failed(&mut self, failed_before: u64, stats: &RunStats, elapsed: Duration) {
+        if let Some(notifier) = self.notifier
+            && notifier.failures_crossed(failed_before, stats.failed)
+        {
+            notifier.spawn(&mut self.tasks, self.client, "failing", stats, elapsed, None);
+        }
+    }
+
+    fn round_completed(&mut self, round: u64, succeeded: u64, failed: u64, stats: &RunStats) {
+        if let (Some(url), true) = (self.webhook, self.webhook_per_round) {
+            notify_webhook(
+                &mut self.tasks,
+                self.client,
+                url,
+                serde_json::json!({
+                    "event": "round_completed",
+                    "round": round,
+                    "succeeded": succeeded,
+                    "failed": failed,
+                    "sent_count": stats.succeeded,
+                    "time": Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                }),
+            );
+        }
+    }
+
+    fn reload(&mut self, intervals: &mut SendIntervals, count: &mut u64, random_key: &mut Option<String>) {
+        if let Some(rx) = self.reload_rx.as_mut()
+            && rx.has_changed().unwrap_or(false)
+        {
+            let reloaded = rx.borrow_and_update().clone();
+            apply_profile_reload(
+                self.matches,
+                self.profile_name,
+                &mut self.current_profile,
+                reloaded,
+                intervals,
+                count,
+                random_key,
+            );
+        }
+    }
+}
+
+/// POST一条运行通知，失败只打印警告
+async fn post_notification(client: Client, url: String, payload: Value) {
+    match client.post(&url).json(&payload).timeout(NOTIFY_TIMEOUT).send().await {
+        Ok(response) if !response.status().is_success() => {
+            warn!("{}", t!("notify.bad_status", url, response.status()));
+        }
+        Ok(_) => info!("{}", t!("notify.sent", url)),
+        Err(e) => warn!("{}", t!("notify.failed", url, e)),
+    }
+}
+
+/// 把通知模板中的 `{{name}}` 占位符替换为对应的值
+///
+/// 字符串按JSON字符串内容转义（不含两侧引号），因此应写在模板的引号中；
+/// 数字原样写入，值为空时替换为空串
+///
+/// # 参数
+///
+/// * `template` - 通知模板
+/// * `vars` - 占位符取值
+///
+/// # 错误
+///
+/// 当占位符没有结尾的 `}}` 或名称未知时返回错误
+pub fn render_notify_template(template: &str, vars: &serde_json::Map<String, Value>) -> PushResult<String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| PushError::ConfigInvalid(t!("notify.template_unclosed")))?;
+        let name = after[..end].trim();
+        match vars.get(name).ok_or_else(|| PushError::ConfigInvalid(t!("notify.template_unknown", name)))? {
+            Value::String(text) => {
+                // 字符串总能序列化为JSON
+                let quoted = serde_json::to_string(text).unwrap();
+                rendered.push_str(&quoted[1..quoted.len() - 1]);
+            }
+            Value::Null => {}
+            other => rendered.push_str(&other.to_string()),
+        }
+        rest = &after[end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// 把 `--summary-json` 汇总展开为推回ThingsBoard的统计遥测
+///
+/// 字段名统一为 [`STATS_TELEMETRY_PREFIX`] 加汇总中的字段名，嵌套字段用下划线连接，
+/// 带单位的字段以单位结尾（如 `push_latency_p95_ms`）；汇总中为null的字段（如没有延迟样本）不输出
+///
+/// # 参数
+///
+/// * `summary` - 运行结束时的JSON汇总
+pub fn stats_telemetry(summary: &Value) -> serde_json::Map<String, Value> {
+    let mut values = serde_json::Map::new();
+    let mut put = |name: &str, value: &Value| {
+        if !value.is_null() {
+            values.insert(format!("{}{}", STATS_TELEMETRY_PREFIX, name), value.clone());
+        }
+    };
+    for name in ["attempted", "sent", "generated", "failed", "skipped", "requeued", "recovered"] {
+        put(name, &summary["counts"][name]);
+    }
+    for name in ["success_rate", "bytes", "elapsed_ms", "rate", "timestamps_adjusted", "throttled", "throttle_wait_ms"] {
+        put(name, &summary[name]);
+    }
+    for quantile in ["min", "mean", "p50", "p95", "p99", "max"] {
+        put(&format!("latency_{}_ms", quantile), &summary["latency_ms"][quantile]);
+    }
+    for name in ["interrupted", "dry_run", "exit_code", "exit_reason"] {
+        put(name, &summary[name]);
+    }
+    for name in ["device", "data_file"] {
+        put(name, &summary["config"][name]);
+    }
+    // 随机种子可能超出ThingsBoard长整型的范围，按字符串保存
+    if let Some(seed) = summary["seed"].as_u64() {
+        put("seed", &Value::from(seed.to_string()));
+    }
+    values
+}
+
+/// 把运行统计作为一条遥测推送到 `--stats-to-tb` 指定的压测监控设备，失败只打印警告
+///
+/// # 参数
+///
+/// * `client` - HTTP客户端实例
+/// * `url_template` - 设备API请求地址模板
+/// * `server` - ThingsBoard服务器地址
+/// * `token` - 压测监控设备的访问令牌
+/// * `summary` - 运行结束时的JSON汇总
+pub async fn push_stats_to_tb(client: &Client, url_template: &UrlTemplate, server: &str, token: &str, summary: &Value) {
+    let url = url_template.render(server, token, "telemetry");
+    let masked_url = redact_url_tokens(&url);
+    let payload = serde_json::json!({
+        "ts": chrono::Utc::now().timestamp_millis(),
+        "values": stats_telemetry(summary),
+    });
+    match client.post(&url).json(&payload).timeout(NOTIFY_TIMEOUT).send().await {
+        Ok(response) if !response.status().is_success() => {
+            warn!("{}", t!("stats_tb.bad_status", masked_url, response.status()));
+        }
+        Ok(_) => info!("{}", t!("stats_tb.sent", masked_url)),
+        Err(e) => warn!("{}", t!("stats_tb.failed", masked_url, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notify_template_escapes_values_and_checks_placeholders() {
+        let vars = serde_json::json!({"text": "运行 \"a\"\n失败", "failed": 3, "exit_code": null});
+        let Value::Object(vars) = vars else { unreachable!() };
+        let rendered =
+            render_notify_template(r#"{"text": "{{text}}", "n": {{ failed }}, "code": "{{exit_code}}"}"#, &vars).unwrap();
+        let payload: Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(payload["text"], "运行 \"a\"\n失败");
+        assert_eq!(payload["n"], 3);
+        assert_eq!(payload["code"], "");
+        assert!(render_notify_template("{{unknown}}", &vars).is_err());
+        assert!(render_notify_template("{{text", &vars).is_err());
+
+        assert!(Notifier::new("http://localhost", Some(r#"{"code": {{exit_code}}}"#.to_string()), None).is_ok());
+        assert!(Notifier::new("http://localhost", Some(r#"{"code": {{exit_code}}}"#.to_string()), Some(5)).is_err());
+    }
+
+    #[test]
+    fn failure_notifications_fire_once_per_multiple_crossed() {
+        let notifier = Notifier::new("http://127.0.0.1:9/notify", None, Some(10)).unwrap();
+        // 逐条发送：每次失败加1，恰好到达整数倍时通知
+        let fired: Vec<u64> = (1..=30).filter(|&failed| notifier.failures_crossed(failed - 1, failed)).collect();
+        assert_eq!(fired, [10, 20, 30]);
+        // 合并发送：每批失败加7，超过阈值之后不会每批都通知
+        let fired: Vec<u64> =
+            (1..=6).map(|batch| batch * 7).filter(|&failed| notifier.failures_crossed(failed - 7, failed)).collect();
+        assert_eq!(fired, [14, 21, 35, 42]);
+        assert!(!Notifier::new("http://127.0.0.1:9/notify", None, None).unwrap().failures_crossed(9, 10));
+    }
+
+    #[test]
+    fn stats_telemetry_flattens_summary_with_prefix() {
+        let summary = serde_json::json!({
+            "schema": 1,
+            "interrupted": false,
+            "dry_run": false,
+            "counts": {"attempted": 10, "sent": 9, "generated": 9, "failed": 1, "skipped": 0},
+            "success_rate": 0.9,
+            "bytes": 1234,
+            "elapsed_ms": 5000,
+            "rate": 1.8,
+            "failures": {"HTTP 500": 1},
+            "latency_ms": {"min": 1.0, "mean": 2.5, "p50": 2.0, "p95": 4.0, "p99": 5.0, "max": 5.5},
+            "timestamps_adjusted": 3,
+            "seed": 18446744073709551615u64,
+            "config": {"device": "abcd****qrst", "data_file": "data.json", "servers": ["http://a"]},
+            "exit_code": 2,
+            "exit_reason": "1 条发送失败",
+        });
+        let values = stats_telemetry(&summary);
+        assert_eq!(values["push_attempted"], 10);
+        assert_eq!(values["push_failed"], 1);
+        assert_eq!(values["push_success_rate"], 0.9);
+        assert_eq!(values["push_latency_p95_ms"], 4.0);
+        assert_eq!(values["push_timestamps_adjusted"], 3);
+        assert_eq!(values["push_exit_code"], 2);
+        assert_eq!(values["push_device"], "abcd****qrst");
+        assert_eq!(values["push_seed"], "18446744073709551615");
+        assert!(values.keys().all(|key| key.starts_with(STATS_TELEMETRY_PREFIX)));
+        // 失败原因的键不是固定的字段名，不推送
+        assert!(!values.contains_key("push_failures"));
+
+        // 没有延迟样本和随机种子时不输出对应字段
+        let empty = serde_json::json!({"counts": {"attempted": 0}, "latency_ms": null, "seed": null});
+        let values = stats_telemetry(&empty);
+        assert!(!values.keys().any(|key| key.starts_with("push_latency_")));
+        assert!(!values.contains_key("push_seed"));
+    }
+}
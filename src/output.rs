@@ -0,0 +1,663 @@
+//! 日志输出：控制台和JSON格式、纯文本模式、进度条和syslog
+
+use serde_json::Value;
+use std::borrow::Cow;
+use std::env;
+use std::fs;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use chrono::Local;
+use indicatif::{ProgressBar, ProgressStyle};
+use tracing::{Level, info, warn};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::{self, FormatEvent, FormatFields};
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriterExt};
+use tracing_subscriber::fmt::FmtContext;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::registry::LookupSpan;
+use tokio::time::Duration;
+use crate::error::{PushError, PushResult};
+use crate::sender::redact_url_tokens;
+use crate::tui::{TUI_ACTIVE, TuiLayer};
+
+/// 逐条发送过程信息使用的日志target，`-q` 时过滤掉
+pub const ITEM_TARGET: &str = "push_message_thingsboard::item";
+
+/// 请求/响应调试信息使用的日志target，`-v` 时开启
+pub const HTTP_TARGET: &str = "push_message_thingsboard::http";
+
+/// 心跳信息使用的日志target，`-q`/`-qq` 时仍然输出
+pub const HEARTBEAT_TARGET: &str = "push_message_thingsboard::heartbeat";
+
+/// 日志输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// 带emoji的人类可读格式（默认）
+    Human,
+    /// 每行一个JSON对象的结构化格式
+    Json,
+}
+
+/// 收集日志事件的消息和结构化字段
+#[derive(Default)]
+pub struct EventFields {
+    /// 人类可读消息
+    message: String,
+    /// 除消息外的其它字段
+    pub fields: serde_json::Map<String, Value>,
+}
+
+impl tracing::field::Visit for EventFields {
+    fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.insert(field.name().to_string(), Value::from(value));
+        }
+    }
+
+    fn record_u64(&mut self, field: &tracing::field::Field, value: u64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &tracing::field::Field, value: i64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &tracing::field::Field, value: f64) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &tracing::field::Field, value: bool) {
+        self.fields.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+        let text = format!("{:?}", value);
+        if field.name() == "message" {
+            self.message = text;
+        } else if !field.name().starts_with("log.") {
+            self.fields.insert(field.name().to_string(), Value::from(text));
+        }
+    }
+}
+
+/// 纯文本输出模式：用ASCII标签代替emoji，并且不输出任何颜色
+///
+/// 由 `--plain` 开启，设置了 `NO_COLOR` 或标准输出不是终端时自动开启
+pub static PLAIN_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// 纯文本模式下emoji到ASCII标签的对照表
+pub const EMOJI_TAGS: &[(&str, &str)] = &[
+    ("✅", "[OK]"),
+    ("❌", "[ERR]"),
+    ("⚠", "[WARN]"),
+    ("🎲", "[RAND]"),
+    ("📤", "[SENT]"),
+    ("📊", "[DATA]"),
+    ("🕒", "[TIME]"),
+    ("⏳", "[WAIT]"),
+    ("🎉", "[DONE]"),
+    ("🚀", "[START]"),
+    ("🔍", "[DETECT]"),
+    ("📁", "[PROFILE]"),
+    ("🔑", "[TOKEN]"),
+    ("⚖", "[LB]"),
+    ("♻", "[LB]"),
+    ("🧭", "[GEO]"),
+    ("💉", "[INJECT]"),
+    ("🔢", "[SEQ]"),
+    ("🔔", "[HOOK]"),
+    ("🔏", "[SIGN]"),
+    ("🏷", "[ATTR]"),
+    ("🧪", "[DRY]"),
+    ("⛔", "[STOP]"),
+    ("📦", "[BYTES]"),
+    ("⏱", "[LATENCY]"),
+    ("🕰", "[CLOCK]"),
+    ("📝", "[AUDIT]"),
+    ("🔄", "[RELOAD]"),
+    ("🚦", "[EXIT]"),
+    ("📄", "[REPORT]"),
+    ("📈", "[PROGRESS]"),
+    ("🚦", "[RATE]"),
+    ("🧾", "[SUMMARY]"),
+    ("🔀", "[COND]"),
+    ("📣", "[NOTIFY]"),
+    ("🪪", "[UA]"),
+    ("🪞", "[TEE]"),
+    ("⏲", "[GROUP]"),
+    ("🧷", "[IDEMP]"),
+    ("📡", "[METRICS]"),
+    ("📨", "[RESP]"),
+    ("🪝", "[HOOK]"),
+    ("💓", "[HEARTBEAT]"),
+    ("⏭", "[SKIP]"),
+    ("📋", "[PLAN]"),
+    ("🗓", "[PLAN]"),
+    ("➡", "[REQ]"),
+    ("⬅", "[RESP]"),
+    ("⏸", "[PAUSE]"),
+    ("📂", "[DIR]"),
+    ("📏", "[RANGE]"),
+    ("🔎", "[CHECK]"),
+    ("💡", "[HINT]"),
+    ("🌙", "[DAEMON]"),
+    ("💾", "[STATE]"),
+    ("⏩", "[RESUME]"),
+    ("↪", "[REDIRECT]"),
+    ("🐢", "[THROTTLE]"),
+    ("🔃", "[WEIGHT]"),
+    ("🔗", "[URL]"),
+    ("🔌", "[CONN]"),
+    ("🔁", "[REQUEUE]"),
+    ("⚙", "[CONFIG]"),
+];
+
+/// 按当前输出模式处理要显示的文本
+///
+/// 纯文本模式下把emoji替换为ASCII标签，其余文字和顺序保持不变，
+/// 这样基于文字部分的grep脚本在两种模式下都能匹配
+pub fn display_text(text: &str) -> Cow<'_, str> {
+    if !PLAIN_OUTPUT.load(Ordering::Relaxed) {
+        return Cow::Borrowed(text);
+    }
+    // 先去掉emoji变体选择符，再按对照表替换
+    let mut plain = text.replace('\u{FE0F}', "");
+    for (emoji, tag) in EMOJI_TAGS {
+        if plain.contains(emoji) {
+            plain = plain.replace(emoji, tag);
+        }
+    }
+    Cow::Owned(plain)
+}
+
+/// 控制台日志格式
+///
+/// 保留原有的emoji提示风格，在每行前加上ISO时间戳和日志级别，
+/// 并对消息中出现在URL里的设备令牌做脱敏处理。结构化字段只在JSON格式中输出
+pub struct ConsoleFormat;
+
+impl<S, N> FormatEvent<S, N> for ConsoleFormat
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+        writeln!(
+            writer,
+            "{} {:>5} {}",
+            Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+            event.metadata().level(),
+            display_text(&redact_url_tokens(&fields.message))
+        )
+    }
+}
+
+/// JSON日志格式
+///
+/// 每个带 `event` 字段的日志事件输出为一行JSON，字段结构见 `--log-format` 的帮助。
+/// 不带 `event` 字段的纯展示信息（如启动横幅的明细行）不输出
+pub struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        _ctx: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &tracing::Event<'_>,
+    ) -> std::fmt::Result {
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+        if !fields.fields.contains_key("event") {
+            return Ok(());
+        }
+
+        let mut record = serde_json::Map::new();
+        record.insert(
+            "ts".to_string(),
+            Value::from(Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string()),
+        );
+        record.insert("level".to_string(), Value::from(event.metadata().level().as_str()));
+        for (key, value) in fields.fields {
+            let value = match value {
+                Value::String(text) => Value::String(redact_url_tokens(&text)),
+                other => other,
+            };
+            record.insert(key, value);
+        }
+        record.insert("message".to_string(), Value::from(redact_url_tokens(&fields.message)));
+        writeln!(writer, "{}", Value::Object(record))
+    }
+}
+
+/// 当前显示中的进度条，日志输出时先暂时隐藏进度条，避免两者互相覆盖
+pub static PROGRESS_BAR: OnceLock<ProgressBar> = OnceLock::new();
+
+/// 与进度条协同的输出流
+///
+/// 进度条显示期间，每次写入都通过 [`ProgressBar::suspend`] 进行，
+/// 使日志行出现在进度条上方而不是把它冲掉；TUI仪表盘占用终端期间直接丢弃
+pub struct ProgressAwareWriter<W> {
+    /// 实际的输出流
+    inner: W,
+}
+
+impl<W: std::io::Write> std::io::Write for ProgressAwareWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if TUI_ACTIVE.load(Ordering::Relaxed) {
+            return Ok(buf.len());
+        }
+        match PROGRESS_BAR.get() {
+            Some(bar) if !bar.is_finished() => bar.suspend(|| self.inner.write(buf)),
+            _ => self.inner.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 发送进度显示
+///
+/// 总数已知时，终端下显示原地刷新的进度条；输出不是终端（如CI日志）时
+/// 退化为定期打印的纯文本进度行
+pub enum Progress {
+    /// 不显示进度（总数未知、静默模式或JSON日志模式）
+    Hidden,
+    /// 终端中的进度条
+    Bar(ProgressBar),
+    /// 定期打印的纯文本进度
+    Plain {
+        /// 总发送条数
+        total: u64,
+        /// 已完成条数
+        done: u64,
+        /// 失败条数
+        failed: u64,
+        /// 开始时刻
+        started: Instant,
+        /// 上一次打印的时刻
+        last_print: Instant,
+    },
+}
+
+/// 纯文本进度行的打印间隔
+pub const PLAIN_PROGRESS_INTERVAL: Duration = Duration::from_secs(10);
+
+impl Progress {
+    /// 根据运行模式创建进度显示
+    ///
+    /// # 参数
+    ///
+    /// * `total` - 总发送条数，None表示无限循环
+    /// * `enabled` - 当前输出模式是否允许显示进度
+    pub fn new(total: Option<u64>, enabled: bool) -> Self {
+        let Some(total) = total.filter(|_| enabled) else {
+            return Progress::Hidden;
+        };
+        if !std::io::stdout().is_terminal() {
+            let now = Instant::now();
+            return Progress::Plain {
+                total,
+                done: 0,
+                failed: 0,
+                started: now,
+                last_print: now,
+            };
+        }
+
+        let bar = ProgressBar::new(total);
+        // 纯文本模式下不使用颜色
+        let bar_style = if PLAIN_OUTPUT.load(Ordering::Relaxed) {
+            "{bar:40}"
+        } else {
+            "{bar:40.cyan/blue}"
+        };
+        let template = t!("progress.bar", bar_style);
+        bar.set_style(
+            // 模板中的占位符都是固定的，总是有效
+            ProgressStyle::with_template(&template).unwrap().progress_chars("=> "),
+        );
+        bar.set_message("0");
+        let _ = PROGRESS_BAR.set(bar.clone());
+        Progress::Bar(bar)
+    }
+
+    /// 记录一条发送完成
+    pub fn record(&mut self, success: bool) {
+        match self {
+            Progress::Hidden => {}
+            Progress::Bar(bar) => {
+                if !success {
+                    let failed = bar.message().parse::<u64>().unwrap_or(0) + 1;
+                    bar.set_message(failed.to_string());
+                }
+                bar.inc(1);
+            }
+            Progress::Plain {
+                total,
+                done,
+                failed,
+                started,
+                last_print,
+            } => {
+                *done += 1;
+                if !success {
+                    *failed += 1;
+                }
+                if last_print.elapsed() >= PLAIN_PROGRESS_INTERVAL || *done == *total {
+                    *last_print = Instant::now();
+                    let elapsed = started.elapsed().as_secs_f64();
+                    let rate = if elapsed > 0.0 { *done as f64 / elapsed } else { 0.0 };
+                    let eta = if rate > 0.0 { (*total - *done) as f64 / rate } else { 0.0 };
+                    info!(
+                        "{}",
+                        t!(
+                            "progress.plain",
+                            done,
+                            total,
+                            format!("{:.1}", *done as f64 * 100.0 / *total as f64),
+                            format!("{:.1}", rate),
+                            failed,
+                            format!("{:.0}", eta)
+                        )
+                    );
+                }
+            }
+        }
+    }
+
+    /// 结束进度显示，在打印汇总之前调用
+    pub fn finish(&self) {
+        if let Progress::Bar(bar) = self {
+            bar.finish();
+        }
+    }
+}
+
+/// 把秒数格式化为 `1h02m03s` 形式的剩余时间
+pub fn format_eta(secs: u64) -> String {
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+/// 初始化日志系统
+///
+/// 设置了 `RUST_LOG` 时按其过滤（例如 `RUST_LOG=push=debug`），否则根据 `-q`/`-v` 的次数
+/// 选择过滤级别。人类可读格式下INFO级别输出到stdout，其余级别（警告、错误、调试）输出到stderr，
+/// 保证stdout仍然可以用于管道；JSON格式全部输出到stderr。`stdout_reserved` 时INFO级别也输出到stderr，
+/// stdout留给 `--summary-json -`。指定了日志文件时全部写入该文件。指定 `--log-target syslog` 时
+/// 以JSON格式发往本地syslog，套接字不可用时退回stderr并警告一次。
+/// 结构化事件同时转交给 [`TuiLayer`]，供 `--tui` 仪表盘使用
+///
+/// # 参数
+///
+/// * `quiet` - `-q` 出现的次数
+/// * `verbose` - `-v` 出现的次数
+/// * `log_format` - 日志输出格式
+/// * `log_file` - 可选的日志文件路径
+/// * `stdout_reserved` - stdout是否保留给其它输出，为true时日志一律不写stdout
+/// * `syslog` - 日志发往syslog时的配置
+///
+/// # 错误
+///
+/// 当日志文件无法打开时返回错误
+pub fn init_logging(
+    quiet: u8,
+    verbose: u8,
+    log_format: LogFormat,
+    log_file: Option<&str>,
+    stdout_reserved: bool,
+    syslog: Option<&SyslogTarget>,
+) -> PushResult<()> {
+    let directives = match (quiet, verbose) {
+        (0, 0) => "warn,push_message_thingsboard=info".to_string(),
+        (1, _) => format!("warn,push_message_thingsboard=info,{}=warn", ITEM_TARGET),
+        (_, 0) => format!("error,{}=info", HEARTBEAT_TARGET),
+        (_, 1) => "warn,push_message_thingsboard=debug".to_string(),
+        _ => "warn,push_message_thingsboard=trace,reqwest=trace,hyper=trace,hyper_util=trace".to_string(),
+    };
+    let filter = match env::var("RUST_LOG") {
+        Ok(spec) if !spec.is_empty() => EnvFilter::new(spec),
+        _ => EnvFilter::new(directives),
+    };
+
+    let mut syslog_unavailable = None;
+    let syslog_writer = syslog.and_then(|target| match target.connect() {
+        Ok(writer) => Some(writer),
+        Err(e) => {
+            syslog_unavailable = Some(e);
+            None
+        }
+    });
+    let writer = match (syslog_writer, log_file, log_format) {
+        (Some(writer), _, _) => writer,
+        (None, Some(path), _) => {
+            let file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|source| PushError::Io { message: t!("log.open_failed", path), source })?;
+            BoxMakeWriter::new(Mutex::new(file))
+        }
+        (None, None, LogFormat::Json) => BoxMakeWriter::new(|| ProgressAwareWriter { inner: std::io::stderr() }),
+        (None, None, LogFormat::Human) if stdout_reserved => {
+            BoxMakeWriter::new(|| ProgressAwareWriter { inner: std::io::stderr() })
+        }
+        (None, None, LogFormat::Human) => BoxMakeWriter::new(
+            (|| ProgressAwareWriter { inner: std::io::stderr() })
+                .with_filter(|meta: &tracing::Metadata<'_>| *meta.level() != Level::INFO)
+                .or_else(|| ProgressAwareWriter { inner: std::io::stdout() }),
+        ),
+    };
+    let builder = tracing_subscriber::fmt().with_env_filter(filter).with_writer(writer);
+    match log_format {
+        LogFormat::Human => builder.event_format(ConsoleFormat).finish().with(TuiLayer).init(),
+        LogFormat::Json => builder.event_format(JsonFormat).finish().with(TuiLayer).init(),
+    }
+    if let Some(e) = syslog_unavailable {
+        warn!(event = "syslog_unavailable", error = e.to_string().as_str(), "{}", t!("log.syslog_unavailable", e));
+    }
+    Ok(())
+}
+
+/// syslog设施名称及其在RFC 5424中的编号
+pub const SYSLOG_FACILITIES: &[(&str, u8)] = &[
+    ("kern", 0),
+    ("user", 1),
+    ("mail", 2),
+    ("daemon", 3),
+    ("auth", 4),
+    ("syslog", 5),
+    ("lpr", 6),
+    ("news", 7),
+    ("uucp", 8),
+    ("cron", 9),
+    ("authpriv", 10),
+    ("ftp", 11),
+    ("local0", 16),
+    ("local1", 17),
+    ("local2", 18),
+    ("local3", 19),
+    ("local4", 20),
+    ("local5", 21),
+    ("local6", 22),
+    ("local7", 23),
+];
+
+/// 未指定 `--syslog-socket` 时依次尝试的本地syslog套接字（Linux、macOS、BSD）
+pub const SYSLOG_SOCKETS: &[&str] = &["/dev/log", "/var/run/syslog", "/var/run/log"];
+
+/// `--log-target syslog` 的配置
+#[derive(Debug)]
+pub struct SyslogTarget {
+    /// 设施编号
+    pub facility: u8,
+    /// 消息头中的APP-NAME
+    pub app_name: String,
+    /// 指定的syslog套接字，为None时自动查找
+    pub socket: Option<String>,
+}
+
+impl SyslogTarget {
+    /// 连接本地syslog套接字，返回按事件发送数据报的日志输出
+    ///
+    /// # 错误
+    ///
+    /// 当套接字都无法连接，或当前平台不支持Unix数据报套接字时返回错误
+    #[cfg(unix)]
+    fn connect(&self) -> std::io::Result<BoxMakeWriter> {
+        let socket = std::os::unix::net::UnixDatagram::unbound()?;
+        let candidates = match self.socket {
+            Some(ref path) => vec![path.as_str()],
+            None => SYSLOG_SOCKETS.to_vec(),
+        };
+        let mut last_error = None;
+        for path in candidates {
+            match socket.connect(path) {
+                Ok(()) => {
+                    last_error = None;
+                    break;
+                }
+                Err(e) => last_error = Some(std::io::Error::new(e.kind(), format!("{}: {}", path, e))),
+            }
+        }
+        if let Some(e) = last_error {
+            return Err(e);
+        }
+        // 主机名只用于消息头，读不到时按RFC 5424写作 -
+        let hostname = fs::read_to_string("/proc/sys/kernel/hostname")
+            .ok()
+            .or_else(|| env::var("HOSTNAME").ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_graphic()))
+            .unwrap_or_else(|| "-".to_string());
+        Ok(BoxMakeWriter::new(SyslogMakeWriter {
+            socket,
+            header: format!("{} {} {}", hostname, self.app_name, std::process::id()),
+            facility: self.facility,
+        }))
+    }
+
+    /// 当前平台没有Unix数据报套接字，总是返回错误
+    #[cfg(not(unix))]
+    fn connect(&self) -> std::io::Result<BoxMakeWriter> {
+        Err(std::io::Error::new(std::io::ErrorKind::Unsupported, t!("log.syslog_unsupported")))
+    }
+}
+
+/// 发往本地syslog的日志输出
+#[cfg(unix)]
+pub struct SyslogMakeWriter {
+    /// 已连接的syslog套接字
+    socket: std::os::unix::net::UnixDatagram,
+    /// 消息头中固定的 `HOSTNAME APP-NAME PROCID` 部分
+    header: String,
+    /// 设施编号
+    facility: u8,
+}
+
+#[cfg(unix)]
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for SyslogMakeWriter {
+    type Writer = SyslogWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        SyslogWriter {
+            target: self,
+            severity: 6,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        // 按tracing级别映射syslog严重级别：err、warning、info、debug
+        let severity = match *meta.level() {
+            Level::ERROR => 3,
+            Level::WARN => 4,
+            Level::INFO => 6,
+            _ => 7,
+        };
+        SyslogWriter {
+            target: self,
+            severity,
+            buffer: Vec::new(),
+        }
+    }
+}
+
+/// 单个日志事件的syslog输出，写入的内容先缓存，结束时按RFC 5424格式作为一个数据报发出
+#[cfg(unix)]
+pub struct SyslogWriter<'a> {
+    /// 所属的syslog输出
+    target: &'a SyslogMakeWriter,
+    /// syslog严重级别
+    severity: u8,
+    /// 已写入的消息内容
+    buffer: Vec<u8>,
+}
+
+#[cfg(unix)]
+impl std::io::Write for SyslogWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SyslogWriter<'_> {
+    fn drop(&mut self) {
+        let message = String::from_utf8_lossy(&self.buffer);
+        let message = message.trim_end();
+        if message.is_empty() {
+            return;
+        }
+        let datagram = format!(
+            "<{}>1 {} {} - - {}",
+            self.target.facility * 8 + self.severity,
+            Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z"),
+            self.target.header,
+            message
+        );
+        // syslog守护进程暂时不可用时丢弃该条日志，不影响发送
+        let _ = self.target.socket.send(datagram.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eta_is_formatted_with_padded_units() {
+        assert_eq!(format_eta(0), "0s");
+        assert_eq!(format_eta(59), "59s");
+        assert_eq!(format_eta(61), "1m01s");
+        assert_eq!(format_eta(3600), "1h00m00s");
+        assert_eq!(format_eta(3723), "1h02m03s");
+    }
+}
@@ -0,0 +1,203 @@
+//! 发送前的预览：`--plan` 的发送时间线和 `--inspect` 的数据文件概况，只打印不发送
+
+use chrono::Local;
+use serde_json::Value;
+use crate::data::{DataFileResult, field_stats};
+use crate::random::{FieldGenerator, RandomWalkConfig, WalkBounds};
+use crate::schedule::{RateSchedule, SendIntervals};
+
+/// 发送计划的时间线预览，每个元素是一行
+///
+/// 按照主循环的发送节奏推算每条记录的预计发送时刻：同一轮内的记录之间间隔 `intervals.item` 秒，
+/// 两轮之间间隔 `intervals.round` 秒，最后一轮结束后不再等待；指定 `--rate` 时与发送循环一样
+/// 按速率计划（含预热）推算，不再使用间隔。
+/// 推算不包含网络请求本身的耗时，因此实际时刻会略晚于预览。
+///
+/// # 参数
+///
+/// * `data_result` - 已加载的数据文件
+/// * `intervals` - 记录间隔和轮次间隔
+/// * `rate_schedule` - `--rate` 的速率计划
+/// * `count` - 发送轮数，0表示无限循环
+/// * `limit` - 最多列出的记录条数
+pub fn send_plan(
+    data_result: &DataFileResult,
+    intervals: SendIntervals,
+    rate_schedule: Option<&RateSchedule>,
+    count: u64,
+    limit: usize,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    let items_per_round = data_result.data.len() as u64;
+    let total = if count == 0 { None } else { Some(count * items_per_round) };
+    let start = Local::now();
+    // 第 seq 条（从0开始）相对第一条的发送时刻（秒）
+    let offset = |seq: u64| match rate_schedule {
+        Some(schedule) => schedule.offset(seq),
+        None => intervals.offset(seq, items_per_round) as f64,
+    };
+    let at = |offset: f64| start + chrono::Duration::milliseconds((offset * 1000.0).round() as i64);
+    // 按间隔发送时都是整数秒，按速率发送时保留两位小数
+    let plan_seconds = |seconds: f64| match rate_schedule {
+        Some(_) => format!("{:.2}", seconds),
+        None => seconds.to_string(),
+    };
+
+    match rate_schedule {
+        Some(schedule) => lines.push(t!("plan.header_rate", schedule.rate, schedule.warmup, items_per_round)),
+        None => lines.push(t!("plan.header", intervals.item, intervals.round, items_per_round)),
+    }
+    if let Some(ref key) = data_result.random_key {
+        lines.push(t!("plan.random_key", key));
+    }
+
+    let listed = match total {
+        Some(total) => total.min(limit as u64),
+        None => limit as u64,
+    };
+    for seq in 0..listed {
+        let round = seq / items_per_round + 1;
+        let index = (seq % items_per_round) as usize;
+        let offset = offset(seq);
+        let fields = match &data_result.data[index] {
+            Value::Object(obj) => obj.keys().cloned().collect::<Vec<_>>().join(", "),
+            other => t!("plan.non_object", other),
+        };
+        let at = at(offset).format("%Y-%m-%d %H:%M:%S");
+        let offset = plan_seconds(offset);
+        lines.push(t!("plan.item", format!("{:<5}", seq + 1), at, offset, round, index + 1, items_per_round, fields));
+    }
+
+    match total {
+        Some(total) => {
+            if total > listed {
+                lines.push(t!("plan.omitted", total - listed));
+            }
+            let duration = offset(total - 1);
+            let end = at(duration).format("%Y-%m-%d %H:%M:%S");
+            lines.push(t!("plan.total", count, total, plan_seconds(duration), end));
+        }
+        None => {
+            // 按速率发送时取预热结束后的稳定速率
+            let round_span = match rate_schedule {
+                Some(schedule) => items_per_round as f64 / schedule.rate,
+                None => intervals.round_span(items_per_round) as f64,
+            };
+            lines.push(t!("plan.total_infinite", items_per_round, plan_seconds(round_span)));
+        }
+    }
+    lines
+}
+
+/// 打印发送计划的时间线预览，参数同 [`send_plan`]
+pub fn print_send_plan(
+    data_result: &DataFileResult,
+    intervals: SendIntervals,
+    rate_schedule: Option<&RateSchedule>,
+    count: u64,
+    limit: usize,
+) {
+    for line in send_plan(data_result, intervals, rate_schedule, count, limit) {
+        out!("{}", line);
+    }
+}
+
+/// 打印 `--inspect` 的数据文件概况
+///
+/// 列出每个字段的出现次数和值类型，数值字段附带 min/max/avg，
+/// 之后是随机键、字段生成配置以及数据文件中的其它字段配置
+///
+/// # 参数
+///
+/// * `data_result` - 已加载的数据文件（已应用 `--random-key` 覆盖）
+/// * `source` - 数据文件或目录路径，用于标题
+pub fn print_data_inspection(data_result: &DataFileResult, source: &str) {
+    let items = data_result.data.len();
+    let fields = field_stats(&data_result.data);
+    out!("{}", t!("inspect.header", source, items, fields.len()));
+    // 平均值保留3位小数，避免浮点误差产生很长的尾数
+    let round = |value: f64| (value * 1000.0).round() / 1000.0;
+    for field in &fields {
+        let numeric = match (&field.min, &field.max, field.avg()) {
+            (Some(min), Some(max), Some(avg)) => t!("inspect.numeric", min, max, round(avg)),
+            _ => String::new(),
+        };
+        out!("{}{}", t!("inspect.field", field.path, field.count, items, field.types.join("/")), numeric);
+    }
+
+    match data_result.random_key {
+        Some(ref key) => match data_result.random_targets() {
+            targets if targets.is_empty() => out!("{}", t!("banner.random_key_unmatched", key)),
+            targets => out!("{}", t!("banner.random_key", key, targets.join(", "))),
+        },
+        None => out!("{}", t!("banner.random_off")),
+    }
+    for (name, generator) in &data_result.random_config {
+        match generator {
+            FieldGenerator::Geo(config) => {
+                out!("{}", t!("inspect.geo", name, format!("{:?}", config.start), config.speed, config.waypoints.len()))
+            }
+            FieldGenerator::RandomWalk(config) => out!(
+                "{}",
+                t!(
+                    "banner.random_walk",
+                    config.field.as_deref().unwrap_or(name),
+                    config.step_std,
+                    describe_walk_bounds(config)
+                )
+            ),
+        }
+    }
+    let configured = [
+        ("attribute_fields", data_result.attribute_fields.clone()),
+        ("conditions", data_result.conditions.keys().cloned().collect()),
+        ("valid_range", data_result.valid_range.keys().cloned().collect()),
+        ("field_groups", data_result.field_groups.keys().cloned().collect()),
+    ];
+    for (name, keys) in configured.iter().filter(|(_, keys)| !keys.is_empty()) {
+        out!("{}", t!("inspect.config", name, keys.join(", ")));
+    }
+}
+
+/// 随机游走上下界的说明，只有一侧边界时另一侧显示为 `-∞`/`∞`
+pub fn describe_walk_bounds(config: &RandomWalkConfig) -> String {
+    if config.min.is_none() && config.max.is_none() {
+        return t!("walk.unbounded");
+    }
+    let min = config.min.map_or("-∞".to_string(), |min| min.to_string());
+    let max = config.max.map_or("∞".to_string(), |max| max.to_string());
+    match config.bounds {
+        WalkBounds::Reflect => t!("walk.reflect", min, max),
+        WalkBounds::Clamp => t!("walk.clamp", min, max),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 取出每条记录行中 `(+…s)` 的相对时刻
+    fn offsets(lines: &[String]) -> Vec<&str> {
+        lines.iter().filter_map(|line| line.split_once("(+")).map(|(_, rest)| rest.split(')').next().unwrap()).collect()
+    }
+
+    #[test]
+    fn plan_offsets_follow_item_and_round_intervals() {
+        let data = vec![serde_json::json!({"a": 1}), serde_json::json!({"b": 2})];
+        let data_result = crate::data::records_only(data).unwrap();
+        let intervals = SendIntervals { item: 5, round: 30 };
+
+        let lines = send_plan(&data_result, intervals, None, 2, 3);
+        assert_eq!(offsets(&lines), ["0s", "5s", "35s"]);
+        // 超出上限的记录只给出条数，总耗时仍按全部记录计算
+        assert_eq!(lines.len(), 6, "{:?}", lines);
+        assert!(lines[4].contains(" 1 "), "{:?}", lines);
+        assert!(lines.last().unwrap().contains(" 40 "), "{:?}", lines);
+
+        // 按速率发送时改用速率计划的时刻
+        let schedule = RateSchedule { rate: 2.0, warmup: 0.0, curve: crate::schedule::WarmupCurve::Linear };
+        let lines = send_plan(&data_result, intervals, Some(&schedule), 0, 3);
+        assert_eq!(offsets(&lines), ["0.00s", "0.50s", "1.00s"]);
+        assert!(lines.last().unwrap().contains(" 1.00 "), "{:?}", lines);
+    }
+}
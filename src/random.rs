@@ -0,0 +1,718 @@
+//! 随机值生成：random_key、字段生成器和地理轨迹
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Instant;
+use rand_chacha::ChaCha12Rng;
+use rand::{Rng, SeedableRng};
+use crate::error::{PushError, PushResult};
+use crate::schedule::{GeoTrackState, RandomWalkState};
+use crate::telemetry::NonFinitePolicy;
+
+/// 字段生成配置
+///
+/// 对应包装对象格式数据文件中 `random_config` 的一项，`mode` 字段决定生成方式
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum FieldGenerator {
+    /// 地理轨迹：每次发送沿轨迹前进，生成连续变化的经纬度
+    Geo(GeoConfig),
+    /// 随机游走：每次发送在上一个值的基础上加一个正态分布的增量，没有固定方向
+    RandomWalk(RandomWalkConfig),
+}
+
+/// 地理轨迹生成配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeoConfig {
+    /// 起点 `[纬度, 经度]`
+    pub start: [f64; 2],
+    /// 移动速度（米/秒）
+    pub speed: f64,
+    /// 可选的路径点，提供时沿 起点 -> 路径点 的折线移动，否则随机游走
+    #[serde(default)]
+    pub waypoints: Vec<[f64; 2]>,
+    /// 到达折线终点后是否从起点重新开始
+    #[serde(default = "default_true")]
+    r#loop: bool,
+    /// 每次发送之间的模拟时长（秒），未指定时使用实际经过的时间
+    step_seconds: Option<f64>,
+    /// 纬度字段名
+    #[serde(default = "default_lat_field")]
+    pub lat_field: String,
+    /// 经度字段名
+    #[serde(default = "default_lng_field")]
+    pub lng_field: String,
+}
+
+/// 随机游走生成配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct RandomWalkConfig {
+    /// 写入的字段名，未指定时使用配置名称
+    pub field: Option<String>,
+    /// 每步增量的标准差
+    pub step_std: f64,
+    /// 起始值，未指定时使用第一次发送的数据项中该字段的值
+    pub start: Option<f64>,
+    /// 下界
+    pub min: Option<f64>,
+    /// 上界
+    pub max: Option<f64>,
+    /// 越过上下界时的处理方式
+    #[serde(default)]
+    pub bounds: WalkBounds,
+}
+
+/// 随机游走越过上下界时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WalkBounds {
+    /// 撞界反弹：越过边界的部分折回区间内
+    #[default]
+    Reflect,
+    /// 截断到边界上
+    Clamp,
+}
+
+impl WalkBounds {
+    /// 把值限制在 `[min, max]` 内，只有一侧边界时只处理这一侧
+    pub fn apply(self, value: f64, min: Option<f64>, max: Option<f64>) -> f64 {
+        match (self, min, max) {
+            (WalkBounds::Clamp, min, max) => value.max(min.unwrap_or(f64::MIN)).min(max.unwrap_or(f64::MAX)),
+            // 两侧都有边界时按 2×区间宽度 的周期折叠，任意远的越界都能折回区间内
+            (WalkBounds::Reflect, Some(min), Some(max)) => {
+                let width = max - min;
+                if width <= 0.0 {
+                    return min;
+                }
+                let folded = (value - min).rem_euclid(2.0 * width);
+                min + if folded > width { 2.0 * width - folded } else { folded }
+            }
+            (WalkBounds::Reflect, Some(min), None) if value < min => 2.0 * min - value,
+            (WalkBounds::Reflect, None, Some(max)) if value > max => 2.0 * max - value,
+            (WalkBounds::Reflect, _, _) => value,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_lat_field() -> String {
+    "lat".to_string()
+}
+
+fn default_lng_field() -> String {
+    "lng".to_string()
+}
+
+/// 每纬度对应的米数（近似值）
+pub const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// 地理轨迹的运行状态
+#[derive(Debug)]
+pub struct GeoTrack {
+    /// 轨迹配置
+    config: GeoConfig,
+    /// 当前位置 `[纬度, 经度]`
+    position: [f64; 2],
+    /// 随机游走的当前航向（弧度，0为正北）
+    heading: f64,
+    /// 沿折线移动时下一个目标点的下标（0为第一个路径点）
+    target: usize,
+    /// 上一次前进的时刻
+    last_step: Option<Instant>,
+    /// 随机游走使用的随机数生成器
+    rng: ChaCha12Rng,
+}
+
+impl GeoTrack {
+    /// 创建一条从起点出发的轨迹
+    ///
+    /// # 参数
+    ///
+    /// * `config` - 轨迹配置
+    /// * `rng` - 随机游走使用的随机数生成器，指定 `--seed` 时为确定的子种子
+    pub fn new(config: GeoConfig, mut rng: ChaCha12Rng) -> Self {
+        GeoTrack {
+            position: config.start,
+            heading: rng.gen_range(0.0..std::f64::consts::TAU),
+            target: 0,
+            last_step: None,
+            config,
+            rng,
+        }
+    }
+
+    /// 保存到状态文件中的轨迹状态
+    pub fn state(&self) -> GeoTrackState {
+        GeoTrackState {
+            position: self.position,
+            heading: self.heading,
+            target: self.target,
+            rng: self.rng.get_word_pos().to_string(),
+        }
+    }
+
+    /// 从状态文件恢复轨迹，下一步从保存的位置继续前进
+    ///
+    /// # 错误
+    ///
+    /// 随机数生成器的位置不是有效的数字时返回错误
+    pub fn restore(&mut self, state: &GeoTrackState) -> PushResult<()> {
+        self.rng.set_word_pos(parse_word_pos(&state.rng)?);
+        self.position = state.position;
+        self.heading = state.heading;
+        self.target = state.target;
+        // 恢复后的第一步按时长正常前进，而不是停在原地
+        self.last_step = Some(Instant::now());
+        Ok(())
+    }
+
+    /// 沿轨迹前进一步并返回新的位置 `[纬度, 经度]`
+    ///
+    /// 第一次调用返回起点。之后按 速度 × 时长 计算移动距离：有路径点时沿折线移动，
+    /// 否则航向随机偏转 ±30° 后直线前进
+    pub fn advance(&mut self) -> [f64; 2] {
+        let now = Instant::now();
+        let seconds = match (self.last_step, self.config.step_seconds) {
+            (None, _) => 0.0,
+            (Some(_), Some(step)) => step,
+            (Some(last), None) => now.duration_since(last).as_secs_f64(),
+        };
+        self.last_step = Some(now);
+
+        let distance = self.config.speed * seconds;
+        if self.config.waypoints.is_empty() {
+            self.heading += self.rng.gen_range(-0.5..=0.5);
+            self.position = offset_position(self.position, self.heading, distance);
+        } else {
+            self.follow_path(distance);
+        }
+        self.position
+    }
+
+    /// 沿 起点 -> 路径点 的折线移动指定距离（米）
+    fn follow_path(&mut self, mut distance: f64) {
+        let mut previous = self.config.start;
+        let path_length: f64 = self
+            .config
+            .waypoints
+            .iter()
+            .map(|&point| {
+                let length = distance_meters(previous, point);
+                previous = point;
+                length
+            })
+            .sum();
+        if path_length == 0.0 {
+            return;
+        }
+
+        while distance > 0.0 {
+            if self.target >= self.config.waypoints.len() {
+                if !self.config.r#loop {
+                    return;
+                }
+                self.position = self.config.start;
+                self.target = 0;
+            }
+            let target = self.config.waypoints[self.target];
+            let remaining = distance_meters(self.position, target);
+            if remaining <= distance {
+                self.position = target;
+                self.target += 1;
+                distance -= remaining;
+            } else {
+                let ratio = distance / remaining;
+                self.position = [
+                    self.position[0] + (target[0] - self.position[0]) * ratio,
+                    self.position[1] + (target[1] - self.position[1]) * ratio,
+                ];
+                return;
+            }
+        }
+    }
+
+    /// 把当前位置写入数据项
+    ///
+    /// 与随机字段的匹配规则一致：顶层或嵌套对象中已有经纬度字段时就地替换，
+    /// 都没有时在顶层新增。坐标不是有限数时按 `non_finite` 策略处理，被省略的坐标连同原值一起去掉
+    ///
+    /// # 错误
+    ///
+    /// 坐标不是有限数且策略为 `error` 时返回错误
+    pub fn write_position(&self, item: &mut Value, position: [f64; 2], non_finite: NonFinitePolicy) -> PushResult<()> {
+        let lat = non_finite.convert(&self.config.lat_field, (position[0] * 1e6).round() / 1e6)?;
+        let lng = non_finite.convert(&self.config.lng_field, (position[1] * 1e6).round() / 1e6)?;
+        let Value::Object(obj) = item else {
+            return Ok(());
+        };
+        let put = |fields: &mut serde_json::Map<String, Value>, name: &String, value: &Option<Value>| match value {
+            Some(value) => {
+                fields.insert(name.clone(), value.clone());
+            }
+            None => {
+                fields.remove(name);
+            }
+        };
+
+        let mut written = false;
+        for nested in obj.values_mut() {
+            if let Value::Object(nested_obj) = nested
+                && nested_obj.contains_key(&self.config.lat_field)
+            {
+                put(nested_obj, &self.config.lat_field, &lat);
+                put(nested_obj, &self.config.lng_field, &lng);
+                written = true;
+            }
+        }
+        if !written {
+            put(obj, &self.config.lat_field, &lat);
+            put(obj, &self.config.lng_field, &lng);
+        }
+        Ok(())
+    }
+}
+
+/// 随机游走的运行状态
+#[derive(Debug)]
+pub struct RandomWalk {
+    /// 写入的字段名
+    field: String,
+    /// 游走配置
+    config: RandomWalkConfig,
+    /// 上一次生成的值，还没有生成过时为None
+    value: Option<f64>,
+    /// 生成增量使用的随机数生成器
+    rng: ChaCha12Rng,
+}
+
+impl RandomWalk {
+    /// 创建随机游走
+    ///
+    /// # 参数
+    ///
+    /// * `name` - random_config 中的配置名称，未指定 `field` 时作为字段名
+    /// * `config` - 游走配置
+    /// * `rng` - 生成增量使用的随机数生成器，指定 `--seed` 时为确定的子种子
+    ///
+    /// # 错误
+    ///
+    /// 步长标准差为负数或不是有限数，或者下界大于上界时返回错误
+    pub fn new(name: &str, config: RandomWalkConfig, rng: ChaCha12Rng) -> PushResult<Self> {
+        if !config.step_std.is_finite() || config.step_std < 0.0 {
+            return Err(PushError::DataFileInvalid(t!("data.walk_step_invalid", name, config.step_std)));
+        }
+        if let (Some(min), Some(max)) = (config.min, config.max)
+            && min > max
+        {
+            return Err(PushError::DataFileInvalid(t!("data.walk_bounds_invalid", name, min, max)));
+        }
+        Ok(RandomWalk {
+            field: config.field.clone().unwrap_or_else(|| name.to_string()),
+            config,
+            value: None,
+            rng,
+        })
+    }
+
+    /// 写入的字段名
+    pub fn field(&self) -> &str {
+        &self.field
+    }
+
+    /// 游走配置
+    pub fn config(&self) -> &RandomWalkConfig {
+        &self.config
+    }
+
+    /// 保存到状态文件中的游走状态
+    pub fn state(&self) -> RandomWalkState {
+        RandomWalkState {
+            value: self.value,
+            rng: self.rng.get_word_pos().to_string(),
+        }
+    }
+
+    /// 从状态文件恢复游走，下一步从保存的值继续
+    ///
+    /// # 错误
+    ///
+    /// 随机数生成器的位置不是有效的数字时返回错误
+    pub fn restore(&mut self, state: &RandomWalkState) -> PushResult<()> {
+        self.rng.set_word_pos(parse_word_pos(&state.rng)?);
+        self.value = state.value;
+        Ok(())
+    }
+
+    /// 前进一步并把新值写入数据项
+    ///
+    /// 第一次调用写入起始值（`start`，未指定时取数据项中该字段的值，都没有时为0），之后每次在上一个值上
+    /// 加一个标准差为 `step_std` 的正态增量，再按 `bounds` 限制在上下界内。字段的查找规则与地理轨迹一致：
+    /// 顶层或嵌套对象中已有该字段时就地替换，都没有时在顶层新增；原值是整数时写入四舍五入后的整数
+    ///
+    /// # 错误
+    ///
+    /// 新值不是有限数且策略为 `error` 时返回错误
+    pub fn advance(&mut self, item: &mut Value, non_finite: NonFinitePolicy) -> PushResult<()> {
+        let Value::Object(obj) = item else {
+            return Ok(());
+        };
+        let field = self.field.as_str();
+        let nested_key = obj
+            .iter()
+            .find(|(_, nested)| nested.as_object().is_some_and(|nested| nested.contains_key(field)))
+            .map(|(key, _)| key.clone());
+        let target = match nested_key {
+            // 嵌套对象刚刚找到
+            Some(key) => obj.get_mut(&key).and_then(Value::as_object_mut).unwrap(),
+            None => obj,
+        };
+        let current = target.get(field);
+        let integer = current.is_some_and(|value| value.is_i64() || value.is_u64());
+        let next = match self.value {
+            None => self.config.start.or_else(|| current.and_then(Value::as_f64)).unwrap_or(0.0),
+            Some(previous) => random_walk_step(previous, self.config.step_std, &mut self.rng),
+        };
+        let next = self.config.bounds.apply(next, self.config.min, self.config.max);
+        self.value = Some(next);
+
+        let value = if integer && next.is_finite() {
+            Some(Value::from(next.round() as i64))
+        } else {
+            non_finite.convert(field, (next * 1e6).round() / 1e6)?
+        };
+        match value {
+            Some(value) => target.insert(field.to_string(), value),
+            None => target.remove(field),
+        };
+        Ok(())
+    }
+}
+
+/// 随机游走的一步：在上一个值上加一个均值为0、标准差为 `step_std` 的正态增量
+///
+/// 正态分布的随机数用Box-Muller变换从两个均匀分布的随机数得到
+pub fn random_walk_step(previous: f64, step_std: f64, rng: &mut impl Rng) -> f64 {
+    // gen::<f64>() 的范围是 [0, 1)，取 1 - u 避免对0取对数
+    let radius = (-2.0 * (1.0 - rng.r#gen::<f64>()).ln()).sqrt();
+    let angle = std::f64::consts::TAU * rng.r#gen::<f64>();
+    previous + step_std * radius * angle.cos()
+}
+
+/// 两点间的近似距离（米），在小范围内使用等距矩形投影
+pub fn distance_meters(from: [f64; 2], to: [f64; 2]) -> f64 {
+    let mean_lat = ((from[0] + to[0]) / 2.0).to_radians();
+    let dy = (to[0] - from[0]) * METERS_PER_DEGREE;
+    let dx = (to[1] - from[1]) * METERS_PER_DEGREE * mean_lat.cos();
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// 从某点沿航向移动指定距离（米）后的位置
+pub fn offset_position(from: [f64; 2], heading: f64, distance: f64) -> [f64; 2] {
+    let lat = from[0] + distance * heading.cos() / METERS_PER_DEGREE;
+    let lng = from[1] + distance * heading.sin() / (METERS_PER_DEGREE * from[0].to_radians().cos());
+    [lat, lng.clamp(-180.0, 180.0)]
+}
+
+/// 地理轨迹子种子的流编号起点，与设备编号（从0开始）错开
+pub const GEO_SEED_STREAM: u64 = 1 << 32;
+
+/// 随机游走子种子的流编号起点，与设备编号和地理轨迹错开
+pub const WALK_SEED_STREAM: u64 = 2 << 32;
+
+/// 从全局种子派生某个随机流的子种子
+///
+/// 采用SplitMix64：`z = seed + (stream + 1) × 0x9E3779B97F4A7C15`，再对 `z` 做两轮
+/// 异或移位与乘法混合。设备 `i` 使用流 `i`，第 `j` 条地理轨迹使用流 `2^32 + j`，
+/// 第 `k` 个随机游走字段使用流 `2^33 + k`。
+/// 相邻的流得到的子种子互不相关，同一全局种子下结果固定，便于复现
+///
+/// # 参数
+///
+/// * `seed` - 全局种子（`--seed`）
+/// * `stream` - 随机流编号
+pub fn derive_seed(seed: u64, stream: u64) -> u64 {
+    let mut z = seed.wrapping_add(stream.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// 创建某个随机流的随机数生成器，未指定全局种子时使用系统熵
+pub fn seeded_rng(seed: Option<u64>, stream: u64) -> ChaCha12Rng {
+    match seed {
+        Some(seed) => ChaCha12Rng::seed_from_u64(derive_seed(seed, stream)),
+        None => ChaCha12Rng::from_entropy(),
+    }
+}
+
+/// 能被f64精确表示的最大整数（2^53 - 1），超过它的整数在多数JSON解析器中会丢失精度
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// 解析状态文件中保存的随机数生成器位置
+fn parse_word_pos(text: &str) -> PushResult<u128> {
+    text.parse().map_err(|e| PushError::ConfigInvalid(t!("state.rng_position_invalid", text, e)))
+}
+
+/// 根据原始值的类型生成随机值
+///
+/// 支持数字类型的随机生成，保持原始值的数据类型
+///
+/// # 参数
+///
+/// * `field` - 字段名，用于非有限值的警告和错误信息
+/// * `original_value` - 原始值，用于确定生成随机值的类型和范围
+/// * `non_finite` - 生成的浮点数为 NaN 或无穷大时的处理策略
+/// * `rng` - 随机数生成器
+///
+/// # 返回值
+///
+/// * `PushResult<Option<Value>>` - 成功时返回随机生成的值，非有限值按策略被省略时返回None
+///
+/// # 随机值生成规则
+///
+/// 1. 整数：正数生成 [1, 原值*2] 范围内的随机整数，原值*2溢出时上限取 `i64::MAX`；
+///    负数对称地生成 [原值*2, -1]，0生成 [0, 1]
+/// 2. 绝对值超过 [`MAX_SAFE_INTEGER`] 的数字（如设备ID、`1.2e18`）以及超出i64/u64范围的整数：
+///    保持原始的 `Number` 不变，序列化结果与输入逐字节一致
+/// 3. 浮点数：不小于0.5时生成 [1.0, 原值*2.0] 范围内的随机浮点数，上限不超过 `f64::MAX / 2`
+///    （随机数生成器要求区间宽度有限）；小于0.5的正数（如雨量 `0.2`）生成 [原值/2, 原值*2]，
+///    负数对称地生成 [原值*2, 原值/2]，0生成 [0.0, 1.0]。区间总是非空，不会因下限大于上限而panic
+/// 4. 数组（如多通道读数 `[1, 2, 3]`）：对每个数字元素分别按以上规则随机，嵌套数组同样处理，
+///    字符串、对象等非数字元素和数组长度保持不变；按策略被省略的元素改为 `null` 占位
+/// 5. 其他类型：保持原值不变
+pub fn generate_random_value(
+    field: &str,
+    original_value: &Value,
+    non_finite: NonFinitePolicy,
+    rng: &mut impl Rng,
+) -> PushResult<Option<Value>> {
+    match original_value {
+        Value::Number(num) => {
+            let big_number = match (num.as_i64(), num.as_u64(), num.as_f64()) {
+                (Some(int_val), _, _) => int_val.unsigned_abs() > MAX_SAFE_INTEGER,
+                // 超出i64范围的无符号整数
+                (None, Some(_), _) => true,
+                // 浮点写法或超出u64范围的整数，f64无法精确表示时不参与随机（NaN和无穷大也在此排除）
+                (None, None, Some(float_val)) => float_val.is_nan() || float_val.abs() > MAX_SAFE_INTEGER as f64,
+                (None, None, None) => true,
+            };
+            if big_number {
+                // 大数多为ID类字段，随机化没有意义且经过f64会丢精度，保持原始文本
+                Ok(Some(original_value.clone()))
+            } else if let Some(int_val) = num.as_i64() {
+                // 整数类型：正数生成 [1, 原值*2]，负数生成 [原值*2, -1]
+                let (min_val, max_val) = match int_val {
+                    0 => (0, 1),
+                    positive if positive > 0 => (1, positive.saturating_mul(2)),
+                    negative => (negative.saturating_mul(2), -1),
+                };
+                let random_val = rng.gen_range(min_val..=max_val);
+                Ok(Some(Value::Number(serde_json::Number::from(random_val))))
+            } else if let Some(float_val) = num.as_f64() {
+                // 浮点数类型：区间按原值的大小和符号确定，下限总是不大于上限
+                let limit = f64::MAX / 2.0;
+                let (min_val, max_val) = if float_val >= 0.5 {
+                    (1.0, (float_val * 2.0).min(limit))
+                } else if float_val > 0.0 {
+                    (float_val / 2.0, float_val * 2.0)
+                } else if float_val < 0.0 {
+                    ((float_val * 2.0).max(-limit), float_val / 2.0)
+                } else {
+                    (0.0, 1.0)
+                };
+                let random_val = rng.gen_range(min_val..=max_val);
+                non_finite.convert(field, random_val)
+            } else {
+                // 无法识别的数字类型，保持原值
+                Ok(Some(original_value.clone()))
+            }
+        }
+        Value::Array(elements) => {
+            let randomized = elements
+                .iter()
+                .map(|element| match element {
+                    Value::Number(_) | Value::Array(_) => {
+                        Ok(generate_random_value(field, element, non_finite, rng)?.unwrap_or(Value::Null))
+                    }
+                    _ => Ok(element.clone()),
+                })
+                .collect::<PushResult<Vec<_>>>()?;
+            Ok(Some(Value::Array(randomized)))
+        }
+        _ => {
+            // 非数字类型，保持原值不变
+            Ok(Some(original_value.clone()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::load_data_file;
+    use crate::telemetry::{FieldRules, extract_telemetry_values};
+
+    #[test]
+    fn random_value_handles_large_numbers() {
+        let rng = &mut rand::thread_rng();
+        // 超过安全整数范围的ID类字段保持原值
+        for id in [serde_json::json!(9_007_199_254_740_993_i64), serde_json::json!(u64::MAX), serde_json::json!(i64::MIN)] {
+            assert_eq!(generate_random_value("id", &id, NonFinitePolicy::Error, rng).unwrap(), Some(id));
+        }
+        let value = generate_random_value("v", &serde_json::json!(4_000_000_000_000_000_i64), NonFinitePolicy::Error, rng);
+        assert!(value.unwrap().unwrap().as_i64().is_some_and(|v| (1..=8_000_000_000_000_000).contains(&v)));
+        let value = generate_random_value("v", &serde_json::json!(f64::MAX), NonFinitePolicy::Error, rng);
+        assert!(value.unwrap().unwrap().as_f64().is_some_and(f64::is_finite));
+    }
+
+    #[test]
+    fn device_seeds_differ_but_are_reproducible() {
+        assert_eq!(derive_seed(42, 0), derive_seed(42, 0));
+        assert_ne!(derive_seed(42, 0), derive_seed(42, 1));
+        assert_ne!(derive_seed(42, 0), derive_seed(43, 0));
+
+        let generate = |stream| {
+            let mut rng = seeded_rng(Some(42), stream);
+            generate_random_value("v", &serde_json::json!(1000), NonFinitePolicy::Drop, &mut rng).unwrap()
+        };
+        assert_eq!(generate(3), generate(3));
+        assert_ne!((0..4).map(generate).collect::<Vec<_>>(), (1..5).map(generate).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn array_fields_randomize_each_number() {
+        let mut rng = seeded_rng(Some(3), 0);
+        let original = serde_json::json!([10, 2.5, "ch3", null, [4, "x"], {"v": 1}]);
+        let randomized = generate_random_value("channels", &original, NonFinitePolicy::Drop, &mut rng).unwrap().unwrap();
+        let elements = randomized.as_array().unwrap();
+        assert_eq!(elements.len(), 6);
+        assert!(elements[0].as_i64().is_some_and(|v| (1..=20).contains(&v)), "{}", randomized);
+        assert!(elements[1].as_f64().is_some_and(|v| (1.0..=5.0).contains(&v)) && !elements[1].is_i64(), "{}", randomized);
+        assert_eq!(elements[2..4], original.as_array().unwrap()[2..4]);
+        assert!(elements[4][0].as_i64().is_some_and(|v| (1..=8).contains(&v)) && elements[4][1] == "x", "{}", randomized);
+        assert_eq!(elements[5], original[5]);
+
+        // random_key 指向数组字段时整组随机，其它字段不变
+        let item = serde_json::json!({"meter": {"phases": [220, 221, 219], "unit": "V"}});
+        let rules = FieldRules::default();
+        let values = extract_telemetry_values(&item, &Some("phases".to_string()), &rules, &mut rng).unwrap();
+        let phases = values["meter"]["phases"].as_array().unwrap();
+        assert_eq!(phases.len(), 3);
+        assert!(phases.iter().all(|v| v.as_i64().is_some_and(|v| (1..=442).contains(&v))));
+        assert_eq!(values["meter"]["unit"], "V");
+    }
+
+    #[test]
+    fn small_positive_floats_do_not_panic() {
+        // {"rain": {"drp": 0.2}} 配合 random_key "drp" 曾因 gen_range(1.0..=0.4) 为空区间而panic
+        let data = load_data_file("tests/fixtures/data_small_float.json").unwrap();
+        assert_eq!(data.random_key.as_deref(), Some("drp"));
+        let rules = FieldRules::default();
+        for seed in 0..200 {
+            let mut rng = seeded_rng(Some(seed), 0);
+            let values = extract_telemetry_values(&data.data[0], &data.random_key, &rules, &mut rng).unwrap();
+            let drp = values["rain"]["drp"].as_f64().unwrap();
+            assert!((0.1..=0.4).contains(&drp), "drp = {}", drp);
+        }
+
+        let mut rng = seeded_rng(Some(1), 0);
+        let mut random = |value: Value| generate_random_value("v", &value, NonFinitePolicy::Drop, &mut rng).unwrap().unwrap();
+        for _ in 0..100 {
+            assert!(random(serde_json::json!(-0.2)).as_f64().is_some_and(|v| (-0.4..=-0.1).contains(&v)));
+            assert!(random(serde_json::json!(-3)).as_i64().is_some_and(|v| (-6..=-1).contains(&v)));
+            assert!(random(serde_json::json!(0)).as_i64().is_some_and(|v| (0..=1).contains(&v)));
+            assert!(random(serde_json::json!(0.0)).as_f64().is_some_and(|v| (0.0..=1.0).contains(&v)));
+            assert!(random(serde_json::json!(-(1_i64 << 40))).as_i64().is_some_and(|v| v < 0));
+        }
+    }
+
+    #[test]
+    fn random_walk_moves_smoothly_within_bounds() {
+        let config: RandomWalkConfig = serde_json::from_value(serde_json::json!({
+            "step_std": 2.0, "min": 0, "max": 10
+        }))
+        .unwrap();
+        assert_eq!(config.bounds, WalkBounds::Reflect);
+        let walk_values = |seed| {
+            let mut walk = RandomWalk::new("level", config.clone(), seeded_rng(Some(seed), WALK_SEED_STREAM)).unwrap();
+            let mut item = serde_json::json!({"tank": {"level": 5.0}});
+            (0..500)
+                .map(|_| {
+                    walk.advance(&mut item, NonFinitePolicy::Error).unwrap();
+                    item["tank"]["level"].as_f64().unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+        let values = walk_values(7);
+        // 第一次使用数据项中的原值，之后每步变化有限且始终在区间内，同一种子得到同样的序列
+        assert_eq!(values[0], 5.0);
+        assert!(values.iter().all(|v| (0.0..=10.0).contains(v)));
+        assert!(values.windows(2).all(|pair| (pair[1] - pair[0]).abs() < 2.0 * 6.0));
+        assert!(values.windows(2).any(|pair| pair[1] != pair[0]));
+        assert_eq!(values, walk_values(7));
+        assert_ne!(values, walk_values(8));
+
+        // 反弹把越界部分折回区间内，截断停在边界上
+        assert_eq!(WalkBounds::Reflect.apply(12.0, Some(0.0), Some(10.0)), 8.0);
+        assert_eq!(WalkBounds::Reflect.apply(-3.0, Some(0.0), Some(10.0)), 3.0);
+        assert_eq!(WalkBounds::Reflect.apply(25.0, Some(0.0), Some(10.0)), 5.0);
+        assert_eq!(WalkBounds::Reflect.apply(-1.0, Some(0.0), None), 1.0);
+        assert_eq!(WalkBounds::Clamp.apply(12.0, Some(0.0), Some(10.0)), 10.0);
+        assert_eq!(WalkBounds::Clamp.apply(-12.0, None, Some(10.0)), -12.0);
+
+        // 整数字段保持整数，没有该字段时新增在顶层，状态恢复后从保存的值继续
+        let config: RandomWalkConfig =
+            serde_json::from_value(serde_json::json!({"field": "count", "step_std": 3, "start": 100, "bounds": "clamp"})).unwrap();
+        let mut walk = RandomWalk::new("c", config.clone(), seeded_rng(Some(1), WALK_SEED_STREAM)).unwrap();
+        let mut item = serde_json::json!({"count": 1});
+        walk.advance(&mut item, NonFinitePolicy::Error).unwrap();
+        assert_eq!(item["count"], 100);
+        walk.advance(&mut item, NonFinitePolicy::Error).unwrap();
+        assert!(item["count"].is_i64());
+        let saved = walk.state();
+        let mut restored = RandomWalk::new("c", config, seeded_rng(Some(1), WALK_SEED_STREAM)).unwrap();
+        restored.restore(&saved).unwrap();
+        let (mut a, mut b) = (serde_json::json!({}), serde_json::json!({}));
+        walk.advance(&mut a, NonFinitePolicy::Error).unwrap();
+        restored.advance(&mut b, NonFinitePolicy::Error).unwrap();
+        assert_eq!(a, b);
+        assert!(a["count"].is_f64());
+
+        let bad = |value| RandomWalk::new("x", serde_json::from_value(value).unwrap(), seeded_rng(None, 0)).is_err();
+        assert!(bad(serde_json::json!({"step_std": -1})));
+        assert!(bad(serde_json::json!({"step_std": 1, "min": 5, "max": 1})));
+    }
+
+    #[test]
+    fn geo_track_follows_waypoints_and_resumes_from_state() {
+        let config: GeoConfig = serde_json::from_value(serde_json::json!({
+            "start": [30.0, 120.0], "speed": 100.0, "step_seconds": 5.0,
+            "waypoints": [[30.0, 120.01]], "loop": false
+        }))
+        .unwrap();
+        let leg = distance_meters([30.0, 120.0], [30.0, 120.01]);
+        assert!((leg - 963.9).abs() < 1.0, "{}", leg);
+        assert!((distance_meters(offset_position([30.0, 120.0], 0.0, 500.0), [30.0, 120.0]) - 500.0).abs() < 1e-6);
+
+        // 第一次返回起点，之后每步沿折线前进 速度 × 模拟时长，到达终点后不循环时停住
+        let mut track = GeoTrack::new(config.clone(), seeded_rng(Some(1), GEO_SEED_STREAM));
+        assert_eq!(track.advance(), [30.0, 120.0]);
+        let second = track.advance();
+        assert!((distance_meters([30.0, 120.0], second) - 500.0).abs() < 1e-6);
+        let state = track.state();
+        assert_eq!(track.advance(), [30.0, 120.01]);
+        assert_eq!(track.advance(), [30.0, 120.01]);
+
+        // 从状态恢复后从保存的位置继续
+        let mut resumed = GeoTrack::new(config, seeded_rng(Some(1), GEO_SEED_STREAM));
+        resumed.restore(&state).unwrap();
+        assert_eq!(resumed.advance(), [30.0, 120.01]);
+
+        let mut item = serde_json::json!({"gps": {"lat": 0.0, "lng": 0.0}});
+        resumed.write_position(&mut item, [30.1234567, 120.7654321], NonFinitePolicy::Error).unwrap();
+        assert_eq!(item, serde_json::json!({"gps": {"lat": 30.123457, "lng": 120.765432}}));
+    }
+}
@@ -0,0 +1,138 @@
+//! 配置热更新：使用配置档时，收到SIGHUP重新读取配置档并应用可热更新的参数
+
+use clap::ArgMatches;
+#[cfg(unix)]
+use tokio::sync::watch;
+use tracing::{info, warn};
+use crate::config::{ProfileSettings, resolve_arg};
+#[cfg(unix)]
+use crate::config::load_config_file;
+#[cfg(unix)]
+use crate::error::{PushError, PushResult};
+use crate::schedule::SendIntervals;
+
+/// 在Unix上监听SIGHUP信号，收到后重新读取配置文件中的配置档
+///
+/// 重新读取失败时只输出警告，继续沿用原来的配置
+///
+/// # 参数
+///
+/// * `config_path` - 配置文件路径
+/// * `profile_name` - 配置档名称
+/// * `initial` - 启动时读取的配置档
+///
+/// # 返回值
+///
+/// * `PushResult<watch::Receiver<ProfileSettings>>` - 每次重新读取成功后收到新的配置档
+///
+/// # 错误
+///
+/// 当无法注册信号处理器时返回错误
+#[cfg(unix)]
+pub fn spawn_reload_listener(
+    config_path: String,
+    profile_name: String,
+    initial: ProfileSettings,
+) -> PushResult<watch::Receiver<ProfileSettings>> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = signal(SignalKind::hangup())
+        .map_err(|source| PushError::Io { message: t!("reload.listen_failed"), source })?;
+    let (sender, receiver) = watch::channel(initial);
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            let reloaded = load_config_file(&config_path).and_then(|config_file| {
+                config_file
+                    .profiles
+                    .get(&profile_name)
+                    .cloned()
+                    .ok_or_else(|| PushError::ConfigInvalid(t!("config.profile_not_found", config_path, profile_name)))
+            });
+            match reloaded {
+                Ok(profile) => {
+                    if sender.send(profile).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => warn!("{}", t!("reload.failed", e)),
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// 应用重新读取的配置档中可热更新的参数
+///
+/// 可热更新：发送间隔、发送轮数、随机字段；命令行已指定的参数仍以命令行为准。
+/// 不可热更新的参数（服务器、设备令牌、数据文件）发生变化时忽略并给出警告
+///
+/// # 参数
+///
+/// * `matches` - 命令行解析结果
+/// * `profile_name` - 配置档名称
+/// * `current` - 当前生效的配置档，应用后更新为新配置档
+/// * `reloaded` - 新读取的配置档
+/// * `intervals` - 记录间隔和轮次间隔，配置档中的 interval 不覆盖命令行单独指定的间隔
+/// * `count` - 发送轮数
+/// * `random_key` - 随机字段
+pub fn apply_profile_reload(
+    matches: &ArgMatches,
+    profile_name: &str,
+    current: &mut ProfileSettings,
+    reloaded: ProfileSettings,
+    intervals: &mut SendIntervals,
+    count: &mut u64,
+    random_key: &mut Option<String>,
+) {
+    info!("{}", t!("reload.reloaded", profile_name));
+
+    let mut changed = false;
+    let (interval_arg, _) = resolve_arg(matches, "interval", reloaded.interval.map(|v| v.to_string()));
+    match interval_arg.parse::<u64>().map(|value| SendIntervals::resolve(matches, value)) {
+        Ok(Ok(value)) if value != *intervals => {
+            info!("{}", t!("reload.intervals", intervals.item, value.item, intervals.round, value.round));
+            *intervals = value;
+            changed = true;
+        }
+        Ok(_) => {}
+        Err(_) => warn!("{}", t!("reload.interval_invalid", interval_arg)),
+    }
+    let (count_arg, _) = resolve_arg(matches, "count", reloaded.count.map(|v| v.to_string()));
+    match count_arg.parse::<u64>() {
+        Ok(value) if value != *count => {
+            info!("{}", t!("reload.count", count, value));
+            *count = value;
+            changed = true;
+        }
+        Ok(_) => {}
+        Err(_) => warn!("{}", t!("reload.count_invalid", count_arg)),
+    }
+    if !matches.contains_id("random-key") && reloaded.random_key != current.random_key {
+        info!("{}", t!("reload.random_key", format!("{:?}", random_key), format!("{:?}", reloaded.random_key)));
+        random_key.clone_from(&reloaded.random_key);
+        changed = true;
+    }
+    if !changed {
+        info!("{}", t!("reload.unchanged"));
+    }
+
+    // 不可热更新的参数只提示，需要重启才能生效
+    let fixed = [
+        ("server", &current.server, &reloaded.server),
+        ("device_token", &current.device_token, &reloaded.device_token),
+        ("data_file", &current.data_file, &reloaded.data_file),
+    ];
+    for (name, old, new) in fixed {
+        if old != new {
+            warn!("{}", t!("reload.not_reloadable", name));
+        }
+    }
+
+    // 不可热更新的参数保持原值，下次比较时仍以启动时为准
+    *current = ProfileSettings {
+        server: current.server.take(),
+        device_token: current.device_token.take(),
+        data_file: current.data_file.take(),
+        ..reloaded
+    };
+}
@@ -0,0 +1,1047 @@
+//! 发送循环：按轮次派发数据项、按派发顺序处理结果、重新入队、中止条件和断点续传位置
+//!
+//! 循环只依赖传输层 [`Sender`]，命令行工具传入HTTP传输，测试传入 [`MockSender`](crate::sender::MockSender)
+
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+use rand::{Rng, SeedableRng};
+use tokio::sync::{oneshot, watch};
+use tracing::{error, warn};
+use tokio::time::Duration;
+use rand_chacha::ChaCha12Rng;
+use crate::config::TokenPool;
+use crate::error::{PushError, PushResult};
+use crate::output::Progress;
+use crate::random::{GeoTrack, RandomWalk, seeded_rng};
+use crate::schedule::{CountPolicy, Pacer, RateSchedule, RunPosition, SendIntervals, SendQuota, StateFile, unless_stopped};
+use crate::sender::{
+    PendingSend, SendJob, SendOptions, SendReport, SendWorkers, Sender, mask_token, send_telemetry,
+    send_timeseries_batch,
+};
+use crate::stats::{CsvReport, Heartbeat, Metrics, RunStats, StatsdClient, TeeFile, WindowStats};
+use crate::telemetry::{FieldScheduler, Injection, TelemetryData, apply_injection};
+use crate::tui::TuiCommand;
+
+/// 发送循环中交给调用方处理的事件，默认什么也不做
+///
+/// 命令行工具用它发出webhook回调和运行通知，并应用SIGHUP重新读取的配置
+pub trait SendEvents {
+    /// 一条数据项发送成功（合并时序的批次不触发）
+    fn item_sent(&mut self, _round: u64, _index: usize, _stats: &RunStats, _telemetry: &TelemetryData) {}
+
+    /// 发送失败，失败数从 `failed_before` 增加到 `stats.failed`
+    fn failed(&mut self, _failed_before: u64, _stats: &RunStats, _elapsed: Duration) {}
+
+    /// 一轮发送结束，`succeeded` 和 `failed` 为本轮的成功和失败条数
+    fn round_completed(&mut self, _round: u64, _succeeded: u64, _failed: u64, _stats: &RunStats) {}
+
+    /// 每次逐条发送之前调用，可以修改发送间隔、发送次数和随机字段
+    fn reload(&mut self, _intervals: &mut SendIntervals, _count: &mut u64, _random_key: &mut Option<String>) {}
+}
+
+impl SendEvents for () {}
+
+/// 已经完成的发送结果，还在发送时返回None；工作者没有回传结果就退出时按发送失败处理
+fn ready_outcome(
+    outcome: &mut oneshot::Receiver<(PushResult<Option<SendReport>>, Duration)>,
+) -> Option<(PushResult<Option<SendReport>>, Duration)> {
+    match outcome.try_recv() {
+        Ok(outcome) => Some(outcome),
+        Err(oneshot::error::TryRecvError::Empty) => None,
+        Err(oneshot::error::TryRecvError::Closed) => {
+            Some((Err(PushError::payload(t!("send.workers_stopped"))), Duration::ZERO))
+        }
+    }
+}
+
+/// 一轮中的成功和失败条数
+#[derive(Debug, Default)]
+struct RoundTally {
+    /// 成功条数
+    succeeded: u64,
+    /// 失败条数
+    failed: u64,
+}
+
+/// 发送循环及其运行状态
+///
+/// 由 [`SendLoop::new`] 创建后按需覆盖字段，调用 [`SendLoop::run`] 发送；
+/// 结束后统计、断点续传位置和随机数状态留在字段中，由调用方输出汇总和保存
+pub struct SendLoop<'a> {
+    /// 数据项
+    pub data: &'a [Value],
+    /// 未使用令牌池时的设备令牌，合并时序同样使用它
+    pub token: &'a str,
+    /// 令牌池，为None时总是使用 `token`
+    pub token_pool: Option<&'a TokenPool>,
+    /// 影响消息内容的发送选项
+    pub options: &'a SendOptions,
+    /// 随机字段，可以被热更新
+    pub random_key: Option<String>,
+    /// 发送次数，0表示无限循环，可以被热更新
+    pub count: u64,
+    /// 随机种子，为None时使用系统熵源
+    pub seed: Option<u64>,
+    /// 合并时序的每批条数，为None时逐条发送
+    pub merge_size: Option<usize>,
+    /// `--rate` 的发送计划，为None时按间隔发送
+    pub rate_schedule: Option<&'a RateSchedule>,
+    /// 并发发送工作者的任务队列，为None时在循环中逐条发送；中止时被取走并关闭
+    pub workers: Option<SendWorkers>,
+    /// 已派发、尚未处理结果的发送数上限，并发时为 工作者数 + 队列容量
+    pub pipeline_depth: usize,
+    /// 失败的数据项在本轮末尾重新入队的次数上限
+    pub requeue: u64,
+    /// 连续失败多少次后中止
+    pub max_consecutive_failures: Option<u64>,
+    /// 是否为 `--fail-fast`，只影响中止时的提示
+    pub fail_fast: bool,
+    /// 认证失败后是否继续发送
+    pub ignore_auth_errors: bool,
+    /// 是否经由ThingsBoard Edge发送，只影响认证失败的提示
+    pub edge: bool,
+    /// TUI仪表盘的控制通道，暂停时等待恢复后再发送
+    pub control: Option<watch::Receiver<TuiCommand>>,
+    /// 运行统计
+    pub stats: RunStats,
+    /// 周期性心跳，由调用方按间隔输出
+    pub heartbeat: Arc<Heartbeat>,
+    /// 按时间窗口滚动的统计，由调用方按间隔输出
+    pub window_stats: Arc<WindowStats>,
+    /// Prometheus指标
+    pub metrics: Arc<Metrics>,
+    /// StatsD上报
+    pub statsd: Option<StatsdClient>,
+    /// 进度显示
+    pub progress: Progress,
+    /// `--report-csv` 逐条报告
+    pub csv_report: Option<CsvReport>,
+    /// `--tee` 数据副本
+    pub tee: Option<TeeFile>,
+    /// 记录间隔和轮次间隔的等待，以及停止请求
+    pub pacer: Pacer,
+    /// 每个数据项的发送次数
+    pub quota: SendQuota,
+    /// 已经开始的轮次，从1计数
+    pub round: u64,
+    /// 第一轮从哪个数据项开始，断点续传时由状态文件给出
+    pub resume_from: usize,
+    /// 断点续传之前已经发送的次数
+    pub sequence_base: u64,
+    /// 断点续传的位置，随处理结果前进
+    pub position: RunPosition,
+    /// 断点续传的状态文件
+    pub state_file: Option<StateFile>,
+    /// 每个设备的随机数生成器
+    pub device_rngs: HashMap<usize, ChaCha12Rng>,
+    /// 地理轨迹
+    pub geo_tracks: Vec<GeoTrack>,
+    /// 随机游走字段
+    pub walks: Vec<RandomWalk>,
+    /// 阶跃注入
+    pub injections: Vec<Injection>,
+    /// 按字段分组的发送间隔
+    pub field_scheduler: FieldScheduler,
+    /// 当前的连续失败次数
+    pub consecutive_failures: u64,
+    /// 因认证失败中止时的状态码
+    pub auth_failure: Option<u16>,
+    /// 是否已经因认证失败、连续失败等原因中止，中止后处理剩余结果时不再重复提示
+    pub aborted: bool,
+    /// 运行开始的时刻，`--rate` 的计划和注入的时间条件都从这里计时
+    pub started_at: Instant,
+}
+
+impl<'a> SendLoop<'a> {
+    /// 创建逐条发送、按发送次数计数的发送循环，其余字段为默认值
+    ///
+    /// # 参数
+    ///
+    /// * `data` - 数据项
+    /// * `token` - 设备令牌
+    /// * `options` - 发送选项
+    /// * `count` - 发送次数，0表示无限循环
+    /// * `pacer` - 记录间隔和轮次间隔的等待
+    pub fn new(data: &'a [Value], token: &'a str, options: &'a SendOptions, count: u64, pacer: Pacer) -> Self {
+        SendLoop {
+            data,
+            token,
+            token_pool: None,
+            options,
+            random_key: None,
+            count,
+            seed: None,
+            merge_size: None,
+            rate_schedule: None,
+            workers: None,
+            pipeline_depth: 1,
+            requeue: 0,
+            max_consecutive_failures: None,
+            fail_fast: false,
+            ignore_auth_errors: false,
+            edge: false,
+            control: None,
+            stats: RunStats::new(options.dry_run),
+            heartbeat: Arc::new(Heartbeat::new(None)),
+            window_stats: Arc::new(WindowStats::new(false)),
+            metrics: Arc::new(Metrics::default()),
+            statsd: None,
+            progress: Progress::new(None, false),
+            csv_report: None,
+            tee: None,
+            pacer,
+            quota: SendQuota::new(CountPolicy::Attempts, data.len()),
+            round: 0,
+            resume_from: 0,
+            sequence_base: 0,
+            position: RunPosition { round: 1, next_index: 0, sequence: 0 },
+            state_file: None,
+            device_rngs: HashMap::new(),
+            geo_tracks: Vec::new(),
+            walks: Vec::new(),
+            injections: Vec::new(),
+            field_scheduler: FieldScheduler::new(&BTreeMap::new()),
+            consecutive_failures: 0,
+            auth_failure: None,
+            aborted: false,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// 运行发送循环，直到发完、收到停止请求或满足中止条件
+    ///
+    /// # 参数
+    ///
+    /// * `sender` - 传输层，并发发送时工作者使用的传输应与之相同
+    /// * `events` - 交给调用方处理的事件
+    ///
+    /// # 返回值
+    ///
+    /// * `bool` - 因认证失败、连续失败等原因中止时返回true
+    pub async fn run(&mut self, sender: &dyn Sender, events: &mut dyn SendEvents) -> bool {
+        loop {
+            self.round += 1;
+            let mut tally = RoundTally::default();
+            let flow = match self.merge_size {
+                Some(batch_size) => self.send_merged_round(sender, events, batch_size, &mut tally).await,
+                None => self.send_round(sender, events, &mut tally).await,
+            };
+            if flow.is_break() {
+                return true;
+            }
+            events.round_completed(self.round, tally.succeeded, tally.failed, &self.stats);
+
+            // 检查是否达到指定的发送次数，被跳过的空记录也计入完成数；未完成时等待轮次间隔
+            let done = self.quota.is_done(self.round, self.count);
+            // 中途停止时本轮没有发完，位置保持在最后处理的数据项之后
+            if !self.pacer.stopped() {
+                self.position = RunPosition {
+                    round: self.round + 1,
+                    next_index: 0,
+                    sequence: self.sequence_base + self.stats.attempted,
+                };
+            }
+            self.save_state(done);
+            if !self.pacer.finish_round(done).await {
+                return false;
+            }
+        }
+    }
+
+    /// 把当前位置、发送次数和随机数状态写入状态文件，没有状态文件时什么也不做
+    ///
+    /// # 参数
+    ///
+    /// * `finished` - 是否已经全部发完
+    pub fn save_state(&mut self, finished: bool) {
+        if let Some(ref mut state_file) = self.state_file {
+            state_file.save(self.position, &self.quota, &self.device_rngs, &self.geo_tracks, &self.walks, finished);
+        }
+    }
+
+    /// 按状态文件的保存间隔保存状态
+    fn save_state_if_due(&mut self) {
+        if self.state_file.as_ref().is_some_and(StateFile::due) {
+            self.save_state(false);
+        }
+    }
+
+    /// 在TUI仪表盘中暂停时，等待恢复或停止请求
+    async fn wait_while_paused(&mut self) {
+        if let Some(ref mut control) = self.control
+            && *control.borrow() == TuiCommand::Paused
+        {
+            let _ = unless_stopped(&self.pacer.stop, control.wait_for(|command| *command != TuiCommand::Paused)).await;
+        }
+    }
+
+    /// 记录中止并返回 [`ControlFlow::Break`]
+    fn abort<T>(&mut self) -> ControlFlow<(), T> {
+        self.aborted = true;
+        ControlFlow::Break(())
+    }
+
+    /// 达到连续失败次数上限时输出原因并返回中止
+    fn check_consecutive_failures(&mut self) -> ControlFlow<()> {
+        if !self.aborted && self.max_consecutive_failures.is_some_and(|max| self.consecutive_failures >= max) {
+            if self.fail_fast {
+                error!("{}", t!("send.fail_fast_stop"));
+            } else {
+                error!("{}", t!("send.consecutive_failures_stop", self.consecutive_failures));
+            }
+            return self.abort();
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// 认证失败且未指定 `--ignore-auth-errors` 时记录状态码并返回中止
+    fn check_auth_failure(&mut self, e: &PushError, device: &str) -> ControlFlow<()> {
+        if !self.ignore_auth_errors
+            && !self.aborted
+            && let PushError::Auth(err) = e
+        {
+            let key = if self.edge { "send.auth_failed_stop_edge" } else { "send.auth_failed_stop" };
+            error!(
+                event = "auth_failed",
+                status_code = err.status.as_u16(),
+                device,
+                url = err.url.as_str(),
+                "{}",
+                t!(key, err.status.as_u16(), device, err.url)
+            );
+            self.auth_failure = Some(err.status.as_u16());
+            return self.abort();
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// 记录一次发送的耗时和结果到心跳、时间窗口、Prometheus和StatsD
+    fn record_metrics(&self, result: &PushResult<Option<SendReport>>, latency: Duration) {
+        self.heartbeat.record(result.is_ok(), latency);
+        self.window_stats.record(result.is_ok(), latency);
+        self.metrics.record(result, latency);
+        if let Some(ref statsd) = self.statsd {
+            statsd.record(result, latency);
+        }
+    }
+
+    /// 合并时序模式下发送一轮：本轮要发送的数据项按批打包成时序数组，每批一个请求，条数按数据点计
+    async fn send_merged_round(
+        &mut self,
+        sender: &dyn Sender,
+        events: &mut dyn SendEvents,
+        batch_size: usize,
+        tally: &mut RoundTally,
+    ) -> ControlFlow<()> {
+        let data = self.data;
+        let wanted: Vec<usize> = (std::mem::take(&mut self.resume_from)..data.len())
+            .filter(|&index| self.quota.wants(index, self.count))
+            .collect();
+        for chunk in wanted.chunks(batch_size) {
+            self.pacer.before_item().await;
+            self.wait_while_paused().await;
+            if self.pacer.stopped() {
+                break;
+            }
+            self.stats.attempted += chunk.len() as u64;
+
+            // 合并时序面向单个设备，与令牌池互斥
+            let token = self.token;
+            let seed = self.seed;
+            let rng = self.device_rngs.entry(0).or_insert_with(|| seeded_rng(seed, 0));
+            let records: Vec<&Value> = chunk.iter().map(|&index| &data[index]).collect();
+
+            let send_started = Instant::now();
+            self.metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+            let result = send_timeseries_batch(sender, token, self.options, &records, &self.random_key, rng).await;
+            self.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+            let latency = send_started.elapsed();
+            let latency_ms = latency.as_millis() as u64;
+            let device = mask_token(token);
+            self.record_metrics(&result, latency);
+            for &index in chunk {
+                self.quota.record(index, result.is_ok());
+                self.progress.record(result.is_ok());
+            }
+            let round = self.round;
+            let (first, last, total) = (chunk[0] + 1, chunk[chunk.len() - 1] + 1, data.len());
+            match result {
+                Ok(None) => {
+                    chunk.iter().for_each(|_| self.stats.record_skip());
+                    item_info!("{}", t!("send.merged_skipped", first, last, total));
+                }
+                Ok(Some(report)) => {
+                    // 被跳过的空记录不在请求中
+                    (report.points..chunk.len() as u64).for_each(|_| self.stats.record_skip());
+                    self.stats.record_success(latency, report.bytes, report.server_offset_ms, report.points);
+                    self.consecutive_failures = 0;
+                    tally.succeeded += report.points;
+                    let (event, message) = if self.options.dry_run {
+                        ("generated", t!("send.merged_generated", report.points, first, last, total))
+                    } else {
+                        ("send", t!("send.merged_succeeded", report.points, first, last, total, latency_ms))
+                    };
+                    item_info!(
+                        event,
+                        item_index = chunk[0] as u64,
+                        round,
+                        points = report.points,
+                        status_code = report.status_code,
+                        latency_ms,
+                        bytes = report.bytes as u64,
+                        device = device.as_str(),
+                        "{}",
+                        message
+                    );
+                }
+                Err(e) => {
+                    tally.failed += chunk.len() as u64;
+                    let failed_before = self.stats.failed;
+                    let status_code = e.status_code();
+                    self.stats.record_failure(
+                        latency,
+                        status_code.map_or_else(|| e.kind().to_string(), |code| format!("HTTP {}", code)),
+                        e.detail(),
+                        chunk.len() as u64,
+                    );
+                    self.consecutive_failures += 1;
+                    error!(
+                        event = "send_failed",
+                        item_index = chunk[0] as u64,
+                        round,
+                        points = chunk.len() as u64,
+                        error_kind = e.kind(),
+                        error = e.detail().as_str(),
+                        latency_ms,
+                        device = device.as_str(),
+                        status_code,
+                        "{}",
+                        t!("send.merged_failed", first, last, total, latency_ms, e)
+                    );
+                    self.check_auth_failure(&e, &device)?;
+                    events.failed(failed_before, &self.stats, self.started_at.elapsed());
+                    self.check_consecutive_failures()?;
+                }
+            }
+            self.position = RunPosition {
+                round,
+                next_index: chunk[chunk.len() - 1] + 1,
+                sequence: self.sequence_base + self.stats.attempted,
+            };
+            self.save_state_if_due();
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// 逐条发送一轮，并发发送时按派发顺序处理结果
+    async fn send_round(
+        &mut self,
+        sender: &dyn Sender,
+        events: &mut dyn SendEvents,
+        tally: &mut RoundTally,
+    ) -> ControlFlow<()> {
+        let data = self.data;
+        // 已派发的发送按派发顺序处理结果，统计和计数与逐条发送一致
+        let mut pending: VecDeque<PendingSend> = VecDeque::new();
+        // 本轮待发送的数据项及其已重新入队的次数，断点续传的第一轮从状态文件记录的数据项开始；
+        // 指定 --requeue 时发送失败的数据项放回末尾
+        let mut queue: VecDeque<(usize, u64)> =
+            (std::mem::take(&mut self.resume_from)..data.len()).map(|index| (index, 0)).collect();
+        loop {
+            // 流水线未满时派发下一条，否则等待最早派发的一条完成；收到停止请求后只处理已派发的发送
+            let next =
+                if pending.len() < self.pipeline_depth && !self.pacer.stopped() { queue.pop_front() } else { None };
+            if let Some((index, requeued)) = next {
+                // 按成功次数计数时，补发轮次跳过已经发够的数据项
+                if !self.quota.wants(index, self.count) {
+                    continue;
+                }
+                self.pacer.before_item().await;
+                self.wait_while_paused().await;
+                // 指定 --rate 时按计划时刻发送，预热阶段的间隔逐步缩短
+                if let Some(schedule) = self.rate_schedule {
+                    let due = Duration::from_secs_f64(schedule.offset(self.stats.attempted));
+                    let due = tokio::time::Instant::from_std(self.started_at + due);
+                    unless_stopped(&self.pacer.stop, tokio::time::sleep_until(due)).await;
+                }
+                if self.pacer.stopped() {
+                    continue;
+                }
+                // 已经完成的发送先按派发顺序处理，不必等到流水线满了
+                while let Some((result, latency)) =
+                    pending.front_mut().and_then(|sent| ready_outcome(&mut sent.outcome))
+                {
+                    let Some(sent) = pending.pop_front() else {
+                        break;
+                    };
+                    if self.handle_result(events, &sent, result, latency, &mut queue, tally).is_break() {
+                        return self.finish_aborted(events, pending, &mut queue, tally).await;
+                    }
+                }
+                self.stats.attempted += 1;
+                let ControlFlow::Continue(sent) = self.dispatch(sender, events, index, requeued).await else {
+                    self.stats.attempted -= 1;
+                    return self.finish_aborted(events, pending, &mut queue, tally).await;
+                };
+                pending.push_back(sent);
+                if pending.len() < self.pipeline_depth && !queue.is_empty() {
+                    continue;
+                }
+            }
+
+            let Some(mut sent) = pending.pop_front() else {
+                break;
+            };
+            let (result, latency) = match (&mut sent.outcome).await {
+                Ok(outcome) => outcome,
+                Err(_) => (Err(PushError::payload(t!("send.workers_stopped"))), Duration::ZERO),
+            };
+            if self.handle_result(events, &sent, result, latency, &mut queue, tally).is_break() {
+                return self.finish_aborted(events, pending, &mut queue, tally).await;
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// 中止后关闭工作者的任务队列，不再发送尚未开始的数据项
+    ///
+    /// 已经在发送的请求完成后照常记录结果，被工作者丢弃的数据项不计入尝试发送的条数
+    async fn finish_aborted(
+        &mut self,
+        events: &mut dyn SendEvents,
+        pending: VecDeque<PendingSend>,
+        queue: &mut VecDeque<(usize, u64)>,
+        tally: &mut RoundTally,
+    ) -> ControlFlow<()> {
+        if let Some(workers) = self.workers.take() {
+            workers.abort();
+        }
+        for mut sent in pending {
+            match (&mut sent.outcome).await {
+                Ok((result, latency)) => {
+                    let _ = self.handle_result(events, &sent, result, latency, queue, tally);
+                }
+                Err(_) => self.stats.attempted -= 1,
+            }
+        }
+        ControlFlow::Break(())
+    }
+
+    /// 生成一条数据项的本次内容并派发发送
+    ///
+    /// 逐条发送时在这里等待发送完成；并发发送时交给工作者，队列满时在这里等待
+    ///
+    /// # 返回值
+    ///
+    /// * `ControlFlow<(), PendingSend>` - 已派发的发送；令牌读取失败或工作者已经退出时中止
+    async fn dispatch(
+        &mut self,
+        sender: &dyn Sender,
+        events: &mut dyn SendEvents,
+        index: usize,
+        requeued: u64,
+    ) -> ControlFlow<(), PendingSend> {
+        let data = self.data;
+        let options = self.options;
+        let item = &data[index];
+
+        // 应用SIGHUP重新读取的配置
+        events.reload(&mut self.pacer.intervals, &mut self.count, &mut self.random_key);
+
+        // 沿地理轨迹前进，写入新的经纬度；坐标不是有限数且策略为 error 时本条记为发送失败
+        let mut injected_item = None;
+        let mut generate_error = None;
+        for track in self.geo_tracks.iter_mut() {
+            let position = track.advance();
+            let target = injected_item.get_or_insert_with(|| item.clone());
+            if let Err(e) = track.write_position(target, position, options.field_rules.non_finite) {
+                generate_error.get_or_insert(e);
+            }
+        }
+
+        // 应用当前生效的阶跃注入，被注入的字段本次不再随机
+        let mut random_key = self.random_key.clone();
+
+        // 随机游走字段在上一次的值上前进一步，由游走决定的字段本次不再随机
+        for walk in self.walks.iter_mut() {
+            let target = injected_item.get_or_insert_with(|| item.clone());
+            if let Err(e) = walk.advance(target, options.field_rules.non_finite) {
+                generate_error.get_or_insert(e);
+            }
+            if random_key.as_deref() == Some(walk.field()) {
+                random_key = None;
+            }
+        }
+        for injection in self.injections.iter_mut() {
+            if !injection.is_active(self.stats.attempted, self.started_at.elapsed()) {
+                continue;
+            }
+            injection.applied += 1;
+            let target = injected_item.get_or_insert_with(|| item.clone());
+            apply_injection(target, &injection.field, &injection.value);
+            if random_key.as_deref() == Some(injection.field.as_str()) {
+                random_key = None;
+            }
+            item_info!(
+                "{}",
+                t!("send.injected", injection.field, injection.value, injection.applied, injection.times)
+            );
+        }
+
+        // 按字段分组的发送间隔只保留本次到期的字段，记录中的字段都未到期时本次不发送
+        let omitted = self.field_scheduler.omitted(index, self.started_at.elapsed());
+        let mut nothing_due = false;
+        if !omitted.is_empty()
+            && let Value::Object(fields) = injected_item.get_or_insert_with(|| item.clone())
+        {
+            let before = fields.len();
+            fields.retain(|key, _| !omitted.contains(key));
+            nothing_due = before > 0 && fields.is_empty();
+        }
+        let item = injected_item.as_ref().unwrap_or(item);
+
+        // 确定本次发送使用的设备令牌
+        let token_index = self.token_pool.map(|pool| pool.pick(index));
+        let token = match (self.token_pool, token_index) {
+            (Some(pool), Some(i)) => match pool.tokens.get(i) {
+                Ok(token) => token,
+                Err(e) => {
+                    error!("❌ {:#}", e);
+                    return self.abort();
+                }
+            },
+            _ => Cow::Borrowed(self.token),
+        };
+        let token = token.as_ref();
+
+        // 同一设备的轮换令牌共用一个随机序列，令牌池中的每个令牌视为不同设备；
+        // 未指定种子时各设备的随机序列没有区别，共用一个，避免大令牌池为每个设备保存生成器
+        let seed = self.seed;
+        let device_index = match (self.token_pool, token_index) {
+            (Some(pool), Some(i)) if !pool.rotation && seed.is_some() => i,
+            _ => 0,
+        };
+        let rng = self.device_rngs.entry(device_index).or_insert_with(|| seeded_rng(seed, device_index as u64));
+
+        // 同一数据项在成功投递之前重发时沿用同一个幂等键
+        let idempotency_key = options
+            .idempotency
+            .as_ref()
+            .map(|keys| keys.key(token, index, self.quota.next_delivery(index), &data[index]));
+
+        // 尝试发送遥测数据到ThingsBoard；并发发送时交给发送工作者，队列满时在此等待
+        let (reply, outcome) = oneshot::channel();
+        if let Some(e) = generate_error {
+            let _ = reply.send((Err(e), Duration::ZERO));
+        } else if nothing_due {
+            let _ = reply.send((Ok(None), Duration::ZERO));
+        } else if let Some(ref workers) = self.workers {
+            let job = SendJob {
+                token: token.to_string(),
+                item: item.clone(),
+                random_key,
+                idempotency_key,
+                rng: ChaCha12Rng::seed_from_u64(rng.r#gen()),
+                reply,
+            };
+            if workers.send(job).await.is_err() {
+                error!("{}", t!("send.workers_stopped"));
+                return self.abort();
+            }
+        } else {
+            let send_started = Instant::now();
+            self.metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+            let result =
+                send_telemetry(sender, token, options, item, &random_key, idempotency_key.as_deref(), rng).await;
+            self.metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+            let _ = reply.send((result, send_started.elapsed()));
+        }
+        ControlFlow::Continue(PendingSend {
+            index,
+            token_index,
+            device: mask_token(token),
+            nothing_due,
+            requeued,
+            outcome,
+        })
+    }
+
+    /// 处理一条发送的结果：统计、日志、重新入队和中止条件
+    ///
+    /// # 返回值
+    ///
+    /// * `ControlFlow<()>` - 认证失败或达到连续失败次数上限时中止
+    fn handle_result(
+        &mut self,
+        events: &mut dyn SendEvents,
+        sent: &PendingSend,
+        result: PushResult<Option<SendReport>>,
+        latency: Duration,
+        queue: &mut VecDeque<(usize, u64)>,
+        tally: &mut RoundTally,
+    ) -> ControlFlow<()> {
+        let PendingSend { index, token_index, ref device, nothing_due, requeued, .. } = *sent;
+        let round = self.round;
+        let total = self.data.len();
+        let latency_ms = latency.as_millis() as u64;
+        if let Some(report) = self.csv_report.as_mut() {
+            report.record(round, index, device, latency, &result, self.options.dry_run);
+        }
+        self.record_metrics(&result, latency);
+        self.quota.record(index, result.is_ok());
+        if result.is_ok() {
+            self.stats.recovered += requeued;
+        }
+        match result {
+            Ok(None) => {
+                self.progress.record(true);
+                self.stats.record_skip();
+                let message = if nothing_due { "send.not_due" } else { "send.skipped" };
+                item_info!("{}", t!(message, index + 1, total));
+            }
+            Ok(Some(report)) => {
+                self.progress.record(true);
+                self.stats.record_success(latency, report.bytes, report.server_offset_ms, 1);
+                self.consecutive_failures = 0;
+                tally.succeeded += 1;
+                if let (Some(pool), Some(i)) = (self.token_pool, token_index) {
+                    pool.record_sent(i);
+                }
+                if let Some(tee) = self.tee.as_mut()
+                    && !self.options.dry_run
+                {
+                    tee.record(device, &report.telemetry);
+                }
+                let keys = report.telemetry.values.keys().cloned().collect::<Vec<_>>().join(",");
+                if self.options.dry_run {
+                    item_info!(
+                        event = "generated",
+                        item_index = index as u64,
+                        round,
+                        bytes = report.bytes as u64,
+                        device = device.as_str(),
+                        keys = keys.as_str(),
+                        "{}",
+                        t!("send.generated", self.stats.succeeded, index + 1, total)
+                    );
+                } else {
+                    item_info!(
+                        event = "send",
+                        item_index = index as u64,
+                        round,
+                        status_code = report.status_code,
+                        latency_ms,
+                        headers_ms = report.headers_latency.as_millis() as u64,
+                        server_offset_ms = report.server_offset_ms,
+                        bytes = report.bytes as u64,
+                        device = device.as_str(),
+                        keys = keys.as_str(),
+                        "{}",
+                        t!(
+                            "send.succeeded",
+                            self.stats.succeeded,
+                            index + 1,
+                            total,
+                            latency_ms,
+                            report.headers_latency.as_millis()
+                        )
+                    );
+                }
+                events.item_sent(round, index, &self.stats, &report.telemetry);
+            }
+            Err(e) => {
+                self.progress.record(false);
+                tally.failed += 1;
+                let failed_before = self.stats.failed;
+                let status_code = e.status_code();
+                self.stats.record_failure(
+                    latency,
+                    status_code.map_or_else(|| e.kind().to_string(), |code| format!("HTTP {}", code)),
+                    e.detail(),
+                    1,
+                );
+                self.consecutive_failures += 1;
+                error!(
+                    event = "send_failed",
+                    item_index = index as u64,
+                    round,
+                    error_kind = e.kind(),
+                    error = e.detail().as_str(),
+                    latency_ms,
+                    device = device.as_str(),
+                    status_code,
+                    "{}",
+                    t!("send.failed", latency_ms, e)
+                );
+                self.check_auth_failure(&e, device)?;
+                if requeued < self.requeue && !self.pacer.stopped() && !self.aborted {
+                    queue.push_back((index, requeued + 1));
+                    self.stats.requeued += 1;
+                    warn!(
+                        event = "requeued",
+                        item_index = index as u64,
+                        round,
+                        requeued = requeued + 1,
+                        "{}",
+                        t!("send.requeued", index + 1, requeued + 1, self.requeue)
+                    );
+                }
+                events.failed(failed_before, &self.stats, self.started_at.elapsed());
+                self.check_consecutive_failures()?;
+            }
+        }
+        // 重新入队的数据项排在本轮末尾，位置只随第一次发送前进
+        if requeued == 0 {
+            self.position =
+                RunPosition { round, next_index: index + 1, sequence: self.sequence_base + self.stats.attempted };
+        }
+        self.save_state_if_due();
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sender::{MockMessage, MockSender, spawn_send_workers};
+    use std::collections::HashSet;
+
+    /// 不按间隔等待、从未停止的发送节奏
+    fn unpaced() -> Pacer {
+        let (_stop_tx, stop_rx) = watch::channel(false);
+        Pacer::new(SendIntervals { item: 0, round: 0 }, false, stop_rx)
+    }
+
+    /// 记录每条成功发送的数据项下标，以及处理它时已派发、尚未处理结果的条数
+    #[derive(Default)]
+    struct Recorder {
+        /// 按处理顺序排列的数据项下标
+        sent: Vec<usize>,
+        /// 处理每条结果时仍在等待结果的条数
+        outstanding: Vec<u64>,
+    }
+
+    impl SendEvents for Recorder {
+        fn item_sent(&mut self, _round: u64, index: usize, stats: &RunStats, _telemetry: &TelemetryData) {
+            self.sent.push(index);
+            self.outstanding.push(stats.attempted - stats.succeeded - stats.failed - stats.skipped);
+        }
+    }
+
+    /// 用 `workers` 个工作者、容量 `queue_size` 的队列并发发送的发送循环
+    fn concurrent<'a>(
+        data: &'a [Value],
+        options: &'a Arc<SendOptions>,
+        sender: &Arc<dyn Sender>,
+        workers: usize,
+        queue_size: usize,
+    ) -> SendLoop<'a> {
+        let metrics = Arc::new(Metrics::default());
+        SendLoop {
+            workers: Some(spawn_send_workers(workers, queue_size, sender, options, &metrics)),
+            pipeline_depth: workers + queue_size,
+            metrics,
+            ..SendLoop::new(data, "token", options, 1, unpaced())
+        }
+    }
+
+    /// 模拟传输收到的消息中某个字段出现的次数
+    fn count_field(messages: &[MockMessage], field: &str) -> u64 {
+        messages.iter().filter(|message| message.body["values"].get(field).is_some()).count() as u64
+    }
+
+    #[tokio::test]
+    async fn run_ends_after_intended_attempts_when_half_fail() {
+        let options = SendOptions::default();
+        let data = [serde_json::json!({"a": 1}), serde_json::json!({"b": 2}), serde_json::json!({"c": 3})];
+        let count = 4;
+
+        for policy in [CountPolicy::Attempts, CountPolicy::Successes] {
+            let sender = MockSender::failing_every(2);
+            let mut send_loop = SendLoop {
+                seed: Some(1),
+                quota: SendQuota::new(policy, data.len()),
+                ..SendLoop::new(&data, "token", &options, count, unpaced())
+            };
+            let aborted = send_loop.run(&sender, &mut ()).await;
+            assert!(!aborted);
+            let stats = &send_loop.stats;
+            assert!(send_loop.round < 50, "{:?} 没有结束", policy);
+            let messages = sender.messages();
+            let sent = ["a", "b", "c"].map(|field| count_field(&messages, field));
+            match policy {
+                CountPolicy::Attempts => {
+                    assert_eq!(send_loop.round, count);
+                    assert_eq!(stats.attempted, 12);
+                    assert_eq!(stats.failed, 6);
+                    assert_eq!(sent.iter().sum::<u64>(), stats.succeeded);
+                }
+                // 补发只针对没发够的数据项，每项恰好成功 count 次
+                CountPolicy::Successes => {
+                    assert_eq!(sent, [count; 3]);
+                    assert_eq!(stats.succeeded, 12);
+                    assert_eq!(stats.attempted, 12 + stats.failed);
+                    assert!(send_loop.round > count);
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn final_round_finishes_without_waiting() {
+        let sender = MockSender::failing_every(2);
+        let options = SendOptions::default();
+        let data = [serde_json::json!({"a": 1})];
+
+        // --count 1 --interval 60，单条数据：发完即结束，不应再等一整个间隔
+        let started = Instant::now();
+        let (_stop_tx, stop_rx) = watch::channel(false);
+        let pacer = Pacer::new(SendIntervals { item: 60, round: 60 }, true, stop_rx);
+        let mut send_loop = SendLoop::new(&data, "token", &options, 1, pacer);
+        assert!(!send_loop.run(&sender, &mut ()).await);
+        assert_eq!(send_loop.round, 1);
+        assert_eq!(send_loop.stats.succeeded, 1);
+        assert!(started.elapsed() < Duration::from_secs(10), "耗时 {:?}", started.elapsed());
+    }
+
+    #[tokio::test]
+    async fn mock_sender_sees_paced_randomized_payloads_without_network() {
+        let options = SendOptions {
+            attribute_fields: HashSet::from(["firmware".to_string()]),
+            ..SendOptions::default()
+        };
+        let data = [
+            serde_json::json!({"sensor": {"temperature": 20.0}, "firmware": "1.2"}),
+            serde_json::json!({"sensor": {"temperature": 30.0}}),
+        ];
+        let run = async |seed| {
+            let sender = MockSender::new();
+            let (_stop_tx, stop_rx) = watch::channel(false);
+            let pacer = Pacer::new(SendIntervals { item: 1, round: 1 }, true, stop_rx);
+            let mut send_loop = SendLoop {
+                seed: Some(seed),
+                random_key: Some("temperature".to_string()),
+                ..SendLoop::new(&data, "token", &options, 1, pacer)
+            };
+            assert!(!send_loop.run(&sender, &mut ()).await);
+            assert_eq!(send_loop.stats.succeeded, 2);
+            sender.shutdown().await;
+            assert!(sender.is_shut_down());
+            sender.messages()
+        };
+
+        // 第一条记录拆成属性和遥测两条消息，记录之间按间隔等待
+        let messages = run(7).await;
+        let channels: Vec<_> = messages.iter().map(|message| message.channel).collect();
+        assert_eq!(channels, ["attributes", "telemetry", "telemetry"]);
+        assert_eq!(messages[0].body, serde_json::json!({"firmware": "1.2"}));
+        let gap = messages[2].received_at - messages[1].received_at;
+        assert!(gap >= Duration::from_secs(1), "间隔 {:?}", gap);
+
+        // 嵌套对象中随机键的值被随机修改，同一种子得到同样的负载，不同种子得到不同的负载
+        let temperatures = |messages: &[MockMessage]| -> Vec<f64> {
+            let temperature = |message: &MockMessage| message.body["values"]["sensor"]["temperature"].as_f64().unwrap();
+            messages[1..].iter().map(temperature).collect()
+        };
+        let sent = temperatures(&messages);
+        assert_ne!(sent[0], 20.0);
+        assert_ne!(sent[1], 30.0);
+        assert_eq!(temperatures(&run(7).await), sent);
+        assert_ne!(temperatures(&run(8).await), sent);
+    }
+
+    #[tokio::test]
+    async fn concurrent_workers_share_one_transport() {
+        let options = Arc::new(SendOptions::default());
+        let data: Vec<Value> = (0..20).map(|index| serde_json::json!({"index": index})).collect();
+        let sender: Arc<dyn Sender> = Arc::new(MockSender::failing_every(5));
+        let mut send_loop = concurrent(&data, &options, &sender, 4, 8);
+
+        // 并发工作者通过同一个传输发送，全部完成后没有进行中的请求
+        assert!(!send_loop.run(sender.as_ref(), &mut ()).await);
+        assert_eq!(send_loop.stats.attempted, 20);
+        assert_eq!(send_loop.stats.failed, 4);
+        assert_eq!(send_loop.position.round, 2);
+        assert_eq!(send_loop.metrics.in_flight.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn slow_transport_applies_backpressure_and_keeps_result_order() {
+        let options = Arc::new(SendOptions::default());
+        let data: Vec<Value> = (0..24).map(|index| serde_json::json!({"index": index})).collect();
+        // 快慢交替的传输，后派发的请求经常先完成
+        let delays = [Duration::from_millis(60), Duration::from_millis(5), Duration::from_millis(20)];
+        let mock = Arc::new(MockSender::new().with_delays(&delays));
+        let sender: Arc<dyn Sender> = mock.clone();
+        let mut send_loop = concurrent(&data, &options, &sender, 3, 2);
+        let mut recorder = Recorder::default();
+
+        assert!(!send_loop.run(sender.as_ref(), &mut recorder).await);
+        // 结果按派发顺序处理，同时进行的请求不超过工作者数，积压不超过 工作者数 + 队列容量
+        assert_eq!(recorder.sent, (0..24).collect::<Vec<_>>());
+        assert_eq!(mock.max_in_flight(), 3);
+        assert!(recorder.outstanding.iter().all(|&n| n < 5), "{:?}", recorder.outstanding);
+        assert_eq!(mock.calls(), 24);
+    }
+
+    #[tokio::test]
+    async fn finished_results_are_handled_before_the_pipeline_fills() {
+        let options = Arc::new(SendOptions::default());
+        let data: Vec<Value> = (0..10).map(|index| serde_json::json!({"index": index})).collect();
+        let mock = Arc::new(MockSender::new().with_delays(&[Duration::from_millis(2)]));
+        let sender: Arc<dyn Sender> = mock.clone();
+        // 每20毫秒派发一条，每条2毫秒完成：结果应在下一次派发时处理，而不是等到积压8条
+        let schedule = RateSchedule { rate: 50.0, warmup: 0.0, curve: crate::schedule::WarmupCurve::Linear };
+        let mut send_loop = SendLoop { rate_schedule: Some(&schedule), ..concurrent(&data, &options, &sender, 4, 4) };
+        let mut recorder = Recorder::default();
+
+        assert!(!send_loop.run(sender.as_ref(), &mut recorder).await);
+        assert_eq!(recorder.sent.len(), 10);
+        let eager = &recorder.outstanding[..9];
+        assert!(eager.iter().all(|&n| n <= 2), "{:?}", recorder.outstanding);
+    }
+
+    #[tokio::test]
+    async fn abort_under_concurrency_stops_sending_queued_items() {
+        let options = Arc::new(SendOptions::default());
+        let data: Vec<Value> = (0..40).map(|index| serde_json::json!({"index": index})).collect();
+        let delay = [Duration::from_millis(50)];
+
+        // 认证失败：第一条结果为401时中止，队列中的数据项不再发送
+        let mock =
+            Arc::new(MockSender::failing_every(1).with_fail_status(reqwest::StatusCode::UNAUTHORIZED).with_delays(&delay));
+        let sender: Arc<dyn Sender> = mock.clone();
+        let mut send_loop = concurrent(&data, &options, &sender, 2, 8);
+        assert!(send_loop.run(sender.as_ref(), &mut ()).await);
+        assert!(send_loop.aborted);
+        assert_eq!(send_loop.auth_failure, Some(401));
+        assert!(send_loop.workers.is_none());
+        let calls = mock.calls();
+        // 最多是中止时正在发送的请求，以及工作者在结果处理之前取走的下一条
+        assert!(calls <= 4, "中止后仍发送了 {} 条", calls);
+        let stats = &send_loop.stats;
+        assert_eq!(stats.attempted, calls);
+        assert_eq!(stats.attempted, stats.succeeded + stats.failed);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(mock.calls(), calls);
+
+        // 连续失败次数上限：总是失败的传输在连续失败3次后中止
+        let mock = Arc::new(MockSender::failing_every(1).with_delays(&delay));
+        let sender: Arc<dyn Sender> = mock.clone();
+        let mut send_loop =
+            SendLoop { max_consecutive_failures: Some(3), ..concurrent(&data, &options, &sender, 4, 8) };
+        assert!(send_loop.run(sender.as_ref(), &mut ()).await);
+        assert_eq!(send_loop.auth_failure, None);
+        let calls = mock.calls();
+        assert!(calls <= 8, "中止后仍发送了 {} 条", calls);
+        assert_eq!(send_loop.stats.attempted, calls);
+        assert_eq!(send_loop.stats.failed, calls);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(mock.calls(), calls);
+    }
+}
@@ -0,0 +1,622 @@
+//! 发送节奏：间隔、速率与预热、发送次数和断点续传状态
+
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use sha2::Sha256;
+use std::fs;
+use std::time::Instant;
+use chrono::Local;
+use tokio::sync::watch;
+use tracing::warn;
+use tokio::time::{sleep, Duration};
+use rand_chacha::ChaCha12Rng;
+use crate::error::{PushError, PushResult};
+use crate::data::{describe_json_error, strip_bom};
+use crate::random::{GeoTrack, RandomWalk};
+
+/// 预热阶段速率的爬升曲线
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarmupCurve {
+    /// 速率随时间线性增长
+    Linear,
+    /// 速率先缓后急，按指数曲线增长
+    Exponential,
+}
+
+/// 指数爬升曲线的陡峭程度，预热进行到一半时速率约为目标的12%
+pub const WARMUP_EXP_STEEPNESS: f64 = 4.0;
+
+/// 按 `--rate` 计算每条消息的发送时刻
+///
+/// 速率在前 `warmup` 秒内从0按曲线爬升到 `rate`，之后保持不变。
+/// 发送时刻由累计发送量反推，慢启动阶段不会因为某一次等待过长而整体滞后
+#[derive(Debug, Clone)]
+pub struct RateSchedule {
+    /// 目标速率（条/秒）
+    pub rate: f64,
+    /// 预热时长（秒），0表示不预热
+    pub warmup: f64,
+    /// 预热阶段的爬升曲线
+    pub curve: WarmupCurve,
+}
+
+impl RateSchedule {
+    /// 开始发送后 `t` 秒内按计划应发出的累计条数
+    fn cumulative(&self, t: f64) -> f64 {
+        if self.warmup <= 0.0 {
+            return self.rate * t;
+        }
+        let ramp = |x: f64| match self.curve {
+            WarmupCurve::Linear => x * x / 2.0,
+            WarmupCurve::Exponential => {
+                let k = WARMUP_EXP_STEEPNESS;
+                ((k * x).exp_m1() / k - x) / k.exp_m1()
+            }
+        };
+        if t < self.warmup {
+            self.rate * self.warmup * ramp(t / self.warmup)
+        } else {
+            self.rate * self.warmup * ramp(1.0) + self.rate * (t - self.warmup)
+        }
+    }
+
+    /// 第 `n` 条消息（从0开始）相对开始发送的计划发送时刻（秒）
+    ///
+    /// 预热阶段内用二分法反解累计条数，预热结束后按恒定速率直接计算
+    pub fn offset(&self, n: u64) -> f64 {
+        let n = n as f64;
+        let ramped = self.cumulative(self.warmup);
+        if n >= ramped {
+            return self.warmup + (n - ramped) / self.rate;
+        }
+        let (mut low, mut high) = (0.0, self.warmup);
+        for _ in 0..60 {
+            let mid = (low + high) / 2.0;
+            if self.cumulative(mid) < n {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        high
+    }
+}
+
+/// `--count` 的计数方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CountPolicy {
+    /// 每条数据尝试发送 count 次后结束，失败不补发（默认）
+    Attempts,
+    /// 每条数据都成功 count 次后结束，失败的数据项在后续轮次中补发
+    Successes,
+}
+
+/// 按 `--count` 决定每轮发送哪些数据项以及何时结束
+///
+/// 按尝试次数计数时每轮发送全部数据项，跑满 count 轮结束，服务器完全不可用时也能停下；
+/// 按成功次数计数时分别记录每个数据项的成功次数，补发轮次只发送尚未达到 count 次的数据项，
+/// 不会因为某条失败而多发其它数据项
+pub struct SendQuota {
+    /// 计数方式
+    policy: CountPolicy,
+    /// 每个数据项已成功（或作为空记录跳过）的次数
+    pub delivered: Vec<u64>,
+}
+
+impl SendQuota {
+    /// 创建计数状态
+    ///
+    /// # 参数
+    ///
+    /// * `policy` - 计数方式
+    /// * `items` - 每轮的数据项个数
+    pub fn new(policy: CountPolicy, items: usize) -> Self {
+        SendQuota {
+            policy,
+            delivered: vec![0; items],
+        }
+    }
+
+    /// 本轮是否发送该数据项
+    ///
+    /// # 参数
+    ///
+    /// * `index` - 数据项序号
+    /// * `count` - 当前生效的发送次数，0表示无限循环
+    pub fn wants(&self, index: usize, count: u64) -> bool {
+        match self.policy {
+            CountPolicy::Attempts => true,
+            CountPolicy::Successes => count == 0 || self.delivered[index] < count,
+        }
+    }
+
+    /// 数据项下一次投递的序号（从1开始），发送失败时不变
+    ///
+    /// # 参数
+    ///
+    /// * `index` - 数据项序号
+    pub fn next_delivery(&self, index: usize) -> u64 {
+        self.delivered[index] + 1
+    }
+
+    /// 记录一次发送结果
+    ///
+    /// # 参数
+    ///
+    /// * `index` - 数据项序号
+    /// * `delivered` - 是否发送成功或作为空记录跳过
+    pub fn record(&mut self, index: usize, delivered: bool) {
+        if delivered {
+            self.delivered[index] += 1;
+        }
+    }
+
+    /// 在一轮结束后判断是否已完成全部发送
+    ///
+    /// # 参数
+    ///
+    /// * `round` - 刚结束的轮次（从1开始）
+    /// * `count` - 当前生效的发送次数，0表示无限循环
+    pub fn is_done(&self, round: u64, count: u64) -> bool {
+        count > 0
+            && match self.policy {
+                CountPolicy::Attempts => round >= count,
+                CountPolicy::Successes => self.delivered.iter().all(|&delivered| delivered >= count),
+            }
+    }
+}
+
+/// 状态文件格式版本，格式不兼容地变化时递增
+pub const STATE_VERSION: u32 = 1;
+
+/// 两次写入状态文件之间的最短间隔，每轮结束和退出时总是写入
+pub const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 发送位置：下一条要发送的是第几轮的第几个数据项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunPosition {
+    /// 轮次（从1开始）
+    pub round: u64,
+    /// 本轮下一个要发送的数据项序号（从0开始），等于数据项个数时本轮已发完
+    pub next_index: usize,
+    /// 累计尝试发送的条数，包括之前被中断的运行
+    pub sequence: u64,
+}
+
+/// `--state-file` 中保存的运行状态，`--resume` 时从这里继续
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RunState {
+    /// 状态文件格式版本
+    version: u32,
+    /// 数据内容的SHA-256，数据文件变化后不能继续
+    data_sha256: String,
+    /// 决定发送位置含义的参数，与本次运行不同时不能继续
+    options: BTreeMap<String, Value>,
+    /// 轮次（从1开始）
+    pub round: u64,
+    /// 本轮下一个要发送的数据项序号（从0开始）
+    pub next_index: usize,
+    /// 累计尝试发送的条数
+    pub sequence: u64,
+    /// 每个数据项已成功（或作为空记录跳过）的次数
+    pub delivered: Vec<u64>,
+    /// 指定 `--seed` 时各设备随机数生成器的位置，键为设备序号；u128按字符串保存
+    pub rngs: BTreeMap<usize, String>,
+    /// 地理轨迹的状态，顺序与 random_config 中的轨迹一致
+    pub geo: Vec<GeoTrackState>,
+    /// 随机游走字段的状态，顺序与 random_config 中的游走配置一致；旧版本的状态文件没有这一项
+    #[serde(default)]
+    pub walks: Vec<RandomWalkState>,
+    /// 是否已经完成全部发送
+    pub finished: bool,
+    /// 写入时间
+    saved_at: String,
+}
+
+/// 地理轨迹的可恢复状态
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GeoTrackState {
+    /// 当前位置 `[纬度, 经度]`
+    pub position: [f64; 2],
+    /// 随机游走的当前航向
+    pub heading: f64,
+    /// 下一个目标路径点的下标
+    pub target: usize,
+    /// 随机数生成器的位置
+    pub rng: String,
+}
+
+/// 随机游走字段的可恢复状态
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RandomWalkState {
+    /// 上一次生成的值，还没有生成过时为空
+    pub value: Option<f64>,
+    /// 随机数生成器的位置
+    pub rng: String,
+}
+
+/// 状态文件（`--state-file`）：定期保存发送位置，`--resume` 时读取并校验
+pub struct StateFile {
+    /// 文件路径
+    pub path: String,
+    /// 数据内容的SHA-256
+    data_sha256: String,
+    /// 决定发送位置含义的参数
+    options: BTreeMap<String, Value>,
+    /// 是否指定了随机种子，只有这时随机数生成器的位置才有意义
+    seeded: bool,
+    /// 上一次写入的时刻
+    last_saved: Instant,
+    /// 是否已经提示过写入失败，避免每次写入都刷屏
+    warned: bool,
+}
+
+impl StateFile {
+    /// 创建状态文件，计算数据内容的摘要
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 状态文件路径
+    /// * `data` - 数据项
+    /// * `options` - 决定发送位置含义的参数（种子、发送次数、计数方式等）
+    pub fn new(path: &str, data: &[Value], options: BTreeMap<String, Value>) -> Self {
+        use sha2::Digest;
+        // serde_json的对象按键排序序列化，同一内容总是得到同样的字节
+        let mut hasher = Sha256::new();
+        for item in data {
+            hasher.update(item.to_string().as_bytes());
+            hasher.update([b'\n']);
+        }
+        StateFile {
+            path: path.to_string(),
+            data_sha256: hex::encode(hasher.finalize()),
+            seeded: options.get("seed").is_some_and(|seed| !seed.is_null()),
+            options,
+            last_saved: Instant::now(),
+            warned: false,
+        }
+    }
+
+    /// 读取状态文件并检查它是否属于同一组数据和参数
+    ///
+    /// # 返回值
+    ///
+    /// * `PushResult<Option<RunState>>` - 文件不存在时为 `None`（从头开始）
+    ///
+    /// # 错误
+    ///
+    /// 文件无法解析、版本不同，或者数据内容、关键参数与本次运行不同时返回错误
+    pub fn load(&self) -> PushResult<Option<RunState>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => return Err(PushError::Io { message: t!("state.read_failed", self.path), source }),
+        };
+        let state: RunState = serde_json::from_str(strip_bom(&content)).map_err(|error| {
+            PushError::ConfigInvalid(format!(
+                "{}: {}",
+                t!("state.corrupt", self.path),
+                describe_json_error(&content, &error)
+            ))
+        })?;
+        if state.version != STATE_VERSION {
+            return Err(PushError::ConfigInvalid(t!(
+                "state.version",
+                self.path,
+                state.version,
+                STATE_VERSION
+            )));
+        }
+        if state.data_sha256 != self.data_sha256 {
+            return Err(PushError::ConfigInvalid(t!("state.data_changed", self.path)));
+        }
+        let keys: std::collections::BTreeSet<&String> = state.options.keys().chain(self.options.keys()).collect();
+        for key in keys {
+            let (saved, current) = (state.options.get(key), self.options.get(key));
+            if saved != current {
+                let show = |value: Option<&Value>| value.map_or_else(|| "-".to_string(), Value::to_string);
+                return Err(PushError::ConfigInvalid(t!(
+                    "state.option_changed",
+                    self.path,
+                    key,
+                    show(saved),
+                    show(current)
+                )));
+            }
+        }
+        if state.round == 0 {
+            return Err(PushError::ConfigInvalid(t!("state.corrupt", self.path)));
+        }
+        Ok(Some(state))
+    }
+
+    /// 距离上一次写入是否已经超过 [`STATE_SAVE_INTERVAL`]
+    pub fn due(&self) -> bool {
+        self.last_saved.elapsed() >= STATE_SAVE_INTERVAL
+    }
+
+    /// 写入当前状态，先写临时文件再改名，写到一半被中断时不会留下损坏的状态文件
+    ///
+    /// 写入失败只输出一次警告，不影响发送
+    ///
+    /// # 参数
+    ///
+    /// * `position` - 下一条要发送的位置
+    /// * `quota` - 每个数据项的成功次数
+    /// * `rngs` - 各设备的随机数生成器
+    /// * `geo` - 地理轨迹
+    /// * `walks` - 随机游走字段
+    /// * `finished` - 是否已经完成全部发送
+    pub fn save(
+        &mut self,
+        position: RunPosition,
+        quota: &SendQuota,
+        rngs: &HashMap<usize, ChaCha12Rng>,
+        geo: &[GeoTrack],
+        walks: &[RandomWalk],
+        finished: bool,
+    ) {
+        self.last_saved = Instant::now();
+        let state = RunState {
+            version: STATE_VERSION,
+            data_sha256: self.data_sha256.clone(),
+            options: self.options.clone(),
+            round: position.round,
+            next_index: position.next_index,
+            sequence: position.sequence,
+            delivered: quota.delivered.clone(),
+            rngs: if self.seeded {
+                rngs.iter().map(|(index, rng)| (*index, rng.get_word_pos().to_string())).collect()
+            } else {
+                BTreeMap::new()
+            },
+            geo: geo.iter().map(GeoTrack::state).collect(),
+            walks: walks.iter().map(RandomWalk::state).collect(),
+            finished,
+            saved_at: Local::now().to_rfc3339(),
+        };
+        let temp = format!("{}.tmp", self.path);
+        let result = serde_json::to_string_pretty(&state)
+            .map_err(std::io::Error::from)
+            .and_then(|text| fs::write(&temp, text + "\n"))
+            .and_then(|_| fs::rename(&temp, &self.path));
+        match result {
+            Ok(()) => self.warned = false,
+            Err(e) if !self.warned => {
+                self.warned = true;
+                warn!("{}", t!("state.write_failed", self.path, e));
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// 发送节奏：同一轮内相邻两条记录之间、以及相邻两轮之间各自等待的秒数
+///
+/// `--interval` 同时设置两者，`--item-interval` 和 `--round-interval` 分别覆盖其中之一，都可以为0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendIntervals {
+    /// 同一轮内记录之间的间隔（秒）
+    pub item: u64,
+    /// 轮次之间的间隔（秒）
+    pub round: u64,
+}
+
+impl SendIntervals {
+    /// 以 `--interval`（或配置档中的 interval）为基础，应用单独指定的记录间隔和轮次间隔
+    ///
+    /// # 参数
+    ///
+    /// * `matches` - 命令行解析结果
+    /// * `interval` - 同时作用于两者的基础间隔（秒）
+    ///
+    /// # 错误
+    ///
+    /// 当 `--item-interval` 或 `--round-interval` 不是有效的数字时返回错误
+    pub fn resolve(matches: &ArgMatches, interval: u64) -> PushResult<Self> {
+        let parse = |id: &str| -> PushResult<u64> {
+            match matches.get_one::<String>(id) {
+                Some(value) => value
+                    .parse()
+                    .map_err(|e| PushError::ConfigInvalid(format!("{}: {}", t!("arg.interval_invalid_named", id), e))),
+                None => Ok(interval),
+            }
+        };
+        Ok(SendIntervals {
+            item: parse("item-interval")?,
+            round: parse("round-interval")?,
+        })
+    }
+
+    /// 一整轮从第一条发出到下一轮第一条发出的时长（秒）
+    ///
+    /// # 参数
+    ///
+    /// * `items_per_round` - 每轮的记录条数
+    pub fn round_span(&self, items_per_round: u64) -> u64 {
+        items_per_round.saturating_sub(1) * self.item + self.round
+    }
+
+    /// 第 `seq` 条（从0开始）记录相对第一条的发送时刻（秒），不含网络请求耗时
+    ///
+    /// # 参数
+    ///
+    /// * `seq` - 全局序号
+    /// * `items_per_round` - 每轮的记录条数
+    pub fn offset(&self, seq: u64, items_per_round: u64) -> u64 {
+        let rounds = seq / items_per_round;
+        rounds * self.round_span(items_per_round) + (seq % items_per_round) * self.item
+    }
+}
+
+/// 主循环中记录之间和轮次之间的等待
+///
+/// 结束判断在最后一轮的最后一条之后立即进行，已完成时不再等待轮次间隔；
+/// 同一轮内的记录间隔在发送下一条之前等待，因此每轮最后一条之后也不会多等一次
+pub struct Pacer {
+    /// 记录间隔和轮次间隔，可以被SIGHUP热更新
+    pub intervals: SendIntervals,
+    /// 是否等待（`--fast` 或 `--rate` 时不按间隔等待）
+    enabled: bool,
+    /// 本轮是否已经发送过数据项
+    sent_in_round: bool,
+    /// 停止请求（按下Ctrl-C），收到后等待立即结束
+    pub stop: watch::Receiver<bool>,
+}
+
+impl Pacer {
+    /// 创建等待状态
+    ///
+    /// # 参数
+    ///
+    /// * `intervals` - 记录间隔和轮次间隔
+    /// * `enabled` - 是否按间隔等待
+    /// * `stop` - 停止请求，值变为 `true` 后不再等待
+    pub fn new(intervals: SendIntervals, enabled: bool, stop: watch::Receiver<bool>) -> Self {
+        Pacer {
+            intervals,
+            enabled,
+            sent_in_round: false,
+            stop,
+        }
+    }
+
+    /// 是否已经收到停止请求
+    pub fn stopped(&self) -> bool {
+        *self.stop.borrow()
+    }
+
+    /// 发送一条数据项之前调用：同一轮内从第二条开始先等待记录间隔
+    ///
+    /// 补发轮次中被跳过的数据项不调用，因此不占用间隔
+    pub async fn before_item(&mut self) {
+        if self.sent_in_round && self.enabled && self.intervals.item > 0 {
+            unless_stopped(&self.stop, sleep(Duration::from_secs(self.intervals.item))).await;
+        }
+        self.sent_in_round = true;
+    }
+
+    /// 一轮结束后调用，决定是否继续下一轮
+    ///
+    /// # 参数
+    ///
+    /// * `done` - 是否已完成全部发送
+    ///
+    /// # 返回值
+    ///
+    /// * `bool` - 已完成或收到停止请求时返回false；否则等待轮次间隔后返回true
+    pub async fn finish_round(&mut self, done: bool) -> bool {
+        if done || self.stopped() {
+            return false;
+        }
+        self.sent_in_round = false;
+        if self.enabled && self.intervals.round > 0 {
+            item_info!("{}", t!("send.next_round", self.intervals.round));
+            return unless_stopped(&self.stop, sleep(Duration::from_secs(self.intervals.round))).await.is_some();
+        }
+        true
+    }
+}
+
+/// 等待一个操作完成，收到停止请求时放弃等待
+///
+/// 用于发送之间的间隔、限速和暂停，不用于发送本身，正在进行的发送总是等待其完成
+///
+/// # 返回值
+///
+/// * `Option<T>` - 操作的结果，收到停止请求时为 `None`
+pub async fn unless_stopped<F: Future>(stop: &watch::Receiver<bool>, future: F) -> Option<F::Output> {
+    let mut stop = stop.clone();
+    tokio::select! {
+        output = future => Some(output),
+        Ok(_) = stop.wait_for(|stopped| *stopped) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::env;
+    
+    use crate::random::seeded_rng;
+    
+
+    #[test]
+    fn warmup_ramps_up_to_target_rate() {
+        for curve in [WarmupCurve::Linear, WarmupCurve::Exponential] {
+            let schedule = RateSchedule { rate: 10.0, warmup: 20.0, curve };
+            assert!(schedule.offset(0) < 1e-9);
+            // 预热阶段的间隔逐条缩短，结束后恒为 1/rate
+            let gaps: Vec<f64> = (0..200).map(|n| schedule.offset(n + 1) - schedule.offset(n)).collect();
+            assert!(gaps.windows(2).all(|w| w[1] <= w[0] + 1e-9));
+            assert!((gaps[199] - 0.1).abs() < 1e-9);
+            let ramped = schedule.cumulative(20.0).ceil() as u64;
+            assert!((schedule.offset(ramped + 10) - schedule.offset(ramped) - 1.0).abs() < 1e-6);
+        }
+        let steady = RateSchedule { rate: 4.0, warmup: 0.0, curve: WarmupCurve::Linear };
+        assert_eq!(steady.offset(8), 2.0);
+    }
+
+    #[test]
+    fn send_intervals_separate_items_and_rounds() {
+        let intervals = SendIntervals { item: 2, round: 10 };
+        // 每轮3条：0s、2s、4s，下一轮从 4 + 10 = 14s 开始
+        let offsets: Vec<u64> = (0..6).map(|seq| intervals.offset(seq, 3)).collect();
+        assert_eq!(offsets, vec![0, 2, 4, 14, 16, 18]);
+        assert_eq!(intervals.round_span(3), 14);
+
+        // 两者都可以为0
+        let back_to_back = SendIntervals { item: 0, round: 5 };
+        assert_eq!((0..4).map(|seq| back_to_back.offset(seq, 2)).collect::<Vec<_>>(), vec![0, 0, 5, 5]);
+        assert_eq!(SendIntervals { item: 3, round: 0 }.offset(2, 2), 3);
+    }
+
+    #[test]
+    fn state_file_round_trips_and_rejects_mismatches() {
+        let path = env::temp_dir().join(format!("push_tb_state_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let data = [serde_json::json!({"a": {"v": 1}}), serde_json::json!({"b": {"v": 2}})];
+        let options = || BTreeMap::from([("seed".to_string(), Value::from(7)), ("count".to_string(), Value::from(10))]);
+        let _ = fs::remove_file(path);
+
+        let state_file = StateFile::new(path, &data, options());
+        assert!(state_file.load().unwrap().is_none());
+
+        // 随机数生成器从保存的位置继续，后续序列与不中断时相同
+        let mut rng = seeded_rng(Some(7), 0);
+        let _: Vec<u64> = (0..5).map(|_| rng.r#gen()).collect();
+        let expected: Vec<u64> = rng.clone().sample_iter(rand::distributions::Standard).take(3).collect();
+        let mut quota = SendQuota::new(CountPolicy::Successes, data.len());
+        quota.record(0, true);
+        let mut state_file = StateFile::new(path, &data, options());
+        let position = RunPosition { round: 3, next_index: 1, sequence: 5 };
+        state_file.save(position, &quota, &HashMap::from([(0, rng)]), &[], &[], false);
+
+        let state = StateFile::new(path, &data, options()).load().unwrap().unwrap();
+        assert_eq!((state.round, state.next_index, state.sequence, state.delivered.as_slice()), (3, 1, 5, &[1, 0][..]));
+        let mut restored = seeded_rng(Some(7), 0);
+        restored.set_word_pos(state.rngs[&0].parse().unwrap());
+        let resumed: Vec<u64> = restored.sample_iter(rand::distributions::Standard).take(3).collect();
+        assert_eq!(resumed, expected);
+
+        let changed_data = [serde_json::json!({"a": {"v": 1}})];
+        let error = StateFile::new(path, &changed_data, options()).load().unwrap_err().to_string();
+        assert_eq!(error, t!("state.data_changed", path));
+
+        let mut changed_options = options();
+        changed_options.insert("count".to_string(), Value::from(20));
+        let error = StateFile::new(path, &data, changed_options).load().unwrap_err().to_string();
+        assert_eq!(error, t!("state.option_changed", path, "count", 10, 20));
+
+        fs::write(path, "{\"version\": 1, \"round\": ").unwrap();
+        let error = StateFile::new(path, &data, options()).load().unwrap_err();
+        assert!(matches!(&error, PushError::ConfigInvalid(message) if message.starts_with(&t!("state.corrupt", path))));
+        fs::remove_file(path).ok();
+    }
+}
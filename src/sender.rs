@@ -0,0 +1,2347 @@
+//! 发送：负载构建、传输层、限流与重定向、签名、审计和并发发送工作者
+
+use reqwest::Client;
+use serde_json::Value;
+use std::borrow::Cow;
+use std::future::Future;
+use std::pin::Pin;
+use std::collections::HashSet;
+use sha2::{Sha256, Sha512};
+use std::fs;
+use std::path::Path;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use chrono::Local;
+use hmac::{Hmac, Mac};
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::{Level, info, warn};
+use tokio::time::Duration;
+use rand_chacha::ChaCha12Rng;
+use crate::error::{PushError, PushResult};
+use crate::config::{Config, LbStrategy, ServerPool};
+use crate::output::{HTTP_TARGET, ITEM_TARGET};
+use crate::schedule::unless_stopped;
+use crate::stats::Metrics;
+use crate::telemetry::{EmptyPolicy, FieldRules, PayloadShape, TelemetryData, extract_telemetry_values};
+
+/// `--auto-clock-adjust` 重新估计时钟偏移的周期，期间的样本合并成一次估计
+pub const CLOCK_ADJUST_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 单条数据因服务器限流累计等待的默认上限
+pub const DEFAULT_MAX_THROTTLE_WAIT: Duration = Duration::from_secs(300);
+
+/// 服务器限流（429）没有给出 Retry-After 时的首次等待，之后每次加倍
+pub const THROTTLE_BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// 没有 Retry-After 时单次等待的上限
+pub const THROTTLE_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// 单次限流等待的下限，避免 `Retry-After: 0` 时不间断地重试
+pub const THROTTLE_MIN_WAIT: Duration = Duration::from_millis(100);
+
+/// 发送遥测数据时的附加选项
+///
+/// 汇总会影响每条消息内容的运行参数，在整个运行期间（包括并发发送时）共享
+#[derive(Debug)]
+pub struct SendOptions {
+    /// 全局序号字段名称，None表示不注入序号
+    pub seq_field: Option<String>,
+    /// 全局单调递增的序号计数器，每条消息发送前自增
+    pub seq_counter: AtomicU64,
+    /// 提取结果为空的记录的处理策略
+    pub on_empty: EmptyPolicy,
+    /// 注入到遥测数据中的发送时间字段名，None表示不注入
+    pub time_field: Option<String>,
+    /// 请求体顶层发送时间字段名，None表示不输出
+    pub payload_time_key: Option<String>,
+    /// 遥测请求体结构
+    pub payload_shape: PayloadShape,
+    /// 请求签名配置，None表示不签名
+    pub signer: Option<RequestSigner>,
+    /// 演练模式：完整生成负载但不发出任何网络请求
+    pub dry_run: bool,
+    /// 发往属性通道的字段名（重命名后的名称），其余字段走遥测通道
+    pub attribute_fields: HashSet<String>,
+    /// 提取遥测值时的字段重命名和上报条件
+    pub field_rules: FieldRules,
+    /// 审计日志，记录每个实际发出的请求
+    pub audit: Option<AuditLog>,
+    /// 成功响应的展示配置，None表示成功时不读取响应体
+    pub show_response: Option<ResponseDisplay>,
+    /// 幂等键配置，None表示不生成幂等键
+    pub idempotency: Option<IdempotencyKeys>,
+    /// 遥测时间戳分配器，保证整个运行期间的 `ts` 严格递增
+    pub timestamps: TimestampAllocator,
+    /// 按服务器时间补偿时间戳，None表示直接使用本机时钟
+    pub clock_adjust: Option<ClockAdjuster>,
+    /// 服务器限流时的等待和重试
+    pub throttle: Throttle,
+    /// 设备API请求地址模板
+    pub url_template: UrlTemplate,
+}
+
+/// 遥测时间戳分配器
+///
+/// 间隔为0或并发较高时多条消息会落在同一毫秒，ThingsBoard对同一键同一时间戳只保留最后一个值。
+/// 分配器记录上次分配的时间戳，遇到相同或更早的时钟读数时顺延1毫秒，在并发发送之间共享
+#[derive(Debug)]
+pub struct TimestampAllocator {
+    /// 是否保证严格递增，false时直接使用系统时钟
+    enabled: bool,
+    /// 上次分配的时间戳（毫秒）
+    last: AtomicU64,
+    /// 被顺延过的时间戳个数
+    adjusted: AtomicU64,
+}
+
+impl TimestampAllocator {
+    /// 创建分配器
+    ///
+    /// # 参数
+    ///
+    /// * `enabled` - 是否保证严格递增，false时原样使用系统时钟
+    pub fn new(enabled: bool) -> Self {
+        TimestampAllocator {
+            enabled,
+            last: AtomicU64::new(0),
+            adjusted: AtomicU64::new(0),
+        }
+    }
+
+    /// 以给定的时钟读数分配时间戳：比上次分配的大时原样返回，否则返回上次分配值加1
+    ///
+    /// # 参数
+    ///
+    /// * `now_ms` - 当前时钟读数（毫秒）
+    fn allocate(&self, now_ms: u64) -> u64 {
+        if !self.enabled {
+            return now_ms;
+        }
+        let previous = self
+            .last
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(now_ms.max(last + 1)))
+            .unwrap_or_else(|last| last);
+        let ts = now_ms.max(previous + 1);
+        if ts != now_ms {
+            self.adjusted.fetch_add(1, Ordering::Relaxed);
+        }
+        ts
+    }
+
+    /// 被顺延过的时间戳个数
+    pub fn adjusted(&self) -> u64 {
+        self.adjusted.load(Ordering::Relaxed)
+    }
+}
+
+/// 服务器限流（HTTP 429）的处理状态，在所有并发发送之间共享
+///
+/// 收到429的发送按 `Retry-After` 等待后重发同一条数据，等待期间其他发送也暂停，避免继续加重限流；
+/// 单条数据累计等待超过上限后不再重试，按发送失败处理
+#[derive(Debug)]
+pub struct Throttle {
+    /// 单条数据因限流累计等待的上限，0表示不重试
+    max_wait: Duration,
+    /// 服务器要求暂停到的时刻，None表示没有暂停
+    paused_until: Mutex<Option<tokio::time::Instant>>,
+    /// 被限流的次数
+    events: AtomicU64,
+    /// 因限流累计等待的时间（毫秒），并发的等待分别累计
+    waited_ms: AtomicU64,
+    /// 停止信号，等待期间收到时放弃等待
+    pub stop: Option<watch::Receiver<bool>>,
+}
+
+impl Throttle {
+    /// 创建限流状态
+    ///
+    /// # 参数
+    ///
+    /// * `max_wait` - 单条数据因限流累计等待的上限
+    pub fn new(max_wait: Duration) -> Self {
+        Throttle {
+            max_wait,
+            paused_until: Mutex::new(None),
+            events: AtomicU64::new(0),
+            waited_ms: AtomicU64::new(0),
+            stop: None,
+        }
+    }
+
+    /// 计算收到429后的等待时间
+    ///
+    /// # 参数
+    ///
+    /// * `retry_after` - 服务器 `Retry-After` 头要求的等待时间
+    /// * `attempts` - 本条数据之前已经被限流的次数，决定没有 `Retry-After` 时的退避时间
+    /// * `waited` - 本条数据已经因限流等待的时间
+    ///
+    /// # 返回值
+    ///
+    /// * `Option<Duration>` - 本次等待时间，不超过剩余的等待额度；额度用完时返回None，不再重试
+    fn next_wait(&self, retry_after: Option<Duration>, attempts: u32, waited: Duration) -> Option<Duration> {
+        let remaining = self.max_wait.saturating_sub(waited);
+        if remaining.is_zero() {
+            return None;
+        }
+        let wait = retry_after
+            .unwrap_or_else(|| THROTTLE_BACKOFF_BASE.saturating_mul(1 << attempts.min(16)).min(THROTTLE_BACKOFF_MAX));
+        Some(wait.max(THROTTLE_MIN_WAIT).min(remaining))
+    }
+
+    /// 记录一次限流并暂停所有发送
+    ///
+    /// # 参数
+    ///
+    /// * `wait` - 暂停时长
+    ///
+    /// # 返回值
+    ///
+    /// * `bool` - 等待完成时为true，等待期间收到停止请求时为false
+    async fn pause(&self, wait: Duration) -> bool {
+        self.events.fetch_add(1, Ordering::Relaxed);
+        self.waited_ms.fetch_add(wait.as_millis() as u64, Ordering::Relaxed);
+        let until = tokio::time::Instant::now() + wait;
+        {
+            let mut paused_until = self.paused_until.lock().unwrap();
+            if paused_until.is_none_or(|current| current < until) {
+                *paused_until = Some(until);
+            }
+        }
+        self.sleep_until(until).await
+    }
+
+    /// 有其他发送触发的暂停时，等待暂停结束后再发送
+    async fn wait_paused(&self) {
+        let until = *self.paused_until.lock().unwrap();
+        if let Some(until) = until
+            && until > tokio::time::Instant::now()
+        {
+            self.sleep_until(until).await;
+        }
+    }
+
+    /// 等待到指定时刻，收到停止请求时提前返回false
+    async fn sleep_until(&self, until: tokio::time::Instant) -> bool {
+        match self.stop {
+            Some(ref stop) => unless_stopped(stop, tokio::time::sleep_until(until)).await.is_some(),
+            None => {
+                tokio::time::sleep_until(until).await;
+                true
+            }
+        }
+    }
+
+    /// 被限流的次数
+    pub fn events(&self) -> u64 {
+        self.events.load(Ordering::Relaxed)
+    }
+
+    /// 因限流累计等待的时间（毫秒）
+    pub fn waited_ms(&self) -> u64 {
+        self.waited_ms.load(Ordering::Relaxed)
+    }
+}
+
+/// 解析 `Retry-After` 响应头，支持秒数和HTTP日期两种写法
+///
+/// # 参数
+///
+/// * `value` - 响应头的值
+/// * `now` - 当前时间，HTTP日期早于该时间时返回0
+///
+/// # 返回值
+///
+/// * `Option<Duration>` - 要求等待的时间，格式无法识别时返回None
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some((date.with_timezone(&chrono::Utc) - now).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// 读取系统时钟的毫秒时间戳
+///
+/// # 错误
+///
+/// 系统时钟早于UNIX纪元时返回错误
+pub fn now_millis() -> PushResult<u64> {
+    Ok(SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| PushError::payload(format!("{}: {}", t!("send.clock_failed"), e)))?
+        .as_millis() as u64)
+}
+
+/// `--auto-clock-adjust` 的时钟偏移估计，在生成 `ts` 时补偿本机与服务器的时间差
+///
+/// 响应的 `Date` 头只精确到秒，单个样本只能确定偏移量落在
+/// `[Date - 收到响应时刻, Date + 1000 - 发出请求时刻]` 区间内。同一周期内的样本区间取交集逐步收窄，
+/// 周期结束时取交集中点作为新的偏移量；交集为空说明本机时钟发生了跳变或漂移，从当前样本重新开始
+#[derive(Debug)]
+pub struct ClockAdjuster {
+    /// 当前补偿到时间戳上的偏移量（毫秒，服务器时间减本机时间）
+    offset_ms: AtomicI64,
+    /// 当前测量周期
+    window: Mutex<OffsetWindow>,
+}
+
+/// 一个测量周期内的样本
+#[derive(Debug)]
+pub struct OffsetWindow {
+    /// 周期开始时刻
+    started: Instant,
+    /// 样本区间的交集（毫秒），还没有样本时为None
+    bounds: Option<(i64, i64)>,
+    /// 是否已经得出过偏移量，第一个样本立即生效，不等待周期结束
+    measured: bool,
+}
+
+impl Default for ClockAdjuster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClockAdjuster {
+    /// 创建偏移量为0的估计器
+    pub fn new() -> Self {
+        ClockAdjuster {
+            offset_ms: AtomicI64::new(0),
+            window: Mutex::new(OffsetWindow {
+                started: Instant::now(),
+                bounds: None,
+                measured: false,
+            }),
+        }
+    }
+
+    /// 当前补偿的偏移量（毫秒）
+    pub fn offset_ms(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// 记录一个偏移量样本
+    ///
+    /// # 参数
+    ///
+    /// * `low` - 偏移量下界（毫秒）
+    /// * `high` - 偏移量上界（毫秒）
+    /// * `now` - 样本的测量时刻
+    ///
+    /// # 返回值
+    ///
+    /// * `Option<(i64, i64)>` - 本次样本使偏移量更新时返回更新前后的偏移量，否则返回None
+    fn observe(&self, low: i64, high: i64, now: Instant) -> Option<(i64, i64)> {
+        let mut window = self.window.lock().unwrap();
+        window.bounds = match window.bounds {
+            Some((lo, hi)) if lo.max(low) <= hi.min(high) => Some((lo.max(low), hi.min(high))),
+            _ => Some((low, high)),
+        };
+        if window.measured && now.duration_since(window.started) < CLOCK_ADJUST_INTERVAL {
+            return None;
+        }
+        let (lo, hi) = window.bounds.take()?;
+        window.started = now;
+        window.measured = true;
+        let offset = lo + (hi - lo) / 2;
+        Some((self.offset_ms.swap(offset, Ordering::Relaxed), offset))
+    }
+}
+
+/// `--show-response` 的展示配置
+#[derive(Debug)]
+pub struct ResponseDisplay {
+    /// 要展示的响应头名称（小写）
+    pub headers: Vec<String>,
+    /// 响应体最多展示的字符数
+    pub max_len: usize,
+}
+
+impl ResponseDisplay {
+    /// 截断过长的响应体，超出部分用省略说明代替
+    fn truncate<'a>(&self, body: &'a str) -> Cow<'a, str> {
+        match body.char_indices().nth(self.max_len) {
+            Some((end, _)) => Cow::Owned(format!(
+                "{}{}",
+                &body[..end],
+                t!("response.truncated", body.chars().count() - self.max_len)
+            )),
+            None => Cow::Borrowed(body),
+        }
+    }
+}
+
+/// 幂等键的生成方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdempotencyMode {
+    /// 设备令牌、数据项序号、投递序号和原始记录内容的SHA-256摘要
+    Hash,
+    /// `数据项序号-投递序号`，只在同一设备内唯一
+    Seq,
+}
+
+/// `--idempotency-key` 的配置
+///
+/// 键只由数据项和它的投递序号（该数据项第几次成功投递）决定：发送失败后重发同一条数据时
+/// 投递序号不变，键也不变，网关或服务端据此去重；成功之后下一次发送才换成新的键
+#[derive(Debug, Clone)]
+pub struct IdempotencyKeys {
+    /// 生成方式
+    pub mode: IdempotencyMode,
+    /// 携带幂等键的请求头名称
+    pub header: String,
+    /// 同时写入遥测数据的字段名，None表示只放在请求头中
+    pub field: Option<String>,
+    /// 参与生成的盐值，默认为空，使中断后重新运行得到相同的键
+    pub salt: String,
+}
+
+impl IdempotencyKeys {
+    /// 生成一条数据的幂等键
+    ///
+    /// # 参数
+    ///
+    /// * `token` - 设备访问令牌
+    /// * `index` - 数据项序号（从0开始）
+    /// * `delivery` - 投递序号（从1开始）
+    /// * `record` - 数据文件中的原始记录（随机修改和注入之前）
+    pub fn key(&self, token: &str, index: usize, delivery: u64, record: &Value) -> String {
+        match self.mode {
+            IdempotencyMode::Seq if self.salt.is_empty() => format!("{}-{}", index + 1, delivery),
+            IdempotencyMode::Seq => format!("{}-{}-{}", self.salt, index + 1, delivery),
+            IdempotencyMode::Hash => {
+                use sha2::Digest;
+                // serde_json的对象按键排序序列化，同一内容总是得到同样的字节
+                let mut hasher = Sha256::new();
+                for part in [self.salt.as_str(), token, &index.to_string(), &delivery.to_string()] {
+                    hasher.update(part.as_bytes());
+                    hasher.update([0]);
+                }
+                hasher.update(record.to_string().as_bytes());
+                hex::encode(&hasher.finalize()[..16])
+            }
+        }
+    }
+}
+
+/// 审计文件的轮转策略
+#[derive(Debug, Clone, Default)]
+pub struct AuditRotation {
+    /// 单个文件的最大字节数，写入下一条会超出时先轮转；None表示不按大小轮转
+    pub max_size: Option<u64>,
+    /// 保留的已轮转文件个数，超出时删除最旧的
+    pub max_files: usize,
+    /// 日期变化时轮转
+    pub daily: bool,
+}
+
+/// 审计文件的写入状态
+#[derive(Debug)]
+pub struct AuditState {
+    /// 当前文件的写入器
+    writer: std::io::BufWriter<fs::File>,
+    /// 当前文件已有的字节数
+    size: u64,
+    /// 当前文件开始写入的日期
+    opened_on: chrono::NaiveDate,
+    /// 本次运行中轮转出的文件（已被清理的不再列出）
+    rotated: Vec<String>,
+    /// 上一次轮转使用的时间戳和序号，同一秒内多次轮转时序号递增
+    last_stamp: Option<(String, u32)>,
+}
+
+/// 审计日志
+///
+/// 以JSON Lines格式追加记录每个实际发出的请求（成功或失败），
+/// 每条记录写入后立即刷新，进程崩溃时最多丢失正在写入的一条。
+/// 配置了轮转时，在两条记录之间把当前文件改名为带时间戳后缀的文件，
+/// 每条记录完整地落在某一个文件中
+#[derive(Debug)]
+pub struct AuditLog {
+    /// 审计文件路径
+    path: String,
+    /// 轮转策略
+    rotation: AuditRotation,
+    /// 写入状态，轮转时整体替换
+    state: Mutex<AuditState>,
+}
+
+impl AuditLog {
+    /// 打开（或创建）审计文件，在发送任何数据之前暴露权限等问题
+    ///
+    /// # 参数
+    ///
+    /// * `path` - 审计文件路径，已存在时追加写入
+    /// * `rotation` - 轮转策略
+    ///
+    /// # 错误
+    ///
+    /// 当文件无法创建或打开时返回错误
+    pub fn open(path: &str, rotation: AuditRotation) -> PushResult<Self> {
+        let file = Self::open_file(path)?;
+        let size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+        Ok(AuditLog {
+            path: path.to_string(),
+            rotation,
+            state: Mutex::new(AuditState {
+                writer: std::io::BufWriter::new(file),
+                size,
+                opened_on: Local::now().date_naive(),
+                rotated: Vec::new(),
+                last_stamp: None,
+            }),
+        })
+    }
+
+    /// 以追加方式打开审计文件
+    fn open_file(path: &str) -> PushResult<fs::File> {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|source| PushError::Io { message: t!("audit.open_failed", path), source })
+    }
+
+    /// 追加一条审计记录
+    ///
+    /// 整条记录先序列化再一次写入，需要轮转时在写入之前完成。写入或轮转失败只输出警告，不中断发送
+    fn record(&self, entry: &Value) {
+        let mut line = match serde_json::to_vec(entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!("{}", t!("audit.write_failed", self.path, e));
+                return;
+            }
+        };
+        line.push(b'\n');
+
+        let mut state = self.state.lock().unwrap();
+        let today = Local::now().date_naive();
+        let oversized = self
+            .rotation
+            .max_size
+            .is_some_and(|max| state.size > 0 && state.size + line.len() as u64 > max);
+        let new_day = self.rotation.daily && today != state.opened_on;
+        if (oversized || new_day)
+            && let Err(e) = self.rotate(&mut state, today)
+        {
+            warn!("{}", t!("audit.rotate_failed", self.path, e.detail()));
+        }
+
+        let result = state.writer.write_all(&line).and_then(|_| state.writer.flush());
+        match result {
+            Ok(()) => state.size += line.len() as u64,
+            Err(e) => warn!("{}", t!("audit.write_failed", self.path, e)),
+        }
+    }
+
+    /// 把当前文件改名为 `<路径>.<YYYYMMDD-HHMMSS>` 并重新打开，随后清理超出保留个数的旧文件
+    fn rotate(&self, state: &mut AuditState, today: chrono::NaiveDate) -> PushResult<()> {
+        let io_failed = |source| PushError::Io { message: self.path.clone(), source };
+        state.writer.flush().map_err(io_failed)?;
+        let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        // 同一秒内多次轮转时追加递增的序号，避免覆盖，也不会复用已被清理的序号
+        let mut seq = match &state.last_stamp {
+            Some((last, seq)) if *last == stamp => seq + 1,
+            _ => 0,
+        };
+        let name = |seq: u32| match seq {
+            0 => format!("{}.{}", self.path, stamp),
+            _ => format!("{}.{}-{}", self.path, stamp, seq),
+        };
+        while Path::new(&name(seq)).exists() {
+            seq += 1;
+        }
+        let rotated_path = name(seq);
+        state.last_stamp = Some((stamp, seq));
+        fs::rename(&self.path, &rotated_path)
+            .map_err(|source| PushError::Io { message: t!("audit.rename_failed", rotated_path), source })?;
+        state.writer = std::io::BufWriter::new(Self::open_file(&self.path)?);
+        state.size = 0;
+        state.opened_on = today;
+        state.rotated.push(rotated_path);
+
+        for removed in self.prune()? {
+            state.rotated.retain(|path| *path != removed);
+        }
+        Ok(())
+    }
+
+    /// 删除超出保留个数的已轮转文件（按文件名中的时间戳从旧到新），返回被删除的路径
+    fn prune(&self) -> PushResult<Vec<String>> {
+        let active = Path::new(&self.path);
+        let dir = match active.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let prefix = format!("{}.", active.file_name().and_then(|name| name.to_str()).unwrap_or_default());
+        let mut rotated: Vec<(String, String)> = fs::read_dir(dir)
+            .map_err(|source| PushError::Io { message: dir.display().to_string(), source })?
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let stamp = name.strip_prefix(&prefix)?;
+                let is_rotated = stamp.len() >= 15
+                    && stamp.as_bytes()[8] == b'-'
+                    && stamp.chars().all(|c| c.is_ascii_digit() || c == '-');
+                is_rotated.then(|| (stamp.to_string(), format!("{}.{}", self.path, stamp)))
+            })
+            .collect();
+        // 同一秒的序号后缀按数值排序，保证 -10 排在 -9 之后
+        rotated.sort_by_key(|(stamp, _)| {
+            let (time, seq) = stamp.split_at(15);
+            (time.to_string(), seq.trim_start_matches('-').parse::<u64>().unwrap_or(0))
+        });
+        let excess = rotated.len().saturating_sub(self.rotation.max_files);
+        let mut removed = Vec::new();
+        for (_, path) in rotated.into_iter().take(excess) {
+            fs::remove_file(&path)
+                .map_err(|source| PushError::Io { message: t!("audit.remove_failed", path), source })?;
+            removed.push(path);
+        }
+        Ok(removed)
+    }
+
+    /// 本次运行写入过的审计文件：轮转出的文件（从旧到新）以及当前文件
+    pub fn files(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap();
+        state.rotated.iter().cloned().chain([self.path.clone()]).collect()
+    }
+}
+
+/// 响应头的值，不是可见ASCII文本时显示占位符
+fn header_text(value: &reqwest::header::HeaderValue) -> String {
+    value.to_str().map_or_else(|_| t!("http.non_text"), str::to_string)
+}
+
+/// 解析带单位的文件大小，如 `50MB`、`512KB`、`1GiB`、`4096`，单位按1024进制，不区分大小写
+pub fn parse_size(text: &str) -> PushResult<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|e| PushError::ConfigInvalid(t!("arg.size_invalid", text, e)))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        other => {
+            return Err(PushError::ConfigInvalid(t!("arg.size_unit_unknown", other)));
+        }
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// 请求签名使用的HMAC摘要算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignAlgorithm {
+    /// HMAC-SHA256（默认）
+    Sha256,
+    /// HMAC-SHA512
+    Sha512,
+}
+
+/// 请求签名配置
+///
+/// 部分自建鉴权网关要求对请求体做HMAC签名并放在请求头中，
+/// 签名始终基于实际发送的请求体字节计算
+#[derive(Clone)]
+pub struct RequestSigner {
+    /// 签名密钥
+    pub key: Vec<u8>,
+    /// 写入签名的请求头名称
+    pub header: String,
+    /// 摘要算法
+    pub algorithm: SignAlgorithm,
+    /// 写入时间戳的请求头名称，设置后签名内容为 请求体+时间戳
+    pub timestamp_header: Option<String>,
+}
+
+impl std::fmt::Debug for RequestSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 不输出密钥本身
+        f.debug_struct("RequestSigner")
+            .field("header", &self.header)
+            .field("algorithm", &self.algorithm)
+            .field("timestamp_header", &self.timestamp_header)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RequestSigner {
+    /// 计算请求签名
+    ///
+    /// # 参数
+    ///
+    /// * `body` - 实际发送的请求体字节
+    /// * `timestamp` - 参与签名的时间戳，拼接在请求体之后；None表示只签名请求体
+    ///
+    /// # 返回值
+    ///
+    /// * `String` - 小写十六进制编码的签名
+    fn sign(&self, body: &[u8], timestamp: Option<&str>) -> String {
+        fn digest<M: Mac + hmac::digest::KeyInit>(key: &[u8], body: &[u8], timestamp: Option<&str>) -> String {
+            // HMAC支持任意长度的密钥
+            let mut mac = <M as Mac>::new_from_slice(key).unwrap();
+            mac.update(body);
+            if let Some(timestamp) = timestamp {
+                mac.update(timestamp.as_bytes());
+            }
+            hex::encode(mac.finalize().into_bytes())
+        }
+
+        match self.algorithm {
+            SignAlgorithm::Sha256 => digest::<Hmac<Sha256>>(&self.key, body, timestamp),
+            SignAlgorithm::Sha512 => digest::<Hmac<Sha512>>(&self.key, body, timestamp),
+        }
+    }
+}
+
+impl Default for SendOptions {
+    fn default() -> Self {
+        SendOptions {
+            seq_field: None,
+            seq_counter: AtomicU64::new(0),
+            on_empty: EmptyPolicy::default(),
+            time_field: Some("send_time".to_string()),
+            payload_time_key: Some("time".to_string()),
+            payload_shape: PayloadShape::Legacy,
+            signer: None,
+            dry_run: false,
+            attribute_fields: HashSet::new(),
+            field_rules: FieldRules::default(),
+            audit: None,
+            show_response: None,
+            idempotency: None,
+            timestamps: TimestampAllocator::new(true),
+            clock_adjust: None,
+            throttle: Throttle::new(DEFAULT_MAX_THROTTLE_WAIT),
+            url_template: UrlTemplate::default(),
+        }
+    }
+}
+
+impl SendOptions {
+    /// 分配下一个全局序号（从1开始），保证并发下不重复
+    fn next_seq(&self) -> u64 {
+        self.seq_counter.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// 分配下一个遥测时间戳：本机时钟加上估计的服务器时间偏移，再保证严格递增
+    ///
+    /// # 错误
+    ///
+    /// 系统时钟早于UNIX纪元时返回错误
+    fn next_timestamp(&self) -> PushResult<u64> {
+        let now_ms = now_millis()?;
+        let offset_ms = self.clock_adjust.as_ref().map_or(0, ClockAdjuster::offset_ms);
+        Ok(self.timestamps.allocate(now_ms.saturating_add_signed(offset_ms)))
+    }
+}
+
+/// 传输层一次成功投递的结果，与具体的传输方式无关，统计只依赖这些字段
+#[derive(Debug, Clone, Default)]
+pub struct SendOutcome {
+    /// 响应状态码，演练模式下为0；负载包含多条消息时为最后一条的状态码
+    pub status_code: u16,
+    /// 消息体字节数之和
+    pub bytes: usize,
+    /// 服务器时间与本地发出请求时间之差（毫秒），传输无法得知服务器时间时为None
+    pub server_offset_ms: Option<i64>,
+    /// 各条消息从发出到收到响应头的耗时之和
+    pub headers_latency: Duration,
+}
+
+/// 一次成功发送的结果
+#[derive(Debug)]
+pub struct SendReport {
+    /// 实际发送的遥测数据
+    pub telemetry: TelemetryData,
+    /// 响应状态码
+    pub status_code: u16,
+    /// 请求体字节数
+    pub bytes: usize,
+    /// 服务器时间（响应 `Date` 头）与本地发出请求时间之差（毫秒），没有 `Date` 头时为None
+    pub server_offset_ms: Option<i64>,
+    /// 各请求从发出到收到响应头的耗时之和，不含读取响应体
+    pub headers_latency: Duration,
+    /// 请求携带的数据点数，合并时序模式下 `telemetry` 为其中最后一个数据点
+    pub points: u64,
+}
+
+impl SendReport {
+    /// 由实际发送的遥测数据和传输层的投递结果组成发送结果
+    fn new(telemetry: TelemetryData, outcome: SendOutcome, points: u64) -> Self {
+        SendReport {
+            telemetry,
+            status_code: outcome.status_code,
+            bytes: outcome.bytes,
+            server_offset_ms: outcome.server_offset_ms,
+            headers_latency: outcome.headers_latency,
+            points,
+        }
+    }
+}
+
+/// 响应体为空的错误响应中用于诊断的响应头，可以看出响应来自网关、代理还是CDN
+pub const DIAGNOSTIC_HEADERS: &[&str] = &[
+    "server",
+    "via",
+    "x-cache",
+    "x-served-by",
+    "x-request-id",
+    "x-amzn-errortype",
+    "cf-ray",
+    "content-type",
+    "retry-after",
+];
+
+/// 服务器返回非成功状态码时的错误
+#[derive(Debug)]
+pub struct HttpStatusError {
+    /// 响应状态码
+    pub status: reqwest::StatusCode,
+    /// 响应体
+    body: String,
+    /// 响应中存在的诊断响应头（`名称: 值`），见 [`DIAGNOSTIC_HEADERS`]
+    headers: Vec<String>,
+    /// 未跟随的重定向目标（`Location` 头，其中的设备令牌已脱敏）
+    location: Option<String>,
+    /// 请求地址，其中的设备令牌已脱敏
+    pub url: String,
+}
+
+impl HttpStatusError {
+    /// 是否为认证类失败：401、403，以及ThingsBoard对无效令牌返回的404
+    ///
+    /// ThingsBoard设备API找不到令牌对应的设备时返回404，响应体为空或提到token；
+    /// 其他404（如路径写错被网关拦截）通常带有不相关的页面内容，不视为认证失败
+    pub fn is_auth_failure(&self) -> bool {
+        match self.status.as_u16() {
+            401 | 403 => true,
+            404 => {
+                let body = self.body.trim().to_lowercase();
+                body.is_empty() || body.contains("token") || body.contains("credentials")
+            }
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Display for HttpStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // 301/302/303会把POST改成GET并丢弃请求体，不跟随，提示直接使用新地址
+        if matches!(self.status.as_u16(), 301..=303) {
+            let location = self.location.as_deref().unwrap_or("-");
+            let server = location.find("/api/v1/").map_or(location, |end| &location[..end]);
+            return write!(f, "{}", t!("send.http_redirect_method", self.status, location, server));
+        }
+        // 不跟随重定向时3xx需要用户确认服务器地址，重定向目标比响应体更有用
+        if self.status.is_redirection() {
+            return write!(f, "{}", t!("send.http_redirect", self.status, self.location.as_deref().unwrap_or("-")));
+        }
+        // 中间件出错时常返回空响应体，补充说明和响应头，避免错误信息只剩状态码
+        if !self.body.trim().is_empty() {
+            return write!(f, "{}", t!("send.http_status", self.status, self.body));
+        }
+        write!(f, "{}", t!("send.http_status_empty", self.status))?;
+        if !self.headers.is_empty() {
+            write!(f, "{}", t!("send.http_status_headers", self.headers.join(", ")))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for HttpStatusError {}
+
+/// HTTP连接和TCP参数（`--tcp-nodelay`、`--tcp-keepalive`、`--pool-idle-timeout`、`--pool-max-idle`）
+///
+/// 默认值与reqwest相同：开启TCP_NODELAY、不设置SO_KEEPALIVE、空闲连接保留90秒、空闲连接数不限
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionTuning {
+    /// 是否开启TCP_NODELAY（禁用Nagle算法），小的遥测请求不必等待合并即可发出
+    pub nodelay: bool,
+    /// SO_KEEPALIVE探测前的空闲时间，None表示不设置
+    pub keepalive: Option<Duration>,
+    /// 空闲的keep-alive连接在连接池中保留的时间，None表示一直保留
+    pub pool_idle_timeout: Option<Duration>,
+    /// 每个服务器最多保留的空闲连接数，0表示不复用连接，每个请求都新建连接
+    pub pool_max_idle: usize,
+}
+
+impl Default for ConnectionTuning {
+    fn default() -> Self {
+        ConnectionTuning {
+            nodelay: true,
+            keepalive: None,
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            pool_max_idle: usize::MAX,
+        }
+    }
+}
+
+impl ConnectionTuning {
+    /// 把连接参数应用到HTTP客户端构建器
+    pub fn apply(&self, builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+        builder
+            .tcp_nodelay(self.nodelay)
+            .tcp_keepalive(self.keepalive)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .pool_max_idle_per_host(self.pool_max_idle)
+    }
+}
+
+/// 发送请求使用的重定向策略
+///
+/// 只跟随保留请求方法和请求体的307/308，每一跳在 `-v` 的HTTP日志中输出；
+/// 301/302/303会让POST变成不带数据的GET，停止跟随并把3xx响应交给调用方按发送失败处理
+///
+/// # 参数
+///
+/// * `max_redirects` - 最多跟随的次数，0表示任何重定向都不跟随
+pub fn redirect_policy(max_redirects: usize) -> reqwest::redirect::Policy {
+    if max_redirects == 0 {
+        return reqwest::redirect::Policy::none();
+    }
+    reqwest::redirect::Policy::custom(move |attempt| {
+        let status = attempt.status();
+        if !matches!(status.as_u16(), 307 | 308) {
+            return attempt.stop();
+        }
+        if attempt.previous().len() > max_redirects {
+            return attempt.error(t!("send.too_many_redirects", max_redirects));
+        }
+        let from = attempt.previous().last().map(|url| redact_url_tokens(url.as_str())).unwrap_or_default();
+        http_debug!("↪️ {} {} -> {}", status, from, redact_url_tokens(attempt.url().as_str()));
+        attempt.follow()
+    })
+}
+
+/// 交给传输层的一条消息
+#[derive(Debug, Clone)]
+pub struct ChannelMessage {
+    /// ThingsBoard设备API的通道：`telemetry` 或 `attributes`
+    pub channel: &'static str,
+    /// 已序列化的JSON消息体，调试输出和实际发送使用同一份字节
+    pub body: Vec<u8>,
+    /// 本条消息的幂等键
+    pub idempotency_key: Option<String>,
+}
+
+/// 一次发送交给传输层的负载，其中的消息按顺序发往同一个服务器
+#[derive(Debug, Clone)]
+pub struct Payload {
+    /// 负载的时间戳（毫秒），用于请求签名
+    pub timestamp: u64,
+    /// 要投递的消息，记录同时有属性和遥测时属性在前
+    pub messages: Vec<ChannelMessage>,
+}
+
+/// 传输层方法返回的Future，trait对象上的异步方法需要装箱
+pub type TransportFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// 发送传输层：把构建好的负载投递给ThingsBoard
+///
+/// 发送循环、并发工作者和统计只依赖这个trait。负载的构建（随机修改、属性拆分、时间和序号注入）
+/// 与传输方式无关，由 [`send_telemetry`] 和 [`send_timeseries_batch`] 完成；
+/// 目前的实现是HTTP设备API（[`HttpSender`]）和不访问网络的 [`MockSender`]
+pub trait Sender: Send + Sync {
+    /// 用设备令牌投递一个负载
+    ///
+    /// # 错误
+    ///
+    /// 任意一条消息投递失败时返回错误，服务器拒绝时为 [`PushError::HttpStatus`] 或 [`PushError::Auth`]
+    fn send<'a>(&'a self, token: &'a str, payload: Payload) -> TransportFuture<'a, PushResult<SendOutcome>>;
+
+    /// 结束发送，释放连接等资源，运行结束时调用一次
+    fn shutdown(&self) -> TransportFuture<'_, ()> {
+        Box::pin(async {})
+    }
+}
+
+/// 通过ThingsBoard设备HTTP API发送
+pub struct HttpSender {
+    /// HTTP客户端
+    client: Client,
+    /// 负载均衡服务器池
+    servers: Arc<ServerPool>,
+    /// 发送选项，决定地址模板、签名、限流和审计等
+    options: Arc<SendOptions>,
+}
+
+impl HttpSender {
+    /// 创建HTTP传输
+    ///
+    /// # 参数
+    ///
+    /// * `client` - HTTP客户端实例
+    /// * `servers` - 负载均衡服务器池
+    /// * `options` - 发送选项
+    pub fn new(client: Client, servers: Arc<ServerPool>, options: Arc<SendOptions>) -> Self {
+        HttpSender { client, servers, options }
+    }
+}
+
+impl Sender for HttpSender {
+    fn send<'a>(&'a self, token: &'a str, payload: Payload) -> TransportFuture<'a, PushResult<SendOutcome>> {
+        Box::pin(async move {
+            // 同一条记录的属性和遥测发往同一个服务器
+            let server = self.servers.pick();
+            let mut outcome = SendOutcome::default();
+            for message in payload.messages {
+                outcome.bytes += message.body.len();
+                let latency;
+                (outcome.status_code, outcome.server_offset_ms, latency) = post_channel(
+                    &self.client,
+                    &self.servers,
+                    &server,
+                    &self.options,
+                    token,
+                    message.channel,
+                    message.body,
+                    payload.timestamp,
+                    message.idempotency_key.as_deref(),
+                )
+                .await?;
+                outcome.headers_latency += latency;
+            }
+            Ok(outcome)
+        })
+    }
+}
+
+/// [`MockSender`] 收到的一条消息
+#[derive(Debug, Clone)]
+pub struct MockMessage {
+    /// 设备令牌
+    pub token: String,
+    /// 通道
+    pub channel: &'static str,
+    /// 解析后的消息体
+    pub body: Value,
+    /// 收到的时刻
+    pub received_at: Instant,
+}
+
+/// 不访问网络的传输，记录收到的每条消息，用于在测试中检查发送循环的计数、间隔和随机修改
+#[derive(Debug, Default)]
+pub struct MockSender {
+    /// 每隔多少次发送失败一次，0表示总是成功
+    fail_every: u64,
+    /// 失败时返回的状态码，为None时返回500
+    fail_status: Option<reqwest::StatusCode>,
+    /// 每次发送前依次等待的时长，为空时立即完成
+    delays: Vec<Duration>,
+    /// 已收到的发送次数
+    calls: AtomicU64,
+    /// 正在进行的发送数
+    in_flight: AtomicU64,
+    /// 同时进行的发送数的最大值
+    max_in_flight: AtomicU64,
+    /// 收到的消息
+    messages: Mutex<Vec<MockMessage>>,
+    /// 是否已经调用过 [`Sender::shutdown`]
+    shut_down: AtomicBool,
+}
+
+impl MockSender {
+    /// 创建总是成功的模拟传输
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 创建每第 `n` 次发送返回500的模拟传输
+    pub fn failing_every(n: u64) -> Self {
+        MockSender { fail_every: n, ..Self::default() }
+    }
+
+    /// 失败的发送返回 `status`（默认500），401、403等状态码按认证失败处理
+    pub fn with_fail_status(mut self, status: reqwest::StatusCode) -> Self {
+        self.fail_status = Some(status);
+        self
+    }
+
+    /// 模拟慢速传输：第n次发送先等待 `delays[(n - 1) % delays.len()]` 再完成
+    pub fn with_delays(mut self, delays: &[Duration]) -> Self {
+        self.delays = delays.to_vec();
+        self
+    }
+
+    /// 到目前为止收到的发送次数，包括失败的发送
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// 同时进行的发送数的最大值
+    pub fn max_in_flight(&self) -> u64 {
+        self.max_in_flight.load(Ordering::Relaxed)
+    }
+
+    /// 到目前为止收到的消息，失败的发送不记录
+    pub fn messages(&self) -> Vec<MockMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// 是否已经结束
+    pub fn is_shut_down(&self) -> bool {
+        self.shut_down.load(Ordering::Relaxed)
+    }
+}
+
+impl Sender for MockSender {
+    fn send<'a>(&'a self, token: &'a str, payload: Payload) -> TransportFuture<'a, PushResult<SendOutcome>> {
+        Box::pin(async move {
+            let call = self.calls.fetch_add(1, Ordering::Relaxed) + 1;
+            let in_flight = self.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+            if !self.delays.is_empty() {
+                tokio::time::sleep(self.delays[(call - 1) as usize % self.delays.len()]).await;
+            }
+            self.in_flight.fetch_sub(1, Ordering::Relaxed);
+            let channel = payload.messages.last().map_or("telemetry", |message| message.channel);
+            if self.fail_every > 0 && call.is_multiple_of(self.fail_every) {
+                return Err(PushError::from_status(HttpStatusError {
+                    status: self.fail_status.unwrap_or(reqwest::StatusCode::INTERNAL_SERVER_ERROR),
+                    body: String::new(),
+                    headers: Vec::new(),
+                    location: None,
+                    url: format!("mock://{}/{}", mask_token(token), channel),
+                }));
+            }
+            let mut outcome = SendOutcome { status_code: 200, ..SendOutcome::default() };
+            let mut messages = self.messages.lock().unwrap();
+            for message in payload.messages {
+                outcome.bytes += message.body.len();
+                messages.push(MockMessage {
+                    token: token.to_string(),
+                    channel: message.channel,
+                    body: serde_json::from_slice(&message.body)
+                        .map_err(|e| PushError::payload(t!("send.payload_reparse_failed", e)))?,
+                    received_at: Instant::now(),
+                });
+            }
+            Ok(outcome)
+        })
+    }
+
+    fn shutdown(&self) -> TransportFuture<'_, ()> {
+        self.shut_down.store(true, Ordering::Relaxed);
+        Box::pin(async {})
+    }
+}
+
+/// 交给发送工作者的一条发送任务
+pub struct SendJob {
+    /// 本次发送使用的设备访问令牌
+    pub token: String,
+    /// 要发送的数据项，已应用轨迹、注入和字段分组
+    pub item: Value,
+    /// 随机键名称，被注入的字段本次不随机
+    pub random_key: Option<String>,
+    /// 本条数据的幂等键
+    pub idempotency_key: Option<String>,
+    /// 本条数据使用的随机数生成器，派发时从设备的随机序列派生，指定种子时结果仍可复现
+    pub rng: ChaCha12Rng,
+    /// 回传发送结果和发送耗时（不含排队时间）
+    pub reply: oneshot::Sender<(PushResult<Option<SendReport>>, Duration)>,
+}
+
+/// 已派发、等待结果的一条发送，结果按派发顺序处理
+pub struct PendingSend {
+    /// 数据项下标
+    pub index: usize,
+    /// 令牌池中使用的令牌下标
+    pub token_index: Option<usize>,
+    /// 打码后的设备令牌，用于日志
+    pub device: String,
+    /// 字段分组都未到期，本次没有发送
+    pub nothing_due: bool,
+    /// 本条在本轮中已经重新入队的次数，第一次发送时为0
+    pub requeued: u64,
+    /// 发送结果和耗时
+    pub outcome: oneshot::Receiver<(PushResult<Option<SendReport>>, Duration)>,
+}
+
+/// 并发发送工作者的任务队列
+pub struct SendWorkers {
+    /// 任务队列的发送端
+    jobs: mpsc::Sender<SendJob>,
+    /// 是否已经中止
+    aborted: Arc<AtomicBool>,
+}
+
+impl SendWorkers {
+    /// 把一条任务放入队列，队列满时等待
+    ///
+    /// # 错误
+    ///
+    /// 工作者已经全部退出时原样返回该任务
+    pub async fn send(&self, job: SendJob) -> Result<(), mpsc::error::SendError<SendJob>> {
+        self.jobs.send(job).await
+    }
+
+    /// 中止发送：关闭任务队列，工作者丢弃队列中尚未开始的任务而不发送，正在进行的请求照常完成
+    pub fn abort(self) {
+        self.aborted.store(true, Ordering::Release);
+    }
+}
+
+/// 启动并发发送的工作者
+///
+/// 工作者共用一个容量为 `queue_size` 的有界队列，队列满时派发方的 `send` 等待，
+/// 形成背压，积压的数据最多为 队列容量 + 工作者数
+///
+/// # 参数
+///
+/// * `concurrency` - 工作者数，即同时进行的请求数
+/// * `queue_size` - 队列容量
+/// * `sender` - 传输层
+/// * `options` - 影响消息内容的发送选项
+/// * `metrics` - Prometheus指标，工作者维护进行中的请求数
+///
+/// # 返回值
+///
+/// * `SendWorkers` - 工作者的任务队列，丢弃或中止后工作者在队列清空时退出
+pub fn spawn_send_workers(
+    concurrency: usize,
+    queue_size: usize,
+    sender: &Arc<dyn Sender>,
+    options: &Arc<SendOptions>,
+    metrics: &Arc<Metrics>,
+) -> SendWorkers {
+    let (jobs, receiver) = mpsc::channel::<SendJob>(queue_size);
+    let receiver = Arc::new(tokio::sync::Mutex::new(receiver));
+    let aborted = Arc::new(AtomicBool::new(false));
+    for _ in 0..concurrency {
+        let receiver = Arc::clone(&receiver);
+        let aborted = Arc::clone(&aborted);
+        let sender = Arc::clone(sender);
+        let options = Arc::clone(options);
+        let metrics = Arc::clone(metrics);
+        tokio::spawn(async move {
+            loop {
+                // 只在取任务时持有锁，发送期间其它工作者可以继续取任务
+                let job = {
+                    let mut receiver = receiver.lock().await;
+                    if aborted.load(Ordering::Acquire) {
+                        receiver.close();
+                    }
+                    receiver.recv().await
+                };
+                let Some(mut job) = job else {
+                    break;
+                };
+                // 中止后只清空队列：丢弃任务会关闭它的结果通道，派发方据此知道这条没有发送
+                if aborted.load(Ordering::Acquire) {
+                    continue;
+                }
+                let started = Instant::now();
+                metrics.in_flight.fetch_add(1, Ordering::Relaxed);
+                let result = send_telemetry(
+                    sender.as_ref(),
+                    &job.token,
+                    &options,
+                    &job.item,
+                    &job.random_key,
+                    job.idempotency_key.as_deref(),
+                    &mut job.rng,
+                )
+                .await;
+                metrics.in_flight.fetch_sub(1, Ordering::Relaxed);
+                let _ = job.reply.send((result, started.elapsed()));
+            }
+        });
+    }
+    SendWorkers { jobs, aborted }
+}
+
+/// [`TelemetrySender`] 跟随重定向的次数上限，与 `--max-redirects` 的默认值相同
+const SENDER_MAX_REDIRECTS: usize = 10;
+
+/// [`TelemetrySender`] 摘除服务器前允许的连续失败次数，与 `--lb-max-failures` 的默认值相同
+const SENDER_LB_MAX_FAILURES: u32 = 3;
+
+/// [`TelemetrySender`] 被摘除的服务器重新参与负载均衡前的等待时间，与 `--lb-cooldown` 的默认值相同
+const SENDER_LB_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// 向ThingsBoard设备API发送数据项的发送器，供其它工具复用命令行工具的发送逻辑
+///
+/// 组合了HTTP客户端、服务器池、设备令牌和发送选项，每次调用 [`TelemetrySender::send`]
+/// 与命令行工具发送一条数据项的行为相同：多个服务器按轮询分摊，同一设备的多个令牌轮换使用
+pub struct TelemetrySender {
+    /// HTTP传输
+    transport: HttpSender,
+    /// 设备令牌
+    tokens: Vec<String>,
+    /// 令牌轮换游标
+    cursor: AtomicUsize,
+}
+
+impl TelemetrySender {
+    /// 按配置创建发送器，使用默认的发送选项
+    ///
+    /// # 错误
+    ///
+    /// 配置中没有设备令牌或HTTP客户端无法创建时返回错误
+    pub fn new(config: &Config) -> PushResult<Self> {
+        if config.device_tokens.is_empty() {
+            return Err(PushError::ConfigMissing(t!("config.token_missing")));
+        }
+        let client = ConnectionTuning::default()
+            .apply(Client::builder())
+            .redirect(redirect_policy(SENDER_MAX_REDIRECTS))
+            .build()
+            .map_err(|e| PushError::ConfigInvalid(format!("{}: {}", t!("banner.client_failed"), e)))?;
+        let servers = ServerPool::new(&config.servers, LbStrategy::RoundRobin, SENDER_LB_MAX_FAILURES, SENDER_LB_COOLDOWN);
+        Ok(TelemetrySender {
+            transport: HttpSender::new(client, Arc::new(servers), Arc::new(SendOptions::default())),
+            tokens: config.device_tokens.clone(),
+            cursor: AtomicUsize::new(0),
+        })
+    }
+
+    /// 替换发送选项
+    pub fn with_options(mut self, options: SendOptions) -> Self {
+        self.transport.options = Arc::new(options);
+        self
+    }
+
+    /// 当前的发送选项
+    pub fn options(&self) -> &SendOptions {
+        &self.transport.options
+    }
+
+    /// 发送一个数据项，见 [`send_telemetry`]
+    ///
+    /// # 参数
+    ///
+    /// * `item` - 要发送的JSON数据项
+    /// * `random_key` - 可选的随机键名称
+    /// * `rng` - 随机数生成器，见 [`crate::seeded_rng`]
+    ///
+    /// # 错误
+    ///
+    /// 当网络请求失败、服务器返回错误状态码或数据格式错误时返回错误
+    pub async fn send(
+        &self,
+        item: &Value,
+        random_key: &Option<String>,
+        rng: &mut ChaCha12Rng,
+    ) -> PushResult<Option<SendReport>> {
+        let token = &self.tokens[self.cursor.fetch_add(1, Ordering::Relaxed) % self.tokens.len()];
+        send_telemetry(&self.transport, token, &self.transport.options, item, random_key, None, rng).await
+    }
+}
+
+/// 向ThingsBoard发送遥测数据
+///
+/// 将JSON数据转换为ThingsBoard遥测格式并交给传输层发送
+/// 如果提供了随机键，会随机修改对应字段的值；配置为属性的字段拆分出来单独发往属性端点
+///
+/// # 参数
+///
+/// * `sender` - 传输层
+/// * `token` - 本次发送使用的设备访问令牌
+/// * `options` - 影响消息内容的发送选项
+/// * `data` - 要发送的JSON数据
+/// * `random_key` - 可选的随机键名称，如果存在会随机修改对应字段的值
+/// * `idempotency_key` - 本条数据的幂等键，放入请求头，配置了字段名时同时写入遥测数据
+/// * `rng` - 本设备的随机数生成器
+///
+/// # 返回值
+///
+/// * `PushResult<Option<SendReport>>` - 成功时返回发送结果，记录为空且策略为跳过时返回None，
+///   失败时返回错误信息
+///
+/// # 错误
+///
+/// 当网络请求失败、服务器返回错误状态码或数据格式错误时返回错误
+pub async fn send_telemetry(
+    sender: &dyn Sender,
+    token: &str,
+    options: &SendOptions,
+    data: &Value,
+    random_key: &Option<String>,
+    idempotency_key: Option<&str>,
+    rng: &mut ChaCha12Rng,
+) -> PushResult<Option<SendReport>> {
+    // 获取当前时间戳（毫秒），用于ThingsBoard时间序列数据；同一毫秒内的多条消息依次顺延
+    let timestamp = options.next_timestamp()?;
+    // 获取当前时间的字符串格式 yyyy-MM-dd HH:mm:ss
+    let send_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    // 构建符合ThingsBoard API要求的遥测数据格式
+    let mut values = extract_telemetry_values(data, random_key, &options.field_rules, rng)?;
+    // 按配置把属性字段拆分出来，其余字段默认走遥测
+    let mut attributes = serde_json::Map::new();
+    if !options.attribute_fields.is_empty() {
+        values.retain(|key, value| {
+            if options.attribute_fields.contains(key) {
+                attributes.insert(key.clone(), value.clone());
+                false
+            } else {
+                true
+            }
+        });
+    }
+    // 按策略处理提取结果为空的记录
+    if values.is_empty() && attributes.is_empty() {
+        match options.on_empty {
+            EmptyPolicy::Error => return Err(PushError::payload(t!("send.empty_values"))),
+            EmptyPolicy::Skip => return Ok(None),
+            EmptyPolicy::Send => {}
+        }
+    }
+    // 记录只包含属性字段时不再发送遥测请求
+    let send_telemetry_values = !values.is_empty() || attributes.is_empty();
+    // 将发送时间添加到遥测数据中
+    if let Some(ref field) = options.time_field {
+        values.insert(field.clone(), Value::String(send_time.clone()));
+    }
+    // 注入全局序号
+    if let Some(ref field) = options.seq_field {
+        values.insert(field.clone(), Value::from(options.next_seq()));
+    }
+    // 注入幂等键
+    let idempotency_field = options.idempotency.as_ref().and_then(|keys| keys.field.as_ref());
+    if let (Some(key), Some(field)) = (idempotency_key, idempotency_field) {
+        values.insert(field.clone(), Value::from(key));
+    }
+
+    let telemetry = TelemetryData {
+        ts: timestamp,
+        values,
+        time: send_time,
+        time_key: options.payload_time_key.clone(),
+        shape: options.payload_shape,
+    };
+
+    let mut messages = Vec::with_capacity(2);
+    if !attributes.is_empty() {
+        // 属性和遥测是两个请求，属性请求的幂等键加上后缀，避免按键去重时互相覆盖
+        messages.push(ChannelMessage {
+            channel: "attributes",
+            body: serde_json::to_vec(&attributes)
+                .map_err(|e| PushError::payload(format!("{}: {}", t!("send.serialize_attributes_failed"), e)))?,
+            idempotency_key: idempotency_key.map(|key| format!("{}-attributes", key)),
+        });
+    }
+    if send_telemetry_values {
+        messages.push(ChannelMessage {
+            channel: "telemetry",
+            body: serde_json::to_vec(&telemetry)
+                .map_err(|e| PushError::payload(format!("{}: {}", t!("send.serialize_telemetry_failed"), e)))?,
+            idempotency_key: idempotency_key.map(str::to_string),
+        });
+    }
+    let outcome = sender.send(token, Payload { timestamp, messages }).await?;
+
+    // 静默模式下完全跳过格式化，避免无谓的序列化开销
+    if !options.dry_run && tracing::enabled!(target: ITEM_TARGET, Level::INFO) {
+        item_info!("{}", t!("send.sent"));
+        item_info!("{}", t!("send.sent_time", telemetry.time));
+        if send_telemetry_values {
+            let values = serde_json::to_string_pretty(&telemetry.values)
+                .map_err(|e| PushError::payload(format!("{}: {}", t!("send.serialize_telemetry_failed"), e)))?;
+            item_info!("{}", t!("send.sent_values", values));
+        }
+        if !attributes.is_empty() {
+            let attributes = serde_json::to_string_pretty(&attributes)
+                .map_err(|e| PushError::payload(format!("{}: {}", t!("send.serialize_attributes_failed"), e)))?;
+            item_info!("{}", t!("send.sent_attributes", attributes));
+        }
+    }
+    Ok(Some(SendReport::new(telemetry, outcome, 1)))
+}
+
+/// 合并时序模式下，把多条记录打包为ThingsBoard的时序数组 `[{"ts", "values"}, ...]`，用一个请求发送
+///
+/// 每条记录顶层的 `ts` 字段（毫秒时间戳）作为该数据点的时间戳并从遥测值中去掉，没有 `ts` 字段的记录
+/// 使用发送时刻，同一批内依次顺延保证不重复。随机修改、条件上报、重命名、展开以及发送时间和序号的注入与逐条发送相同
+///
+/// # 参数
+///
+/// * `sender` - 传输层
+/// * `token` - 设备访问令牌
+/// * `options` - 影响消息内容的发送选项
+/// * `records` - 本批的数据记录
+/// * `random_key` - 可选的随机键名称
+/// * `rng` - 随机数生成器
+///
+/// # 返回值
+///
+/// * `PushResult<Option<SendReport>>` - 成功时返回发送结果，`points` 为实际发送的数据点数；
+///   全部记录都为空且策略为跳过时返回None
+///
+/// # 错误
+///
+/// 当记录的 `ts` 不是非负整数、记录为空且策略为报错、网络请求失败或服务器返回错误状态码时返回错误
+pub async fn send_timeseries_batch(
+    sender: &dyn Sender,
+    token: &str,
+    options: &SendOptions,
+    records: &[&Value],
+    random_key: &Option<String>,
+    rng: &mut ChaCha12Rng,
+) -> PushResult<Option<SendReport>> {
+    let timestamp = now_millis()?;
+    let send_time = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let mut points = Vec::with_capacity(records.len());
+    for record in records {
+        // 取出记录自带的时间戳，其余字段作为遥测值
+        let mut record = (*record).clone();
+        let ts = match record.as_object_mut().and_then(|fields| fields.remove("ts")) {
+            None => options.next_timestamp()?,
+            Some(value) => value
+                .as_u64()
+                .ok_or_else(|| PushError::payload(t!("send.merge_ts_invalid", value)))?,
+        };
+        let mut values = extract_telemetry_values(&record, random_key, &options.field_rules, rng)?;
+        if values.is_empty() {
+            match options.on_empty {
+                EmptyPolicy::Error => return Err(PushError::payload(t!("send.empty_values"))),
+                EmptyPolicy::Skip => continue,
+                EmptyPolicy::Send => {}
+            }
+        }
+        if let Some(ref field) = options.time_field {
+            values.insert(field.clone(), Value::String(send_time.clone()));
+        }
+        if let Some(ref field) = options.seq_field {
+            values.insert(field.clone(), Value::from(options.next_seq()));
+        }
+        points.push(TelemetryData {
+            ts,
+            values,
+            time: send_time.clone(),
+            time_key: None,
+            shape: PayloadShape::Strict,
+        });
+    }
+    let Some(last) = points.last() else {
+        return Ok(None);
+    };
+    let last_ts = last.ts;
+
+    let body = serde_json::to_vec(&points)
+        .map_err(|e| PushError::payload(format!("{}: {}", t!("send.serialize_telemetry_failed"), e)))?;
+    let messages = vec![ChannelMessage { channel: "telemetry", body, idempotency_key: None }];
+    let outcome = sender.send(token, Payload { timestamp, messages }).await?;
+    if !options.dry_run {
+        item_info!("{}", t!("send.merged_sent", points.len(), points[0].ts, last_ts));
+    }
+    let count = points.len() as u64;
+    Ok(Some(SendReport::new(points.pop().unwrap(), outcome, count)))
+}
+
+/// 默认的设备API地址模板，ThingsBoard云端和Edge的设备HTTP API都使用这个路径
+pub const DEFAULT_URL_TEMPLATE: &str = "{server}/api/v1/{token}/{channel}";
+
+/// 设备API请求地址模板（`--url-template`），支持 `{server}`、`{token}` 和 `{channel}` 三个占位符
+///
+/// 用于服务器放在带路径前缀的反向代理或网关之后等设备API不在默认路径的情况
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UrlTemplate(String);
+
+impl Default for UrlTemplate {
+    fn default() -> Self {
+        UrlTemplate(DEFAULT_URL_TEMPLATE.to_string())
+    }
+}
+
+impl UrlTemplate {
+    /// 解析并检查模板
+    ///
+    /// # 错误
+    ///
+    /// 缺少 `{token}` 或 `{channel}`，或者含有未知占位符时返回错误
+    pub fn parse(template: &str) -> PushResult<Self> {
+        for required in ["{token}", "{channel}"] {
+            if !template.contains(required) {
+                return Err(PushError::ConfigInvalid(t!("arg.url_template_missing", template, required)));
+            }
+        }
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..].find('}').map_or(rest.len(), |end| start + end + 1);
+            let placeholder = &rest[start..end];
+            if !matches!(placeholder, "{server}" | "{token}" | "{channel}") {
+                return Err(PushError::ConfigInvalid(t!("arg.url_template_unknown", template, placeholder)));
+            }
+            rest = &rest[end..];
+        }
+        Ok(UrlTemplate(template.to_string()))
+    }
+
+    /// 模板原文
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// 填入服务器地址、设备令牌和通道名称（telemetry 或 attributes）
+    pub fn render(&self, server: &str, token: &str, channel: &str) -> String {
+        self.0.replace("{server}", server).replace("{token}", token).replace("{channel}", channel)
+    }
+}
+
+/// 向设备API的某个通道（telemetry 或 attributes）发送一个请求
+///
+/// # 参数
+///
+/// * `client` - HTTP客户端实例
+/// * `server_pool` - 负载均衡服务器池，用于上报本次请求的结果
+/// * `server` - 本次请求发往的服务器
+/// * `options` - 发送选项（签名、演练模式）
+/// * `token` - 设备访问令牌
+/// * `channel` - API通道名称，拼接在 `/api/v1/{token}/` 之后
+/// * `body` - 已序列化的请求体，签名和发送使用同一份字节
+/// * `timestamp` - 本次发送的毫秒时间戳，用于签名
+/// * `idempotency_key` - 放入幂等请求头的键，None表示不携带
+///
+/// # 返回值
+///
+/// * `PushResult<(u16, Option<i64>, Duration)>` - 成功时返回响应状态码、响应 `Date` 头表示的服务器时间
+///   与本地发出请求时间之差（毫秒，没有 `Date` 头时为None），以及从发出请求到收到响应头的耗时；
+///   演练模式下返回 `(0, None, 0)`
+///
+/// # 错误
+///
+/// 当网络请求失败或服务器返回错误状态码时返回错误
+#[allow(clippy::too_many_arguments)]
+pub async fn post_channel(
+    client: &Client,
+    server_pool: &ServerPool,
+    server: &str,
+    options: &SendOptions,
+    token: &str,
+    channel: &str,
+    body: Vec<u8>,
+    timestamp: u64,
+    idempotency_key: Option<&str>,
+) -> PushResult<(u16, Option<i64>, Duration)> {
+    // 构建ThingsBoard设备API的请求URL
+    let url = options.url_template.render(server, token, channel);
+    http_debug!("➡️ POST {}", redact_url_tokens(&url));
+    http_debug!("   Content-Type: application/json");
+    http_debug!("   Content-Length: {}", body.len());
+    http_debug!("{}", t!("http.request_body", String::from_utf8_lossy(&body)));
+
+    let mut request = client.post(&url).header("Content-Type", "application/json");
+    if let (Some(key), Some(keys)) = (idempotency_key, &options.idempotency) {
+        http_debug!("   {}: {}", keys.header, key);
+        request = request.header(keys.header.as_str(), key);
+    }
+    // 对即将发送的请求体字节签名
+    if let Some(ref signer) = options.signer {
+        let sign_timestamp = signer.timestamp_header.as_ref().map(|_| timestamp.to_string());
+        if let (Some(header), Some(value)) = (&signer.timestamp_header, &sign_timestamp) {
+            http_debug!("   {}: {}", header, value);
+            request = request.header(header.as_str(), value.as_str());
+        }
+        let signature = signer.sign(&body, sign_timestamp.as_deref());
+        http_debug!("   {}: {}", signer.header, signature);
+        request = request.header(signer.header.as_str(), signature);
+    }
+
+    // 演练模式：打印将要发送的内容后直接返回，不发出网络请求
+    if options.dry_run {
+        if tracing::enabled!(target: ITEM_TARGET, Level::INFO) {
+            item_info!("{}", t!("send.dry_run_post", redact_url_tokens(&url)));
+            let pretty = serde_json::from_slice::<Value>(&body)
+                .and_then(|payload| serde_json::to_string_pretty(&payload))
+                .map_err(|e| PushError::payload(t!("send.payload_reparse_failed", e)))?;
+            item_info!("{}", t!("send.dry_run_payload", pretty));
+        }
+        return Ok((0, None, Duration::ZERO));
+    }
+
+    // 审计记录需要完整的请求体，只在开启审计时保留一份
+    let audit_payload = options.audit.as_ref().map(|_| {
+        serde_json::from_slice::<Value>(&body)
+            .unwrap_or_else(|_| Value::from(String::from_utf8_lossy(&body)))
+    });
+    let audit = |status: Option<u16>, latency: Duration, error: Option<String>| {
+        if let (Some(log), Some(payload)) = (&options.audit, &audit_payload) {
+            log.record(&serde_json::json!({
+                "ts": Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string(),
+                "device": mask_token(token),
+                "server": server,
+                "channel": channel,
+                "payload": payload,
+                "status": status,
+                "latency_ms": latency.as_millis() as u64,
+                "error": error,
+            }));
+        }
+    };
+
+    // 发送HTTP POST请求到ThingsBoard；服务器限流（429）时按 Retry-After 等待后重发同一个请求体
+    let mut throttled = 0;
+    let mut throttle_waited = Duration::ZERO;
+    let (response, request_started, sent_at) = loop {
+        options.throttle.wait_paused().await;
+        // 请求体在发送时才设置，请求总是可以复制
+        let attempt = request.try_clone().unwrap();
+        let request_started = Instant::now();
+        let sent_at = chrono::Utc::now();
+        let response = match attempt.body(body.clone()).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                server_pool.report(server, false);
+                http_debug!("{}", t!("http.request_failed", request_started.elapsed().as_millis()));
+                let e = PushError::from_request(server, e);
+                audit(None, request_started.elapsed(), Some(redact_url_tokens(&e.detail())));
+                return Err(e);
+            }
+        };
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+            if let Some(wait) = options.throttle.next_wait(retry_after, throttled, throttle_waited) {
+                audit(Some(response.status().as_u16()), request_started.elapsed(), None);
+                throttled += 1;
+                throttle_waited += wait;
+                let source = match retry_after {
+                    Some(_) => "retry_after",
+                    None => "backoff",
+                };
+                warn!(
+                    event = "throttled",
+                    channel,
+                    attempt = throttled,
+                    wait_ms = wait.as_millis() as u64,
+                    source,
+                    "{}",
+                    t!(
+                        "send.throttled",
+                        format!("{:.1}", wait.as_secs_f64()),
+                        t!(if retry_after.is_some() { "send.throttle_retry_after" } else { "send.throttle_backoff" }),
+                        throttled
+                    )
+                );
+                // 等待期间收到停止请求时不再重试，本次按429失败处理
+                if options.throttle.pause(wait).await {
+                    continue;
+                }
+            }
+        }
+        break (response, request_started, sent_at);
+    };
+    let headers_latency = request_started.elapsed();
+    audit(Some(response.status().as_u16()), headers_latency, None);
+
+    // 检查响应状态并处理结果，5xx 视为该服务器节点故障
+    let status = response.status();
+    server_pool.report(server, !status.is_server_error());
+    // 响应的Date头只精确到秒，差值用于粗略区分网络延迟和服务端处理延迟
+    let server_offset_ms = response
+        .headers()
+        .get(reqwest::header::DATE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| chrono::DateTime::parse_from_rfc2822(value).ok())
+        .map(|server_time| (server_time.with_timezone(&chrono::Utc) - sent_at).num_milliseconds());
+    // 服务器在发出和收到之间的某个时刻生成Date头，且Date头截断到秒
+    if let (Some(clock), Some(offset)) = (&options.clock_adjust, server_offset_ms)
+        && let Some((previous, adjusted)) =
+            clock.observe(offset - headers_latency.as_millis() as i64, offset + 1000, Instant::now())
+        && previous != adjusted
+    {
+        info!(
+            event = "clock_adjust",
+            offset_ms = adjusted,
+            previous_ms = previous,
+            "{}",
+            t!("send.clock_adjusted", adjusted, previous)
+        );
+    }
+    // 成功时需要展示的响应头在读取响应体之前取出
+    let shown_headers: Vec<String> = match options.show_response {
+        Some(ref display) if status.is_success() => display
+            .headers
+            .iter()
+            .filter_map(|name| {
+                let value = response.headers().get(name.as_str())?;
+                Some(format!("{}: {}", name, header_text(value)))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let diagnostic_headers: Vec<String> = if status.is_success() {
+        Vec::new()
+    } else {
+        DIAGNOSTIC_HEADERS
+            .iter()
+            .filter_map(|name| {
+                let value = response.headers().get(*name)?;
+                Some(format!("{}: {}", name, header_text(value)))
+            })
+            .collect()
+    };
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .map(|value| redact_url_tokens(&header_text(value)));
+    http_debug!("⬅️ {} {:?}", status, response.version());
+    if tracing::enabled!(target: HTTP_TARGET, Level::DEBUG) {
+        for (name, value) in response.headers() {
+            http_debug!("   {}: {}", name, header_text(value));
+        }
+    }
+    // 始终读完响应体，发送耗时包含完整响应，连接也能被复用
+    let text = response.text().await.unwrap_or_default();
+    http_debug!("{}", t!("http.response_body", if text.is_empty() { t!("http.empty_body") } else { text.clone() }));
+    http_debug!("{}", t!("http.timing", headers_latency.as_millis(), request_started.elapsed().as_millis()));
+
+    if status.is_success() {
+        if let Some(ref display) = options.show_response {
+            item_info!("{}", t!("response.status", channel, status));
+            for header in &shown_headers {
+                item_info!("   {}", header);
+            }
+            let body = if text.is_empty() { t!("response.empty") } else { display.truncate(&text).into_owned() };
+            item_info!("{}", t!("response.body", body));
+        }
+        Ok((status.as_u16(), server_offset_ms, headers_latency))
+    } else {
+        Err(PushError::from_status(HttpStatusError {
+            status,
+            body: text,
+            headers: diagnostic_headers,
+            location,
+            url: redact_url_tokens(&url),
+        }))
+    }
+}
+
+/// 用设备令牌读取一次设备属性，检查服务器可达并且接受该令牌
+///
+/// 读取属性不会写入任何数据，适合在正式发送前确认配置是否正确
+///
+/// # 参数
+///
+/// * `client` - HTTP客户端
+/// * `url_template` - 设备API请求地址模板
+/// * `server` - 服务器地址
+/// * `token` - 设备访问令牌
+///
+/// # 返回值
+///
+/// * `PushResult<(u16, Duration)>` - 成功时返回状态码和完整响应耗时
+///
+/// # 错误
+///
+/// 请求失败、超时或服务器返回非2xx状态码时返回错误，令牌被拒绝时为 [`PushError::Auth`]
+pub async fn check_device(
+    client: &Client,
+    url_template: &UrlTemplate,
+    server: &str,
+    token: &str,
+) -> PushResult<(u16, Duration)> {
+    let url = url_template.render(server, token, "attributes");
+    let started = Instant::now();
+    let response = client.get(&url).send().await.map_err(|e| PushError::from_request(server, e))?;
+    let status = response.status();
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)
+        .map(|value| redact_url_tokens(&header_text(value)));
+    let text = response.text().await.unwrap_or_default();
+    if status.is_success() {
+        return Ok((status.as_u16(), started.elapsed()));
+    }
+    Err(PushError::from_status(HttpStatusError {
+        status,
+        body: text,
+        headers: Vec::new(),
+        location,
+        url: redact_url_tokens(&url),
+    }))
+}
+
+/// 把文本中 `/api/v1/{token}/` 形式的设备令牌替换为脱敏后的值，地址模板中的 `{token}` 占位符保持原样
+pub fn redact_url_tokens(text: &str) -> String {
+    const PREFIX: &str = "/api/v1/";
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(PREFIX) {
+        let (before, after) = rest.split_at(pos + PREFIX.len());
+        result.push_str(before);
+        let end = after.find('/').unwrap_or(after.len());
+        match &after[..end] {
+            "{token}" => result.push_str("{token}"),
+            token => result.push_str(&mask_token(token)),
+        }
+        rest = &after[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// 对设备令牌做脱敏处理，只保留前4位和后4位
+pub fn mask_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}****{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{LbStrategy, ProfileSettings, load_config};
+    use crate::random::seeded_rng;
+
+    /// 读取模拟服务器收到的一个完整HTTP请求（请求头和Content-Length长度的请求体）
+    async fn read_http_request(stream: &mut tokio::net::TcpStream) -> String {
+        use tokio::io::AsyncReadExt;
+
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            // 连接被对方关闭时返回已读到的内容，没有读到请求时为空
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            if n == 0 {
+                return String::from_utf8_lossy(&request).into_owned();
+            }
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|line| {
+                        let line = line.to_ascii_lowercase();
+                        line.strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    return text.into_owned();
+                }
+            }
+        }
+    }
+
+    /// 每隔一个请求返回500的模拟服务器，每个连接只处理一个请求
+    async fn half_failing_server() -> String {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            for served in 1u64.. {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let status = if served % 2 == 0 { "500 Internal Server Error" } else { "200 OK" };
+                tokio::spawn(async move {
+                    read_http_request(&mut stream).await;
+                    let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn telemetry_sender_sends_through_configured_server() {
+        let server = half_failing_server().await;
+        let config = Config { servers: vec![server], device_tokens: vec!["a".to_string(), "b".to_string()] };
+        let sender = TelemetrySender::new(&config).unwrap();
+        let mut rng = seeded_rng(Some(1), 0);
+        let item = serde_json::json!({"temperature": 21});
+
+        let report = sender.send(&item, &None, &mut rng).await.unwrap().expect("应有发送结果");
+        assert_eq!(report.status_code, 200);
+        assert_eq!(report.telemetry.values["temperature"], 21);
+        let error = sender.send(&item, &None, &mut rng).await.unwrap_err();
+        assert!(matches!(&error, PushError::HttpStatus(status) if status.status.as_u16() == 500), "{:?}", error);
+
+        let empty = Config { servers: vec!["http://127.0.0.1:1".to_string()], device_tokens: Vec::new() };
+        assert!(matches!(TelemetrySender::new(&empty), Err(PushError::ConfigMissing(_))));
+    }
+
+    #[test]
+    fn short_device_token_is_masked_without_panicking() {
+        let profile = ProfileSettings {
+            server: Some("http://127.0.0.1:8080".to_string()),
+            device_token: Some("abc".to_string()),
+            ..Default::default()
+        };
+        // 短令牌只警告，不应导致加载失败
+        let config = load_config(Some(&profile), true).unwrap();
+        assert_eq!(config.device_tokens, vec!["abc".to_string()]);
+
+        // 启动横幅曾经直接截取前8个字节，3个字符的令牌会在这里崩溃
+        assert_eq!(mask_token(&config.device_tokens[0]), "***");
+        assert!(t!("banner.token", mask_token(&config.device_tokens[0])).ends_with("***"));
+        assert_eq!(mask_token("令牌"), "**");
+        assert_eq!(mask_token("abcdefghijklmnopqrst"), "abcd****qrst");
+    }
+
+    #[test]
+    fn idempotency_keys_are_stable_until_delivered() {
+        let record = serde_json::json!({"temperature": 21.5, "humidity": 40});
+        let reordered = serde_json::json!({"humidity": 40, "temperature": 21.5});
+        let mut keys = IdempotencyKeys {
+            mode: IdempotencyMode::Hash,
+            header: "Idempotency-Key".to_string(),
+            field: None,
+            salt: String::new(),
+        };
+        let key = keys.key("token", 0, 1, &record);
+        assert_eq!(key.len(), 32);
+        // 重发同一条数据（投递序号不变）得到同一个键，字段顺序不影响结果
+        assert_eq!(keys.key("token", 0, 1, &reordered), key);
+        assert_ne!(keys.key("token", 0, 2, &record), key);
+        assert_ne!(keys.key("token", 1, 1, &record), key);
+        assert_ne!(keys.key("other", 0, 1, &record), key);
+        assert_ne!(keys.key("token", 0, 1, &serde_json::json!({"temperature": 22})), key);
+        keys.salt = "rerun".to_string();
+        assert_ne!(keys.key("token", 0, 1, &record), key);
+
+        keys.mode = IdempotencyMode::Seq;
+        assert_eq!(keys.key("token", 2, 5, &record), "rerun-3-5");
+        keys.salt.clear();
+        assert_eq!(keys.key("token", 2, 5, &record), "3-5");
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn timestamp_allocator_is_unique_and_monotonic_across_tasks() {
+        const TASKS: usize = 8;
+        const PER_TASK: usize = 2000;
+        let allocator = Arc::new(TimestampAllocator::new(true));
+        let mut handles = Vec::new();
+        for task in 0..TASKS {
+            let allocator = allocator.clone();
+            handles.push(tokio::spawn(async move {
+                let mut allocated = Vec::with_capacity(PER_TASK);
+                for i in 0..PER_TASK {
+                    // 一半任务用真实时钟，一半用固定的时钟读数，制造大量同一毫秒的碰撞
+                    let ts = if task % 2 == 0 { allocator.allocate(now_millis().unwrap()) } else { allocator.allocate(1_000) };
+                    allocated.push(ts);
+                    if i % 100 == 0 {
+                        tokio::task::yield_now().await;
+                    }
+                }
+                allocated
+            }));
+        }
+        let mut all = Vec::new();
+        for handle in handles {
+            let allocated = handle.await.unwrap();
+            // 每个任务看到的时间戳严格递增
+            assert!(allocated.windows(2).all(|pair| pair[0] < pair[1]));
+            all.extend(allocated);
+        }
+        let unique: HashSet<u64> = all.iter().copied().collect();
+        assert_eq!(unique.len(), TASKS * PER_TASK);
+        // 固定读数的请求都落在之前分配的时间戳之后，必然被顺延
+        assert!(allocator.adjusted() >= (TASKS / 2 * PER_TASK) as u64);
+        let last = *all.iter().max().unwrap();
+        assert_eq!(allocator.allocate(1_000), last + 1);
+
+        // 关闭后原样返回时钟读数，不计入顺延
+        let raw = TimestampAllocator::new(false);
+        assert_eq!(raw.allocate(5), 5);
+        assert_eq!(raw.allocate(5), 5);
+        assert_eq!(raw.adjusted(), 0);
+    }
+
+    #[test]
+    fn clock_adjuster_narrows_offset_and_remeasures() {
+        let clock = ClockAdjuster::new();
+        let start = Instant::now();
+        // 第一个样本立即生效，取区间中点
+        assert_eq!(clock.observe(-200, 1000, start), Some((0, 400)));
+        assert_eq!(clock.offset_ms(), 400);
+        // 周期内的样本只收窄区间，不改变当前偏移量
+        assert_eq!(clock.observe(300, 1500, start + Duration::from_secs(10)), None);
+        assert_eq!(clock.observe(100, 700, start + Duration::from_secs(20)), None);
+        assert_eq!(clock.offset_ms(), 400);
+        // 周期结束时取交集 [300, 700] 的中点
+        let later = start + CLOCK_ADJUST_INTERVAL;
+        assert_eq!(clock.observe(250, 900, later), Some((400, 500)));
+        // 与之前不相交的样本说明时钟跳变，从该样本重新开始
+        assert_eq!(clock.observe(4000, 5000, later + Duration::from_secs(1)), None);
+        assert_eq!(clock.observe(4200, 4600, later + CLOCK_ADJUST_INTERVAL), Some((500, 4400)));
+
+        let options = SendOptions {
+            clock_adjust: Some(clock),
+            ..Default::default()
+        };
+        let local = now_millis().unwrap();
+        let ts = options.next_timestamp().unwrap();
+        assert!(ts >= local + 4400 && ts < local + 4400 + 1000);
+    }
+
+    #[test]
+    fn auth_failures_are_recognized() {
+        let error = |status: u16, body: &str| HttpStatusError {
+            status: reqwest::StatusCode::from_u16(status).unwrap(),
+            body: body.to_string(),
+            headers: Vec::new(),
+            location: None,
+            url: "http://localhost:8080/api/v1/abcd****qrst/telemetry".to_string(),
+        };
+        assert!(error(401, "").is_auth_failure());
+        assert!(error(403, "Forbidden").is_auth_failure());
+        assert!(error(404, "").is_auth_failure());
+        assert!(error(404, "Invalid device TOKEN").is_auth_failure());
+        assert!(!error(404, "<html>Not Found</html>").is_auth_failure());
+        assert!(!error(500, "token").is_auth_failure());
+        assert!(!error(400, "").is_auth_failure());
+    }
+
+    #[tokio::test]
+    async fn transport_failures_map_to_typed_errors() {
+        use tokio::io::AsyncWriteExt;
+
+        let item = serde_json::json!({"temperature": 21});
+        let mut rng = seeded_rng(Some(1), 0);
+
+        // 401：令牌被拒绝
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                read_http_request(&mut stream).await;
+                let response = "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        let config = Config { servers: vec![format!("http://{}", addr)], device_tokens: vec!["t".to_string()] };
+        let error = TelemetrySender::new(&config).unwrap().send(&item, &None, &mut rng).await.unwrap_err();
+        assert!(matches!(&error, PushError::Auth(status) if status.status.as_u16() == 401), "{:?}", error);
+        assert_eq!(error.status_code(), Some(401));
+
+        // 连接被拒绝：端口上没有监听
+        let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let config = Config { servers: vec![format!("http://{}", closed)], device_tokens: vec!["t".to_string()] };
+        let error = TelemetrySender::new(&config).unwrap().send(&item, &None, &mut rng).await.unwrap_err();
+        assert!(matches!(error, PushError::Network { .. }), "{:?}", error);
+        assert_eq!(error.kind(), "network");
+
+        // 超时：服务器接受连接但一直不响应
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                held.push(stream);
+            }
+        });
+        let client = Client::builder().timeout(Duration::from_millis(200)).build().unwrap();
+        let servers = ServerPool::new(&[server], LbStrategy::RoundRobin, SENDER_LB_MAX_FAILURES, SENDER_LB_COOLDOWN);
+        let options = SendOptions::default();
+        let transport = HttpSender::new(client, Arc::new(servers), Arc::new(SendOptions::default()));
+        let error = send_telemetry(&transport, "t", &options, &item, &None, None, &mut rng).await.unwrap_err();
+        assert!(matches!(error, PushError::Timeout { .. }), "{:?}", error);
+        assert!(error.detail().starts_with(&error.to_string()), "{}", error.detail());
+    }
+
+    #[tokio::test]
+    async fn check_device_reads_attributes_without_sending() {
+        use tokio::io::AsyncWriteExt;
+
+        // 按请求行回应：正确令牌返回200，其它令牌返回401
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let request = read_http_request(&mut stream).await;
+                let line = request.lines().next().unwrap_or_default().to_string();
+                let response = if line.contains("/good/") {
+                    "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}"
+                } else {
+                    "HTTP/1.1 401 Unauthorized\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                };
+                seen.lock().unwrap().push(line);
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        let client = Client::new();
+        let template = UrlTemplate::parse(DEFAULT_URL_TEMPLATE).unwrap();
+
+        let (status, _) = check_device(&client, &template, &server, "good").await.unwrap();
+        assert_eq!(status, 200);
+        let error = check_device(&client, &template, &server, "bad-token").await.unwrap_err();
+        assert!(matches!(&error, PushError::Auth(status) if !status.url.contains("bad-token")), "{:?}", error);
+        // 令牌同时出现在路径其它位置时，只脱敏令牌所在的路径段
+        let error = check_device(&client, &template, &server, "api").await.unwrap_err();
+        assert!(
+            matches!(&error, PushError::Auth(status) if status.url == format!("{}/api/v1/***/attributes", server)),
+            "{:?}",
+            error
+        );
+        assert_eq!(
+            *requests.lock().unwrap(),
+            [
+                "GET /api/v1/good/attributes HTTP/1.1",
+                "GET /api/v1/bad-token/attributes HTTP/1.1",
+                "GET /api/v1/api/attributes HTTP/1.1"
+            ]
+        );
+    }
+
+    #[test]
+    fn throttle_waits_follow_retry_after_and_cap() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT").unwrap().to_utc();
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 ", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2026 07:28:30 GMT", now), Some(Duration::from_secs(30)));
+        // 已经过去的日期不需要等待
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2026 07:27:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+
+        let throttle = Throttle::new(Duration::from_secs(10));
+        // 有 Retry-After 时按服务器要求，0秒也至少等待一小段时间
+        assert_eq!(throttle.next_wait(Some(Duration::from_secs(3)), 0, Duration::ZERO), Some(Duration::from_secs(3)));
+        assert_eq!(throttle.next_wait(Some(Duration::ZERO), 0, Duration::ZERO), Some(THROTTLE_MIN_WAIT));
+        // 没有 Retry-After 时指数退避
+        assert_eq!(throttle.next_wait(None, 0, Duration::ZERO), Some(Duration::from_secs(1)));
+        assert_eq!(throttle.next_wait(None, 2, Duration::ZERO), Some(Duration::from_secs(4)));
+        // 不超过剩余的等待额度，额度用完后不再重试
+        assert_eq!(throttle.next_wait(Some(Duration::from_secs(60)), 0, Duration::from_secs(7)), Some(Duration::from_secs(3)));
+        assert_eq!(throttle.next_wait(None, 30, Duration::from_secs(2)), Some(Duration::from_secs(8)));
+        assert_eq!(throttle.next_wait(None, 0, Duration::from_secs(10)), None);
+        assert_eq!(Throttle::new(Duration::ZERO).next_wait(Some(Duration::from_secs(1)), 0, Duration::ZERO), None);
+    }
+
+    /// 按路径返回重定向的模拟服务器，`/moved/` 下的请求返回200，收到的请求行和请求体记录在返回的列表中
+    ///
+    /// 令牌决定响应：`perm` 返回308、`found` 返回302，`loop` 重定向到自身
+    async fn redirecting_server() -> (String, Arc<Mutex<Vec<String>>>) {
+        use tokio::io::AsyncWriteExt;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let (base, log) = (addr.clone(), Arc::clone(&received));
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let (base, log) = (base.clone(), Arc::clone(&log));
+                tokio::spawn(async move {
+                    let request = read_http_request(&mut stream).await;
+                    let line = request.lines().next().unwrap_or_default().to_string();
+                    let body = request.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+                    log.lock().unwrap().push(format!("{} {}", line, body));
+                    let path = line.split(' ').nth(1).unwrap_or_default();
+                    let response = match path.split('/').nth(3).unwrap_or_default() {
+                        _ if path.starts_with("/moved/") => "200 OK".to_string(),
+                        "perm" => format!("308 Permanent Redirect\r\nLocation: {}/moved{}", base, path),
+                        "found" => format!("302 Found\r\nLocation: {}/moved{}", base, path),
+                        _ => format!("307 Temporary Redirect\r\nLocation: {}", path),
+                    };
+                    let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", response);
+                    stream.write_all(response.as_bytes()).await.unwrap();
+                });
+            }
+        });
+        (addr, received)
+    }
+
+    #[tokio::test]
+    async fn pool_max_idle_zero_opens_a_connection_per_request() {
+        use tokio::io::AsyncWriteExt;
+
+        // 支持keep-alive的模拟服务器，记录建立的连接数
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        let connections = Arc::new(AtomicUsize::new(0));
+        let accepted = Arc::clone(&connections);
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                accepted.fetch_add(1, Ordering::Relaxed);
+                tokio::spawn(async move {
+                    while !read_http_request(&mut stream).await.is_empty() {
+                        let response = "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n";
+                        if stream.write_all(response.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        let options = Arc::new(SendOptions::default());
+        let item = serde_json::json!({"temperature": 21});
+        let mut rng = seeded_rng(Some(1), 0);
+        let mut connections_for = async |tuning: ConnectionTuning| {
+            connections.store(0, Ordering::Relaxed);
+            let client = tuning.apply(Client::builder()).build().unwrap();
+            let pool = ServerPool::new(std::slice::from_ref(&server), LbStrategy::RoundRobin, u32::MAX, Duration::from_secs(1));
+            let sender = HttpSender::new(client, Arc::new(pool), Arc::clone(&options));
+            for _ in 0..3 {
+                send_telemetry(&sender, "token", &options, &item, &None, None, &mut rng).await.unwrap();
+            }
+            connections.load(Ordering::Relaxed)
+        };
+        assert_eq!(connections_for(ConnectionTuning::default()).await, 1);
+        let no_reuse = ConnectionTuning { pool_max_idle: 0, nodelay: false, ..ConnectionTuning::default() };
+        assert_eq!(connections_for(no_reuse).await, 3);
+        let keepalive = ConnectionTuning { keepalive: Some(Duration::from_secs(30)), ..ConnectionTuning::default() };
+        assert_eq!(connections_for(keepalive).await, 1);
+    }
+
+    #[tokio::test]
+    async fn redirects_keep_post_for_308_and_stop_at_302() {
+        let (server, received) = redirecting_server().await;
+        let client = Client::builder().redirect(redirect_policy(3)).build().unwrap();
+        let pool = ServerPool::new(std::slice::from_ref(&server), LbStrategy::RoundRobin, u32::MAX, Duration::from_secs(1));
+        let options = Arc::new(SendOptions::default());
+        let sender = HttpSender::new(client, Arc::new(pool), Arc::clone(&options));
+        let item = serde_json::json!({"temperature": 21});
+        let mut rng = seeded_rng(Some(1), 0);
+        let mut send = async |token: &str| {
+            received.lock().unwrap().clear();
+            let result = send_telemetry(&sender, token, &options, &item, &None, None, &mut rng).await;
+            (result, received.lock().unwrap().clone())
+        };
+
+        // 308：跟随到新地址，仍是带原请求体的POST
+        let (result, requests) = send("perm").await;
+        assert!(result.is_ok(), "{:?}", result);
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("POST /moved/api/v1/perm/telemetry "), "{}", requests[1]);
+        assert!(requests[1].contains(r#""temperature":21"#), "{}", requests[1]);
+
+        // 302：不跟随，按发送失败处理并给出应改用的服务器地址
+        let (result, requests) = send("found").await;
+        assert_eq!(requests.len(), 1);
+        let error = result.unwrap_err();
+        let status = error.http_status().expect("应为HTTP状态错误");
+        assert_eq!(status.status.as_u16(), 302);
+        assert!(status.to_string().contains(&format!("{}/moved", server)), "{}", status);
+
+        // 重定向次数超过上限时停止
+        let (result, requests) = send("loop").await;
+        assert!(result.is_err());
+        assert_eq!(requests.len(), 4);
+    }
+
+    #[test]
+    fn url_template_renders_and_rejects_bad_placeholders() {
+        let default = UrlTemplate::default();
+        assert_eq!(default.render("http://tb:8080", "tok", "telemetry"), "http://tb:8080/api/v1/tok/telemetry");
+        let prefixed = UrlTemplate::parse("{server}/edge-a/api/v1/{token}/{channel}").unwrap();
+        assert_eq!(prefixed.render("https://gw", "tok", "attributes"), "https://gw/edge-a/api/v1/tok/attributes");
+        assert!(UrlTemplate::parse("{server}/api/v1/{token}/telemetry").is_err());
+        assert!(UrlTemplate::parse("{server}/api/v1/{device}/{channel}").is_err());
+        assert!(UrlTemplate::parse("{server}/api/v1/{token}/{channel}{").is_err());
+    }
+}
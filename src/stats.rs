@@ -0,0 +1,945 @@
+//! 运行统计：心跳、时间窗口、Prometheus指标、StatsD、CSV报告和数据副本
+
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use chrono::Local;
+use hdrhistogram::Histogram;
+use tracing::{info, warn};
+use tokio::time::Duration;
+use crate::output::{HEARTBEAT_TARGET, format_eta};
+use crate::error::{PushError, PushResult};
+use crate::sender::{SendReport, redact_url_tokens};
+use crate::telemetry::{PayloadShape, TelemetryData};
+
+/// 心跳中计算近期吞吐量所用的采样点个数
+pub const HEARTBEAT_WINDOW: usize = 5;
+
+/// 心跳中计算近期延迟所用的最近发送次数
+pub const HEARTBEAT_LATENCY_SAMPLES: usize = 50;
+
+/// 长时间运行时的周期性心跳
+///
+/// 与进度显示不同，心跳在静默模式和JSON日志模式下也会输出，用于确认工具仍在运行。
+/// 发送循环通过共享引用记录结果，由主任务按固定间隔调用 [`Heartbeat::emit`]
+pub struct Heartbeat {
+    /// 总发送条数，None表示无限循环
+    total: Option<u64>,
+    /// 已完成条数（含失败和跳过）
+    done: AtomicU64,
+    /// 成功条数（含跳过）
+    succeeded: AtomicU64,
+    /// 最近若干次发送的延迟
+    recent_latencies: Mutex<std::collections::VecDeque<Duration>>,
+    /// 最近几次心跳时的 (时刻, 已完成条数)，用于计算滑动平均吞吐量
+    samples: Mutex<std::collections::VecDeque<(Instant, u64)>>,
+}
+
+impl Heartbeat {
+    /// 创建心跳记录
+    ///
+    /// # 参数
+    ///
+    /// * `total` - 总发送条数，None表示无限循环
+    pub fn new(total: Option<u64>) -> Self {
+        Heartbeat {
+            total,
+            done: AtomicU64::new(0),
+            succeeded: AtomicU64::new(0),
+            recent_latencies: Mutex::new(std::collections::VecDeque::with_capacity(HEARTBEAT_LATENCY_SAMPLES)),
+            samples: Mutex::new(std::collections::VecDeque::from([(Instant::now(), 0)])),
+        }
+    }
+
+    /// 记录一条发送完成
+    pub fn record(&self, success: bool, latency: Duration) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+        if success {
+            self.succeeded.fetch_add(1, Ordering::Relaxed);
+        }
+        let mut latencies = self.recent_latencies.lock().unwrap();
+        if latencies.len() == HEARTBEAT_LATENCY_SAMPLES {
+            latencies.pop_front();
+        }
+        latencies.push_back(latency);
+    }
+
+    /// 输出一行心跳
+    ///
+    /// 预计剩余时间按最近 [`HEARTBEAT_WINDOW`] 次心跳之间的吞吐量计算，
+    /// 发送变慢时能较快反映出来
+    pub fn emit(&self) {
+        let done = self.done.load(Ordering::Relaxed);
+        let succeeded = self.succeeded.load(Ordering::Relaxed);
+        let success_rate = if done > 0 { succeeded as f64 * 100.0 / done as f64 } else { 100.0 };
+        let recent_latency_ms = {
+            let latencies = self.recent_latencies.lock().unwrap();
+            (!latencies.is_empty()).then(|| {
+                latencies.iter().map(Duration::as_secs_f64).sum::<f64>() * 1000.0 / latencies.len() as f64
+            })
+        };
+
+        let now = Instant::now();
+        let rate = {
+            let mut samples = self.samples.lock().unwrap();
+            samples.push_back((now, done));
+            if samples.len() > HEARTBEAT_WINDOW + 1 {
+                samples.pop_front();
+            }
+            let (oldest_at, oldest_done) = samples[0];
+            let elapsed = now.duration_since(oldest_at).as_secs_f64();
+            if elapsed > 0.0 { (done - oldest_done) as f64 / elapsed } else { 0.0 }
+        };
+        let percent = self.total.map(|total| done as f64 * 100.0 / total.max(1) as f64);
+        let eta_secs = self
+            .total
+            .filter(|_| rate > 0.0)
+            .map(|total| (total.saturating_sub(done) as f64 / rate).round() as u64);
+
+        let mut message = t!(
+            "heartbeat.message",
+            done,
+            format!("{:.1}", success_rate),
+            recent_latency_ms.map_or_else(|| "-".to_string(), |ms| format!("{:.1}", ms)),
+            format!("{:.2}", rate)
+        );
+        if let Some(percent) = percent {
+            let eta = eta_secs.map_or_else(|| "-".to_string(), format_eta);
+            message.push_str(&t!("heartbeat.total", format!("{:.1}", percent), eta));
+        }
+        let round2 = |value: f64| (value * 100.0).round() / 100.0;
+        info!(
+            target: HEARTBEAT_TARGET,
+            event = "heartbeat",
+            done,
+            succeeded,
+            success_rate = round2(success_rate),
+            recent_latency_ms = recent_latency_ms.map(round2),
+            rate = round2(rate),
+            percent = percent.map(round2),
+            eta_secs,
+            "{}",
+            message
+        );
+    }
+}
+
+/// 滚动统计每输出多少行重复一次表头
+pub const WINDOW_HEADER_EVERY: u64 = 20;
+
+/// 滚动统计的表头，各列与 [`WindowStats::emit`] 输出的行对齐
+pub const WINDOW_HEADER: &str = "    time   done   rate/s    ok%   avg_ms   max_ms  failed";
+
+/// 当前统计窗口内的计数
+#[derive(Debug, Default)]
+pub struct WindowCounters {
+    /// 完成条数（含失败和跳过）
+    done: u64,
+    /// 失败条数
+    failed: u64,
+    /// 延迟之和
+    latency_sum: Duration,
+    /// 最大延迟
+    latency_max: Duration,
+}
+
+/// 按固定时间窗口滚动统计发送结果（`--stats-interval`）
+///
+/// 每个窗口结束时像 `vmstat` 一样输出一行本窗口的速率、成功率和延迟，然后清零重新计数
+pub struct WindowStats {
+    /// 本窗口的计数
+    current: Mutex<WindowCounters>,
+    /// 本窗口的开始时刻
+    window_start: Mutex<Instant>,
+    /// 已输出的行数，用于周期性重复表头
+    rows: AtomicU64,
+    /// 是否输出表头，JSON日志中每行都是完整的事件，不需要表头
+    header: bool,
+}
+
+impl WindowStats {
+    /// 创建滚动统计，窗口从此刻开始
+    pub fn new(header: bool) -> Self {
+        WindowStats {
+            current: Mutex::new(WindowCounters::default()),
+            window_start: Mutex::new(Instant::now()),
+            rows: AtomicU64::new(0),
+            header,
+        }
+    }
+
+    /// 记录一条发送完成
+    pub fn record(&self, success: bool, latency: Duration) {
+        let mut current = self.current.lock().unwrap();
+        current.done += 1;
+        if !success {
+            current.failed += 1;
+        }
+        current.latency_sum += latency;
+        current.latency_max = current.latency_max.max(latency);
+    }
+
+    /// 输出本窗口的统计并开始下一个窗口
+    pub fn emit(&self) {
+        let now = Instant::now();
+        let window = std::mem::take(&mut *self.current.lock().unwrap());
+        let seconds = {
+            let mut start = self.window_start.lock().unwrap();
+            let seconds = now.duration_since(*start).as_secs_f64();
+            *start = now;
+            seconds
+        };
+        let rate = if seconds > 0.0 { window.done as f64 / seconds } else { 0.0 };
+        let success_rate = if window.done > 0 {
+            (window.done - window.failed) as f64 * 100.0 / window.done as f64
+        } else {
+            100.0
+        };
+        let mean_latency_ms =
+            (window.done > 0).then(|| window.latency_sum.as_secs_f64() * 1000.0 / window.done as f64);
+        let max_latency_ms = (window.done > 0).then_some(window.latency_max.as_secs_f64() * 1000.0);
+
+        if self.header && self.rows.fetch_add(1, Ordering::Relaxed).is_multiple_of(WINDOW_HEADER_EVERY) {
+            info!(target: HEARTBEAT_TARGET, "📈 {}", WINDOW_HEADER);
+        }
+        let ms = |value: Option<f64>| value.map_or_else(|| "-".to_string(), |ms| format!("{:.1}", ms));
+        let row = format!(
+            "{:>8} {:>6} {:>8.2} {:>6.1} {:>8} {:>8} {:>7}",
+            Local::now().format("%H:%M:%S"),
+            window.done,
+            rate,
+            success_rate,
+            ms(mean_latency_ms),
+            ms(max_latency_ms),
+            window.failed
+        );
+        let round2 = |value: f64| (value * 100.0).round() / 100.0;
+        info!(
+            target: HEARTBEAT_TARGET,
+            event = "window",
+            seconds = round2(seconds),
+            done = window.done,
+            rate = round2(rate),
+            success_rate = round2(success_rate),
+            mean_latency_ms = mean_latency_ms.map(round2),
+            max_latency_ms = max_latency_ms.map(round2),
+            failed = window.failed,
+            "📈 {}",
+            row
+        );
+    }
+}
+
+/// 延迟直方图的桶上限（秒）
+pub const METRICS_LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// `--metrics-listen` 的详细帮助
+pub const METRICS_HELP: &str = "\
+在指定地址（如 0.0.0.0:9184）上提供Prometheus格式的 /metrics 端点，
+HTTP服务与发送共用同一个tokio运行时，不会阻塞发送。
+
+指标：
+  tb_push_messages_sent_total            counter    发送成功的消息数（--dry-run 时为生成的负载数）
+  tb_push_messages_failed_total{class}   counter    发送失败的消息数，class 为状态码类别（4xx、5xx）
+                                                    或 network、payload
+  tb_push_bytes_sent_total               counter    发送成功的请求体字节数
+  tb_push_in_flight                      gauge      正在进行中的发送数
+  tb_push_last_send_timestamp_seconds    gauge      最近一次发送成功的Unix时间戳（秒），尚未成功时为0
+  tb_push_send_latency_seconds           histogram  每次发送的耗时（秒，含失败）";
+
+/// Prometheus指标
+///
+/// 发送循环更新，`/metrics` 端点读取，全部使用原子变量，两边互不阻塞
+#[derive(Default)]
+pub struct Metrics {
+    /// 发送成功的消息数
+    sent: AtomicU64,
+    /// 按类别统计的失败消息数
+    failed: Mutex<BTreeMap<String, u64>>,
+    /// 发送成功的请求体字节数
+    bytes: AtomicU64,
+    /// 正在进行中的发送数
+    pub in_flight: AtomicU64,
+    /// 最近一次发送成功的Unix时间戳（毫秒）
+    last_send_ms: AtomicU64,
+    /// 各延迟桶（不含+Inf）的计数，非累积
+    latency_buckets: [AtomicU64; METRICS_LATENCY_BUCKETS.len()],
+    /// 延迟总和（微秒）
+    latency_sum_micros: AtomicU64,
+    /// 延迟样本数
+    latency_count: AtomicU64,
+}
+
+impl Metrics {
+    /// 记录一次发送的结果，跳过的空记录只计入延迟
+    pub fn record(&self, result: &PushResult<Option<SendReport>>, latency: Duration) {
+        match result {
+            Ok(Some(report)) => {
+                self.sent.fetch_add(1, Ordering::Relaxed);
+                self.bytes.fetch_add(report.bytes as u64, Ordering::Relaxed);
+                let now_ms = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_millis() as u64);
+                self.last_send_ms.store(now_ms, Ordering::Relaxed);
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let class = match e.status_code() {
+                    Some(code) => format!("{}xx", code / 100),
+                    None => e.kind().to_string(),
+                };
+                *self.failed.lock().unwrap().entry(class).or_default() += 1;
+            }
+        }
+
+        let seconds = latency.as_secs_f64();
+        if let Some(bucket) = METRICS_LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+            self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        }
+        self.latency_sum_micros.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+        self.latency_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 按Prometheus文本格式（0.0.4）输出全部指标
+    fn render(&self) -> String {
+        use std::fmt::Write as _;
+
+        let mut out = String::new();
+        let header = |out: &mut String, name: &str, kind: &str, help: &str| {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+        };
+
+        header(&mut out, "tb_push_messages_sent_total", "counter", "Messages sent successfully.");
+        let _ = writeln!(out, "tb_push_messages_sent_total {}", self.sent.load(Ordering::Relaxed));
+
+        header(&mut out, "tb_push_messages_failed_total", "counter", "Messages that failed to send, by status class.");
+        // 常见类别即使为0也输出，便于直接用 rate() 告警
+        let mut failed: BTreeMap<String, u64> =
+            ["4xx", "5xx", "network", "payload"].into_iter().map(|class| (class.to_string(), 0)).collect();
+        failed.extend(self.failed.lock().unwrap().iter().map(|(class, count)| (class.clone(), *count)));
+        for (class, count) in &failed {
+            let _ = writeln!(out, "tb_push_messages_failed_total{{class=\"{}\"}} {}", class, count);
+        }
+
+        header(&mut out, "tb_push_bytes_sent_total", "counter", "Request body bytes sent successfully.");
+        let _ = writeln!(out, "tb_push_bytes_sent_total {}", self.bytes.load(Ordering::Relaxed));
+
+        header(&mut out, "tb_push_in_flight", "gauge", "Sends currently in progress.");
+        let _ = writeln!(out, "tb_push_in_flight {}", self.in_flight.load(Ordering::Relaxed));
+
+        header(&mut out, "tb_push_last_send_timestamp_seconds", "gauge", "Unix time of the last successful send.");
+        let last_send_ms = self.last_send_ms.load(Ordering::Relaxed);
+        let _ = writeln!(out, "tb_push_last_send_timestamp_seconds {:.3}", last_send_ms as f64 / 1000.0);
+
+        header(&mut out, "tb_push_send_latency_seconds", "histogram", "Send latency in seconds, including failures.");
+        let mut cumulative = 0;
+        for (le, bucket) in METRICS_LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "tb_push_send_latency_seconds_bucket{{le=\"{}\"}} {}", le, cumulative);
+        }
+        let count = self.latency_count.load(Ordering::Relaxed);
+        let _ = writeln!(out, "tb_push_send_latency_seconds_bucket{{le=\"+Inf\"}} {}", count);
+        let sum = self.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let _ = writeln!(out, "tb_push_send_latency_seconds_sum {}", sum);
+        let _ = writeln!(out, "tb_push_send_latency_seconds_count {}", count);
+        out
+    }
+}
+
+/// StatsD（DogStatsD格式）指标上报
+///
+/// 每次发送后通过UDP发出计数和耗时，非阻塞发送且忽略所有错误，
+/// sidecar不可用时不影响发送循环
+pub struct StatsdClient {
+    /// 已连接到sidecar地址的非阻塞UDP套接字
+    socket: std::net::UdpSocket,
+    /// 指标名前缀
+    pub prefix: String,
+}
+
+impl StatsdClient {
+    /// 创建上报客户端，启动时解析地址，地址无效时立即报错
+    ///
+    /// # 参数
+    ///
+    /// * `addr` - sidecar地址，形如 `host:port`
+    /// * `prefix` - 指标名前缀
+    ///
+    /// # 错误
+    ///
+    /// 当地址无法解析或套接字无法创建时返回错误
+    pub fn connect(addr: &str, prefix: &str) -> PushResult<Self> {
+        let target = std::net::ToSocketAddrs::to_socket_addrs(addr)
+            .ok()
+            .and_then(|mut addrs| addrs.next())
+            .ok_or_else(|| PushError::ConfigInvalid(t!("statsd.resolve_failed", addr)))?;
+        let local = if target.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        let socket_failed = |source| PushError::Io { message: t!("statsd.socket_failed"), source };
+        let socket = std::net::UdpSocket::bind(local).map_err(socket_failed)?;
+        socket
+            .connect(target)
+            .map_err(|source| PushError::Io { message: t!("statsd.resolve_failed", addr), source })?;
+        socket.set_nonblocking(true).map_err(socket_failed)?;
+        Ok(StatsdClient {
+            socket,
+            prefix: prefix.trim_end_matches('.').to_string(),
+        })
+    }
+
+    /// 上报一次发送的结果，跳过的空记录不上报
+    ///
+    /// 计数指标 `{prefix}.send` 带 `result`（ok/failed）和 `status_class`（2xx、5xx、network等）标签，
+    /// 耗时指标为 `{prefix}.latency`（毫秒）
+    pub fn record(&self, result: &PushResult<Option<SendReport>>, latency: Duration) {
+        let (outcome, class) = match result {
+            Ok(None) => return,
+            Ok(Some(report)) if report.status_code == 0 => ("ok", "dry_run".to_string()),
+            Ok(Some(report)) => ("ok", format!("{}xx", report.status_code / 100)),
+            Err(e) => (
+                "failed",
+                match e.status_code() {
+                    Some(code) => format!("{}xx", code / 100),
+                    None => e.kind().to_string(),
+                },
+            ),
+        };
+        let payload = format!(
+            "{prefix}.send:1|c|#result:{},status_class:{}\n{prefix}.latency:{:.3}|ms",
+            outcome,
+            class,
+            latency.as_secs_f64() * 1000.0,
+            prefix = self.prefix
+        );
+        // 发后即忘：缓冲区满、sidecar未启动等错误一律忽略
+        let _ = self.socket.send(payload.as_bytes());
+    }
+}
+
+/// 提供 `/metrics` 端点的最小HTTP服务
+///
+/// 每个连接在独立的任务中处理，只响应 `GET /metrics`，其余路径返回404
+pub async fn serve_metrics(listener: tokio::net::TcpListener, metrics: Arc<Metrics>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let metrics = Arc::clone(&metrics);
+                tokio::spawn(async move {
+                    if let Err(e) = handle_metrics_request(stream, &metrics).await {
+                        tracing::debug!("{}", t!("metrics.request_failed", e));
+                    }
+                });
+            }
+            Err(e) => warn!("{}", t!("metrics.accept_failed", e)),
+        }
+    }
+}
+
+/// 处理一个指标端点的HTTP请求
+pub async fn handle_metrics_request(mut stream: tokio::net::TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // 只需要请求行，读到请求头结束或缓冲区满即可
+    let mut buffer = [0u8; 4096];
+    let mut len = 0;
+    while len < buffer.len() {
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer[len..]))
+            .await
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))??;
+        if read == 0 {
+            break;
+        }
+        len += read;
+        if buffer[..len].windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+    }
+    let request = String::from_utf8_lossy(&buffer[..len]);
+    let mut parts = request.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", "text/plain; version=0.0.4", metrics.render()),
+        _ => ("404 Not Found", "text/plain", "not found\n".to_string()),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// CSV发送报告中的一行，对应一次发送尝试
+#[derive(Debug, Serialize)]
+pub struct ReportRow<'a> {
+    /// 记录时间
+    timestamp: String,
+    /// 轮次（从1开始）
+    round: u64,
+    /// 数据项序号（从0开始）
+    item_index: usize,
+    /// 打码后的设备令牌
+    device: &'a str,
+    /// 结果：ok、failed、skipped，演练模式下成功为 generated
+    status: &'static str,
+    /// HTTP状态码，没有收到响应时为空
+    http_code: Option<u16>,
+    /// 耗时（毫秒）
+    latency_ms: u64,
+    /// 请求体字节数
+    bytes: usize,
+    /// 错误信息
+    error: Option<String>,
+}
+
+/// CSV发送报告
+///
+/// 运行过程中逐行写入并立即刷新，可以直接用Excel打开
+pub struct CsvReport {
+    /// 报告文件路径
+    pub path: String,
+    /// CSV写入器
+    writer: csv::Writer<fs::File>,
+    /// 已写入的数据行数（不含表头）
+    pub rows: u64,
+}
+
+impl CsvReport {
+    /// 创建报告文件，已存在时覆盖
+    ///
+    /// 文件开头写入UTF-8 BOM，使Excel能正确识别中文错误信息
+    ///
+    /// # 错误
+    ///
+    /// 当文件无法创建时返回错误
+    pub fn create(path: &str) -> PushResult<Self> {
+        let mut file = fs::File::create(path)
+            .map_err(|source| PushError::Io { message: t!("report.create_failed", path), source })?;
+        file.write_all("\u{FEFF}".as_bytes())
+            .map_err(|source| PushError::Io { message: t!("report.write_failed", path), source })?;
+        Ok(CsvReport {
+            path: path.to_string(),
+            writer: csv::Writer::from_writer(file),
+            rows: 0,
+        })
+    }
+
+    /// 写入一次发送尝试的结果
+    ///
+    /// 写入失败只输出警告，不中断发送
+    ///
+    /// # 参数
+    ///
+    /// * `round` - 轮次
+    /// * `item_index` - 数据项序号
+    /// * `device` - 打码后的设备令牌
+    /// * `latency` - 本次发送耗时
+    /// * `result` - 发送结果
+    /// * `dry_run` - 是否为演练模式
+    pub fn record(
+        &mut self,
+        round: u64,
+        item_index: usize,
+        device: &str,
+        latency: Duration,
+        result: &PushResult<Option<SendReport>>,
+        dry_run: bool,
+    ) {
+        let (status, http_code, bytes, error) = match result {
+            Ok(None) => ("skipped", None, 0, None),
+            Ok(Some(report)) if dry_run => ("generated", None, report.bytes, None),
+            Ok(Some(report)) => ("ok", Some(report.status_code), report.bytes, None),
+            Err(e) => (
+                "failed",
+                e.status_code(),
+                0,
+                Some(redact_url_tokens(&e.detail())),
+            ),
+        };
+        let row = ReportRow {
+            timestamp: Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%:z").to_string(),
+            round,
+            item_index,
+            device,
+            status,
+            http_code,
+            latency_ms: latency.as_millis() as u64,
+            bytes,
+            error,
+        };
+        match self.writer.serialize(&row).and_then(|_| self.writer.flush().map_err(csv::Error::from)) {
+            Ok(()) => self.rows += 1,
+            Err(e) => warn!("{}", t!("report.row_failed", self.path, e)),
+        }
+    }
+}
+
+/// `--tee` 数据副本
+///
+/// 以JSON Lines格式逐条写入发送成功的遥测数据，内容与请求体相同，另加打码后的设备令牌，
+/// 每条写入后立即刷新
+pub struct TeeFile {
+    /// 副本文件路径
+    pub path: String,
+    /// 文件写入器
+    writer: std::io::BufWriter<fs::File>,
+    /// 已写入的条数
+    pub lines: u64,
+}
+
+impl TeeFile {
+    /// 创建副本文件，已存在时覆盖
+    ///
+    /// # 错误
+    ///
+    /// 当文件无法创建时返回错误
+    pub fn create(path: &str) -> PushResult<Self> {
+        let file = fs::File::create(path)
+            .map_err(|source| PushError::Io { message: t!("tee.create_failed", path), source })?;
+        Ok(TeeFile {
+            path: path.to_string(),
+            writer: std::io::BufWriter::new(file),
+            lines: 0,
+        })
+    }
+
+    /// 写入一条已发送的遥测数据，写入失败只输出警告，不中断发送
+    ///
+    /// # 参数
+    ///
+    /// * `device` - 打码后的设备令牌
+    /// * `telemetry` - 已发送的遥测数据
+    pub fn record(&mut self, device: &str, telemetry: &TelemetryData) {
+        // 扁平请求体没有时间戳，副本中仍按 ts/values 结构记录，避免数据字段与 device 混在一起
+        let line = match telemetry.shape {
+            PayloadShape::Flat => Ok(serde_json::json!({"ts": telemetry.ts, "values": telemetry.values})),
+            _ => serde_json::to_value(telemetry),
+        };
+        let result = line
+            .map(|mut line| {
+                if let Value::Object(ref mut fields) = line {
+                    fields.insert("device".to_string(), Value::from(device));
+                }
+                line
+            })
+            .and_then(|line| serde_json::to_writer(&mut self.writer, &line))
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"))
+            .and_then(|_| self.writer.flush());
+        match result {
+            Ok(()) => self.lines += 1,
+            Err(e) => warn!("{}", t!("tee.write_failed", self.path, e)),
+        }
+    }
+}
+
+/// 整个运行期间的发送统计
+///
+/// 延迟记录在HDR直方图中，内存占用固定，无限循环运行也不会随发送次数增长
+pub struct RunStats {
+    /// 尝试发送的条数
+    pub attempted: u64,
+    /// 发送成功的条数
+    pub succeeded: u64,
+    /// 发送失败的条数
+    pub failed: u64,
+    /// 因记录为空被跳过的条数
+    pub skipped: u64,
+    /// 发送失败后重新入队的次数，见 `--requeue`
+    pub requeued: u64,
+    /// 重新入队后发送成功的数据项此前失败的次数，这些失败不影响退出码
+    pub recovered: u64,
+    /// 按失败原因（HTTP状态码或错误类型）分组的失败次数
+    failures: BTreeMap<String, u64>,
+    /// 第一次失败的错误信息
+    pub first_error: Option<String>,
+    /// 最近一次失败的错误信息
+    pub last_error: Option<String>,
+    /// 成功发送的请求体总字节数
+    bytes: u64,
+    /// 每次请求的延迟分布（微秒）
+    latency: Histogram<u64>,
+    /// 服务器时间与本地发送时间之差的统计
+    server_offset: OffsetStats,
+    /// 是否为演练模式，演练时成功数表示生成的负载条数
+    pub dry_run: bool,
+}
+
+impl RunStats {
+    /// 创建空的统计，延迟可记录范围为1微秒到1小时
+    pub fn new(dry_run: bool) -> Self {
+        RunStats {
+            attempted: 0,
+            succeeded: 0,
+            failed: 0,
+            skipped: 0,
+            requeued: 0,
+            recovered: 0,
+            failures: BTreeMap::new(),
+            first_error: None,
+            last_error: None,
+            bytes: 0,
+            // 范围和精度都是常量，总是有效
+            latency: Histogram::new_with_bounds(1, 3_600_000_000, 3).unwrap(),
+            server_offset: OffsetStats::default(),
+            dry_run,
+        }
+    }
+
+    /// 记录一次成功发送
+    ///
+    /// # 参数
+    ///
+    /// * `latency` - 本次请求耗时
+    /// * `bytes` - 请求体字节数
+    /// * `server_offset_ms` - 服务器时间与本地发送时间之差，没有 `Date` 头时为None
+    /// * `points` - 请求携带的数据点数，逐条发送时为1，合并时序模式下为本批的数据点数
+    pub fn record_success(&mut self, latency: Duration, bytes: usize, server_offset_ms: Option<i64>, points: u64) {
+        self.succeeded += points;
+        self.bytes += bytes as u64;
+        self.latency.saturating_record(latency.as_micros() as u64);
+        if let Some(offset) = server_offset_ms {
+            self.server_offset.record(offset);
+        }
+    }
+
+    /// 记录一次失败发送
+    ///
+    /// # 参数
+    ///
+    /// * `latency` - 本次请求耗时
+    /// * `reason` - 失败原因分组，如 `HTTP 500`、`network`
+    /// * `error` - 完整的错误信息
+    /// * `points` - 请求携带的数据点数，逐条发送时为1
+    pub fn record_failure(&mut self, latency: Duration, reason: String, error: String, points: u64) {
+        self.failed += points;
+        *self.failures.entry(reason).or_default() += points;
+        if self.first_error.is_none() {
+            self.first_error = Some(error.clone());
+        }
+        self.last_error = Some(error);
+        self.latency.saturating_record(latency.as_micros() as u64);
+    }
+
+    /// 重新入队后仍未发送成功的失败次数
+    pub fn unrecovered(&self) -> u64 {
+        self.failed - self.recovered
+    }
+
+    /// 记录一条被跳过的空记录
+    pub fn record_skip(&mut self) {
+        self.skipped += 1;
+    }
+
+    /// 把统计结果转换为传给 `--on-complete` 命令的环境变量
+    ///
+    /// # 参数
+    ///
+    /// * `elapsed` - 总耗时
+    pub fn env_vars(&self, elapsed: Duration) -> Vec<(&'static str, String)> {
+        let p95_ms = if self.latency.is_empty() { 0 } else { self.latency.value_at_quantile(0.95) / 1000 };
+        vec![
+            ("PUSH_ATTEMPTED", self.attempted.to_string()),
+            ("PUSH_SUCCEEDED", self.succeeded.to_string()),
+            ("PUSH_FAILED", self.failed.to_string()),
+            ("PUSH_SKIPPED", self.skipped.to_string()),
+            ("PUSH_BYTES", self.bytes.to_string()),
+            ("PUSH_ELAPSED_MS", elapsed.as_millis().to_string()),
+            ("PUSH_LATENCY_P95_MS", p95_ms.to_string()),
+        ]
+    }
+
+    /// `--summary-json` 中的统计部分：各类条数、失败原因分布和延迟分位数
+    ///
+    /// 没有延迟样本时延迟各项为null
+    pub fn summary_json(&self, elapsed: Duration) -> Value {
+        let elapsed_secs = elapsed.as_secs_f64();
+        let ms = |micros: u64| micros as f64 / 1000.0;
+        let latency = (!self.latency.is_empty()).then(|| {
+            serde_json::json!({
+                "min": ms(self.latency.min()),
+                "mean": self.latency.mean() / 1000.0,
+                "p50": ms(self.latency.value_at_quantile(0.5)),
+                "p95": ms(self.latency.value_at_quantile(0.95)),
+                "p99": ms(self.latency.value_at_quantile(0.99)),
+                "max": ms(self.latency.max()),
+            })
+        });
+        let completed = self.succeeded + self.failed;
+        serde_json::json!({
+            "counts": {
+                "attempted": self.attempted,
+                "sent": if self.dry_run { 0 } else { self.succeeded },
+                "generated": self.succeeded,
+                "failed": self.failed,
+                "skipped": self.skipped,
+                "requeued": self.requeued,
+                "recovered": self.recovered,
+            },
+            "success_rate": if completed > 0 { self.succeeded as f64 / completed as f64 } else { 1.0 },
+            "bytes": self.bytes,
+            "elapsed_ms": elapsed.as_millis() as u64,
+            "rate": if elapsed_secs > 0.0 { self.succeeded as f64 / elapsed_secs } else { 0.0 },
+            "failures": self.failures,
+            "latency_ms": latency,
+        })
+    }
+
+    /// 输出运行结束时的统计汇总
+    ///
+    /// # 参数
+    ///
+    /// * `elapsed` - 运行总耗时
+    /// * `interrupted` - 是否被Ctrl-C中断
+    pub fn print_summary(&self, elapsed: Duration, interrupted: bool) {
+        let elapsed_secs = elapsed.as_secs_f64();
+        let rate = if elapsed_secs > 0.0 { self.succeeded as f64 / elapsed_secs } else { 0.0 };
+        let ms = |micros: u64| micros as f64 / 1000.0;
+        let recorded = !self.latency.is_empty();
+        let percentile = |q: f64| if recorded { ms(self.latency.value_at_quantile(q)) } else { 0.0 };
+        let (min, mean, max) = if recorded {
+            (ms(self.latency.min()), self.latency.mean() / 1000.0, ms(self.latency.max()))
+        } else {
+            (0.0, 0.0, 0.0)
+        };
+
+        let generated = self.succeeded;
+        let sent = if self.dry_run { 0 } else { self.succeeded };
+        let message = match (interrupted, self.dry_run) {
+            (true, false) => t!("summary.interrupted", sent),
+            (false, false) => t!("summary.done", sent),
+            (true, true) => t!("summary.dry_run_interrupted", generated),
+            (false, true) => t!("summary.dry_run_done", generated),
+        };
+        info!(
+            event = "summary",
+            attempted = self.attempted,
+            sent,
+            generated,
+            dry_run = self.dry_run,
+            failed = self.failed,
+            skipped = self.skipped,
+            requeued = self.requeued,
+            recovered = self.recovered,
+            bytes = self.bytes,
+            elapsed_ms = elapsed.as_millis() as u64,
+            rate,
+            latency_min_ms = min,
+            latency_mean_ms = mean,
+            latency_p50_ms = percentile(0.5),
+            latency_p95_ms = percentile(0.95),
+            latency_p99_ms = percentile(0.99),
+            latency_max_ms = max,
+            server_offset_min_ms = self.server_offset.min(),
+            server_offset_mean_ms = self.server_offset.mean(),
+            server_offset_max_ms = self.server_offset.max(),
+            interrupted,
+            "{}",
+            message
+        );
+        let (counts_key, bytes_key) = if self.dry_run {
+            ("summary.counts_dry_run", "summary.bytes_dry_run")
+        } else {
+            ("summary.counts", "summary.bytes")
+        };
+        info!("{}", t!(counts_key, self.attempted, self.succeeded, self.failed, self.skipped));
+        info!(
+            "{}",
+            t!(bytes_key, self.bytes, format!("{:.1}", elapsed_secs), format!("{:.2}", rate))
+        );
+        // 演练时没有网络请求，延迟没有参考意义
+        if recorded && !self.dry_run {
+            let latencies = [min, mean, percentile(0.5), percentile(0.95), percentile(0.99), max]
+                .map(|value| format!("{:.1}", value));
+            let [min, mean, p50, p95, p99, max] = &latencies;
+            info!("{}", t!("summary.latency", min, mean, p50, p95, p99, max));
+        }
+        if let (Some(min), Some(mean), Some(max)) =
+            (self.server_offset.min(), self.server_offset.mean(), self.server_offset.max())
+        {
+            info!(
+                "{}",
+                t!("summary.server_offset", min, format!("{:.0}", mean), max, self.server_offset.count)
+            );
+        }
+        if self.requeued > 0 {
+            info!("{}", t!("summary.requeued", self.requeued, self.recovered));
+        }
+        if !self.failures.is_empty() {
+            info!("{}", t!("summary.failures"));
+            for (reason, times) in &self.failures {
+                info!("{}", t!("summary.failure_reason", reason, times));
+            }
+        }
+    }
+}
+
+/// 服务器时间差的累计统计，只保存汇总值，内存占用固定
+#[derive(Debug, Default)]
+pub struct OffsetStats {
+    /// 样本数
+    count: u64,
+    /// 差值总和（毫秒）
+    sum: i64,
+    /// 最小差值（毫秒）
+    min: i64,
+    /// 最大差值（毫秒）
+    max: i64,
+}
+
+impl OffsetStats {
+    /// 记录一个差值样本
+    fn record(&mut self, offset_ms: i64) {
+        if self.count == 0 {
+            self.min = offset_ms;
+            self.max = offset_ms;
+        } else {
+            self.min = self.min.min(offset_ms);
+            self.max = self.max.max(offset_ms);
+        }
+        self.count += 1;
+        self.sum += offset_ms;
+    }
+
+    /// 最小差值，没有样本时为None
+    fn min(&self) -> Option<i64> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    /// 最大差值，没有样本时为None
+    fn max(&self) -> Option<i64> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// 平均差值，没有样本时为None
+    fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum as f64 / self.count as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_stats_reset_after_each_window() {
+        let window = WindowStats::new(false);
+        window.record(true, Duration::from_millis(10));
+        window.record(false, Duration::from_millis(30));
+        {
+            let current = window.current.lock().unwrap();
+            assert_eq!((current.done, current.failed), (2, 1));
+            assert_eq!((current.latency_sum, current.latency_max), (Duration::from_millis(40), Duration::from_millis(30)));
+        }
+        window.emit();
+        let current = window.current.lock().unwrap();
+        assert_eq!((current.done, current.failed, current.latency_max), (0, 0, Duration::ZERO));
+    }
+}
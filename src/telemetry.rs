@@ -0,0 +1,1193 @@
+//! 遥测内容：字段提取、重命名、条件、合理范围和请求体结构
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use chrono::Local;
+use tracing::warn;
+use tokio::time::Duration;
+use rand::Rng;
+use crate::error::{PushError, PushResult};
+use crate::random::generate_random_value;
+
+/// 提取结果为空的记录的处理策略
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyPolicy {
+    /// 视为发送失败（默认，兼容原有行为）
+    #[default]
+    Error,
+    /// 跳过该条记录继续发送
+    Skip,
+    /// 照常发送，数据部分为空对象（仍带有发送时间等注入字段）
+    Send,
+}
+/// 阶跃注入的触发条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectTrigger {
+    /// 从第 N 次发送开始（从1计数）
+    AtSend(u64),
+    /// 从运行开始后第 N 秒开始
+    AfterSecs(u64),
+}
+
+/// 数据值的阶跃变化注入
+///
+/// 在指定的发送次数或时间点把某字段临时设为固定值，持续若干次后恢复正常，
+/// 用于制造异常事件来验证ThingsBoard的规则链和告警
+#[derive(Debug, Clone)]
+pub struct Injection {
+    /// 被注入的字段名称
+    pub field: String,
+    /// 注入的值
+    pub value: Value,
+    /// 触发条件
+    pub trigger: InjectTrigger,
+    /// 持续的发送次数
+    pub times: u64,
+    /// 已经注入的次数
+    pub applied: u64,
+}
+
+impl Injection {
+    /// 判断本次发送是否应当注入
+    ///
+    /// # 参数
+    ///
+    /// * `attempt` - 本次是第几次发送（从1计数）
+    /// * `elapsed` - 距运行开始经过的时间
+    pub fn is_active(&self, attempt: u64, elapsed: Duration) -> bool {
+        if self.applied >= self.times {
+            return false;
+        }
+        match self.trigger {
+            InjectTrigger::AtSend(n) => attempt >= n,
+            InjectTrigger::AfterSecs(secs) => elapsed.as_secs() >= secs,
+        }
+    }
+}
+
+/// 遥测请求体的结构
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PayloadShape {
+    /// `{"ts", "values", "time"}`，顶层附带发送时间字符串（默认，兼容旧版本）
+    #[default]
+    Legacy,
+    /// 只有文档中的 `{"ts", "values"}`
+    Strict,
+    /// 只发送 `values` 对象本身，由服务器分配时间戳
+    Flat,
+}
+
+/// ThingsBoard遥测数据结构体
+///
+/// 符合ThingsBoard API要求的遥测数据格式，序列化结果由 `shape` 决定
+#[derive(Debug)]
+pub struct TelemetryData {
+    /// 时间戳（毫秒）
+    pub ts: u64,
+    /// 遥测数据键值对
+    pub values: HashMap<String, Value>,
+    /// 发送时间
+    pub time: String,
+    /// 序列化时发送时间使用的顶层字段名，None表示不输出；只在 [`PayloadShape::Legacy`] 下使用
+    pub time_key: Option<String>,
+    /// 请求体结构
+    pub shape: PayloadShape,
+}
+
+impl Serialize for TelemetryData {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        if self.shape == PayloadShape::Flat {
+            return self.values.serialize(serializer);
+        }
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("ts", &self.ts)?;
+        map.serialize_entry("values", &self.values)?;
+        if let (PayloadShape::Legacy, Some(key)) = (self.shape, &self.time_key) {
+            map.serialize_entry(key, &self.time)?;
+        }
+        map.end()
+    }
+}
+
+/// 字段分组
+///
+/// 对应包装对象格式数据文件中 `field_groups` 的一项，组内字段按各自的间隔发送，
+/// 未列入任何分组的字段每次都发送
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldGroup {
+    /// 发送间隔（秒）
+    pub interval: f64,
+    /// 组内字段（数据记录中的原始字段名）
+    pub fields: Vec<String>,
+}
+
+/// 按字段分组的发送间隔决定每次发送包含哪些字段
+///
+/// 每个数据项的每个分组分别记录下次到期的时刻，时刻以运行开始后的时长表示，
+/// 到期后按间隔顺延；落后超过一个间隔时从当前时刻重新计时，不会连续补发
+pub struct FieldScheduler {
+    /// 分组名称及配置
+    groups: Vec<(String, FieldGroup)>,
+    /// (数据项序号, 分组序号) 下次到期的时刻
+    next_due: HashMap<(usize, usize), Duration>,
+}
+
+impl FieldScheduler {
+    /// 创建调度器
+    pub fn new(groups: &BTreeMap<String, FieldGroup>) -> Self {
+        FieldScheduler {
+            groups: groups.iter().map(|(name, group)| (name.clone(), group.clone())).collect(),
+            next_due: HashMap::new(),
+        }
+    }
+
+    /// 返回本次发送时未到期、需要省略的字段，并把到期的分组顺延到下一个间隔
+    ///
+    /// # 参数
+    ///
+    /// * `item_index` - 数据项序号
+    /// * `now` - 运行开始后的时长
+    pub fn omitted(&mut self, item_index: usize, now: Duration) -> HashSet<String> {
+        let mut omitted = HashSet::new();
+        for (group_index, (_, group)) in self.groups.iter().enumerate() {
+            let interval = Duration::from_secs_f64(group.interval);
+            let next_due = self.next_due.entry((item_index, group_index)).or_insert(now);
+            if now < *next_due {
+                omitted.extend(group.fields.iter().cloned());
+                continue;
+            }
+            *next_due += interval;
+            if *next_due <= now {
+                *next_due = now + interval;
+            }
+        }
+        omitted
+    }
+}
+
+/// 解析字段重命名映射
+///
+/// 每个配置可以是逗号分隔的 `old=new` 列表，也可以是JSON对象（如 `{"temp":"temperature"}`），
+/// 多个配置合并为一个映射
+///
+/// # 参数
+///
+/// * `specs` - 命令行中的各个 `--rename` 值
+///
+/// # 返回值
+///
+/// * `PushResult<HashMap<String, String>>` - 成功时返回原名到新名的映射，失败时返回错误信息
+///
+/// # 错误
+///
+/// 格式错误、同一字段映射到不同新名或多个字段映射到同一新名时返回错误
+pub fn parse_rename<'a>(specs: impl IntoIterator<Item = &'a String>) -> PushResult<HashMap<String, String>> {
+    let mut rename: HashMap<String, String> = HashMap::new();
+    for spec in specs {
+        let pairs: Vec<(String, String)> = if spec.trim_start().starts_with('{') {
+            serde_json::from_str::<serde_json::Map<String, Value>>(spec)
+                .map_err(|e| PushError::ConfigInvalid(t!("rename.json_invalid", spec, e)))?
+                .into_iter()
+                .map(|(old, new)| match new {
+                    Value::String(new) => Ok((old, new)),
+                    other => Err(PushError::ConfigInvalid(t!("rename.json_not_string", old, other))),
+                })
+                .collect::<PushResult<_>>()?
+        } else {
+            spec.split(',')
+                .map(str::trim)
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    pair.split_once('=')
+                        .map(|(old, new)| (old.trim().to_string(), new.trim().to_string()))
+                        .ok_or_else(|| PushError::ConfigInvalid(t!("rename.pair_invalid", pair)))
+                })
+                .collect::<PushResult<_>>()?
+        };
+        for (old, new) in pairs {
+            if old.is_empty() || new.is_empty() {
+                return Err(PushError::ConfigInvalid(t!("rename.empty_name", old, new)));
+            }
+            if let Some(existing) = rename.get(&old)
+                && *existing != new
+            {
+                return Err(PushError::ConfigInvalid(t!("rename.conflicting_targets", old, existing, new)));
+            }
+            rename.insert(old, new);
+        }
+    }
+    let mut targets: HashMap<&str, &str> = HashMap::new();
+    for (old, new) in rename.iter().collect::<BTreeMap<_, _>>() {
+        if let Some(other) = targets.insert(new, old) {
+            return Err(PushError::ConfigInvalid(t!("rename.duplicate_target", other, old, new)));
+        }
+    }
+    Ok(rename)
+}
+
+/// 解析阶跃注入配置
+///
+/// 格式为 `FIELD=VALUE@WHEN[xTIMES]`，其中 `WHEN` 为 `N`（第N次发送）或 `Ns`（开始后第N秒），
+/// `VALUE` 按JSON解析，解析失败时作为字符串处理。例如 `temperature=80@10x3`
+///
+/// # 参数
+///
+/// * `spec` - 命令行中的注入配置字符串
+///
+/// # 返回值
+///
+/// * `PushResult<Injection>` - 成功时返回注入配置，失败时返回错误信息
+pub fn parse_injection(spec: &str) -> PushResult<Injection> {
+    let (assignment, when) = spec
+        .rsplit_once('@')
+        .ok_or_else(|| PushError::ConfigInvalid(t!("inject.missing_trigger", spec)))?;
+    let (field, raw_value) = assignment
+        .split_once('=')
+        .ok_or_else(|| PushError::ConfigInvalid(t!("inject.missing_value", spec)))?;
+    if field.is_empty() {
+        return Err(PushError::ConfigInvalid(t!("inject.empty_field", spec)));
+    }
+
+    let (when, times) = match when.split_once('x') {
+        Some((when, times)) => {
+            let times: u64 = times
+                .parse()
+                .map_err(|e| PushError::ConfigInvalid(t!("inject.times_invalid", spec, e)))?;
+            (when, times)
+        }
+        None => (when, 1),
+    };
+    let trigger = match when.strip_suffix('s') {
+        Some(secs) => InjectTrigger::AfterSecs(
+            secs.parse()
+                .map_err(|e| PushError::ConfigInvalid(t!("inject.secs_invalid", spec, e)))?,
+        ),
+        None => InjectTrigger::AtSend(
+            when.parse()
+                .map_err(|e| PushError::ConfigInvalid(t!("inject.send_invalid", spec, e)))?,
+        ),
+    };
+
+    let value = serde_json::from_str(raw_value).unwrap_or_else(|_| Value::String(raw_value.to_string()));
+    Ok(Injection {
+        field: field.to_string(),
+        value,
+        trigger,
+        times,
+        applied: 0,
+    })
+}
+
+/// 把注入值写入数据项
+///
+/// 与随机字段的匹配规则一致：顶层同名字段和嵌套对象中的同名字段都会被设置
+///
+/// # 参数
+///
+/// * `item` - 要修改的数据项
+/// * `field` - 字段名称
+/// * `value` - 注入的值
+pub fn apply_injection(item: &mut Value, field: &str, value: &Value) {
+    if let Value::Object(obj) = item {
+        for (key, nested) in obj.iter_mut() {
+            if key == field {
+                *nested = value.clone();
+            } else if let Value::Object(nested_obj) = nested
+                && let Some(target) = nested_obj.get_mut(field)
+            {
+                *target = value.clone();
+            }
+        }
+    }
+}
+
+/// 从JSON数据中提取遥测值
+///
+/// 默认保持原始的JSON对象结构；配置了 `--flatten` 时，最后把嵌套对象展开为扁平键
+/// 如果提供了随机键，会在对应字段中随机修改相关值；之后省略上报条件为假的字段，
+/// 最后按重命名映射替换顶层字段名
+///
+/// # 参数
+///
+/// * `data` - 输入的JSON数据
+/// * `random_key` - 可选的随机键名称，如果存在会随机修改对应字段的值
+/// * `rules` - 字段重命名和上报条件
+/// * `rng` - 生成随机值使用的随机数生成器
+///
+/// # 返回值
+///
+/// * `PushResult<HashMap<String, Value>>` - 成功时返回遥测数据键值对，失败时返回错误信息
+///
+/// # 数据转换规则
+///
+/// 1. 对于嵌套对象（如{"rain": {...}}），会：
+///    - 保持完整的对象结构作为值
+///    - 使用顶层键名作为字段名
+///    - 如果指定了随机键，会在嵌套对象中查找并随机修改对应字段的值
+/// 2. 对于非对象值，直接使用原键值对
+/// 3. 值为时间占位标记（见 [`TIME_PLACEHOLDERS`]）的字段替换为当前时间，嵌套对象和数组中同样替换
+/// 4. 配置了上报条件的字段，条件按随机修改后的整条记录求值，为假时省略
+/// 5. 配置了合理范围的字段，越界时告警，按配置截断到边界
+/// 6. 配置了展开规则时，重命名之后把嵌套对象（可选包括数组）展开为以分隔符连接的键，
+///    如 `{"rain": {"level": 1}}` 展开为 `rain.level`
+///
+/// # 错误
+///
+/// 当输入数据不是JSON对象，或重命名、展开后出现重复字段名时返回错误。
+/// 提取结果为空时由调用方按 `--on-empty` 策略处理
+pub fn extract_telemetry_values(
+    data: &Value,
+    random_key: &Option<String>,
+    rules: &FieldRules,
+    rng: &mut impl Rng,
+) -> PushResult<HashMap<String, Value>> {
+    let mut extracted = serde_json::Map::new();
+
+    match data {
+        Value::Object(obj) => {
+            for (key, value) in obj {
+                // 如果存在随机键且当前值是对象，则尝试随机修改对应字段
+                if let (Some(random_field), Value::Object(nested_obj)) = (random_key, value) {
+                    if let Some(random_value) = nested_obj.get(random_field) {
+                        // 创建修改后的嵌套对象
+                        let mut modified_nested = nested_obj.clone();
+                        let new_random_value =
+                            generate_random_value(random_field, random_value, rules.non_finite, rng)?;
+                        match new_random_value {
+                            Some(new_random_value) => {
+                                item_info!(
+                                    "{}",
+                                    t!("send.random_modified", random_field, random_value, new_random_value)
+                                );
+                                modified_nested.insert(random_field.clone(), new_random_value);
+                            }
+                            None => {
+                                modified_nested.remove(random_field);
+                            }
+                        }
+                        
+                        extracted.insert(key.clone(), Value::Object(modified_nested));
+                    } else {
+                        // 如果没有找到随机字段，保持原始结构
+                        extracted.insert(key.clone(), value.clone());
+                    }
+                } else {
+                    // 直接使用原始的键值对，保持对象结构
+                    extracted.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        _ => {
+            return Err(PushError::payload(t!("send.not_object")));
+        }
+    }
+
+    // 填充时间占位标记，同一条记录中的标记使用同一个时刻
+    let now = Local::now();
+    for value in extracted.values_mut() {
+        fill_time_placeholders(value, &now);
+    }
+
+    // 所有条件都针对完整记录求值，再统一省略，结果与字段顺序无关
+    let omitted: Vec<String> = rules
+        .conditions
+        .iter()
+        .filter(|(field, condition)| extracted.contains_key(*field) && !condition.evaluate(&extracted))
+        .map(|(field, _)| field.clone())
+        .collect();
+    for field in &omitted {
+        extracted.remove(field);
+    }
+
+    // 随机修改之后校验合理范围，路径按 `.` 逐层进入嵌套对象
+    for (field, range) in &rules.valid_range {
+        let mut segments = field.split('.');
+        let mut current = segments.next().and_then(|first| extracted.get_mut(first));
+        for segment in segments {
+            current = current.and_then(|value| value.get_mut(segment));
+        }
+        if let Some(value) = current {
+            range.check(field, value);
+        }
+    }
+
+    let mut values = HashMap::new();
+    for (original, value) in extracted {
+        let key = rules.rename.get(&original).cloned().unwrap_or_else(|| original.clone());
+        if values.contains_key(&key) {
+            return Err(PushError::payload(t!("send.rename_conflict", original, key)));
+        }
+        values.insert(key, value);
+    }
+
+    // 随机修改之后再展开，被修改的嵌套字段以扁平键发送
+    match rules.flatten {
+        Some(ref flatten) => flatten.flatten(values),
+        None => Ok(values),
+    }
+}
+
+/// 数据记录中的时间占位标记替换成的内容
+#[derive(Debug, Clone, Copy)]
+pub enum TimePlaceholder {
+    /// 按给定格式输出的本地时间字符串
+    Format(&'static str),
+    /// 毫秒时间戳
+    Millis,
+    /// 秒级时间戳
+    Seconds,
+}
+
+/// 字段值等于这些标记时，发送前替换为当前时间；新增标记只需在此登记，未登记的标记原样发送
+pub const TIME_PLACEHOLDERS: &[(&str, TimePlaceholder)] = &[
+    ("__now__", TimePlaceholder::Format("%Y-%m-%d %H:%M:%S")),
+    ("__date__", TimePlaceholder::Format("%Y-%m-%d")),
+    ("__time__", TimePlaceholder::Format("%H:%M:%S")),
+    ("__iso__", TimePlaceholder::Format("%Y-%m-%dT%H:%M:%S%.3f%:z")),
+    ("__ts__", TimePlaceholder::Millis),
+    ("__unix__", TimePlaceholder::Seconds),
+];
+
+impl TimePlaceholder {
+    /// 按当前时刻生成替换值
+    fn render(self, now: &chrono::DateTime<Local>) -> Value {
+        match self {
+            TimePlaceholder::Format(format) => Value::from(now.format(format).to_string()),
+            TimePlaceholder::Millis => Value::from(now.timestamp_millis()),
+            TimePlaceholder::Seconds => Value::from(now.timestamp()),
+        }
+    }
+}
+
+/// 把值中的时间占位标记替换为当前时间，递归进入嵌套对象和数组
+///
+/// # 参数
+///
+/// * `value` - 要替换的值
+/// * `now` - 当前时刻，同一条记录中的标记使用同一个时刻
+pub fn fill_time_placeholders(value: &mut Value, now: &chrono::DateTime<Local>) {
+    match value {
+        Value::String(text) => {
+            if let Some((_, placeholder)) = TIME_PLACEHOLDERS.iter().find(|(marker, _)| marker == text) {
+                *value = placeholder.render(now);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| fill_time_placeholders(item, now)),
+        Value::Object(fields) => fields.values_mut().for_each(|field| fill_time_placeholders(field, now)),
+        _ => {}
+    }
+}
+
+/// 提取遥测值时对顶层字段应用的规则
+#[derive(Debug, Clone, Default)]
+pub struct FieldRules {
+    /// 字段重命名映射（原名 -> 新名），未列出的字段保持原名
+    pub rename: HashMap<String, String>,
+    /// 字段的上报条件（数据文件中的 "conditions"），按原名索引，求值为假时省略该字段
+    pub conditions: BTreeMap<String, Condition>,
+    /// 字段的合理范围（数据文件中的 "valid_range"），按原名路径索引，越界时告警或截断
+    pub valid_range: BTreeMap<String, ValidRange>,
+    /// 把嵌套对象展开为带分隔符的扁平键，None表示保持嵌套结构
+    pub flatten: Option<FlattenRules>,
+    /// 生成的字段值为 NaN 或无穷大时的处理策略
+    pub non_finite: NonFinitePolicy,
+}
+
+/// 字段值为 NaN 或无穷大时的处理策略
+///
+/// JSON没有这些值，`serde_json` 转换时会失败或悄悄变成null，因此在浮点数写入记录时统一处理
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinitePolicy {
+    /// 省略该字段并输出警告（默认）
+    #[default]
+    Drop,
+    /// 以null发送
+    Null,
+    /// 视为本条记录发送失败
+    Error,
+    /// 无穷大取 ±`f64::MAX`；NaN没有对应的有限值，仍省略并警告
+    Clamp,
+}
+
+impl NonFinitePolicy {
+    /// 把浮点数转换为JSON值
+    ///
+    /// # 参数
+    ///
+    /// * `field` - 字段名，用于警告和错误信息
+    /// * `value` - 浮点数值
+    ///
+    /// # 返回值
+    ///
+    /// * `PushResult<Option<Value>>` - 有限值原样转换；非有限值按策略处理，None表示省略该字段
+    ///
+    /// # 错误
+    ///
+    /// 值不是有限数且策略为 `error` 时返回错误
+    pub fn convert(self, field: &str, value: f64) -> PushResult<Option<Value>> {
+        let value = match self {
+            _ if value.is_finite() => value,
+            NonFinitePolicy::Null => return Ok(Some(Value::Null)),
+            NonFinitePolicy::Error => return Err(PushError::payload(t!("send.non_finite", field, value))),
+            NonFinitePolicy::Clamp if value.is_infinite() => f64::MAX.copysign(value),
+            NonFinitePolicy::Drop | NonFinitePolicy::Clamp => {
+                warn!("{}", t!("send.non_finite_dropped", field, value));
+                return Ok(None);
+            }
+        };
+        Ok(serde_json::Number::from_f64(value).map(Value::Number))
+    }
+}
+
+/// 字段的合理范围，对应数据文件中 `valid_range` 的一项
+///
+/// 用于在生成阶段发现不合理的随机配置（如湿度超过100%），只校验数字值
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ValidRange {
+    /// 下限，未指定时不限
+    pub min: Option<f64>,
+    /// 上限，未指定时不限
+    pub max: Option<f64>,
+    /// 越界时是否截断到边界，否则只告警并按原值发送
+    #[serde(default)]
+    pub clamp: bool,
+}
+
+impl ValidRange {
+    /// 校验字段值，越界时告警，配置了截断时改写为最近的边界
+    ///
+    /// 整数值截断到整数边界时保持整数类型
+    fn check(&self, field: &str, value: &mut Value) {
+        let Some(number) = value.as_f64() else {
+            return;
+        };
+        let bound = match (self.min, self.max) {
+            (Some(min), _) if number < min => min,
+            (_, Some(max)) if number > max => max,
+            _ => return,
+        };
+        if !self.clamp {
+            warn!("{}", t!("send.out_of_range", field, value, self));
+            return;
+        }
+        warn!("{}", t!("send.out_of_range_clamped", field, value, self, bound));
+        *value = if value.is_i64() && bound.fract() == 0.0 {
+            Value::from(bound as i64)
+        } else {
+            Value::from(bound)
+        };
+    }
+}
+
+impl std::fmt::Display for ValidRange {
+    /// 以Rust区间的写法显示，如 `0..100`、`0..`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(min) = self.min {
+            write!(f, "{}", min)?;
+        }
+        write!(f, "..")?;
+        if let Some(max) = self.max {
+            write!(f, "{}", max)?;
+        }
+        Ok(())
+    }
+}
+
+/// `--flatten` 的展开方式
+#[derive(Debug, Clone)]
+pub struct FlattenRules {
+    /// 父子键之间的分隔符
+    pub separator: String,
+    /// 是否按下标展开数组（`readings.0`），否则数组整体作为一个值
+    pub arrays: bool,
+}
+
+impl Default for FlattenRules {
+    /// 以 `.` 连接父子键，数组整体作为一个值
+    fn default() -> Self {
+        FlattenRules { separator: ".".to_string(), arrays: false }
+    }
+}
+
+impl FlattenRules {
+    /// 展开一条记录的全部字段，叶子值以完整路径为键
+    ///
+    /// # 参数
+    ///
+    /// * `values` - 记录的顶层字段
+    ///
+    /// # 错误
+    ///
+    /// 展开后出现重复的键时返回错误
+    pub fn flatten(&self, values: impl IntoIterator<Item = (String, Value)>) -> PushResult<HashMap<String, Value>> {
+        let mut flat = HashMap::new();
+        for (key, value) in values {
+            self.flatten_into(key, value, &mut flat)?;
+        }
+        Ok(flat)
+    }
+
+    /// 把一个值展开写入结果，叶子值以完整路径为键
+    ///
+    /// 空对象和空数组没有叶子，按原样保留，避免数据悄悄消失
+    ///
+    /// # 错误
+    ///
+    /// 展开后的键与已有的键重名时返回错误
+    fn flatten_into(&self, key: String, value: Value, out: &mut HashMap<String, Value>) -> PushResult<()> {
+        match value {
+            Value::Object(obj) if !obj.is_empty() => {
+                for (child, nested) in obj {
+                    self.flatten_into(format!("{}{}{}", key, self.separator, child), nested, out)?;
+                }
+            }
+            Value::Array(items) if self.arrays && !items.is_empty() => {
+                for (index, nested) in items.into_iter().enumerate() {
+                    self.flatten_into(format!("{}{}{}", key, self.separator, index), nested, out)?;
+                }
+            }
+            leaf => {
+                if out.contains_key(&key) {
+                    return Err(PushError::payload(t!("send.flatten_conflict", key)));
+                }
+                out.insert(key, leaf);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 上报条件中的比较运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+}
+
+/// 字段上报条件表达式，语法见 README 的“条件上报”一节
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// 字面量（数字、字符串、true/false/null）
+    Literal(Value),
+    /// 同一记录中的字段，按 `.` 分隔逐层进入嵌套对象
+    Field(Vec<String>),
+    /// `!expr`
+    Not(Box<Condition>),
+    /// `a && b`
+    And(Box<Condition>, Box<Condition>),
+    /// `a || b`
+    Or(Box<Condition>, Box<Condition>),
+    /// `a <op> b`
+    Compare(Box<Condition>, CompareOp, Box<Condition>),
+}
+
+impl Condition {
+    /// 按记录求值，结果按真值规则转换为布尔值
+    fn evaluate(&self, record: &serde_json::Map<String, Value>) -> bool {
+        is_truthy(&self.value(record))
+    }
+
+    /// 求表达式的值，引用不存在的字段时为null
+    fn value(&self, record: &serde_json::Map<String, Value>) -> Value {
+        match self {
+            Condition::Literal(value) => value.clone(),
+            Condition::Field(path) => {
+                let mut current = record.get(&path[0]);
+                for segment in &path[1..] {
+                    current = current.and_then(|value| value.get(segment));
+                }
+                current.cloned().unwrap_or(Value::Null)
+            }
+            Condition::Not(inner) => Value::Bool(!inner.evaluate(record)),
+            Condition::And(left, right) => Value::Bool(left.evaluate(record) && right.evaluate(record)),
+            Condition::Or(left, right) => Value::Bool(left.evaluate(record) || right.evaluate(record)),
+            Condition::Compare(left, op, right) => {
+                let (left, right) = (left.value(record), right.value(record));
+                let ordering = match (&left, &right) {
+                    (Value::Number(a), Value::Number(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+                    (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+                Value::Bool(match (op, ordering) {
+                    (CompareOp::Eq, Some(ordering)) => ordering.is_eq(),
+                    (CompareOp::Ne, Some(ordering)) => ordering.is_ne(),
+                    (CompareOp::Eq, None) => left == right,
+                    (CompareOp::Ne, None) => left != right,
+                    (CompareOp::Gt, Some(ordering)) => ordering.is_gt(),
+                    (CompareOp::Ge, Some(ordering)) => ordering.is_ge(),
+                    (CompareOp::Lt, Some(ordering)) => ordering.is_lt(),
+                    (CompareOp::Le, Some(ordering)) => ordering.is_le(),
+                    // 类型不同或不可比较时，大小比较一律为假
+                    _ => false,
+                })
+            }
+        }
+    }
+}
+
+/// JSON值的真值：null、false、0、空字符串、空数组和空对象为假，其余为真
+pub fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+    }
+}
+
+/// 条件表达式的词法单元
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionToken {
+    /// 字面量
+    Literal(Value),
+    /// 字段路径
+    Field(Vec<String>),
+    /// 比较运算符
+    Compare(CompareOp),
+    /// `!`
+    Not,
+    /// `&&`
+    And,
+    /// `||`
+    Or,
+    /// `(`
+    Open,
+    /// `)`
+    Close,
+}
+
+/// 把条件表达式切分为词法单元
+pub fn tokenize_condition(expr: &str) -> PushResult<Vec<ConditionToken>> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let next = chars.get(i + 1).copied();
+        let (token, len) = match (chars[i], next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('(', _) => (ConditionToken::Open, 1),
+            (')', _) => (ConditionToken::Close, 1),
+            ('&', Some('&')) => (ConditionToken::And, 2),
+            ('|', Some('|')) => (ConditionToken::Or, 2),
+            ('=', Some('=')) => (ConditionToken::Compare(CompareOp::Eq), 2),
+            ('!', Some('=')) => (ConditionToken::Compare(CompareOp::Ne), 2),
+            ('!', _) => (ConditionToken::Not, 1),
+            ('>', Some('=')) => (ConditionToken::Compare(CompareOp::Ge), 2),
+            ('>', _) => (ConditionToken::Compare(CompareOp::Gt), 1),
+            ('<', Some('=')) => (ConditionToken::Compare(CompareOp::Le), 2),
+            ('<', _) => (ConditionToken::Compare(CompareOp::Lt), 1),
+            (quote @ ('\'' | '"'), _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == quote)
+                    .ok_or_else(|| PushError::ConfigInvalid(t!("condition.unclosed_string", quote)))?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                (ConditionToken::Literal(Value::String(text)), end + 2)
+            }
+            (c, _) if c.is_ascii_digit() || c == '-' => {
+                let len = chars[i..]
+                    .iter()
+                    .position(|&c| !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+')))
+                    .unwrap_or(chars.len() - i);
+                let text: String = chars[i..i + len].iter().collect();
+                let number: serde_json::Number = serde_json::from_str(&text)
+                    .map_err(|e| PushError::ConfigInvalid(t!("condition.number_invalid", text, e)))?;
+                (ConditionToken::Literal(Value::Number(number)), len)
+            }
+            (c, _) if c.is_alphabetic() || c == '_' => {
+                let len = chars[i..]
+                    .iter()
+                    .position(|&c| !(c.is_alphanumeric() || matches!(c, '_' | '.')))
+                    .unwrap_or(chars.len() - i);
+                let text: String = chars[i..i + len].iter().collect();
+                let token = match text.as_str() {
+                    "true" => ConditionToken::Literal(Value::Bool(true)),
+                    "false" => ConditionToken::Literal(Value::Bool(false)),
+                    "null" => ConditionToken::Literal(Value::Null),
+                    _ if text.split('.').any(str::is_empty) => {
+                        return Err(PushError::ConfigInvalid(t!("condition.path_invalid", text)));
+                    }
+                    _ => ConditionToken::Field(text.split('.').map(String::from).collect()),
+                };
+                (token, len)
+            }
+            (c, _) => return Err(PushError::ConfigInvalid(t!("condition.unknown_char", c))),
+        };
+        tokens.push(token);
+        i += len;
+    }
+    Ok(tokens)
+}
+
+/// 解析字段上报条件表达式
+///
+/// 运算符优先级从低到高为 `||`、`&&`、`!`、比较运算符，可以用括号改变结合顺序
+///
+/// # 参数
+///
+/// * `expr` - 条件表达式，如 `status == 'fault' && temp.value > 80`
+///
+/// # 错误
+///
+/// 表达式为空或存在语法错误时返回错误
+pub fn parse_condition(expr: &str) -> PushResult<Condition> {
+    /// 递归下降解析器
+    struct Parser {
+        tokens: Vec<ConditionToken>,
+        pos: usize,
+    }
+
+    impl Parser {
+        fn peek(&self) -> Option<&ConditionToken> {
+            self.tokens.get(self.pos)
+        }
+
+        fn next(&mut self) -> Option<ConditionToken> {
+            let token = self.tokens.get(self.pos).cloned();
+            self.pos += 1;
+            token
+        }
+
+        fn or(&mut self) -> PushResult<Condition> {
+            let mut left = self.and()?;
+            while self.peek() == Some(&ConditionToken::Or) {
+                self.pos += 1;
+                left = Condition::Or(Box::new(left), Box::new(self.and()?));
+            }
+            Ok(left)
+        }
+
+        fn and(&mut self) -> PushResult<Condition> {
+            let mut left = self.unary()?;
+            while self.peek() == Some(&ConditionToken::And) {
+                self.pos += 1;
+                left = Condition::And(Box::new(left), Box::new(self.unary()?));
+            }
+            Ok(left)
+        }
+
+        fn unary(&mut self) -> PushResult<Condition> {
+            if self.peek() == Some(&ConditionToken::Not) {
+                self.pos += 1;
+                return Ok(Condition::Not(Box::new(self.unary()?)));
+            }
+            let left = self.primary()?;
+            if let Some(&ConditionToken::Compare(op)) = self.peek() {
+                self.pos += 1;
+                return Ok(Condition::Compare(Box::new(left), op, Box::new(self.primary()?)));
+            }
+            Ok(left)
+        }
+
+        fn primary(&mut self) -> PushResult<Condition> {
+            match self.next() {
+                Some(ConditionToken::Literal(value)) => Ok(Condition::Literal(value)),
+                Some(ConditionToken::Field(path)) => Ok(Condition::Field(path)),
+                Some(ConditionToken::Open) => {
+                    let inner = self.or()?;
+                    match self.next() {
+                        Some(ConditionToken::Close) => Ok(inner),
+                        _ => Err(PushError::ConfigInvalid(t!("condition.missing_close"))),
+                    }
+                }
+                Some(token) => Err(PushError::ConfigInvalid(t!("condition.unexpected", format!("{:?}", token)))),
+                None => Err(PushError::ConfigInvalid(t!("condition.incomplete"))),
+            }
+        }
+    }
+
+    let mut parser = Parser { tokens: tokenize_condition(expr)?, pos: 0 };
+    let condition = parser.or()?;
+    if let Some(token) = parser.peek() {
+        return Err(PushError::ConfigInvalid(t!("condition.trailing", format!("{:?}", token))));
+    }
+    Ok(condition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{GeoConfig, GeoTrack, seeded_rng};
+
+    #[test]
+    fn rename_maps_fields_and_rejects_conflicts() {
+        let specs = ["temp=temperature, hum=humidity".to_string(), r#"{"pm":"pm25"}"#.to_string()];
+        let rules = FieldRules { rename: parse_rename(&specs).unwrap(), ..Default::default() };
+        assert_eq!(rules.rename.len(), 3);
+        assert_eq!(rules.rename["pm"], "pm25");
+        assert!(parse_rename(&["a=x,b=x".to_string()]).is_err());
+        assert!(parse_rename(&["a=x".to_string(), "a=y".to_string()]).is_err());
+        assert!(parse_rename(&["a".to_string()]).is_err());
+
+        let rng = &mut rand::thread_rng();
+        let record = serde_json::json!({"temp": 20, "other": 1});
+        let values = extract_telemetry_values(&record, &None, &rules, rng).unwrap();
+        assert_eq!(values["temperature"], 20);
+        assert_eq!(values["other"], 1);
+        let clash = serde_json::json!({"temp": 20, "temperature": 21});
+        assert!(matches!(
+            extract_telemetry_values(&clash, &None, &rules, rng),
+            Err(PushError::PayloadInvalid { index: None, .. })
+        ));
+        assert!(matches!(parse_rename(&["a".to_string()]), Err(PushError::ConfigInvalid(_))));
+    }
+
+    #[test]
+    fn payload_and_expression_errors_have_specific_variants() {
+        let rng = &mut rand::thread_rng();
+        let error = extract_telemetry_values(&serde_json::json!([1, 2]), &None, &FieldRules::default(), rng).unwrap_err();
+        assert!(matches!(error, PushError::PayloadInvalid { .. }), "{:?}", error);
+        assert_eq!(error.kind(), "payload");
+        assert!(matches!(NonFinitePolicy::Error.convert("t", f64::NAN), Err(PushError::PayloadInvalid { .. })));
+        assert!(matches!(parse_condition("a >"), Err(PushError::ConfigInvalid(_))));
+        assert!(matches!(parse_injection("temperature=80"), Err(PushError::ConfigInvalid(_))));
+    }
+
+    #[test]
+    fn conditions_omit_fields_when_false() {
+        let record = serde_json::json!({
+            "status": {"state": "fault", "temp": 85},
+            "fault_code": 17,
+            "alarm": true,
+            "note": "x",
+        });
+        let Value::Object(fields) = &record else { unreachable!() };
+        let holds = |expr: &str| parse_condition(expr).unwrap().evaluate(fields);
+        assert!(holds("status.state == 'fault'"));
+        assert!(holds("status.temp > 80 && !(status.state != \"fault\")"));
+        assert!(holds("missing == null || false"));
+        assert!(!holds("status.temp >= 'hot'"));
+        assert!(holds("alarm && fault_code"));
+        assert!(!holds("1 == 2 || 3 < 2 && true"));
+        for invalid in ["", "a ==", "(a", "a b", "'open", "a..b", "a # b"] {
+            assert!(parse_condition(invalid).is_err(), "{}", invalid);
+        }
+
+        let rules = FieldRules {
+            conditions: BTreeMap::from([
+                ("fault_code".to_string(), parse_condition("status.state == 'fault'").unwrap()),
+                ("note".to_string(), parse_condition("status.temp < 50").unwrap()),
+            ]),
+            ..Default::default()
+        };
+        let values = extract_telemetry_values(&record, &None, &rules, &mut rand::thread_rng()).unwrap();
+        assert!(values.contains_key("fault_code"));
+        assert!(!values.contains_key("note"));
+        assert_eq!(values.len(), 3);
+    }
+
+    #[test]
+    fn field_groups_send_only_due_fields() {
+        let groups = BTreeMap::from([(
+            "slow".to_string(),
+            FieldGroup { interval: 3.0, fields: vec!["temperature".to_string()] },
+        )]);
+        let mut scheduler = FieldScheduler::new(&groups);
+        let due_at = |scheduler: &mut FieldScheduler, secs: f64| {
+            scheduler.omitted(0, Duration::from_secs_f64(secs)).is_empty()
+        };
+        // 首次发送即到期，之后按间隔顺延，略晚的发送不会推迟后续到期时刻
+        assert!(due_at(&mut scheduler, 0.0));
+        assert!(!due_at(&mut scheduler, 1.0));
+        assert!(!due_at(&mut scheduler, 2.9));
+        assert!(due_at(&mut scheduler, 3.2));
+        assert!(due_at(&mut scheduler, 6.0));
+        // 落后超过一个间隔时从当前时刻重新计时，不连续补发
+        assert!(due_at(&mut scheduler, 20.0));
+        assert!(!due_at(&mut scheduler, 21.0));
+        assert!(due_at(&mut scheduler, 23.0));
+        // 每个数据项分别计时
+        assert!(scheduler.omitted(1, Duration::from_secs(21)).is_empty());
+    }
+
+    #[test]
+    fn payload_shapes_serialize_exactly() {
+        let telemetry = |shape| TelemetryData {
+            ts: 1735696800000,
+            values: HashMap::from([("temperature".to_string(), serde_json::json!(25.6))]),
+            time: "2025-01-01 10:00:00".to_string(),
+            time_key: Some("time".to_string()),
+            shape,
+        };
+        let body = |shape| serde_json::to_string(&telemetry(shape)).unwrap();
+        assert_eq!(
+            body(PayloadShape::Legacy),
+            r#"{"ts":1735696800000,"values":{"temperature":25.6},"time":"2025-01-01 10:00:00"}"#
+        );
+        assert_eq!(body(PayloadShape::Strict), r#"{"ts":1735696800000,"values":{"temperature":25.6}}"#);
+        assert_eq!(body(PayloadShape::Flat), r#"{"temperature":25.6}"#);
+    }
+
+    #[test]
+    fn flatten_expands_nested_objects_into_dotted_keys() {
+        let record = serde_json::json!({
+            "rain": {"intensity": 2.5, "total": 10, "gauge": {"ok": true}},
+            "readings": [1, 2],
+            "tags": {},
+            "device": "d1",
+        });
+        let rng = &mut seeded_rng(Some(7), 0);
+        let nested = extract_telemetry_values(&record, &None, &FieldRules::default(), rng).unwrap();
+        assert_eq!(nested["rain"], serde_json::json!({"intensity": 2.5, "total": 10, "gauge": {"ok": true}}));
+        assert_eq!(nested.len(), 4);
+
+        let mut rules = FieldRules {
+            flatten: Some(FlattenRules { separator: ".".to_string(), arrays: false }),
+            ..Default::default()
+        };
+        let flat = extract_telemetry_values(&record, &None, &rules, rng).unwrap();
+        let expected = serde_json::json!({
+            "rain.intensity": 2.5,
+            "rain.total": 10,
+            "rain.gauge.ok": true,
+            "readings": [1, 2],
+            "tags": {},
+            "device": "d1",
+        });
+        assert_eq!(serde_json::to_value(&flat).unwrap(), expected);
+
+        rules.flatten = Some(FlattenRules { separator: "_".to_string(), arrays: true });
+        let indexed = extract_telemetry_values(&record, &None, &rules, rng).unwrap();
+        assert_eq!((&indexed["readings_0"], &indexed["readings_1"]), (&serde_json::json!(1), &serde_json::json!(2)));
+        assert!(!indexed.contains_key("readings"));
+
+        // 随机修改作用于展开后的叶子
+        let randomized = extract_telemetry_values(&record, &Some("intensity".to_string()), &rules, rng).unwrap();
+        assert!(randomized["rain_intensity"].is_number());
+        assert_eq!(randomized["rain_total"], 10);
+
+        let clash = serde_json::json!({"a": {"b": 1}, "a_b": 2});
+        assert!(extract_telemetry_values(&clash, &None, &rules, rng).is_err());
+    }
+
+    #[test]
+    fn non_finite_values_follow_policy() {
+        for value in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(NonFinitePolicy::Drop.convert("t", value).unwrap(), None);
+            assert_eq!(NonFinitePolicy::Null.convert("t", value).unwrap(), Some(Value::Null));
+            assert!(NonFinitePolicy::Error.convert("t", value).unwrap_err().to_string().contains("'t'"));
+        }
+        assert_eq!(NonFinitePolicy::Clamp.convert("t", f64::INFINITY).unwrap(), Some(serde_json::json!(f64::MAX)));
+        assert_eq!(NonFinitePolicy::Clamp.convert("t", f64::NEG_INFINITY).unwrap(), Some(serde_json::json!(-f64::MAX)));
+        assert_eq!(NonFinitePolicy::Clamp.convert("t", f64::NAN).unwrap(), None);
+        for policy in [NonFinitePolicy::Drop, NonFinitePolicy::Null, NonFinitePolicy::Error, NonFinitePolicy::Clamp] {
+            assert_eq!(policy.convert("t", 21.5).unwrap(), Some(serde_json::json!(21.5)));
+        }
+
+        // 轨迹坐标溢出时按策略省略，不留下旧值，也不会中断发送
+        let config: GeoConfig = serde_json::from_value(serde_json::json!({"start": [30.0, 120.0], "speed": 1.0})).unwrap();
+        let track = GeoTrack::new(config, seeded_rng(Some(1), 0));
+        let mut item = serde_json::json!({"gps": {"lat": 1.0, "lng": 2.0}, "speed": 3});
+        track.write_position(&mut item, [f64::NAN, 120.5], NonFinitePolicy::Drop).unwrap();
+        assert_eq!(item, serde_json::json!({"gps": {"lng": 120.5}, "speed": 3}));
+        assert!(track.write_position(&mut item, [f64::INFINITY, 0.0], NonFinitePolicy::Error).is_err());
+        track.write_position(&mut item, [30.0, f64::INFINITY], NonFinitePolicy::Null).unwrap();
+        assert_eq!(item["lat"], serde_json::json!(30.0));
+        assert_eq!(item["lng"], Value::Null);
+    }
+
+    #[test]
+    fn valid_range_warns_and_clamps_out_of_range_values() {
+        let range = |min, max, clamp| ValidRange { min, max, clamp };
+        let rules = FieldRules {
+            valid_range: BTreeMap::from([
+                ("humidity".to_string(), range(Some(0.0), Some(100.0), true)),
+                ("rain.intensity".to_string(), range(Some(0.0), None, true)),
+                ("pressure".to_string(), range(None, Some(1100.0), false)),
+                ("missing.field".to_string(), range(Some(0.0), Some(1.0), true)),
+            ]),
+            ..Default::default()
+        };
+        let record = serde_json::json!({
+            "humidity": 130,
+            "rain": {"intensity": -2.5, "total": 3},
+            "pressure": 1200.5,
+            "status": "ok",
+        });
+        let values = extract_telemetry_values(&record, &None, &rules, &mut rand::thread_rng()).unwrap();
+        assert_eq!(values["humidity"], serde_json::json!(100));
+        assert_eq!(values["rain"], serde_json::json!({"intensity": 0.0, "total": 3}));
+        // 未配置截断时只告警，按原值发送
+        assert_eq!(values["pressure"], serde_json::json!(1200.5));
+        assert_eq!(values["status"], "ok");
+        assert_eq!(range(Some(0.0), None, false).to_string(), "0..");
+        assert_eq!(range(Some(-1.5), Some(2.0), false).to_string(), "-1.5..2");
+    }
+
+    #[test]
+    fn time_placeholders_are_filled_and_unknown_markers_kept() {
+        let record = serde_json::json!({
+            "reported_at": "__now__",
+            "ts_ms": "__ts__",
+            "meta": {"day": "__date__", "stamps": ["__iso__", "__unix__", 1]},
+            "custom": "__later__",
+            "label": "now",
+        });
+        let before = chrono::Utc::now().timestamp_millis();
+        let values =
+            extract_telemetry_values(&record, &None, &FieldRules::default(), &mut rand::thread_rng()).unwrap();
+        let after = chrono::Utc::now().timestamp_millis();
+
+        let ts = values["ts_ms"].as_i64().unwrap();
+        assert!((before..=after).contains(&ts));
+        let reported = values["reported_at"].as_str().unwrap();
+        assert!(chrono::NaiveDateTime::parse_from_str(reported, "%Y-%m-%d %H:%M:%S").is_ok());
+        let day = values["meta"]["day"].as_str().unwrap();
+        assert!(reported.starts_with(day));
+        let iso = values["meta"]["stamps"][0].as_str().unwrap();
+        assert_eq!(chrono::DateTime::parse_from_rfc3339(iso).unwrap().timestamp_millis(), ts);
+        assert_eq!(values["meta"]["stamps"][1], ts / 1000);
+        assert_eq!(values["meta"]["stamps"][2], 1);
+        // 未登记的标记和普通字符串原样保留
+        assert_eq!(values["custom"], "__later__");
+        assert_eq!(values["label"], "now");
+    }
+
+    #[test]
+    fn injections_parse_trigger_and_apply_to_nested_fields() {
+        let injection = parse_injection("temperature=99.5@3x2").unwrap();
+        assert_eq!((injection.field.as_str(), injection.times), ("temperature", 2));
+        assert_eq!(injection.value, serde_json::json!(99.5));
+        assert!(matches!(injection.trigger, InjectTrigger::AtSend(3)));
+        // 不是JSON的值按字符串注入，持续次数默认为1
+        let timed = parse_injection("status=alarm@30s").unwrap();
+        assert_eq!((timed.value.clone(), timed.times), (serde_json::json!("alarm"), 1));
+        assert!(matches!(timed.trigger, InjectTrigger::AfterSecs(30)));
+        for spec in ["temperature=1", "temperature@3", "=1@3", "t=1@x", "t=1@3xa", "t=1@as"] {
+            assert!(matches!(parse_injection(spec), Err(PushError::ConfigInvalid(_))), "{}", spec);
+        }
+
+        // 到达触发条件后生效，注入满持续次数后停止
+        let mut injection = injection;
+        assert!(!injection.is_active(2, Duration::ZERO));
+        assert!(injection.is_active(3, Duration::ZERO));
+        injection.applied = 2;
+        assert!(!injection.is_active(4, Duration::ZERO));
+        assert!(!timed.is_active(1, Duration::from_secs(29)));
+        assert!(timed.is_active(1, Duration::from_secs(30)));
+
+        let mut item = serde_json::json!({"temperature": 20, "room": {"temperature": 21, "humidity": 40}});
+        apply_injection(&mut item, "temperature", &serde_json::json!(99.5));
+        assert_eq!(item, serde_json::json!({"temperature": 99.5, "room": {"temperature": 99.5, "humidity": 40}}));
+    }
+}
@@ -0,0 +1,267 @@
+//! 终端仪表盘（`--tui`）
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, OnceLock};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use chrono::Local;
+use tokio::sync::watch;
+use tokio::time::Duration;
+use crate::output::EventFields;
+
+/// TUI仪表盘中保留的最近发送记录条数
+pub const TUI_RECENT_ITEMS: usize = 8;
+
+/// TUI仪表盘延迟火花线保留的采样点个数
+pub const TUI_LATENCY_SAMPLES: usize = 120;
+
+/// TUI仪表盘计算当前速率的时间窗口
+pub const TUI_RATE_WINDOW: Duration = Duration::from_secs(10);
+
+/// TUI仪表盘的运行状态，指定 `--tui` 时设置
+///
+/// 由 [`TuiLayer`] 从日志事件中累计，与JSON日志使用同一组结构化字段，
+/// 仪表盘线程定期读取并重绘
+pub static TUI_STATE: OnceLock<Mutex<TuiState>> = OnceLock::new();
+
+/// TUI仪表盘是否正在占用终端，期间普通日志输出被丢弃，避免把画面冲乱
+pub static TUI_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// TUI仪表盘显示的统计数据
+#[derive(Default)]
+pub struct TuiState {
+    /// 启动横幅中的设备和服务器描述
+    target: String,
+    /// 成功条数（演练模式下为生成条数）
+    sent: u64,
+    /// 失败条数
+    failed: u64,
+    /// 最近完成发送的时刻，用于计算当前速率
+    completions: std::collections::VecDeque<Instant>,
+    /// 最近若干次发送的延迟（毫秒）
+    latencies: std::collections::VecDeque<u64>,
+    /// 最近若干条发送的摘要
+    recent: std::collections::VecDeque<String>,
+    /// 按设备统计的 (成功, 失败) 条数
+    devices: BTreeMap<String, (u64, u64)>,
+}
+
+impl TuiState {
+    /// 按日志事件更新统计，不关心的事件直接忽略
+    fn apply(&mut self, fields: &serde_json::Map<String, Value>) {
+        let text = |key: &str| fields.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+        let number = |key: &str| fields.get(key).and_then(Value::as_u64);
+        let event = text("event");
+        let success = match event.as_str() {
+            "startup" => {
+                self.target = format!("{} → {}", text("device"), text("servers"));
+                return;
+            }
+            "send" | "generated" => true,
+            "send_failed" => false,
+            _ => return,
+        };
+
+        let now = Instant::now();
+        self.completions.push_back(now);
+        while self.completions.front().is_some_and(|at| now.duration_since(*at) > TUI_RATE_WINDOW) {
+            self.completions.pop_front();
+        }
+        if let Some(latency) = number("latency_ms") {
+            if self.latencies.len() == TUI_LATENCY_SAMPLES {
+                self.latencies.pop_front();
+            }
+            self.latencies.push_back(latency);
+        }
+        let device = self.devices.entry(text("device")).or_default();
+        let summary = if success {
+            self.sent += 1;
+            device.0 += 1;
+            format!(
+                "✅ #{} {} {} B [{}]",
+                number("item_index").unwrap_or_default() + 1,
+                number("status_code").map_or_else(|| "-".to_string(), |code| code.to_string()),
+                number("bytes").unwrap_or_default(),
+                text("keys")
+            )
+        } else {
+            self.failed += 1;
+            device.1 += 1;
+            format!("❌ #{} {}", number("item_index").unwrap_or_default() + 1, text("error"))
+        };
+        if self.recent.len() == TUI_RECENT_ITEMS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(format!("{} {}", Local::now().format("%H:%M:%S"), summary));
+    }
+
+    /// 最近 [`TUI_RATE_WINDOW`] 内的发送速率（条/秒）
+    fn rate(&self) -> f64 {
+        match (self.completions.front(), self.completions.back()) {
+            (Some(first), Some(last)) if self.completions.len() > 1 => {
+                let elapsed = last.duration_since(*first).as_secs_f64();
+                if elapsed > 0.0 { (self.completions.len() - 1) as f64 / elapsed } else { 0.0 }
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// 把带 `event` 字段的日志事件转交给TUI仪表盘的日志层
+///
+/// 未指定 `--tui` 时 [`TUI_STATE`] 为空，事件直接忽略
+pub struct TuiLayer;
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for TuiLayer {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: tracing_subscriber::layer::Context<'_, S>) {
+        let Some(state) = TUI_STATE.get() else {
+            return;
+        };
+        let mut fields = EventFields::default();
+        event.record(&mut fields);
+        if fields.fields.contains_key("event") {
+            state.lock().unwrap().apply(&fields.fields);
+        }
+    }
+}
+
+/// TUI仪表盘发给发送循环的控制指令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuiCommand {
+    /// 正常发送
+    Running,
+    /// 暂停发送，当前请求完成后等待恢复
+    Paused,
+    /// 退出，等同于按下Ctrl-C
+    Quit,
+}
+
+/// 运行TUI仪表盘，直到 `stop` 被置位或用户按键退出
+///
+/// 在独立线程中执行：接管终端、按固定间隔重绘，并处理 `p`/空格（暂停/继续）
+/// 和 `q`/Esc/Ctrl-C（退出）按键。返回前恢复终端
+///
+/// # 参数
+///
+/// * `commands` - 向发送循环发出控制指令的通道
+/// * `stop` - 发送结束时由主任务置位
+pub fn run_tui(commands: watch::Sender<TuiCommand>, stop: Arc<AtomicBool>) -> std::io::Result<()> {
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+
+    let mut terminal = ratatui::init();
+    let result = (|| {
+        while !stop.load(Ordering::Relaxed) {
+            let command = *commands.borrow();
+            terminal.draw(|frame| draw_tui(frame, command))?;
+            if !event::poll(Duration::from_millis(200))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let quit = key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+            match key.code {
+                KeyCode::Char('p') | KeyCode::Char(' ') => commands.send_modify(|command| {
+                    *command = match command {
+                        TuiCommand::Paused => TuiCommand::Running,
+                        _ => TuiCommand::Paused,
+                    }
+                }),
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    commands.send_replace(TuiCommand::Quit);
+                }
+                _ if quit => {
+                    commands.send_replace(TuiCommand::Quit);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+    result
+}
+
+/// 绘制一帧TUI仪表盘
+pub fn draw_tui(frame: &mut ratatui::Frame, command: TuiCommand) {
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::widgets::{Block, List, Paragraph, Sparkline};
+
+    // TUI状态在启动仪表盘前设置
+    let state = TUI_STATE.get().unwrap().lock().unwrap();
+    let [header, latency, bottom, footer] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Length(6),
+        Constraint::Min(4),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [recent, devices] = Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(bottom);
+
+    let status = t!(if command == TuiCommand::Paused { "tui.paused" } else { "tui.running" });
+    frame.render_widget(
+        Paragraph::new(vec![
+            state.target.clone().into(),
+            t!("tui.totals", state.sent, state.failed, format!("{:.2}", state.rate()), status).into(),
+        ])
+        .block(Block::bordered().title(t!("tui.title"))),
+        header,
+    );
+
+    let latencies: Vec<u64> = state.latencies.iter().copied().collect();
+    let last = latencies.last().map_or_else(|| "-".to_string(), |ms| ms.to_string());
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(t!("tui.latency", last)))
+            .data(&latencies),
+        latency,
+    );
+
+    frame.render_widget(
+        List::new(state.recent.iter().rev().cloned()).block(Block::bordered().title(t!("tui.recent"))),
+        recent,
+    );
+    frame.render_widget(
+        List::new(
+            state
+                .devices
+                .iter()
+                .map(|(device, (sent, failed))| format!("{}  ✅ {}  ❌ {}", device, sent, failed)),
+        )
+        .block(Block::bordered().title(t!("tui.devices"))),
+        devices,
+    );
+    frame.render_widget(Paragraph::new(t!("tui.keys")), footer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tui_state_counts_send_events_per_device() {
+        let mut state = TuiState::default();
+        let event = |value: Value| value.as_object().unwrap().clone();
+        state.apply(&event(serde_json::json!({"event": "startup", "device": "abcd****qrst", "servers": "http://a"})));
+        state.apply(&event(serde_json::json!({
+            "event": "send", "device": "d1", "item_index": 0, "status_code": 200, "bytes": 42, "keys": "t", "latency_ms": 5
+        })));
+        state.apply(&event(serde_json::json!({"event": "send_failed", "device": "d1", "item_index": 1, "error": "超时"})));
+        state.apply(&event(serde_json::json!({"event": "generated", "device": "d2", "item_index": 0})));
+        // 与发送结果无关的事件不计入
+        state.apply(&event(serde_json::json!({"event": "heartbeat", "device": "d1"})));
+
+        assert_eq!(state.target, "abcd****qrst → http://a");
+        assert_eq!((state.sent, state.failed), (2, 1));
+        assert_eq!(state.devices["d1"], (1, 1));
+        assert_eq!(state.devices["d2"], (1, 0));
+        assert_eq!(state.latencies, [5]);
+        assert!(state.recent[0].ends_with("✅ #1 200 42 B [t]"), "{}", state.recent[0]);
+        assert!(state.recent[1].ends_with("❌ #2 超时"), "{}", state.recent[1]);
+    }
+}
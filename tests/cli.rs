@@ -0,0 +1,376 @@
+//! 命令行集成测试：在本地启动模拟的ThingsBoard设备API，用真实的可执行文件发送数据，
+//! 检查请求地址、请求头、请求体结构、失败重试、认证中止、发送次数、发送计划预览、中断以及后台运行和停止
+
+use serde_json::{Value, json};
+use std::path::PathBuf;
+use std::process::Output;
+use std::time::{Duration, Instant};
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+/// 测试使用的设备令牌（长度与ThingsBoard生成的令牌相同，避免过短令牌的警告）
+const TOKEN: &str = "A1b2C3d4E5f6G7h8I9j0";
+
+/// 遥测通道的请求路径
+fn telemetry_path() -> String {
+    format!("/api/v1/{}/telemetry", TOKEN)
+}
+
+/// 把数据文件写入本测试独占的临时目录，返回目录路径
+///
+/// 程序在该目录中运行，不会读到仓库或用户目录下的 `.env` 和配置文件
+fn workdir(name: &str, data: &Value) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("push-cli-{}-{}", std::process::id(), name));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("data.json"), data.to_string()).unwrap();
+    dir
+}
+
+/// 以给定参数运行程序，服务器指向模拟服务
+async fn run(server: &MockServer, dir: &PathBuf, args: &[&str]) -> Output {
+    run_with_env(server, dir, args, &[]).await
+}
+
+/// 在目录中以给定参数运行程序的命令，服务器指向模拟服务，不受外部环境变量影响
+fn program(server: &MockServer, dir: &PathBuf, args: &[&str]) -> tokio::process::Command {
+    let mut command = tokio::process::Command::new(env!("CARGO_BIN_EXE_push-message-thingsboard"));
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("server", server.uri())
+        .env("device_token", TOKEN)
+        .env_remove("server_FILE")
+        .env_remove("device_token_FILE")
+        .env_remove("sign_key")
+        .env_remove("sign_key_FILE")
+        .env("LANG", "zh_CN.UTF-8")
+        .env_remove("LC_ALL");
+    command
+}
+
+/// 以给定参数和额外的环境变量运行程序
+async fn run_with_env(server: &MockServer, dir: &PathBuf, args: &[&str], envs: &[(&str, &str)]) -> Output {
+    let output = program(server, dir, args).envs(envs.iter().copied()).output().await.unwrap();
+    std::fs::remove_dir_all(dir).ok();
+    output
+}
+
+/// 进程退出码，附带输出便于定位失败原因
+fn exit_code(output: &Output) -> i32 {
+    let code = output.status.code().unwrap();
+    if code != 0 {
+        eprintln!("stdout:\n{}", String::from_utf8_lossy(&output.stdout));
+        eprintln!("stderr:\n{}", String::from_utf8_lossy(&output.stderr));
+    }
+    code
+}
+
+/// 模拟服务收到的请求
+async fn received(server: &MockServer) -> Vec<Request> {
+    server.received_requests().await.unwrap()
+}
+
+/// 解析请求体，并把随时间变化的字段替换为占位值
+///
+/// `ts` 必须是毫秒时间戳，`time` 和 `send_time` 必须是 `YYYY-MM-DD HH:MM:SS` 格式的字符串
+fn normalized_body(request: &Request) -> Value {
+    fn normalize(value: &mut Value) {
+        let Value::Object(fields) = value else {
+            return;
+        };
+        for (key, value) in fields.iter_mut() {
+            match key.as_str() {
+                "ts" => {
+                    assert!(value.as_u64().is_some_and(|ts| ts > 1_600_000_000_000), "ts: {}", value);
+                    *value = json!("<ts>");
+                }
+                "time" | "send_time" => {
+                    let text = value.as_str().unwrap_or_default();
+                    let parsed = chrono::NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S");
+                    assert!(parsed.is_ok(), "{}: {}", key, value);
+                    *value = json!("<time>");
+                }
+                _ => normalize(value),
+            }
+        }
+    }
+    let mut body: Value = serde_json::from_slice(&request.body).unwrap();
+    normalize(&mut body);
+    body
+}
+
+/// 返回200的遥测接口
+async fn accepting_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(telemetry_path()))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn telemetry_is_posted_to_the_token_url_as_json() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(telemetry_path()))
+        .and(header("content-type", "application/json"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(2)
+        .mount(&server)
+        .await;
+    let dir = workdir("url", &json!([{"temperature": 21.5}, {"humidity": 60}]));
+
+    let output = run(&server, &dir, &["-i", "0", "-c", "1", "-f", "data.json"]).await;
+    assert_eq!(exit_code(&output), 0);
+    let requests = received(&server).await;
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|request| request.url.path().contains(TOKEN)));
+}
+
+#[tokio::test]
+async fn payload_modes_produce_the_documented_bodies() {
+    let data = json!([{"temperature": 21.5, "rain": {"drp": 0.2, "unit": "mm"}}]);
+    let values = json!({"temperature": 21.5, "rain": {"drp": 0.2, "unit": "mm"}});
+    let cases = [
+        // 默认（兼容旧版本）：文档规定的 ts/values 之外，还带有顶层和 values 中的发送时间
+        (
+            vec![],
+            json!({
+                "ts": "<ts>",
+                "time": "<time>",
+                "values": {"temperature": 21.5, "rain": {"drp": 0.2, "unit": "mm"}, "send_time": "<time>"}
+            }),
+        ),
+        (vec!["--strict-payload", "--no-send-time"], json!({"ts": "<ts>", "values": values})),
+        (vec!["--flat-payload", "--no-send-time"], values.clone()),
+    ];
+    for (index, (flags, expected)) in cases.into_iter().enumerate() {
+        let server = accepting_server().await;
+        let dir = workdir(&format!("payload-{}", index), &data);
+        let mut args = vec!["-i", "0", "-c", "1", "-f", "data.json"];
+        args.extend(&flags);
+
+        let output = run(&server, &dir, &args).await;
+        assert_eq!(exit_code(&output), 0, "{:?}", flags);
+        let requests = received(&server).await;
+        assert_eq!(requests.len(), 1, "{:?}", flags);
+        assert_eq!(normalized_body(&requests[0]), expected, "{:?}", flags);
+    }
+}
+
+#[tokio::test]
+async fn server_errors_are_retried_with_requeue() {
+    let data = json!([{"temperature": 21.5}]);
+
+    // 第一次返回500，重新入队后的第二次成功
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+    let dir = workdir("requeue", &data);
+    let output = run(&server, &dir, &["-i", "0", "-c", "1", "-f", "data.json", "--requeue"]).await;
+    assert_eq!(exit_code(&output), 0);
+    let requests = received(&server).await;
+    assert_eq!(requests.len(), 2);
+    assert_eq!(normalized_body(&requests[0])["values"], normalized_body(&requests[1])["values"]);
+
+    // 不重新入队时同样的失败以退出码2结束
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(500)).mount(&server).await;
+    let dir = workdir("no-requeue", &data);
+    let output = run(&server, &dir, &["-i", "0", "-c", "1", "-f", "data.json"]).await;
+    assert_eq!(exit_code(&output), 2);
+    assert_eq!(received(&server).await.len(), 1);
+}
+
+#[tokio::test]
+async fn throttled_requests_are_resent_after_retry_after() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+    let dir = workdir("throttle", &json!([{"temperature": 21.5}]));
+
+    let started = Instant::now();
+    let output = run(&server, &dir, &["-i", "0", "-c", "1", "-f", "data.json"]).await;
+    assert_eq!(exit_code(&output), 0);
+    assert!(started.elapsed() >= Duration::from_secs(1));
+    // 限流后重发的是同一个请求体
+    let requests = received(&server).await;
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].body, requests[1].body);
+}
+
+#[tokio::test]
+async fn auth_failure_aborts_the_run() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST")).respond_with(ResponseTemplate::new(401)).mount(&server).await;
+    let dir = workdir("auth", &json!([{"temperature": 21.5}, {"humidity": 60}]));
+
+    let output = run(&server, &dir, &["-i", "0", "-c", "3", "-f", "data.json"]).await;
+    assert_eq!(exit_code(&output), 5);
+    assert_eq!(received(&server).await.len(), 1);
+}
+
+#[tokio::test]
+async fn count_and_interval_control_the_requests() {
+    // 每轮发送全部数据项，按数据项顺序
+    let server = accepting_server().await;
+    let dir = workdir("count", &json!([{"seq": 1}, {"seq": 2}]));
+    let output = run(&server, &dir, &["-i", "0", "-c", "3", "-f", "data.json"]).await;
+    assert_eq!(exit_code(&output), 0);
+    let seqs: Vec<Value> =
+        received(&server).await.iter().map(|request| normalized_body(request)["values"]["seq"].clone()).collect();
+    assert_eq!(seqs, [1, 2, 1, 2, 1, 2]);
+
+    // 间隔在两次发送之间生效：按请求体中的发送时间戳比较，不受进程启动耗时影响
+    let server = accepting_server().await;
+    let dir = workdir("interval", &json!([{"seq": 1}]));
+    let started = Instant::now();
+    let output = run(&server, &dir, &["-i", "2", "-c", "2", "-f", "data.json"]).await;
+    let elapsed = started.elapsed();
+    assert_eq!(exit_code(&output), 0);
+    let requests = received(&server).await;
+    assert_eq!(requests.len(), 2);
+    let ts = |request: &Request| serde_json::from_slice::<Value>(&request.body).unwrap()["ts"].as_u64().unwrap();
+    let gap = Duration::from_millis(ts(&requests[1]) - ts(&requests[0]));
+    assert!(gap >= Duration::from_millis(1900), "{:?}", gap);
+    // 最后一次发送后不再等待：多等一个间隔时总耗时至少是时间戳间隔加上一个完整间隔
+    assert!(elapsed < gap + Duration::from_secs(2), "elapsed {:?}, gap {:?}", elapsed, gap);
+}
+
+#[tokio::test]
+async fn sign_key_is_read_from_a_secret_file() {
+    use hmac::{Hmac, Mac};
+
+    let server = accepting_server().await;
+    let dir = workdir("sign-key-file", &json!([{"temperature": 21.5}]));
+    std::fs::write(dir.join("sign_key"), "file-secret\n").unwrap();
+    let key_file = dir.join("sign_key").to_string_lossy().into_owned();
+    let envs = [("sign_key_FILE", key_file.as_str())];
+    let output = run_with_env(&server, &dir, &["-i", "0", "-c", "1", "-f", "data.json"], &envs).await;
+    assert_eq!(exit_code(&output), 0);
+    let requests = received(&server).await;
+    assert_eq!(requests.len(), 1);
+    // 文件末尾的换行不属于密钥
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(b"file-secret").unwrap();
+    mac.update(&requests[0].body);
+    let signature = hex::encode(mac.finalize().into_bytes());
+    assert_eq!(requests[0].headers.get("x-signature").unwrap().to_str().unwrap(), signature);
+
+    // 没有任何密钥时，签名相关参数在启动时报错
+    let server = accepting_server().await;
+    let dir = workdir("sign-key-missing", &json!([{"temperature": 21.5}]));
+    let output = run(&server, &dir, &["-i", "0", "-c", "1", "-f", "data.json", "--sign-algorithm", "hmac-sha512"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--sign-algorithm"));
+    assert!(received(&server).await.is_empty());
+}
+
+#[tokio::test]
+async fn send_flags_with_a_subcommand_are_rejected_in_the_selected_language() {
+    let server = accepting_server().await;
+    let dir = workdir("send-flag-subcommand", &json!([]));
+    let output = run(&server, &dir, &["-i", "5", "config", "--lang", "en"]).await;
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("send argument --interval can only be used with send"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn daemon_starts_in_the_background_and_stops_with_its_pid_file() {
+    let server = accepting_server().await;
+    let dir = workdir("daemon", &json!([{"temperature": 21}]));
+    let pid_file = dir.join("push.pid");
+    let alive = |pid: i32| unsafe { libc::kill(pid, 0) } == 0;
+
+    let args = ["--daemon", "--log-file", "push.log", "--pid-file", "push.pid", "-c", "0", "-i", "1"];
+    let started = program(&server, &dir, &args).output().await.unwrap();
+    assert_eq!(exit_code(&started), 0);
+    let pid: i32 = std::fs::read_to_string(&pid_file).unwrap().trim().parse().unwrap();
+    assert!(alive(pid));
+    assert!(String::from_utf8_lossy(&started.stdout).contains(&pid.to_string()));
+
+    // 已有后台进程在运行时拒绝再启动一个
+    let again = program(&server, &dir, &args).output().await.unwrap();
+    assert_ne!(again.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&again.stderr).contains(&pid.to_string()));
+
+    // stop 发送中断信号，后台进程写出汇总、删除PID文件后退出
+    let stopped = program(&server, &dir, &["stop", "--pid-file", "push.pid", "--timeout", "10"]).output().await.unwrap();
+    assert_eq!(exit_code(&stopped), 0);
+    assert!(!pid_file.exists());
+    let log = std::fs::read_to_string(dir.join("push.log")).unwrap();
+    assert!(log.contains("[DATA]"), "{}", log);
+    assert!(!received(&server).await.is_empty());
+
+    // PID文件中的进程已经不存在时清理PID文件，PID文件不存在时报错
+    std::fs::write(&pid_file, "999999999\n").unwrap();
+    let stale = program(&server, &dir, &["stop", "--pid-file", "push.pid"]).output().await.unwrap();
+    assert_eq!(exit_code(&stale), 0);
+    assert!(!pid_file.exists());
+    let missing = program(&server, &dir, &["stop", "--pid-file", "push.pid"]).output().await.unwrap();
+    assert_ne!(missing.status.code(), Some(0));
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn interrupt_finishes_the_request_in_flight_and_prints_the_summary() {
+    // 响应很慢的服务器：中断信号到达时请求还在进行
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(telemetry_path()))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(1500)))
+        .mount(&server)
+        .await;
+    let dir = workdir("interrupt", &json!([{"temperature": 21}, {"temperature": 22}]));
+    let child = program(&server, &dir, &["-c", "0", "-i", "1"])
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while received(&server).await.is_empty() {
+        assert!(Instant::now() < deadline, "没有收到请求");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let pid = child.id().unwrap() as i32;
+    assert_eq!(unsafe { libc::kill(pid, libc::SIGINT) }, 0);
+    let output = child.wait_with_output().await.unwrap();
+    std::fs::remove_dir_all(&dir).ok();
+
+    // 进行中的请求完成并计入汇总，之后不再发送，以130退出
+    assert_eq!(exit_code(&output), 130);
+    let log = format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    assert!(log.contains("发送已中断！总共发送了 1 条数据"), "{}", log);
+    assert!(log.contains("尝试 1 条, 发送成功 1 条, 失败 0 条"), "{}", log);
+    assert!(log.contains("[EXIT] 退出码 130"), "{}", log);
+    assert_eq!(received(&server).await.len(), 1);
+}
+
+#[tokio::test]
+async fn plan_preview_follows_the_rate_schedule() {
+    // 线性预热2秒、目标速率2条/秒：预热阶段发出2条，第2条在√2秒，之后每0.5秒一条
+    let server = accepting_server().await;
+    let dir = workdir("plan-rate", &json!([{"seq": 1}, {"seq": 2}]));
+    let output = run(&server, &dir, &["-f", "data.json", "-c", "2", "--plan", "--rate", "2", "--warmup", "2"]).await;
+    assert_eq!(exit_code(&output), 0);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let offsets: Vec<&str> =
+        stdout.lines().filter_map(|line| line.split_once("(+")).map(|(_, rest)| rest.split(')').next().unwrap()).collect();
+    assert_eq!(offsets, ["0.00s", "1.41s", "2.00s", "2.50s"], "{}", stdout);
+    assert!(stdout.contains("速率 2 条/秒, 预热 2 秒, 每轮 2 条"), "{}", stdout);
+    assert!(stdout.contains("4 条数据, 预计耗时 2.50 秒"), "{}", stdout);
+    assert!(received(&server).await.is_empty());
+}